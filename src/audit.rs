@@ -0,0 +1,1170 @@
+//! High-level audit pipeline
+
+use anyhow::Result;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::time::Instant;
+
+use crate::auditor::{self, Auditor, LoadedImage};
+use crate::baseline::Baseline;
+use crate::box_stats::BoxStats;
+use crate::cache::ResultCache;
+use crate::class_map::{ClassMapping, UnmappedClasses};
+use crate::dataset::{
+    self, ConsistencyReport, CoordinateMistake, LabelError, MultiSplitDataset, YoloDataset,
+};
+use crate::detector::{
+    Detector, DetectorConfig, Device, ModelFormat, NmsMethod, NmsMode, VlmDetector, YoloDetector,
+    ZeroShotBackend,
+};
+use crate::grounding_dino::GroundingDinoDetector;
+use crate::leakage;
+use crate::models::{Annotation, AuditResult, ImageResult, Issue, IssueType};
+use crate::policy::SeverityPolicy;
+use crate::sampling::{self, ImageFilter, SampleStrategy};
+use crate::stream::ResultStream;
+use crate::suppress::Suppressions;
+
+/// Annotations and label issues of each image in a batch
+type BatchLabels = Vec<(Vec<Annotation>, Vec<Issue>)>;
+
+/// Image count above which Grounding DINO runs without `--sample` get a warning
+const GROUNDING_DINO_SAMPLE_HINT: usize = 1000;
+
+/// Options for a single audit run
+///
+/// Serialized, they key the resume cache, so fields that don't change
+/// per-image results are skipped.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditOptions {
+    /// Path to dataset (YOLO format)
+    pub dataset_path: PathBuf,
+    /// Single split to audit (e.g. `val`); defaults to the dataset's validation split
+    pub split: Option<String>,
+    /// Splits to audit together: `all` or a comma-separated list
+    pub splits: Option<String>,
+    /// Images to audit instead of a split; `dataset_path` should be their root
+    pub images: Vec<PathBuf>,
+    /// Class names for `images`, overriding those found at the dataset root
+    pub classes_file: Option<PathBuf>,
+    /// Detection method: `zero-shot`, `grounding-dino`, `vlm`, `yolo`/`byom`, `ensemble`
+    pub method: String,
+    /// Detector behind the `zero-shot` method
+    pub backend: ZeroShotBackend,
+    /// ONNX model path; implies the `yolo` method when set, except for Grounding DINO
+    pub model_path: Option<PathBuf>,
+    /// Output layout of the YOLO model; detected from its output shape if `None`
+    pub model_format: Option<ModelFormat>,
+    /// File naming the model's classes, overriding its ONNX metadata (YOLO only)
+    pub model_classes: Option<PathBuf>,
+    /// Detections of model classes the dataset has no counterpart for
+    pub unmapped_classes: UnmappedClasses,
+    /// File of class synonym groups replacing the built-in ones
+    pub class_synonyms: Option<PathBuf>,
+    pub confidence_threshold: f32,
+    pub iou_threshold: f32,
+    /// Score a class's prompt tokens need for Grounding DINO to assign it
+    pub text_threshold: f32,
+    /// Base URL of the OpenAI-compatible API used by the `vlm` method
+    pub vlm_endpoint: String,
+    /// Vision model the `vlm` method asks for
+    pub vlm_model: String,
+    pub nms_method: NmsMethod,
+    /// Whether NMS suppresses overlapping detections of other classes
+    pub nms_mode: NmsMode,
+    /// Device for ONNX inference; falls back to CPU if unavailable
+    #[serde(skip)]
+    pub device: Device,
+    /// Images per inference call (YOLO only; the model needs a dynamic batch axis above 1)
+    #[serde(skip)]
+    pub batch_size: usize,
+    /// Threads that read and decode images ahead of inference, so loading
+    /// overlaps with it; `None` loads each batch on its inference thread
+    #[serde(skip)]
+    pub io_workers: Option<usize>,
+    /// Laplacian variance below which an image is flagged as blurry (zero-shot only; 0 disables)
+    pub blur_threshold: f32,
+    /// Mean luma below which an image is flagged as too dark (zero-shot only; 0 disables)
+    pub brightness_min: f32,
+    /// Mean luma above which an image is flagged as overexposed (zero-shot only; 255 disables)
+    pub brightness_max: f32,
+    /// GT box area in pixels below which the box is flagged (zero-shot only; 0 disables)
+    pub min_box_pixels: f32,
+    /// GT box side in pixels below which the box is flagged (zero-shot only; 0 disables)
+    pub min_box_side: f32,
+    /// Fraction of the image a GT box may cover before it is flagged (zero-shot only; 1 disables)
+    pub max_box_fraction: f32,
+    /// Flag single-color images, and GT boxes on blurry or single-color regions (zero-shot only)
+    pub quality_checks: bool,
+    /// Pixel standard deviation below which an image or box region counts as a single color
+    pub min_color_std: f32,
+    /// Laplacian variance below which a GT box region is flagged as blurry
+    pub crop_blur_threshold: f32,
+    /// Flag images whose detection confidences are suspiciously uniform (YOLO only)
+    pub check_calibration: bool,
+    /// Flag pairs of near-identical images by perceptual hash
+    pub check_duplicates: bool,
+    /// Flag GT boxes whose stored pre-annotation confidence (sixth label
+    /// column) is below this
+    pub min_gt_confidence: Option<f32>,
+    /// Rescale label files that look like pixel coordinates by their image's
+    /// size instead of reporting them
+    pub assume_pixel_coords: bool,
+    /// Rotate images as their EXIF orientation tag says before checking them,
+    /// as labeling tools do; training pipelines often don't
+    pub apply_exif_rotation: bool,
+    /// Run the model on EXIF-tagged images in the other orientation too, to
+    /// tell which one the labels match; one more inference per tagged image
+    pub compare_exif_orientation: bool,
+    /// Flag GT boxes whose area or aspect ratio is more than this many MADs
+    /// from their class's median, on a log scale; 0, the default, also skips
+    /// gathering the statistics, which reads every label file
+    pub box_outlier_mads: f32,
+    /// Fail instead of warning when the dataset declares a class name under several ids
+    pub strict: bool,
+    /// Class names left out of validation: their labels are ignored and the
+    /// model never detects them
+    pub exclude_classes: Vec<String>,
+    /// Glob patterns an image must match to be audited (empty = all)
+    pub include_images: Vec<String>,
+    /// Glob patterns of images to leave out
+    pub exclude_images: Vec<String>,
+    /// Number of images to sample (0 = all)
+    pub sample: usize,
+    /// How sampled images are chosen
+    pub sample_strategy: SampleStrategy,
+    /// Images each class should appear in when sampling stratified; `None`
+    /// splits the sample evenly across the classes present
+    pub min_per_class: Option<usize>,
+    /// Random seed for sampling
+    pub seed: u64,
+    /// JSON Lines file of finished results, for resuming an interrupted audit
+    #[serde(skip)]
+    pub cache_file: Option<PathBuf>,
+    /// JSON Lines file each image result is written to as soon as it is final
+    #[serde(skip)]
+    pub stream_output: Option<PathBuf>,
+    /// Drop issues whose detector confidence is below this from the results
+    /// (applied after caching, like the baseline and suppressions)
+    #[serde(skip)]
+    pub min_confidence_report: Option<f32>,
+    /// Known issues, marked as baselined
+    #[serde(skip)]
+    pub baseline: Option<Baseline>,
+    /// Rules marking accepted issues as suppressed
+    #[serde(skip)]
+    pub suppressions: Option<Suppressions>,
+    /// Severity given to each issue type
+    pub severity_policy: SeverityPolicy,
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self {
+            dataset_path: PathBuf::from("."),
+            split: None,
+            splits: None,
+            images: Vec::new(),
+            classes_file: None,
+            method: "zero-shot".to_string(),
+            backend: ZeroShotBackend::Heuristic,
+            model_path: None,
+            model_format: None,
+            model_classes: None,
+            unmapped_classes: UnmappedClasses::Report,
+            class_synonyms: None,
+            confidence_threshold: 0.25,
+            iou_threshold: 0.5,
+            text_threshold: 0.25,
+            vlm_endpoint: "http://localhost:11434/v1".to_string(),
+            vlm_model: "llava".to_string(),
+            nms_method: NmsMethod::Hard,
+            nms_mode: NmsMode::PerClass,
+            device: Device::Cpu,
+            batch_size: 1,
+            io_workers: None,
+            blur_threshold: 100.0,
+            brightness_min: 20.0,
+            brightness_max: 235.0,
+            min_box_pixels: 16.0,
+            min_box_side: 2.0,
+            max_box_fraction: 0.95,
+            quality_checks: false,
+            min_color_std: 2.0,
+            crop_blur_threshold: 20.0,
+            check_calibration: false,
+            check_duplicates: false,
+            min_gt_confidence: None,
+            assume_pixel_coords: false,
+            apply_exif_rotation: false,
+            compare_exif_orientation: false,
+            box_outlier_mads: 0.0,
+            strict: false,
+            exclude_classes: Vec::new(),
+            include_images: Vec::new(),
+            exclude_images: Vec::new(),
+            sample: 0,
+            sample_strategy: SampleStrategy::Uniform,
+            min_per_class: Some(20),
+            seed: 42,
+            cache_file: None,
+            stream_output: None,
+            min_confidence_report: None,
+            baseline: None,
+            suppressions: None,
+            severity_policy: SeverityPolicy::default(),
+        }
+    }
+}
+
+impl AuditOptions {
+    /// Method actually used: a model path selects `yolo` unless `ensemble` or
+    /// Grounding DINO was asked for
+    pub fn effective_method(&self) -> String {
+        let grounding_dino = self.method == "grounding-dino"
+            || (self.method == "zero-shot" && self.backend == ZeroShotBackend::GroundingDino);
+        if grounding_dino {
+            "grounding-dino".to_string()
+        } else if self.model_path.is_some() && self.method != "ensemble" {
+            "yolo".to_string()
+        } else {
+            self.method.clone()
+        }
+    }
+
+    /// Everything that affects per-image results; a cache is only reused if this matches
+    fn cache_settings(&self) -> Result<String> {
+        let mut settings = serde_json::to_value(self)?;
+        settings["method"] = self.effective_method().into();
+        Ok(settings.to_string())
+    }
+}
+
+/// Receives progress events during an audit
+///
+/// All methods default to no-ops. `on_image_done` is called from worker
+/// threads, hence the `Sync` bound.
+pub trait AuditObserver: Sync {
+    /// A dataset (or one split of it) was loaded
+    fn on_dataset_loaded(&self, _split: Option<&str>, _dataset: &YoloDataset, _images: usize) {}
+
+    /// Images were randomly sampled
+    fn on_sampled(&self, _sample: usize, _seed: u64) {}
+
+    /// The include/exclude image patterns left this many images
+    fn on_filtered(&self, _images: usize) {}
+
+    /// The detector is about to be created
+    fn on_detector_init(&self, _method: &str) {}
+
+    /// The model's classes were matched to the dataset's
+    fn on_class_mapping(&self, _mapping: &ClassMapping) {}
+
+    /// Something degraded but the audit continues (e.g. CPU fallback)
+    fn on_warning(&self, _message: &str) {}
+
+    /// `_images` results were restored from the resume cache
+    fn on_resumed(&self, _images: usize) {}
+
+    /// Auditing of `_images` images is starting
+    fn on_start(&self, _images: usize) {}
+
+    /// One image finished (successfully or not)
+    fn on_image_done(&self, _image_path: &Path, _result: &Result<ImageResult>) {}
+
+    /// All images were processed, or the audit was cancelled
+    fn on_finish(&self, _result: &AuditResult) {}
+
+    /// Whether to stop auditing images not yet started, e.g. after Ctrl-C
+    ///
+    /// Checked from worker threads before each batch; images in flight are
+    /// finished and the result is marked partial.
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+}
+
+/// Observer that ignores every event
+pub struct NoopObserver;
+
+impl AuditObserver for NoopObserver {}
+
+/// Run an audit without progress reporting
+pub fn run_audit(options: AuditOptions) -> Result<AuditResult> {
+    run_audit_with(options, &NoopObserver)
+}
+
+/// Run an audit, reporting progress to `observer`
+pub fn run_audit_with(options: AuditOptions, observer: &dyn AuditObserver) -> Result<AuditResult> {
+    let start = Instant::now();
+
+    let datasets = load_datasets(&options)?;
+    // Class names come from the dataset root, so every split shares them
+    let dataset = &datasets[0].1;
+    let class_check = dataset.class_name_check();
+    if options.strict && !class_check.duplicates.is_empty() {
+        let names: Vec<String> = class_check
+            .duplicates
+            .iter()
+            .map(|(name, ids)| format!("'{}' ({:?})", name, ids))
+            .collect();
+        anyhow::bail!(
+            "Class names declared under several ids: {}",
+            names.join(", ")
+        );
+    }
+    for warning in class_check.warnings() {
+        observer.on_warning(&warning);
+    }
+
+    // Get images to process, tagged with the index of their split
+    let mut images: Vec<(usize, PathBuf)> = Vec::new();
+    let mut split_totals: Vec<usize> = Vec::new();
+    for (idx, (split, ds)) in datasets.iter().enumerate() {
+        let split_images = ds.get_images();
+        observer.on_dataset_loaded(split.as_deref(), ds, split_images.len());
+        split_totals.push(split_images.len());
+        images.extend(split_images.into_iter().map(|p| (idx, p)));
+    }
+    let total_images: usize = split_totals.iter().sum();
+
+    // First pass, for the outlier check only: box statistics over every label
+    // file, before filtering and sampling
+    let box_stats = if options.box_outlier_mads > 0.0 {
+        BoxStats::from_datasets(datasets.iter().map(|(_, ds)| ds))
+    } else {
+        BoxStats::default()
+    };
+
+    // Filter before sampling so the sample is drawn from the matching images
+    let filter = ImageFilter::new(&options.include_images, &options.exclude_images)?;
+    if !filter.is_empty() {
+        images.retain(|(idx, path)| filter.matches(&datasets[*idx].1.image_key(path)));
+        observer.on_filtered(images.len());
+    }
+
+    if options.sample > 0 && options.sample < images.len() {
+        let picked = match options.sample_strategy {
+            SampleStrategy::Uniform => {
+                sampling::uniform(images.len(), options.sample, options.seed)
+            }
+            SampleStrategy::Stratified => {
+                // Label files are cheap to read compared to running the detector
+                let classes: Vec<BTreeSet<i32>> = images
+                    .par_iter()
+                    .map(|(idx, path)| {
+                        datasets[*idx]
+                            .1
+                            .load_annotations(path)
+                            .iter()
+                            .map(|a| a.class_id)
+                            .collect()
+                    })
+                    .collect();
+                let min_per_class = options
+                    .min_per_class
+                    .unwrap_or_else(|| sampling::even_share(&classes, options.sample));
+                let (picked, shortfalls) =
+                    sampling::stratified(&classes, options.sample, min_per_class, options.seed);
+                if !shortfalls.is_empty() {
+                    let names: Vec<String> = shortfalls
+                        .iter()
+                        .map(|s| {
+                            format!("{} ({})", dataset.get_class_name(s.class_id), s.available)
+                        })
+                        .collect();
+                    observer.on_warning(&format!(
+                        "Fewer than {} images in the dataset for: {}",
+                        min_per_class,
+                        names.join(", ")
+                    ));
+                }
+                picked
+            }
+        };
+        images = picked.into_iter().map(|i| images[i].clone()).collect();
+        observer.on_sampled(options.sample, options.seed);
+    }
+
+    if options.effective_method() == "grounding-dino"
+        && options.sample == 0
+        && images.len() > GROUNDING_DINO_SAMPLE_HINT
+    {
+        observer.on_warning(&format!(
+            "Grounding DINO takes around a second per image on CPU; consider --sample to audit a subset of the {} images",
+            images.len()
+        ));
+    }
+
+    let auditor = create_auditor(&options, dataset, observer)?;
+
+    // Restore results of an interrupted run
+    let (cache, mut cached) = match &options.cache_file {
+        Some(path) => match options
+            .cache_settings()
+            .and_then(|settings| ResultCache::open(path, &settings))
+        {
+            Ok((cache, cached)) => (Some(cache), cached),
+            Err(e) => {
+                // A read-only dataset should not stop the audit
+                observer.on_warning(&format!("Resume cache disabled: {:#}", e));
+                (None, HashMap::new())
+            }
+        },
+        None => (None, HashMap::new()),
+    };
+    let mut cached_results = Vec::new();
+    let mut pending: Vec<&(usize, PathBuf)> = Vec::new();
+    for image in &images {
+        match cached.remove(&image.1) {
+            Some(result) => cached_results.push(result),
+            None => pending.push(image),
+        }
+    }
+    if !cached_results.is_empty() {
+        observer.on_resumed(cached_results.len());
+    }
+
+    // Report filtering and marking happen before results are streamed, so
+    // the stream agrees with the final result; the cache keeps raw results
+    let remaining_baseline = options
+        .baseline
+        .as_ref()
+        .map(|baseline| Mutex::new(baseline.remaining()));
+    let post_process = |issues: &mut Vec<Issue>| {
+        if let Some(min_confidence) = options.min_confidence_report {
+            issues.retain(|issue| issue.confidence.unwrap_or(1.0) >= min_confidence);
+        }
+        for issue in issues {
+            if let Some(remaining) = &remaining_baseline {
+                issue.baselined = remaining.lock().unwrap().take(issue);
+            }
+            if let Some(suppressions) = &options.suppressions {
+                issue.suppressed = suppressions.matches(issue);
+            }
+        }
+    };
+
+    // Near-duplicate issues are only known once every image is hashed, so
+    // with --check-duplicates results are streamed when merged instead
+    let stream = options
+        .stream_output
+        .as_deref()
+        .map(ResultStream::create)
+        .transpose()?;
+    let stream_early = stream.as_ref().filter(|_| !options.check_duplicates);
+    for result in &mut cached_results {
+        post_process(&mut result.issues);
+        if let Some(stream) = stream_early {
+            stream.send(result);
+        }
+    }
+
+    // Process images in parallel
+    observer.on_start(pending.len());
+
+    // Batches never mix splits, since each split has its own class names
+    let batch_size = options.batch_size.max(1);
+    let batches: Vec<(usize, Vec<&PathBuf>)> = (0..datasets.len())
+        .flat_map(|idx| {
+            let paths: Vec<&PathBuf> = pending
+                .iter()
+                .filter(|(i, _)| *i == idx)
+                .map(|(_, path)| path)
+                .collect();
+            paths
+                .chunks(batch_size)
+                .map(|chunk| (idx, chunk.to_vec()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    // Images skipped after cancellation, per dataset
+    let skipped: Vec<AtomicUsize> = datasets.iter().map(|_| AtomicUsize::new(0)).collect();
+
+    // Labels of one batch, with box outliers checked against the whole dataset
+    let load_batch_labels = |idx: usize, paths: &[&PathBuf]| -> BatchLabels {
+        let dataset = &datasets[idx].1;
+        paths
+            .iter()
+            .map(|path| {
+                let (annotations, mut issues) = load_labels(dataset, path, &options);
+                if !issues
+                    .iter()
+                    .any(|i| i.issue_type == IssueType::CoordinateFormat)
+                {
+                    issues.extend(box_stats.outlier_issues(
+                        &annotations,
+                        options.box_outlier_mads,
+                        &options.severity_policy,
+                    ));
+                }
+                (annotations, issues)
+            })
+            .collect()
+    };
+
+    // Audit one batch whose labels are loaded; images are decoded here
+    // unless `decoded` holds them already
+    let audit_loaded_batch = |idx: usize,
+                              paths: &[&PathBuf],
+                              labels: BatchLabels,
+                              decoded: Option<Vec<Result<LoadedImage>>>|
+     -> Vec<ImageResult> {
+        let (split, dataset) = &datasets[idx];
+        let (annotations, label_issues): (Vec<_>, Vec<_>) = labels.into_iter().unzip();
+        let batch: Vec<_> = paths
+            .iter()
+            .map(|path| path.as_path())
+            .zip(annotations)
+            .collect();
+        let started = Instant::now();
+        let batch_results = match decoded {
+            Some(images) => auditor.audit_decoded_batch(&batch, images, &dataset.class_names),
+            None => auditor.audit_batch(&batch, &dataset.class_names),
+        };
+        let elapsed_ms = started.elapsed().as_millis() / batch.len().max(1) as u128;
+        batch_results
+            .into_iter()
+            .zip(batch.iter().zip(label_issues))
+            .map(|(result, ((img_path, annotations), label_issues))| {
+                let finish = |r, label_issues| {
+                    finish_image_result(r, dataset, split.as_deref(), img_path, label_issues)
+                };
+                let result = result.map(|r| finish(r, label_issues.clone()));
+                match &result {
+                    Ok(r) => tracing::debug!(
+                        image = %img_path.display(),
+                        gt = r.gt_count,
+                        detections = r.detection_count,
+                        issues = r.issues.len(),
+                        elapsed_ms,
+                        "Audited image"
+                    ),
+                    Err(e) => tracing::debug!(
+                        image = %img_path.display(),
+                        error = %format!("{:#}", e),
+                        "Failed to audit image"
+                    ),
+                }
+                if let (Some(cache), Ok(r)) = (&cache, &result) {
+                    if let Err(e) = cache.append(img_path, r) {
+                        observer.on_warning(&format!("Failed to update cache: {}", e));
+                    }
+                }
+                observer.on_image_done(img_path, &result);
+
+                // Keep images the detector failed on as image errors
+                // rather than dropping them from the audit
+                let mut result = result.unwrap_or_else(|e| {
+                    let failed = auditor::image_error_result(
+                        String::new(),
+                        annotations,
+                        format!("{:#}", e),
+                        &options.severity_policy,
+                    );
+                    finish(failed, label_issues)
+                });
+                post_process(&mut result.issues);
+                if let Some(stream) = stream_early {
+                    stream.send(&result);
+                }
+                result
+            })
+            .collect()
+    };
+
+    // Duplicate images are hashed alongside the audit
+    let (results, mut duplicates) = rayon::join(
+        || match options.io_workers {
+            None => batches
+                .par_iter()
+                .flat_map_iter(|(idx, paths)| {
+                    if observer.is_cancelled() {
+                        skipped[*idx].fetch_add(paths.len(), Ordering::Relaxed);
+                        return Vec::new();
+                    }
+                    audit_loaded_batch(*idx, paths, load_batch_labels(*idx, paths), None)
+                })
+                .collect::<Vec<_>>(),
+            Some(io_workers) => {
+                // Reader threads load and decode batches into a bounded
+                // queue while the rayon pool runs inference on earlier ones
+                let io_workers = io_workers.max(1);
+                let next = AtomicUsize::new(0);
+                let (sender, receiver) = mpsc::sync_channel(2 * io_workers);
+                std::thread::scope(|scope| {
+                    for _ in 0..io_workers {
+                        let sender = sender.clone();
+                        let (next, batches, skipped, auditor) =
+                            (&next, &batches, &skipped, &auditor);
+                        let load_batch_labels = &load_batch_labels;
+                        scope.spawn(move || loop {
+                            let position = next.fetch_add(1, Ordering::Relaxed);
+                            let Some((idx, paths)) = batches.get(position) else {
+                                break;
+                            };
+                            if observer.is_cancelled() {
+                                skipped[*idx].fetch_add(paths.len(), Ordering::Relaxed);
+                                continue;
+                            }
+                            let labels = load_batch_labels(*idx, paths);
+                            let images: Vec<_> =
+                                paths.iter().map(|path| auditor.load_image(path)).collect();
+                            if sender.send((position, labels, images)).is_err() {
+                                break;
+                            }
+                        });
+                    }
+                    drop(sender);
+
+                    let mut done: Vec<(usize, Vec<ImageResult>)> = receiver
+                        .into_iter()
+                        .par_bridge()
+                        .map(|(position, labels, images)| {
+                            let (idx, paths) = &batches[position];
+                            let results = audit_loaded_batch(*idx, paths, labels, Some(images));
+                            (position, results)
+                        })
+                        .collect();
+                    // Batches finish out of order; keep the order of the serial audit
+                    done.sort_by_key(|(position, _)| *position);
+                    done.into_iter().flat_map(|(_, results)| results).collect()
+                })
+            }
+        },
+        || {
+            if options.check_duplicates {
+                duplicate_image_issues(&datasets, &images, &options.severity_policy)
+            } else {
+                HashMap::new()
+            }
+        },
+    );
+
+    let skipped: Vec<usize> = skipped.into_iter().map(AtomicUsize::into_inner).collect();
+    let images_skipped: usize = skipped.iter().sum();
+
+    // Build audit result
+    let mut audit_result = AuditResult::new(
+        options.dataset_path.to_string_lossy().to_string(),
+        auditor.method(),
+        options.confidence_threshold,
+        options.iou_threshold,
+        total_images,
+        images.len() - images_skipped,
+    );
+    audit_result.excluded_classes = options.exclude_classes.clone();
+    audit_result.box_stats = box_stats;
+    audit_result.partial = images_skipped > 0;
+    audit_result.images_skipped = images_skipped;
+
+    for (idx, (split, _)) in datasets.iter().enumerate() {
+        if let Some(name) = split {
+            let audited = images.iter().filter(|(i, _)| *i == idx).count() - skipped[idx];
+            audit_result.add_split(name.clone(), split_totals[idx], audited);
+        }
+    }
+
+    for mut result in cached_results.into_iter().chain(results) {
+        if let Some(mut issues) =
+            duplicates.remove(&(result.split.clone(), result.filename.clone()))
+        {
+            post_process(&mut issues);
+            result.issues.extend(issues);
+        }
+        if let Some(stream) = stream.as_ref().filter(|_| stream_early.is_none()) {
+            stream.send(&result);
+        }
+        audit_result.add_image_result(result);
+    }
+    for (split, ds) in &datasets {
+        let report = ds.consistency_check();
+        audit_result.missing_label_files += report.missing_labels.len();
+        let mut issues =
+            consistency_issues(ds, &report, split.as_deref(), &options.severity_policy);
+        post_process(&mut issues);
+        for issue in issues {
+            audit_result.add_dataset_issue(issue);
+        }
+    }
+    audit_result.finalize();
+
+    if let Some(stream) = stream {
+        stream.finish(&audit_result.summary, &audit_result.dataset_issues)?;
+    }
+
+    // The run completed, so there is nothing left to resume; an interrupted
+    // run keeps its cache for the next one
+    if let Some(cache) = cache.filter(|_| !audit_result.partial) {
+        cache.finish()?;
+    }
+
+    audit_result.elapsed_secs = start.elapsed().as_secs_f64();
+    observer.on_finish(&audit_result);
+
+    Ok(audit_result)
+}
+
+/// Load the split(s) selected by `options`, tagged with their split name
+///
+/// The name is `None` unless several splits are audited together.
+pub fn load_datasets(options: &AuditOptions) -> Result<Vec<(Option<String>, YoloDataset)>> {
+    if !options.images.is_empty() {
+        let dataset =
+            YoloDataset::from_image_files(&options.images, options.classes_file.as_deref())?;
+        return Ok(vec![(None, dataset)]);
+    }
+    Ok(match &options.splits {
+        Some(spec) => {
+            let mut multi = MultiSplitDataset::load(&options.dataset_path, spec)?;
+            multi
+                .split_names()
+                .into_iter()
+                .filter_map(|name| multi.splits.remove(&name).map(|ds| (Some(name), ds)))
+                .collect()
+        }
+        None => match &options.split {
+            Some(split) => vec![(None, YoloDataset::load_split(&options.dataset_path, split)?)],
+            None => vec![(None, YoloDataset::load(&options.dataset_path)?)],
+        },
+    })
+}
+
+/// Create an auditor running the detector selected by `options`
+pub fn create_auditor(
+    options: &AuditOptions,
+    dataset: &YoloDataset,
+    observer: &dyn AuditObserver,
+) -> Result<Auditor> {
+    // Get class names sorted by ID; the model's classes are mapped onto these
+    let mut dataset_class_names: Vec<String> = Vec::new();
+    let mut class_ids: Vec<i32> = dataset.class_names.keys().copied().collect();
+    class_ids.sort();
+    for id in class_ids {
+        dataset_class_names.push(dataset.class_names.get(&id).cloned().unwrap_or_default());
+    }
+    let load_yolo = |config: DetectorConfig| -> Result<YoloDetector> {
+        let detector = YoloDetector::new(config, dataset_class_names.clone())?;
+        if let Some(warning) = detector.device_warning() {
+            observer.on_warning(warning);
+        }
+        if let Some(warning) = detector.output_warning() {
+            observer.on_warning(warning);
+        }
+        observer.on_class_mapping(detector.class_mapping());
+        Ok(detector)
+    };
+
+    let effective_method = options.effective_method();
+    observer.on_detector_init(&effective_method);
+
+    let config = DetectorConfig {
+        confidence_threshold: options.confidence_threshold,
+        iou_threshold: options.iou_threshold,
+        model_path: options.model_path.clone(),
+        model_format: options.model_format,
+        nms_method: options.nms_method,
+        nms_mode: options.nms_mode,
+        device: options.device,
+        batch_size: options.batch_size,
+        blur_threshold: options.blur_threshold,
+        brightness_min: options.brightness_min,
+        brightness_max: options.brightness_max,
+        min_box_pixels: options.min_box_pixels,
+        min_box_side: options.min_box_side,
+        max_box_fraction: options.max_box_fraction,
+        quality_checks: options.quality_checks,
+        min_color_std: options.min_color_std,
+        crop_blur_threshold: options.crop_blur_threshold,
+        severity_policy: options.severity_policy.clone(),
+        check_confidence_calibration: options.check_calibration,
+        exclude_classes: options.exclude_classes.clone(),
+        text_threshold: options.text_threshold,
+        model_classes: options.model_classes.clone(),
+        unmapped_classes: options.unmapped_classes,
+        class_synonyms: options.class_synonyms.clone(),
+        vlm_endpoint: options.vlm_endpoint.clone(),
+        vlm_model: options.vlm_model.clone(),
+        apply_exif_rotation: options.apply_exif_rotation,
+        compare_exif_orientation: options.compare_exif_orientation,
+    };
+
+    // Label checks run alone for zero-shot, and alongside the model for ensemble
+    let (detector, label_checks): (Option<Box<dyn Detector + Sync>>, bool) =
+        match effective_method.as_str() {
+            "yolo" | "byom" => {
+                if config.model_path.is_none() {
+                    anyhow::bail!("YOLO/BYOM method requires --model path to ONNX model");
+                }
+                (Some(Box::new(load_yolo(config.clone())?)), false)
+            }
+            "zero-shot" => (None, true),
+            "grounding-dino" => {
+                let detector = GroundingDinoDetector::new(config.clone())?;
+                if let Some(warning) = detector.device_warning() {
+                    observer.on_warning(warning);
+                }
+                (Some(Box::new(detector)), false)
+            }
+            "ensemble" => match config.model_path {
+                Some(_) => (Some(Box::new(load_yolo(config.clone())?)), true),
+                None => (None, true),
+            },
+            "vlm" => (Some(Box::new(VlmDetector::new(config.clone())?)), false),
+            _ => anyhow::bail!(
+                "Unknown method: {}. Use: zero-shot, grounding-dino, vlm, yolo, ensemble",
+                options.method
+            ),
+        };
+    Auditor::new(config, detector, label_checks)
+}
+
+/// Audit a single image the same way `run_audit_with` does
+pub fn audit_image(
+    auditor: &Auditor,
+    dataset: &YoloDataset,
+    split: Option<&str>,
+    image_path: &Path,
+    options: &AuditOptions,
+) -> Result<ImageResult> {
+    let (annotations, label_issues) = load_labels(dataset, image_path, options);
+    let result = auditor.audit_image(image_path, &annotations, &dataset.class_names)?;
+    Ok(finish_image_result(
+        result,
+        dataset,
+        split,
+        image_path,
+        label_issues,
+    ))
+}
+
+/// Annotations of an image without excluded classes, and the issues of its label file
+fn load_labels(
+    dataset: &YoloDataset,
+    image_path: &Path,
+    options: &AuditOptions,
+) -> (Vec<Annotation>, Vec<Issue>) {
+    let (mut annotations, errors) = dataset.load_labels(image_path);
+    annotations.retain(|a| !options.exclude_classes.contains(&a.class_name));
+    let mut mistake = CoordinateMistake::detect(&annotations);
+    if mistake == Some(CoordinateMistake::Pixels) && options.assume_pixel_coords {
+        if let Ok((width, height)) = image::image_dimensions(image_path) {
+            dataset::normalize_pixel_coords(&mut annotations, width, height);
+            mistake = None;
+        }
+    }
+    let issues = label_issues(dataset, &annotations, errors, mistake, options);
+    (annotations, issues)
+}
+
+/// Near-duplicate image pairs, keyed by the split and name of the pair's first image
+///
+/// Each pair becomes one issue on its first image that names the other one,
+/// and says whether the two copies carry the same label classes.
+fn duplicate_image_issues(
+    datasets: &[(Option<String>, YoloDataset)],
+    images: &[(usize, PathBuf)],
+    policy: &SeverityPolicy,
+) -> HashMap<(Option<String>, String), Vec<Issue>> {
+    let hashes: Vec<Option<u64>> = images
+        .par_iter()
+        .map(|(_, path)| leakage::dhash_file(path).ok())
+        .collect();
+
+    let name = |i: usize| {
+        let (idx, path) = &images[i];
+        let (split, dataset) = &datasets[*idx];
+        (split.clone(), dataset.image_key(path))
+    };
+    let label_classes = |i: usize| {
+        let (idx, path) = &images[i];
+        let mut classes: Vec<i32> = datasets[*idx]
+            .1
+            .load_labels(path)
+            .0
+            .iter()
+            .map(|a| a.class_id)
+            .collect();
+        classes.sort_unstable();
+        classes
+    };
+
+    let mut issues: HashMap<(Option<String>, String), Vec<Issue>> = HashMap::new();
+    for (a, b, distance) in leakage::near_duplicates(&hashes, leakage::NEAR_DUPLICATE_DISTANCE) {
+        let (first, (other_split, other_key)) = (name(a), name(b));
+        let other = match other_split {
+            Some(split) => format!("{}/{}", split, other_key),
+            None => other_key,
+        };
+        let explanation = if label_classes(a) == label_classes(b) {
+            "Both copies have the same label classes; one may be redundant"
+        } else {
+            "The copies are labeled differently, so one of them is likely wrong"
+        };
+        issues.entry(first.clone()).or_default().push(
+            Issue::builder(
+                first.1,
+                policy.severity(&IssueType::DuplicateImage, None),
+                IssueType::DuplicateImage,
+            )
+            .description(format!(
+                "Near-duplicate of '{}' (dHash distance {})",
+                other, distance
+            ))
+            .explanation(explanation)
+            .build(),
+        );
+    }
+    issues
+}
+
+/// Dataset-level issues for label files and images that don't pair up
+///
+/// Covers the whole split, including images left out by filters or sampling.
+fn consistency_issues(
+    dataset: &YoloDataset,
+    report: &ConsistencyReport,
+    split: Option<&str>,
+    policy: &SeverityPolicy,
+) -> Vec<Issue> {
+    let relative = |path: &Path| {
+        let relative = path.strip_prefix(&dataset.path).unwrap_or(path);
+        relative.to_string_lossy().replace('\\', "/")
+    };
+    let name = |key: String| match split {
+        Some(split) => format!("{}/{}", split, key),
+        None => key,
+    };
+    let issue = |image: String, description: String, explanation: Option<&str>| {
+        let issue = Issue::builder(
+            image,
+            policy.severity(&IssueType::DatasetConsistency, None),
+            IssueType::DatasetConsistency,
+        )
+        .description(description);
+        match explanation {
+            Some(explanation) => issue.explanation(explanation),
+            None => issue,
+        }
+        .build()
+    };
+
+    let mut issues = Vec::new();
+    for label in &report.orphaned_labels {
+        issues.push(issue(
+            name(relative(label)),
+            format!("Label file '{}' has no image", relative(label)),
+            Some("Likely left behind after its image was deleted"),
+        ));
+    }
+    for image in &report.missing_labels {
+        issues.push(issue(
+            name(dataset.image_key(image)),
+            format!(
+                "No label file (expected '{}')",
+                relative(&dataset.get_label_path(image))
+            ),
+            Some("Trainers read this as an image without objects; add an empty label file if that is intended"),
+        ));
+    }
+    for image in &report.empty_labels {
+        issues.push(issue(
+            name(dataset.image_key(image)),
+            format!(
+                "Empty label file '{}'",
+                relative(&dataset.get_label_path(image))
+            ),
+            None,
+        ));
+    }
+    issues
+}
+
+/// Issues found in the label file itself, before any detector runs
+fn label_issues(
+    dataset: &YoloDataset,
+    annotations: &[Annotation],
+    label_errors: Vec<LabelError>,
+    coordinate_mistake: Option<CoordinateMistake>,
+    options: &AuditOptions,
+) -> Vec<Issue> {
+    let severity_policy = &options.severity_policy;
+    let mut issues: Vec<Issue> = label_errors
+        .into_iter()
+        .map(|error| {
+            Issue::builder(
+                String::new(),
+                severity_policy.severity(&IssueType::ParseError, None),
+                IssueType::ParseError,
+            )
+            .description(format!(
+                "Malformed label row {} '{}': {}",
+                error.line_num, error.content, error.reason
+            ))
+            .line_num(error.line_num)
+            .build()
+        })
+        .collect();
+
+    for ann in annotations {
+        if dataset.is_valid_class_id(ann.class_id) {
+            continue;
+        }
+        let valid = match dataset.class_id_range() {
+            Some((min, max)) => format!("valid ids: {}-{}", min, max),
+            None => "no classes declared".to_string(),
+        };
+        issues.push(
+            Issue::builder(
+                String::new(),
+                severity_policy.severity(&IssueType::InvalidClassId, None),
+                IssueType::InvalidClassId,
+            )
+            .description(format!(
+                "Class id {} on line {} is not declared ({})",
+                ann.class_id, ann.line_num, valid
+            ))
+            .gt_class(ann.class_name.clone())
+            .line_num(ann.line_num)
+            .build(),
+        );
+    }
+
+    if let Some(mistake) = coordinate_mistake {
+        let (description, explanation) = match mistake {
+            CoordinateMistake::Pixels => (
+                "Labels appear to be in pixel coordinates, not normalized",
+                "Most values exceed 1; divide by the image size, or rerun with --assume-pixel-coords",
+            ),
+            CoordinateMistake::Corners => (
+                "Labels appear to be x1 y1 x2 y2 corners, not x_center y_center width height",
+                "Every box's width and height exceed its center, and most boxes read that way extend past the image",
+            ),
+        };
+        issues.push(
+            Issue::builder(
+                String::new(),
+                severity_policy.severity(&IssueType::CoordinateFormat, None),
+                IssueType::CoordinateFormat,
+            )
+            .description(format!("{} ({} boxes)", description, annotations.len()))
+            .explanation(explanation)
+            .build(),
+        );
+    }
+
+    if let Some(floor) = options.min_gt_confidence {
+        for ann in annotations {
+            let Some(confidence) = ann.gt_confidence.filter(|c| *c < floor) else {
+                continue;
+            };
+            issues.push(
+                Issue::builder(
+                    String::new(),
+                    severity_policy.severity(&IssueType::SuspectPreAnnotation, Some(confidence)),
+                    IssueType::SuspectPreAnnotation,
+                )
+                .description(format!(
+                    "'{}' on line {} was stored with confidence {:.2} (below {:.2})",
+                    ann.class_name, ann.line_num, confidence, floor
+                ))
+                .gt_class(ann.class_name.clone())
+                .confidence(confidence)
+                .line_num(ann.line_num)
+                .explanation(
+                    "Likely a model prediction kept as ground truth without review".to_string(),
+                )
+                .build(),
+            );
+        }
+    }
+
+    issues
+}
+
+/// Add label file issues and the dataset-relative naming to a detector result
+fn finish_image_result(
+    mut result: ImageResult,
+    dataset: &YoloDataset,
+    split: Option<&str>,
+    image_path: &Path,
+    label_issues: Vec<Issue>,
+) -> ImageResult {
+    if label_issues
+        .iter()
+        .any(|i| i.issue_type == IssueType::CoordinateFormat)
+    {
+        // The boxes are meaningless as read, so one issue for the file
+        // replaces the detector's issues about each of them
+        result.issues.retain(|i| {
+            matches!(
+                i.issue_type,
+                IssueType::ImageQuality | IssueType::ImageError
+            )
+        });
+        result.detections = None;
+        result.match_ious.clear();
+        result.gt_centers.clear();
+    }
+    result.issues.extend(label_issues);
+    // Name images by their subpath so nested files stay distinct
+    let key = dataset.image_key(image_path);
+    for issue in &mut result.issues {
+        issue.image = key.clone();
+    }
+    result.filename = key;
+    result.split = split.map(str::to_string);
+    result.label_file = Some(
+        dataset
+            .get_label_path(image_path)
+            .to_string_lossy()
+            .to_string(),
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_settings_cover_result_options_only() {
+        let base = AuditOptions::default();
+        let settings = base.cache_settings().unwrap();
+
+        let changed = [
+            AuditOptions {
+                backend: ZeroShotBackend::GroundingDino,
+                ..base.clone()
+            },
+            AuditOptions {
+                vlm_endpoint: "http://other:8000/v1".to_string(),
+                ..base.clone()
+            },
+            AuditOptions {
+                check_duplicates: true,
+                ..base.clone()
+            },
+            AuditOptions {
+                strict: true,
+                ..base.clone()
+            },
+        ];
+        for options in &changed {
+            assert_ne!(options.cache_settings().unwrap(), settings);
+        }
+
+        let unchanged = AuditOptions {
+            batch_size: 8,
+            io_workers: Some(4),
+            min_confidence_report: Some(0.5),
+            ..base.clone()
+        };
+        assert_eq!(unchanged.cache_settings().unwrap(), settings);
+    }
+}
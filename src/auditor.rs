@@ -0,0 +1,1496 @@
+//! Turning detections into issues, shared by every detection method
+//!
+//! Detectors only produce boxes (see `Detector`). An `Auditor` loads each
+//! image, runs its detector if it has one, matches the detections against the
+//! GT labels and runs the label heuristics, grading every issue with the
+//! configured severity policy.
+
+use anyhow::{Context, Result};
+use image::metadata::Orientation;
+use image::{DynamicImage, GenericImageView, ImageDecoder, ImageReader};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::class_map::ClassSynonyms;
+use crate::detector::{Detector, DetectorConfig};
+use crate::metrics;
+use crate::models::{Annotation, Detection, ImageResult, Issue, IssueType, SuggestedFix};
+use crate::policy::SeverityPolicy;
+
+/// Detection confidences spread less than this are reported as poorly calibrated
+const CALIBRATION_MIN_STD: f32 = 0.02;
+
+/// Fewest detections in an image for their confidence spread to mean anything
+const CALIBRATION_MIN_DETECTIONS: usize = 3;
+
+/// Slack allowed past the image edges before a box counts as out of bounds
+const EDGE_TOLERANCE: f32 = 0.01;
+
+/// Same-class GT boxes overlapping more than this are reported as duplicates
+const DUPLICATE_IOU: f32 = 0.85;
+
+/// Same-class GT boxes overlapping at least this much (up to `DUPLICATE_IOU`) are reported as overlaps
+const OVERLAP_IOU: f32 = 0.5;
+
+/// Images smaller than this (on either side) are too small for a meaningful blur score
+const MIN_BLUR_CHECK_SIZE: u32 = 64;
+
+/// GT boxes smaller than this in pixels (on either side) are skipped by the region checks
+const MIN_CROP_CHECK_SIZE: u32 = 16;
+
+/// Confidence above which a detection's class or box is suggested as a fix
+const SUGGEST_MIN_CONFIDENCE: f32 = 0.85;
+
+/// IoU above which a confident detection's class is suggested for the GT box
+const SUGGEST_MIN_IOU: f32 = 0.7;
+
+/// Audits images against their labels with an optional detector
+///
+/// With label checks on, each label is also checked on its own: boxes out of
+/// bounds or of implausible size, same-class overlaps, blurry, dark or
+/// overexposed images and images without labels. When both run, an issue from the detector is
+/// dropped if the label checks already reported one of the same type on the
+/// same line.
+pub struct Auditor {
+    detector: Option<Box<dyn Detector + Sync>>,
+    label_checks: bool,
+    config: DetectorConfig,
+    synonyms: ClassSynonyms,
+}
+
+impl Auditor {
+    /// Label checks are always on without a detector, since nothing else
+    /// would be checked
+    pub fn new(
+        config: DetectorConfig,
+        detector: Option<Box<dyn Detector + Sync>>,
+        label_checks: bool,
+    ) -> Result<Self> {
+        let synonyms = ClassSynonyms::from_config(config.class_synonyms.as_deref())?;
+        Ok(Self {
+            label_checks: label_checks || detector.is_none(),
+            detector,
+            config,
+            synonyms,
+        })
+    }
+
+    /// Method recorded in reports: the detector's name, prefixed with
+    /// `ensemble:` when label checks run alongside it, or `zero-shot`
+    pub fn method(&self) -> String {
+        match &self.detector {
+            None => "zero-shot".to_string(),
+            Some(detector) if self.label_checks => format!("ensemble:{}", detector.name()),
+            Some(detector) => detector.name(),
+        }
+    }
+
+    /// Audit one image; an image that can't be loaded becomes an image error
+    pub fn audit_image(
+        &self,
+        image_path: &Path,
+        annotations: &[Annotation],
+        class_names: &HashMap<i32, String>,
+    ) -> Result<ImageResult> {
+        let filename = file_name(image_path);
+        let loaded = match self.load_image(image_path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                return Ok(image_error_result(
+                    filename,
+                    annotations,
+                    format!("{:#}", e),
+                    &self.config.severity_policy,
+                ))
+            }
+        };
+
+        let prompt_classes = self.prompt_classes(class_names);
+        let detections = match &self.detector {
+            Some(detector) => Some(detector.detect(&loaded.image, &prompt_classes)?),
+            None => None,
+        };
+        Ok(self.audit_loaded(filename, &loaded, annotations, detections, &prompt_classes))
+    }
+
+    /// Read and decode an image for `audit_decoded_batch`, rotated as its
+    /// EXIF orientation says if `apply_exif_rotation` is set
+    pub fn load_image(&self, path: &Path) -> Result<LoadedImage> {
+        let decode = || -> image::ImageResult<(DynamicImage, Orientation)> {
+            let mut decoder = ImageReader::open(path)?.into_decoder()?;
+            // An unreadable EXIF block is no reason to skip the image
+            let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+            Ok((DynamicImage::from_decoder(decoder)?, orientation))
+        };
+        let (mut image, orientation) = decode().context("Failed to load image")?;
+
+        let mut alternative = None;
+        let compare = self.config.compare_exif_orientation && self.detector.is_some();
+        if orientation != Orientation::NoTransforms && compare {
+            let mut rotated = image.clone();
+            rotated.apply_orientation(orientation);
+            alternative = Some(rotated);
+            if self.config.apply_exif_rotation {
+                std::mem::swap(&mut image, alternative.as_mut().unwrap());
+            }
+        } else if self.config.apply_exif_rotation {
+            image.apply_orientation(orientation);
+        }
+        Ok(LoadedImage {
+            image,
+            orientation,
+            alternative,
+        })
+    }
+
+    /// Audit several images of the same dataset, one result per image
+    ///
+    /// The images that load are handed to the detector together, so it can
+    /// batch its inference.
+    pub fn audit_batch(
+        &self,
+        batch: &[(&Path, Vec<Annotation>)],
+        class_names: &HashMap<i32, String>,
+    ) -> Vec<Result<ImageResult>> {
+        let loaded = batch
+            .iter()
+            .map(|(path, _)| self.load_image(path))
+            .collect();
+        self.audit_decoded_batch(batch, loaded, class_names)
+    }
+
+    /// `audit_batch` for images already decoded by `load_image`, in the same
+    /// order as `batch`
+    pub fn audit_decoded_batch(
+        &self,
+        batch: &[(&Path, Vec<Annotation>)],
+        loaded: Vec<Result<LoadedImage>>,
+        class_names: &HashMap<i32, String>,
+    ) -> Vec<Result<ImageResult>> {
+        let prompt_classes = self.prompt_classes(class_names);
+        let mut detections = match &self.detector {
+            Some(detector) => {
+                let images: Vec<DynamicImage> = loaded
+                    .iter()
+                    .filter_map(|img| img.as_ref().ok().map(|l| l.image.clone()))
+                    .collect();
+                detector.detect_batch(&images, &prompt_classes)
+            }
+            None => Vec::new(),
+        }
+        .into_iter();
+
+        batch
+            .iter()
+            .zip(loaded)
+            .map(|((path, annotations), image)| {
+                let filename = file_name(path);
+                let loaded = match image {
+                    Ok(loaded) => loaded,
+                    Err(e) => {
+                        return Ok(image_error_result(
+                            filename,
+                            annotations,
+                            format!("{:#}", e),
+                            &self.config.severity_policy,
+                        ))
+                    }
+                };
+                let detections = match &self.detector {
+                    Some(_) => Some(detections.next().unwrap_or_else(|| Ok(Vec::new()))?),
+                    None => None,
+                };
+                Ok(self.audit_loaded(filename, &loaded, annotations, detections, &prompt_classes))
+            })
+            .collect()
+    }
+
+    /// Dataset class names in id order, minus excluded ones, for detectors
+    /// prompted with the classes to look for
+    fn prompt_classes(&self, class_names: &HashMap<i32, String>) -> Vec<String> {
+        let mut ids: Vec<&i32> = class_names.keys().collect();
+        ids.sort();
+        ids.into_iter()
+            .map(|id| class_names[id].clone())
+            .filter(|name| !self.config.exclude_classes.contains(name))
+            .collect()
+    }
+
+    /// Result for a loaded image, with the detector's detections if it ran
+    fn audit_loaded(
+        &self,
+        filename: String,
+        loaded: &LoadedImage,
+        annotations: &[Annotation],
+        detections: Option<Vec<Detection>>,
+        prompt_classes: &[String],
+    ) -> ImageResult {
+        let orientation_issue = (loaded.orientation != Orientation::NoTransforms).then(|| {
+            self.orientation_issue(
+                &filename,
+                loaded,
+                annotations,
+                detections.as_deref(),
+                prompt_classes,
+            )
+        });
+
+        let mut results = Vec::new();
+        if self.label_checks {
+            results.push(self.check_labels(filename.clone(), &loaded.image, annotations));
+        }
+        if let Some(detections) = detections {
+            results.push(self.compare(filename.clone(), annotations, detections));
+        }
+        let mut result =
+            merge(results).unwrap_or_else(|| ImageResult::new(filename, annotations, 0));
+        if let Some(issue) = orientation_issue {
+            result.add_issue(issue);
+        }
+        result
+    }
+
+    /// Issue for an image whose file carries an EXIF orientation tag
+    ///
+    /// With a detector and `compare_exif_orientation`, the image is also run
+    /// in the other orientation; if the labels agree with the detections
+    /// better there, the boxes were drawn on the image the other way round.
+    /// That costs a second inference per tagged image.
+    fn orientation_issue(
+        &self,
+        filename: &str,
+        loaded: &LoadedImage,
+        annotations: &[Annotation],
+        detections: Option<&[Detection]>,
+        prompt_classes: &[String],
+    ) -> Issue {
+        let applied = self.config.apply_exif_rotation;
+        let exif = loaded.orientation.to_exif();
+        let (detector, alternative, detections) =
+            match (&self.detector, &loaded.alternative, detections) {
+                (Some(detector), Some(alternative), Some(detections))
+                    if !annotations.is_empty() =>
+                {
+                    (detector, alternative, detections)
+                }
+                _ => return self.plain_orientation_issue(filename, exif, applied),
+            };
+        let Ok(other) = detector.detect(alternative, prompt_classes) else {
+            return self.plain_orientation_issue(filename, exif, applied);
+        };
+
+        let agreeing = self.agreeing_boxes(annotations, detections);
+        let agreeing_other = self.agreeing_boxes(annotations, &other);
+        if agreeing_other <= agreeing {
+            return self.plain_orientation_issue(filename, exif, applied);
+        }
+        let other_way = if applied { "without" } else { "with" };
+        Issue::builder(
+            filename.to_string(),
+            self.config
+                .severity_policy
+                .severity(&IssueType::ExifOrientation, None),
+            IssueType::ExifOrientation,
+        )
+        .description(format!(
+            "Boxes match the image {} its EXIF rotation (orientation {}): {}/{} boxes agree with the model, against {}/{}",
+            other_way,
+            exif,
+            agreeing_other,
+            annotations.len(),
+            agreeing,
+            annotations.len()
+        ))
+        .explanation(format!(
+            "Rerun {} --apply-exif-rotation if most images are like this one, or relabel the image in the orientation your training pipeline uses",
+            other_way
+        ))
+        .build()
+    }
+
+    /// Orientation issue without evidence either way
+    fn plain_orientation_issue(&self, filename: &str, exif: u8, applied: bool) -> Issue {
+        let explanation = if applied {
+            "The image was rotated before checking (--apply-exif-rotation); boxes drawn on the stored pixels will be off"
+        } else {
+            "The image was checked as stored, without rotation; boxes drawn in a tool that applies the rotation will be off"
+        };
+        Issue::builder(
+            filename.to_string(),
+            self.config
+                .severity_policy
+                .severity(&IssueType::ExifOrientation, None),
+            IssueType::ExifOrientation,
+        )
+        .description(format!("EXIF orientation {} present; verify boxes", exif))
+        .explanation(explanation)
+        .build()
+    }
+
+    /// GT boxes that a detection of an equivalent class overlaps by at least
+    /// the IoU threshold
+    fn agreeing_boxes(&self, annotations: &[Annotation], detections: &[Detection]) -> usize {
+        annotations
+            .iter()
+            .filter(|ann| {
+                detections.iter().any(|det| {
+                    self.synonyms.equivalent(&det.class_name, &ann.class_name)
+                        && det.bbox.iou(&ann.bbox) >= self.config.iou_threshold
+                })
+            })
+            .count()
+    }
+
+    /// Match detections against GT annotations and record disagreements
+    ///
+    /// Detections of model classes the dataset lacks (see
+    /// `Detector::is_unmapped`) aren't matched; each is reported as an object
+    /// the dataset has no class for.
+    pub fn compare(
+        &self,
+        filename: String,
+        annotations: &[Annotation],
+        detections: Vec<Detection>,
+    ) -> ImageResult {
+        let (unmapped, detections): (Vec<_>, Vec<_>) = detections.into_iter().partition(|d| {
+            self.detector
+                .as_ref()
+                .is_some_and(|detector| detector.is_unmapped(&d.class_name))
+        });
+        let config = &self.config;
+        let synonyms = &self.synonyms;
+        let policy = &config.severity_policy;
+        let mut result = ImageResult::new(
+            filename.clone(),
+            annotations,
+            detections.len() + unmapped.len(),
+        );
+        result.detections = Some(metrics::score_detections(
+            annotations,
+            &detections,
+            config.iou_threshold,
+            |a, b| synonyms.equivalent(a, b),
+        ));
+
+        // Best GT match of each detection, if it overlaps one enough
+        let matches: Vec<Option<(usize, f32)>> = detections
+            .iter()
+            .map(|det| {
+                let mut best_iou = 0.0f32;
+                let mut best_gt_idx: Option<usize> = None;
+
+                for (idx, ann) in annotations.iter().enumerate() {
+                    let iou = det.bbox.iou(&ann.bbox);
+                    if iou > best_iou {
+                        best_iou = iou;
+                        best_gt_idx = Some(idx);
+                    }
+                }
+
+                tracing::debug!(
+                    image = %filename,
+                    detected = %det.class_name,
+                    confidence = det.confidence,
+                    gt = best_gt_idx
+                        .filter(|_| best_iou >= 0.3)
+                        .map(|idx| annotations[idx].class_name.as_str()),
+                    iou = best_iou,
+                    "Matched detection against GT"
+                );
+                best_gt_idx
+                    .filter(|_| best_iou >= 0.3)
+                    .map(|idx| (idx, best_iou))
+            })
+            .collect();
+
+        // Of several detections on one GT box only one is checked against it:
+        // one agreeing with the GT class if there is one, else the most confident
+        let mut matched_gt: Vec<Option<usize>> = vec![None; annotations.len()];
+        for (d, m) in matches.iter().enumerate() {
+            if let Some((gt_idx, _)) = *m {
+                let rank = |d: usize| {
+                    let det = &detections[d];
+                    (
+                        synonyms.equivalent(&det.class_name, &annotations[gt_idx].class_name),
+                        det.confidence,
+                    )
+                };
+                let better = match matched_gt[gt_idx] {
+                    Some(best) => rank(d) > rank(best),
+                    None => true,
+                };
+                if better {
+                    matched_gt[gt_idx] = Some(d);
+                }
+            }
+        }
+
+        // Check each detection against GT
+        for (d, det) in detections.iter().enumerate() {
+            let mut matched = matches[d];
+            if let Some(winner) = matched.and_then(|(gt_idx, _)| matched_gt[gt_idx]) {
+                if winner != d {
+                    // Another class on the same object (e.g. "truck" on a
+                    // "car") repeats the checked detection; anything else is
+                    // a second object the labels may lack
+                    let winner = &detections[winner];
+                    if winner.class_name != det.class_name
+                        && winner.bbox.iou(&det.bbox) >= config.iou_threshold
+                    {
+                        tracing::debug!(
+                            image = %filename,
+                            detected = %det.class_name,
+                            confidence = det.confidence,
+                            "Skipped second detection of a matched object"
+                        );
+                        continue;
+                    }
+                    matched = None;
+                }
+            }
+            if let Some((gt_idx, best_iou)) = matched {
+                let gt = &annotations[gt_idx];
+                result.match_ious.push(best_iou);
+
+                // Check for class mismatch
+                if !synonyms.equivalent(&det.class_name, &gt.class_name) {
+                    result.add_issue(
+                        Issue::builder(
+                            filename.clone(),
+                            policy.severity(&IssueType::ClassMismatch, Some(det.confidence)),
+                            IssueType::ClassMismatch,
+                        )
+                        .description(format!(
+                            "Model detects '{}' ({:.1}%), GT says '{}'",
+                            det.class_name,
+                            det.confidence * 100.0,
+                            gt.class_name
+                        ))
+                        .gt_class(gt.class_name.clone())
+                        .detected_class(det.class_name.clone())
+                        .confidence(det.confidence)
+                        .iou(best_iou)
+                        .line_num(gt.line_num)
+                        .suggested_fix(
+                            (det.confidence > SUGGEST_MIN_CONFIDENCE && best_iou > SUGGEST_MIN_IOU)
+                                .then(|| SuggestedFix::ChangeClass {
+                                    to: det.class_name.clone(),
+                                }),
+                        )
+                        .build(),
+                    );
+                } else if best_iou < config.iou_threshold {
+                    // Same class, but the boxes disagree on placement
+                    result.add_issue(
+                        Issue::builder(
+                            filename.clone(),
+                            policy.severity(&IssueType::Localization, Some(det.confidence)),
+                            IssueType::Localization,
+                        )
+                        .description(format!(
+                            "Model box for '{}' ({:.1}%) overlaps GT with IoU {:.2}",
+                            det.class_name,
+                            det.confidence * 100.0,
+                            best_iou
+                        ))
+                        .gt_class(gt.class_name.clone())
+                        .detected_class(det.class_name.clone())
+                        .confidence(det.confidence)
+                        .iou(best_iou)
+                        .line_num(gt.line_num)
+                        .explanation(format!(
+                            "IoU below matching threshold {:.2}",
+                            config.iou_threshold
+                        ))
+                        .build(),
+                    );
+                }
+            } else {
+                // Detection with no matching GT - possible missing label
+                result.add_issue(
+                    Issue::builder(
+                        filename.clone(),
+                        policy.severity(&IssueType::MissingLabel, Some(det.confidence)),
+                        IssueType::MissingLabel,
+                    )
+                    .description(format!(
+                        "Model detects '{}' ({:.1}%) with no matching GT",
+                        det.class_name,
+                        det.confidence * 100.0
+                    ))
+                    .detected_class(det.class_name.clone())
+                    .confidence(det.confidence)
+                    .suggested_fix((det.confidence > SUGGEST_MIN_CONFIDENCE).then(|| {
+                        SuggestedFix::AddBox {
+                            bbox: det.bbox.clone(),
+                            class: det.class_name.clone(),
+                        }
+                    }))
+                    .build(),
+                );
+            }
+        }
+
+        // Check for phantom GT (GT with no detection)
+        for (idx, ann) in annotations.iter().enumerate() {
+            if matched_gt[idx].is_none() {
+                result.add_issue(
+                    Issue::builder(
+                        filename.clone(),
+                        policy.severity(&IssueType::SpuriousLabel, None),
+                        IssueType::SpuriousLabel,
+                    )
+                    .description(format!(
+                        "GT has '{}' but model detects nothing there",
+                        ann.class_name
+                    ))
+                    .gt_class(ann.class_name.clone())
+                    .line_num(ann.line_num)
+                    .build(),
+                );
+            }
+        }
+
+        if config.check_confidence_calibration {
+            if let Some(std_dev) = confidence_std_dev(&detections) {
+                if std_dev < CALIBRATION_MIN_STD {
+                    result.add_issue(
+                        Issue::builder(
+                            filename.clone(),
+                            policy.severity(&IssueType::ImageQuality, None),
+                            IssueType::ImageQuality,
+                        )
+                        .description(format!(
+                            "{} detections have near-identical confidences (std {:.3})",
+                            detections.len(),
+                            std_dev
+                        ))
+                        .explanation(
+                            "Flat scores suggest a degenerate or out-of-distribution model output"
+                                .to_string(),
+                        )
+                        .build(),
+                    );
+                }
+            }
+        }
+
+        for det in unmapped {
+            result.add_issue(
+                Issue::builder(
+                    filename.clone(),
+                    policy.severity(&IssueType::MissingLabel, Some(det.confidence)),
+                    IssueType::MissingLabel,
+                )
+                .description(format!(
+                    "Model detects '{}' ({:.1}%), which has no dataset class",
+                    det.class_name,
+                    det.confidence * 100.0
+                ))
+                .detected_class(det.class_name)
+                .confidence(det.confidence)
+                .explanation(
+                    "Add the class to the dataset, or pass --unmapped-classes ignore".to_string(),
+                )
+                .build(),
+            );
+        }
+        result
+    }
+
+    /// Checks of the labels on their own, needing no detections
+    fn check_labels(
+        &self,
+        filename: String,
+        image: &DynamicImage,
+        annotations: &[Annotation],
+    ) -> ImageResult {
+        let policy = &self.config.severity_policy;
+        let mut result = ImageResult::new(filename.clone(), annotations, 0);
+        let (width, height) = image.dimensions();
+
+        // Validate each annotation
+        for ann in annotations {
+            let (x1, y1, x2, y2) = ann.bbox.to_xyxy();
+            let degenerate = ann.bbox.w <= 0.0 || ann.bbox.h <= 0.0;
+            let out_of_bounds = x1 < -EDGE_TOLERANCE
+                || y1 < -EDGE_TOLERANCE
+                || x2 > 1.0 + EDGE_TOLERANCE
+                || y2 > 1.0 + EDGE_TOLERANCE;
+
+            if degenerate || out_of_bounds {
+                let explanation = if degenerate {
+                    "Bounding box has non-positive width or height"
+                } else {
+                    "Bounding box extends outside the image"
+                };
+                result.add_issue(
+                    Issue::builder(
+                        filename.clone(),
+                        policy.severity(&IssueType::OutOfBounds, None),
+                        IssueType::OutOfBounds,
+                    )
+                    .description(format!(
+                        "Invalid bbox for '{}': x={:.3}, y={:.3}, w={:.3}, h={:.3}",
+                        ann.class_name, ann.bbox.x, ann.bbox.y, ann.bbox.w, ann.bbox.h
+                    ))
+                    .gt_class(ann.class_name.clone())
+                    .line_num(ann.line_num)
+                    .explanation(explanation)
+                    .build(),
+                );
+            } else if let Some(issue) = box_size_issue(&self.config, &filename, ann, width, height)
+            {
+                result.add_issue(issue);
+            }
+        }
+
+        // Check same-class GT boxes against each other
+        for (i, a) in annotations.iter().enumerate() {
+            for b in &annotations[i + 1..] {
+                if a.class_id != b.class_id {
+                    continue;
+                }
+                let iou = a.bbox.iou(&b.bbox);
+                let (issue_type, what) = if iou > DUPLICATE_IOU {
+                    (IssueType::DuplicateAnnotation, "Duplicate")
+                } else if iou >= OVERLAP_IOU {
+                    (IssueType::AnnotationOverlap, "Overlapping")
+                } else {
+                    continue;
+                };
+                // Of two near-identical boxes, either can go
+                let suggested_fix = (issue_type == IssueType::DuplicateAnnotation)
+                    .then_some(SuggestedFix::DeleteLine);
+                result.add_issue(
+                    Issue::builder(
+                        filename.clone(),
+                        policy.severity(&issue_type, None),
+                        issue_type,
+                    )
+                    .description(format!(
+                        "{} '{}' boxes on lines {} and {} (IoU {:.2})",
+                        what, a.class_name, a.line_num, b.line_num, iou
+                    ))
+                    .gt_class(a.class_name.clone())
+                    .iou(iou)
+                    .line_num(a.line_num)
+                    .other_line_num(b.line_num)
+                    .suggested_fix(suggested_fix)
+                    .build(),
+                );
+            }
+        }
+
+        // A single-color frame also reads as blurry and often as badly
+        // exposed, so it is reported on its own
+        let blank_frame = if self.config.quality_checks {
+            self.blank_frame_issue(&filename, image)
+        } else {
+            None
+        };
+        let blank = blank_frame.is_some();
+        if let Some(issue) = blank_frame {
+            result.add_issue(issue);
+        }
+
+        // Check for blur
+        let mut blurry = false;
+        if !blank && width >= MIN_BLUR_CHECK_SIZE && height >= MIN_BLUR_CHECK_SIZE {
+            let variance = laplacian_variance(image);
+            if variance < self.config.blur_threshold {
+                blurry = true;
+                result.add_issue(
+                    Issue::builder(
+                        filename.clone(),
+                        policy.severity(&IssueType::ImageQuality, None),
+                        IssueType::ImageQuality,
+                    )
+                    .description(format!(
+                        "Image looks blurry: Laplacian variance {:.1} (threshold {:.1})",
+                        variance, self.config.blur_threshold
+                    ))
+                    .explanation("Lower --blur-threshold if sharp images are flagged".to_string())
+                    .build(),
+                );
+            }
+        }
+
+        // Check for dark or overexposed images
+        let luma = mean_luma(image);
+        let exposure = if blank {
+            None
+        } else if luma < self.config.brightness_min {
+            Some(("too dark", "--brightness-min"))
+        } else if luma > self.config.brightness_max {
+            Some(("overexposed", "--brightness-max"))
+        } else {
+            None
+        };
+        if let Some((what, flag)) = exposure {
+            result.add_issue(Issue::builder(filename.clone(), policy.severity(&IssueType::ImageQuality, None), IssueType::ImageQuality).description(format!("Image looks {}: mean luma {:.1}", what, luma)).explanation(format!(
+                    "Images taken in poor light are often labeled unreliably; adjust {} if usable images are flagged",
+                    flag
+                )).build());
+        }
+
+        if self.config.quality_checks && !blank {
+            for issue in self.region_issues(&filename, image, annotations, blurry) {
+                result.add_issue(issue);
+            }
+        }
+
+        // Check for no annotations
+        if annotations.is_empty() && width > 100 && height > 100 {
+            result.add_issue(
+                Issue::builder(
+                    filename.clone(),
+                    policy.severity(&IssueType::SpuriousLabel, None),
+                    IssueType::SpuriousLabel,
+                )
+                .description("Image has no annotations")
+                .build(),
+            );
+        }
+
+        result
+    }
+
+    /// `--quality-checks` issue for a single-color frame
+    fn blank_frame_issue(&self, filename: &str, image: &DynamicImage) -> Option<Issue> {
+        let config = &self.config;
+        let std_dev = color_std_dev(image);
+        (std_dev < config.min_color_std).then(|| {
+            quality_issue(
+                config,
+                filename,
+                "Image is a single color".to_string(),
+                format!(
+                    "Pixel standard deviation {:.2} (threshold {:.2}); blank frames usually come from camera or capture failures",
+                    std_dev, config.min_color_std
+                ),
+                None,
+            )
+        })
+    }
+
+    /// `--quality-checks` issues for GT boxes on a blurry or single-color
+    /// region of an otherwise usable image
+    ///
+    /// Regions of an image already flagged as blurry aren't checked for blur.
+    fn region_issues(
+        &self,
+        filename: &str,
+        image: &DynamicImage,
+        annotations: &[Annotation],
+        image_blurry: bool,
+    ) -> Vec<Issue> {
+        let config = &self.config;
+        let issue = |description, explanation, ann| {
+            quality_issue(config, filename, description, explanation, Some(ann))
+        };
+        let (width, height) = image.dimensions();
+        let mut issues = Vec::new();
+        for ann in annotations {
+            let (x1, y1, x2, y2) = ann.bbox.to_xyxy();
+            let x = (x1.max(0.0) * width as f32) as u32;
+            let y = (y1.max(0.0) * height as f32) as u32;
+            let w = ((x2.min(1.0) * width as f32) as u32).saturating_sub(x);
+            let h = ((y2.min(1.0) * height as f32) as u32).saturating_sub(y);
+            if w < MIN_CROP_CHECK_SIZE || h < MIN_CROP_CHECK_SIZE {
+                continue;
+            }
+            let crop = image.crop_imm(x, y, w, h);
+
+            let std_dev = color_std_dev(&crop);
+            if std_dev < config.min_color_std {
+                issues.push(issue(
+                    format!("GT '{}' covers a single-color region", ann.class_name),
+                    format!(
+                        "Pixel standard deviation {:.2} in the box (threshold {:.2}); there is likely no object there",
+                        std_dev, config.min_color_std
+                    ),
+                    ann,
+                ));
+                continue;
+            }
+            if image_blurry {
+                continue;
+            }
+            let variance = laplacian_variance(&crop);
+            if variance < config.crop_blur_threshold {
+                issues.push(issue(
+                    format!("GT '{}' sits on a blurry region", ann.class_name),
+                    format!(
+                        "Laplacian variance {:.1} in the box (threshold {:.1}); lower --crop-blur-threshold if sharp objects are flagged",
+                        variance, config.crop_blur_threshold
+                    ),
+                    ann,
+                ));
+            }
+        }
+        issues
+    }
+}
+
+/// `ImageQuality` issue, about one GT box if `ann` is given
+fn quality_issue(
+    config: &DetectorConfig,
+    filename: &str,
+    description: String,
+    explanation: String,
+    ann: Option<&Annotation>,
+) -> Issue {
+    let issue = Issue::builder(
+        filename.to_string(),
+        config
+            .severity_policy
+            .severity(&IssueType::ImageQuality, None),
+        IssueType::ImageQuality,
+    )
+    .description(description)
+    .explanation(explanation);
+    match ann {
+        Some(ann) => issue
+            .gt_class(ann.class_name.clone())
+            .line_num(ann.line_num),
+        None => issue,
+    }
+    .build()
+}
+
+/// File name of an image, as results report it
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Merge results of separate checks on the same image
+///
+/// An issue is dropped when an earlier result already has one with the same
+/// image, type and line, so checks that overlap (e.g. out of bounds boxes)
+/// are reported once. Issues within one result are never merged.
+fn merge(results: Vec<ImageResult>) -> Option<ImageResult> {
+    let mut results = results.into_iter();
+    let mut merged = results.next()?;
+    for result in results {
+        merged.detection_count = merged.detection_count.max(result.detection_count);
+        if merged.detections.is_none() {
+            merged.detections = result.detections;
+        }
+        if merged.match_ious.is_empty() {
+            merged.match_ious = result.match_ious;
+        }
+        let seen: Vec<(String, IssueType, Option<usize>)> = merged
+            .issues
+            .iter()
+            .map(|i| (i.image.clone(), i.issue_type.clone(), i.line_num))
+            .collect();
+        let mut issues = result.issues;
+        issues.retain(|i| !seen.contains(&(i.image.clone(), i.issue_type.clone(), i.line_num)));
+        merged.issues.extend(issues);
+    }
+    Some(merged)
+}
+
+/// A decoded image and the EXIF orientation of its file
+pub struct LoadedImage {
+    /// Pixels the checks and the detector see
+    pub image: DynamicImage,
+    /// `NoTransforms` for files without an orientation tag
+    pub orientation: Orientation,
+    /// The image in the other orientation than `image`, kept only for
+    /// tagged files when a detector can compare the two
+    pub alternative: Option<DynamicImage>,
+}
+
+/// Result for an image that couldn't be audited, holding a single `ImageError` issue
+///
+/// Its labels are counted but not checked.
+pub fn image_error_result(
+    filename: String,
+    annotations: &[Annotation],
+    description: String,
+    policy: &SeverityPolicy,
+) -> ImageResult {
+    let mut result = ImageResult::new(filename.clone(), annotations, 0);
+    result.add_issue(
+        Issue::builder(
+            filename,
+            policy.severity(&IssueType::ImageError, None),
+            IssueType::ImageError,
+        )
+        .description(description)
+        .build(),
+    );
+    result
+}
+
+/// Standard deviation of detection confidences, if there are enough detections
+fn confidence_std_dev(detections: &[Detection]) -> Option<f32> {
+    if detections.len() < CALIBRATION_MIN_DETECTIONS {
+        return None;
+    }
+    let n = detections.len() as f32;
+    let mean = detections.iter().map(|d| d.confidence).sum::<f32>() / n;
+    let variance = detections
+        .iter()
+        .map(|d| (d.confidence - mean).powi(2))
+        .sum::<f32>()
+        / n;
+    Some(variance.sqrt())
+}
+
+/// Variance of the 3×3 Laplacian over the grayscale image
+///
+/// Sharp images have strong edges and a high variance; blurry ones a low
+/// variance. Border pixels are skipped.
+pub fn laplacian_variance(image: &DynamicImage) -> f32 {
+    let gray = image.to_luma8();
+    let (width, height) = gray.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+
+    let px = |x: u32, y: u32| gray.get_pixel(x, y)[0] as f64;
+    let mut sum = 0.0f64;
+    let mut sum_sq = 0.0f64;
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let lap = px(x - 1, y) + px(x + 1, y) + px(x, y - 1) + px(x, y + 1) - 4.0 * px(x, y);
+            sum += lap;
+            sum_sq += lap * lap;
+        }
+    }
+
+    let n = ((width - 2) * (height - 2)) as f64;
+    let mean = sum / n;
+    (sum_sq / n - mean * mean).max(0.0) as f32
+}
+
+/// Largest standard deviation of the red, green and blue channels
+///
+/// Near zero for an image of a single color, whatever the color.
+pub fn color_std_dev(image: &DynamicImage) -> f32 {
+    let rgb = image.to_rgb8();
+    let n = rgb.pixels().len() as f64;
+    if n == 0.0 {
+        return 0.0;
+    }
+
+    let mut sum = [0.0f64; 3];
+    let mut sum_sq = [0.0f64; 3];
+    for pixel in rgb.pixels() {
+        for (c, &value) in pixel.0.iter().enumerate() {
+            sum[c] += value as f64;
+            sum_sq[c] += value as f64 * value as f64;
+        }
+    }
+    sum.iter()
+        .zip(sum_sq)
+        .map(|(sum, sum_sq)| {
+            let mean = sum / n;
+            (sum_sq / n - mean * mean).max(0.0).sqrt()
+        })
+        .fold(0.0, f64::max) as f32
+}
+
+/// Mean brightness of the grayscale image, from 0 (black) to 255 (white)
+pub fn mean_luma(image: &DynamicImage) -> f32 {
+    let gray = image::imageops::grayscale(image);
+    let pixels = gray.as_raw();
+    if pixels.is_empty() {
+        return 0.0;
+    }
+    (pixels.iter().map(|&p| p as u64).sum::<u64>() as f64 / pixels.len() as f64) as f32
+}
+
+/// Issue for a GT box that is only a few pixels in a `width` x `height`
+/// image, or covers nearly all of it
+fn box_size_issue(
+    config: &DetectorConfig,
+    filename: &str,
+    ann: &Annotation,
+    width: u32,
+    height: u32,
+) -> Option<Issue> {
+    let (w, h) = (ann.bbox.w * width as f32, ann.bbox.h * height as f32);
+    let (description, explanation) =
+        if w * h < config.min_box_pixels || w.min(h) < config.min_box_side {
+            (
+                format!(
+                    "Tiny bbox for '{}': {:.1}x{:.1} px ({:.1} px²)",
+                    ann.class_name,
+                    w,
+                    h,
+                    w * h
+                ),
+                format!(
+                    "Boxes need an area of at least {} px² and sides of at least {} px",
+                    config.min_box_pixels, config.min_box_side
+                ),
+            )
+        } else if ann.bbox.area() > config.max_box_fraction {
+            (
+                format!(
+                    "Bbox for '{}' covers {:.0}% of the image: {:.0}x{:.0} px",
+                    ann.class_name,
+                    ann.bbox.area() * 100.0,
+                    w,
+                    h
+                ),
+                format!(
+                    "Boxes covering more than {:.0}% of the image are usually annotation slips",
+                    config.max_box_fraction * 100.0
+                ),
+            )
+        } else {
+            return None;
+        };
+
+    Some(
+        Issue::builder(
+            filename.to_string(),
+            config.severity_policy.severity(&IssueType::BoxSize, None),
+            IssueType::BoxSize,
+        )
+        .description(description)
+        .gt_class(ann.class_name.clone())
+        .line_num(ann.line_num)
+        .explanation(explanation)
+        .build(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::class_map::UnmappedClasses;
+    use crate::detector::{Device, NmsMethod, NmsMode};
+    use crate::models::{BoundingBox, IssueSeverity};
+
+    /// Detector settings at their defaults, with image quality and box size checks off
+    fn test_config() -> DetectorConfig {
+        DetectorConfig {
+            confidence_threshold: 0.25,
+            iou_threshold: 0.5,
+            model_path: None,
+            model_format: None,
+            nms_method: NmsMethod::Hard,
+            nms_mode: NmsMode::PerClass,
+            device: Device::Cpu,
+            batch_size: 1,
+            blur_threshold: 0.0,
+            brightness_min: 0.0,
+            brightness_max: 255.0,
+            min_box_pixels: 0.0,
+            min_box_side: 0.0,
+            max_box_fraction: 1.0,
+            quality_checks: false,
+            min_color_std: 0.0,
+            crop_blur_threshold: 0.0,
+            severity_policy: SeverityPolicy::default(),
+            check_confidence_calibration: false,
+            exclude_classes: Vec::new(),
+            text_threshold: 0.25,
+            model_classes: None,
+            unmapped_classes: UnmappedClasses::Report,
+            class_synonyms: None,
+            vlm_endpoint: String::new(),
+            vlm_model: String::new(),
+            apply_exif_rotation: false,
+            compare_exif_orientation: false,
+        }
+    }
+
+    /// Detector returning the same boxes for every image
+    struct FixedDetector(Vec<Detection>);
+
+    impl Detector for FixedDetector {
+        fn detect(&self, _image: &DynamicImage, _class_names: &[String]) -> Result<Vec<Detection>> {
+            Ok(self.0.clone())
+        }
+
+        fn name(&self) -> String {
+            "fixed".to_string()
+        }
+
+        fn is_unmapped(&self, class_name: &str) -> bool {
+            class_name == "traffic light"
+        }
+    }
+
+    fn sample_image() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/clean/images/val/sample.png")
+    }
+
+    /// A 40x20 JPEG whose EXIF orientation says to rotate it 90° clockwise
+    fn write_rotated_jpeg(path: &Path) {
+        use image::ImageEncoder;
+        let ifd = [0, 1, 0x01, 0x12, 0, 3, 0, 0, 0, 1, 0, 6, 0, 0, 0, 0, 0, 0];
+        let exif = [b"MM\0*\0\0\0\x08".as_slice(), &ifd].concat();
+        let mut encoder =
+            image::codecs::jpeg::JpegEncoder::new(std::fs::File::create(path).unwrap());
+        encoder.set_exif_metadata(exif).unwrap();
+        image::RgbImage::new(40, 20)
+            .write_with_encoder(encoder)
+            .unwrap();
+    }
+
+    #[test]
+    fn exif_orientation_is_flagged_and_applied_on_request() {
+        let path = std::env::temp_dir().join(format!("gt-audit-exif-{}.jpg", std::process::id()));
+        write_rotated_jpeg(&path);
+
+        let auditor = Auditor::new(test_config(), None, true).unwrap();
+        let loaded = auditor.load_image(&path).unwrap();
+        assert_eq!(loaded.orientation, Orientation::Rotate90);
+        assert_eq!(loaded.image.dimensions(), (40, 20));
+        let result = auditor.audit_image(&path, &[], &HashMap::new()).unwrap();
+        let issue = result
+            .issues
+            .iter()
+            .find(|i| i.issue_type == IssueType::ExifOrientation)
+            .unwrap();
+        assert_eq!(
+            issue.description,
+            "EXIF orientation 6 present; verify boxes"
+        );
+
+        let rotating = Auditor::new(
+            DetectorConfig {
+                apply_exif_rotation: true,
+                compare_exif_orientation: true,
+                ..test_config()
+            },
+            None,
+            true,
+        )
+        .unwrap();
+        let loaded = rotating.load_image(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.image.dimensions(), (20, 40));
+    }
+
+    #[test]
+    fn test_laplacian_variance() {
+        let flat =
+            DynamicImage::ImageLuma8(image::GrayImage::from_pixel(64, 64, image::Luma([128])));
+        assert_eq!(laplacian_variance(&flat), 0.0);
+
+        let checker = DynamicImage::ImageLuma8(image::GrayImage::from_fn(64, 64, |x, y| {
+            image::Luma([if (x + y) % 2 == 0 { 0 } else { 255 }])
+        }));
+        assert!(laplacian_variance(&checker) > 100.0);
+    }
+
+    #[test]
+    fn test_exposure_check() {
+        let auditor = Auditor::new(
+            DetectorConfig {
+                brightness_min: 20.0,
+                brightness_max: 235.0,
+                ..test_config()
+            },
+            None,
+            true,
+        )
+        .unwrap();
+        let solid = |luma: u8| {
+            DynamicImage::ImageLuma8(image::GrayImage::from_pixel(32, 32, image::Luma([luma])))
+        };
+        assert_eq!(mean_luma(&solid(128)), 128.0);
+
+        let gt = [Annotation::builder()
+            .class(0, "car")
+            .bbox(0.5, 0.5, 0.2, 0.2)
+            .build()];
+        let quality = |luma: u8| -> Vec<String> {
+            auditor
+                .check_labels("x.png".to_string(), &solid(luma), &gt)
+                .issues
+                .into_iter()
+                .filter(|i| i.issue_type == IssueType::ImageQuality)
+                .map(|i| i.description)
+                .collect()
+        };
+        assert_eq!(quality(5), ["Image looks too dark: mean luma 5.0"]);
+        assert_eq!(quality(250), ["Image looks overexposed: mean luma 250.0"]);
+        assert!(quality(128).is_empty());
+    }
+
+    #[test]
+    fn quality_checks_flag_blank_images_and_regions() {
+        let auditor = Auditor::new(
+            DetectorConfig {
+                quality_checks: true,
+                min_color_std: 2.0,
+                crop_blur_threshold: 20.0,
+                blur_threshold: 100.0,
+                brightness_min: 20.0,
+                ..test_config()
+            },
+            None,
+            true,
+        )
+        .unwrap();
+        let quality = |image: &DynamicImage, gt: &[Annotation]| -> Vec<(String, Option<usize>)> {
+            auditor
+                .check_labels("x.png".to_string(), image, gt)
+                .issues
+                .into_iter()
+                .filter(|i| i.issue_type == IssueType::ImageQuality)
+                .map(|i| (i.description, i.line_num))
+                .collect()
+        };
+        let gt = |x: f32, line_num: usize| {
+            Annotation::builder()
+                .class(0, "car")
+                .bbox(x, 0.5, 0.25, 0.75)
+                .line_num(line_num)
+                .build()
+        };
+
+        // Not also reported as blurry and too dark
+        let blank =
+            DynamicImage::ImageLuma8(image::GrayImage::from_pixel(64, 64, image::Luma([0])));
+        assert_eq!(
+            quality(&blank, &[gt(0.5, 1)]),
+            [("Image is a single color".to_string(), None)]
+        );
+
+        // A blurry image's boxes aren't flagged for blur one by one
+        let gradient = DynamicImage::ImageLuma8(image::GrayImage::from_fn(128, 64, |x, _| {
+            image::Luma([(x * 2) as u8])
+        }));
+        let issues = quality(&gradient, &[gt(0.5, 1)]);
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].0.starts_with("Image looks blurry"));
+
+        // Sharp checkerboard, flat gray and a smooth gradient side by side
+        let image = DynamicImage::ImageLuma8(image::GrayImage::from_fn(192, 64, |x, y| {
+            image::Luma([match x {
+                0..=63 => (x + y) as u8 % 2 * 255,
+                64..=127 => 128,
+                _ => ((x - 128) * 4) as u8,
+            }])
+        }));
+        assert_eq!(
+            quality(&image, &[gt(1.0 / 6.0, 1), gt(0.5, 2), gt(5.0 / 6.0, 3)]),
+            [
+                ("GT 'car' covers a single-color region".to_string(), Some(2)),
+                ("GT 'car' sits on a blurry region".to_string(), Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_confidence_std_dev() {
+        let det = |conf: f32| Detection {
+            class_name: "person".to_string(),
+            confidence: conf,
+            bbox: BoundingBox::new(0.5, 0.5, 0.2, 0.2),
+        };
+
+        let flat = [det(0.26), det(0.26), det(0.27)];
+        assert!(confidence_std_dev(&flat).unwrap() < CALIBRATION_MIN_STD);
+        let spread = [det(0.3), det(0.6), det(0.9)];
+        assert!(confidence_std_dev(&spread).unwrap() > CALIBRATION_MIN_STD);
+        assert_eq!(confidence_std_dev(&flat[..2]), None);
+    }
+
+    #[test]
+    fn test_merge_drops_duplicates_across_results() {
+        let issue = |issue_type: IssueType, line_num: Option<usize>| {
+            let mut issue = Issue::builder("a.jpg", IssueSeverity::High, issue_type).build();
+            issue.line_num = line_num;
+            issue
+        };
+        let mut heuristic = ImageResult::new("a.jpg".to_string(), &[], 0);
+        heuristic.add_issue(issue(IssueType::OutOfBounds, Some(2)));
+        let mut model = ImageResult::new("a.jpg".to_string(), &[], 3);
+        model.add_issue(issue(IssueType::OutOfBounds, Some(2)));
+        model.add_issue(issue(IssueType::MissingLabel, None));
+        model.add_issue(issue(IssueType::MissingLabel, None));
+
+        let merged = merge(vec![heuristic, model]).unwrap();
+        assert_eq!(merged.detection_count, 3);
+        // Both missing labels come from one result, so both are kept
+        assert_eq!(merged.issues.len(), 3);
+    }
+
+    #[test]
+    fn test_compare_detections() {
+        let ann = |line_num: usize, class_name: &str, x: f32| {
+            Annotation::builder()
+                .class(0, class_name)
+                .bbox(x, 0.5, 0.2, 0.2)
+                .line_num(line_num)
+                .build()
+        };
+        let annotations = [
+            ann(1, "car", 0.2),
+            ann(2, "person", 0.5),
+            ann(3, "dog", 0.8),
+        ];
+
+        let mut swapped = Detection::from_annotation(&annotations[1]);
+        swapped.class_name = "bicycle".to_string();
+        let mut stray = Detection::from_annotation(&annotations[0]);
+        stray.bbox.y = 0.1;
+        let detections = vec![Detection::from_annotation(&annotations[0]), swapped, stray];
+
+        let auditor = Auditor::new(test_config(), None, false).unwrap();
+        let result = auditor.compare("a.jpg".to_string(), &annotations, detections);
+        let lines = |t: IssueType| -> Vec<Option<usize>> {
+            result
+                .issues
+                .iter()
+                .filter(|i| i.issue_type == t)
+                .map(|i| i.line_num)
+                .collect()
+        };
+        assert_eq!(lines(IssueType::ClassMismatch), [Some(2)]);
+        assert_eq!(lines(IssueType::MissingLabel), [None]);
+        assert_eq!(lines(IssueType::SpuriousLabel), [Some(3)]);
+        assert_eq!(result.issues.len(), 3);
+    }
+
+    #[test]
+    fn test_one_issue_per_gt_box() {
+        let gt = [Annotation::builder()
+            .class(0, "car")
+            .bbox(0.5, 0.5, 0.4, 0.4)
+            .line_num(1)
+            .build()];
+        let det = |class_name: &str, confidence: f32| {
+            let mut det = Detection::from_annotation(&gt[0]);
+            det.class_name = class_name.to_string();
+            det.confidence = confidence;
+            det
+        };
+        let auditor = Auditor::new(test_config(), None, false).unwrap();
+        let compare = |detections| auditor.compare("a.jpg".to_string(), &gt, detections);
+
+        // The agreeing detection wins, even when less confident
+        let result = compare(vec![det("truck", 0.9), det("car", 0.7)]);
+        assert!(result.issues.is_empty());
+
+        // Otherwise only the most confident one is reported
+        let result = compare(vec![det("bus", 0.6), det("truck", 0.9)]);
+        assert_eq!(result.issues.len(), 1);
+        assert_eq!(result.issues[0].issue_type, IssueType::ClassMismatch);
+        assert_eq!(result.issues[0].detected_class.as_deref(), Some("truck"));
+        assert_eq!(result.detection_count, 2);
+    }
+
+    #[test]
+    fn test_second_object_on_a_gt_box_is_a_missing_label() {
+        let gt = [Annotation::builder()
+            .class(0, "person")
+            .bbox(0.5, 0.5, 0.2, 0.2)
+            .line_num(1)
+            .build()];
+        // A second person beside the labeled one, overlapping its box with IoU ~0.33
+        let mut neighbour = Detection::from_annotation(&gt[0]);
+        neighbour.bbox.x = 0.6;
+        let mut bicycle = neighbour.clone();
+        bicycle.class_name = "bicycle".to_string();
+        let auditor = Auditor::new(test_config(), None, false).unwrap();
+
+        for beside in [neighbour, bicycle] {
+            let detections = vec![Detection::from_annotation(&gt[0]), beside.clone()];
+            let result = auditor.compare("a.jpg".to_string(), &gt, detections);
+            assert_eq!(result.issues.len(), 1);
+            assert_eq!(result.issues[0].issue_type, IssueType::MissingLabel);
+            assert_eq!(result.issues[0].detected_class, Some(beside.class_name));
+        }
+    }
+
+    #[test]
+    fn test_detections_and_label_checks_combine() {
+        let annotations = [
+            Annotation::builder()
+                .class(0, "car")
+                .bbox(0.5, 0.5, 0.4, 0.4)
+                .line_num(1)
+                .build(),
+            Annotation::builder()
+                .class(0, "car")
+                .bbox(0.95, 0.5, 0.4, 0.4)
+                .line_num(2)
+                .build(),
+        ];
+        let mut light = Detection::from_annotation(&annotations[0]);
+        light.class_name = "traffic light".to_string();
+        let detections = vec![Detection::from_annotation(&annotations[0]), light];
+
+        let detector = Box::new(FixedDetector(detections));
+        let auditor = Auditor::new(test_config(), Some(detector), true).unwrap();
+        assert_eq!(auditor.method(), "ensemble:fixed");
+        let result = auditor
+            .audit_image(&sample_image(), &annotations, &HashMap::new())
+            .unwrap();
+
+        // Line 2 is out of bounds and unmatched; the light has no dataset class
+        let of = |t: IssueType| result.issues.iter().filter(|i| i.issue_type == t).count();
+        assert_eq!(of(IssueType::OutOfBounds), 1);
+        assert_eq!(of(IssueType::SpuriousLabel), 1);
+        assert_eq!(of(IssueType::MissingLabel), 1);
+        assert!(result
+            .issues
+            .iter()
+            .any(|i| i.description.contains("no dataset class")));
+        assert_eq!(result.detection_count, 2);
+        assert_eq!(result.issues.len(), 3);
+    }
+
+    #[test]
+    fn test_same_class_overlaps() {
+        let ann = |line_num: usize, x: f32| {
+            Annotation::builder()
+                .class(0, "person")
+                .bbox(x, 0.5, 0.2, 0.2)
+                .line_num(line_num)
+                .build()
+        };
+        let auditor = Auditor::new(test_config(), None, false).unwrap();
+
+        // Lines 1 and 2 are identical; line 3 overlaps both with IoU ~0.67
+        let annotations = [ann(1, 0.5), ann(2, 0.5), ann(3, 0.54)];
+        let result = auditor
+            .audit_image(&sample_image(), &annotations, &HashMap::new())
+            .unwrap();
+        let of = |t: IssueType| result.issues.iter().filter(|i| i.issue_type == t).count();
+        assert_eq!(of(IssueType::DuplicateAnnotation), 1);
+        assert_eq!(of(IssueType::AnnotationOverlap), 2);
+        assert!(result.issues.iter().all(|i| i.other_line_num.is_some()));
+    }
+
+    #[test]
+    fn test_box_size_checks() {
+        let config = DetectorConfig {
+            min_box_pixels: 16.0,
+            min_box_side: 2.0,
+            max_box_fraction: 0.95,
+            ..test_config()
+        };
+        let ann = |w: f32, h: f32| Annotation::builder().bbox(0.5, 0.5, w, h).build();
+        let check = |ann: &Annotation| box_size_issue(&config, "a.jpg", ann, 100, 100);
+
+        // 10x10 px is fine; 3x3 px and 20x1 px are tiny; 98x98 px covers the image
+        assert!(check(&ann(0.1, 0.1)).is_none());
+        let tiny = check(&ann(0.03, 0.03)).unwrap();
+        assert_eq!(tiny.issue_type, IssueType::BoxSize);
+        assert_eq!(tiny.severity, IssueSeverity::Medium);
+        assert!(tiny.description.contains("3.0x3.0 px"));
+        assert!(check(&ann(0.2, 0.01)).is_some());
+        assert!(check(&ann(0.98, 0.98)).unwrap().description.contains("96%"));
+
+        // Defaults in test_config turn every check off
+        let off = |ann: &Annotation| box_size_issue(&test_config(), "a.jpg", ann, 100, 100);
+        assert!(off(&ann(0.01, 0.01)).is_none());
+        assert!(off(&ann(1.0, 1.0)).is_none());
+    }
+}
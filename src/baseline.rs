@@ -0,0 +1,152 @@
+//! Baselines of known issues, so CI only fails on new ones
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::models::{AuditResult, Issue};
+
+/// Set of accepted issues, stored as fingerprints
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baseline {
+    pub generator_version: String,
+    pub created_at: String,
+    /// Sorted issue fingerprints; repeated when identical issues occur more than once
+    pub fingerprints: Vec<String>,
+}
+
+impl Baseline {
+    /// Baseline accepting every issue in `result`
+    pub fn from_result(result: &AuditResult) -> Self {
+        let mut fingerprints: Vec<String> = result.all_issues().map(fingerprint).collect();
+        fingerprints.sort();
+
+        Self {
+            generator_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            fingerprints,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read baseline: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse baseline: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write baseline: {}", path.display()))
+    }
+
+    /// Mark the issues of `result` that this baseline covers
+    ///
+    /// Each fingerprint covers one issue, so if an image gains a second
+    /// identical issue, the extra one is still reported as new.
+    pub fn apply(&self, result: &mut AuditResult) {
+        let mut remaining = self.remaining();
+        result.apply_baseline(|issue| remaining.take(issue));
+    }
+
+    /// Fingerprints not yet matched, for marking issues as they are found
+    pub fn remaining(&self) -> RemainingFingerprints<'_> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for fp in &self.fingerprints {
+            *counts.entry(fp.as_str()).or_insert(0) += 1;
+        }
+        RemainingFingerprints { counts }
+    }
+}
+
+/// Baseline fingerprints each still covering one more issue
+pub struct RemainingFingerprints<'a> {
+    counts: HashMap<&'a str, usize>,
+}
+
+impl RemainingFingerprints<'_> {
+    /// Whether the baseline covers `issue`, using up its fingerprint if so
+    pub fn take(&mut self, issue: &Issue) -> bool {
+        match self.counts.get_mut(fingerprint(issue).as_str()) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Stable identity of an issue across runs
+///
+/// Built from the image path without extension, issue type, GT line and the
+/// classes involved. Confidence and IoU are left out so that small numeric
+/// differences between runs do not make a known issue look new.
+pub fn fingerprint(issue: &Issue) -> String {
+    let stem = match issue.image.rsplit_once('.') {
+        Some((stem, ext)) if !ext.contains('/') => stem,
+        _ => issue.image.as_str(),
+    };
+    let key = format!(
+        "{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}",
+        stem,
+        issue.issue_type,
+        issue.line_num.map(|l| l.to_string()).unwrap_or_default(),
+        issue.gt_class.as_deref().unwrap_or_default(),
+        issue.detected_class.as_deref().unwrap_or_default()
+    );
+    hex::encode(&Sha256::digest(key.as_bytes())[..16])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ImageResult, IssueSeverity, IssueType};
+
+    fn issue(image: &str, confidence: f32) -> Issue {
+        Issue::builder(image, IssueSeverity::High, IssueType::ClassMismatch)
+            .description(format!("confidence {}", confidence))
+            .gt_class("car")
+            .detected_class("truck")
+            .confidence(confidence)
+            .iou(0.8)
+            .line_num(3)
+            .build()
+    }
+
+    fn result_with(issues: Vec<Issue>) -> AuditResult {
+        let mut result = AuditResult::new("d".into(), "yolo".into(), 0.25, 0.5, 1, 1);
+        let mut image = ImageResult::new("a.jpg".to_string(), &[], 0);
+        for issue in issues {
+            image.add_issue(issue);
+        }
+        result.add_image_result(image);
+        result
+    }
+
+    #[test]
+    fn fingerprint_ignores_confidence_and_extension() {
+        assert_eq!(
+            fingerprint(&issue("a.jpg", 0.91)),
+            fingerprint(&issue("a.png", 0.87))
+        );
+        assert_ne!(
+            fingerprint(&issue("a.jpg", 0.91)),
+            fingerprint(&issue("b.jpg", 0.91))
+        );
+    }
+
+    #[test]
+    fn apply_only_covers_known_issues() {
+        let baseline = Baseline::from_result(&result_with(vec![issue("a.jpg", 0.9)]));
+
+        let mut result = result_with(vec![issue("a.jpg", 0.8), issue("a.jpg", 0.7)]);
+        baseline.apply(&mut result);
+
+        assert_eq!(result.high_count(), 2);
+        assert_eq!(result.new_high_count(), 1);
+        assert_eq!(result.summary.baselined_issues, 1);
+    }
+}
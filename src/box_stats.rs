@@ -0,0 +1,269 @@
+//! Per-class GT box size and shape statistics, and the boxes far outside them
+//!
+//! A "license plate" box covering half the image is almost certainly wrong,
+//! even if no detector runs. Statistics are gathered over every label file
+//! first, then each box's area and aspect ratio are compared with its class.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::dataset::{CoordinateMistake, YoloDataset};
+use crate::models::{Annotation, Issue, IssueSeverity, IssueType};
+use crate::policy::SeverityPolicy;
+
+/// Boxes a class needs before its outliers are flagged
+pub const MIN_CLASS_BOXES: usize = 20;
+
+/// Smallest spread assumed for a class, so near-identical boxes don't make
+/// every small difference an outlier (about 10% either way)
+const MIN_LOG_MAD: f32 = 0.1;
+
+/// Median and spread of one box measure over a class
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Spread {
+    pub median: f32,
+    pub p5: f32,
+    pub p95: f32,
+    /// Median absolute deviation of the natural log of the values, which
+    /// suits sizes that vary by factors rather than offsets
+    pub log_mad: f32,
+}
+
+impl Spread {
+    /// `None` when there are no positive, finite values
+    fn new(mut values: Vec<f32>) -> Option<Self> {
+        values.retain(|v| v.is_finite() && *v > 0.0);
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f32::total_cmp);
+        let median = percentile(&values, 0.5);
+        let mut deviations: Vec<f32> = values
+            .iter()
+            .map(|v| (v.ln() - median.ln()).abs())
+            .collect();
+        deviations.sort_by(f32::total_cmp);
+        Some(Self {
+            median,
+            p5: percentile(&values, 0.05),
+            p95: percentile(&values, 0.95),
+            log_mad: percentile(&deviations, 0.5),
+        })
+    }
+
+    /// Distance of `value` from the median, in MADs on the log scale
+    pub fn deviation(&self, value: f32) -> f32 {
+        (value.ln() - self.median.ln()).abs() / self.log_mad.max(MIN_LOG_MAD)
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty `values`
+fn percentile(values: &[f32], p: f32) -> f32 {
+    values[((values.len() - 1) as f32 * p).round() as usize]
+}
+
+/// Box statistics of one class, in normalized image coordinates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassBoxStats {
+    pub count: usize,
+    /// Box area as a fraction of the image
+    pub area: Spread,
+    /// Width over height
+    pub aspect_ratio: Spread,
+}
+
+/// Box statistics of every class, keyed by class name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct BoxStats {
+    pub classes: BTreeMap<String, ClassBoxStats>,
+}
+
+impl BoxStats {
+    /// Statistics over `annotations`; boxes without area are left out
+    pub fn from_annotations<'a>(annotations: impl IntoIterator<Item = &'a Annotation>) -> Self {
+        let mut boxes: BTreeMap<&str, (Vec<f32>, Vec<f32>)> = BTreeMap::new();
+        for ann in annotations {
+            if ann.bbox.w > 0.0 && ann.bbox.h > 0.0 {
+                let (areas, aspects) = boxes.entry(&ann.class_name).or_default();
+                areas.push(ann.bbox.area());
+                aspects.push(ann.bbox.aspect_ratio());
+            }
+        }
+
+        let classes = boxes
+            .into_iter()
+            .filter_map(|(class_name, (areas, aspects))| {
+                let count = areas.len();
+                Some((
+                    class_name.to_string(),
+                    ClassBoxStats {
+                        count,
+                        area: Spread::new(areas)?,
+                        aspect_ratio: Spread::new(aspects)?,
+                    },
+                ))
+            })
+            .collect();
+        Self { classes }
+    }
+
+    /// Statistics over every label file of `datasets`, pooled by class name
+    ///
+    /// Files that look like pixel or corner coordinates are skipped, since
+    /// their boxes would skew every class they contain.
+    pub fn from_datasets<'a>(datasets: impl IntoIterator<Item = &'a YoloDataset>) -> Self {
+        let annotations: Vec<Annotation> = datasets
+            .into_iter()
+            .flat_map(|dataset| {
+                dataset
+                    .get_images()
+                    .par_iter()
+                    .flat_map_iter(|image| {
+                        let annotations = dataset.load_annotations(image);
+                        match CoordinateMistake::detect(&annotations) {
+                            Some(_) => Vec::new(),
+                            None => annotations,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        Self::from_annotations(&annotations)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.classes.is_empty()
+    }
+
+    /// Localization issues for boxes whose area or aspect ratio is more than
+    /// `max_mads` MADs from their class's median (0 disables)
+    ///
+    /// Classes with fewer than `MIN_CLASS_BOXES` boxes are not checked. Boxes
+    /// beyond twice the limit are Medium rather than Low, unless the severity
+    /// policy has its own rule for localization issues.
+    pub fn outlier_issues(
+        &self,
+        annotations: &[Annotation],
+        max_mads: f32,
+        policy: &SeverityPolicy,
+    ) -> Vec<Issue> {
+        if max_mads <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut issues = Vec::new();
+        for ann in annotations {
+            let Some(stats) = self
+                .classes
+                .get(&ann.class_name)
+                .filter(|s| s.count >= MIN_CLASS_BOXES)
+            else {
+                continue;
+            };
+            if !(ann.bbox.w > 0.0 && ann.bbox.h > 0.0) {
+                continue;
+            }
+
+            let area = ann.bbox.area();
+            let aspect = ann.bbox.aspect_ratio();
+            let (area_dev, aspect_dev) = (
+                stats.area.deviation(area),
+                stats.aspect_ratio.deviation(aspect),
+            );
+            let (deviation, description, spread) = if area_dev >= aspect_dev {
+                let size = if area > stats.area.median {
+                    "large"
+                } else {
+                    "small"
+                };
+                (
+                    area_dev,
+                    format!(
+                        "Unusually {} '{}' box: area {:.3} vs class median {:.3}",
+                        size, ann.class_name, area, stats.area.median
+                    ),
+                    &stats.area,
+                )
+            } else {
+                (
+                    aspect_dev,
+                    format!(
+                        "Unusual shape for a '{}' box: aspect ratio {:.2} vs class median {:.2}",
+                        ann.class_name, aspect, stats.aspect_ratio.median
+                    ),
+                    &stats.aspect_ratio,
+                )
+            };
+            if deviation <= max_mads {
+                continue;
+            }
+
+            let severity = if policy.rules.contains_key("localization") {
+                policy.severity(&IssueType::Localization, None)
+            } else if deviation > 2.0 * max_mads {
+                IssueSeverity::Medium
+            } else {
+                IssueSeverity::Low
+            };
+            issues.push(
+                Issue::builder(String::new(), severity, IssueType::Localization)
+                    .description(description)
+                    .gt_class(ann.class_name.clone())
+                    .line_num(ann.line_num)
+                    .explanation(format!(
+                        "{:.1} MADs from the class median on a log scale; 90% of its {} boxes lie within {:.3}-{:.3}",
+                        deviation, stats.count, spread.p5, spread.p95
+                    ))
+                    .build(),
+            );
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plate(w: f32, h: f32) -> Annotation {
+        Annotation::builder()
+            .class(0, "plate")
+            .bbox(0.5, 0.5, w, h)
+            .build()
+    }
+
+    #[test]
+    fn boxes_far_from_their_class_are_flagged() {
+        let mut annotations: Vec<Annotation> = (0..30)
+            .map(|i| plate(0.2 + 0.002 * i as f32, 0.05 + 0.0005 * i as f32))
+            .collect();
+        let stats = BoxStats::from_annotations(&annotations);
+        let plates = &stats.classes["plate"];
+        assert_eq!(plates.count, 30);
+        assert!((plates.area.median - 0.23 * 0.0575).abs() < 1e-3);
+        assert!(plates.area.p5 < plates.area.median && plates.area.median < plates.area.p95);
+
+        let policy = SeverityPolicy::default();
+        assert!(stats.outlier_issues(&annotations, 6.0, &policy).is_empty());
+
+        // Half the image, and a tall box for a wide class
+        annotations = vec![plate(0.7, 0.7), plate(0.05, 0.2)];
+        let issues = stats.outlier_issues(&annotations, 6.0, &policy);
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0]
+            .description
+            .contains("Unusually large 'plate' box: area 0.490"));
+        assert_eq!(issues[0].severity, IssueSeverity::Medium);
+        assert!(issues[1].description.contains("aspect ratio 0.25"));
+        assert!(issues
+            .iter()
+            .all(|i| i.issue_type == IssueType::Localization));
+
+        // Off, or too few boxes to judge
+        assert!(stats.outlier_issues(&annotations, 0.0, &policy).is_empty());
+        let few = BoxStats::from_annotations(&annotations[..1]);
+        assert!(few.outlier_issues(&annotations, 6.0, &policy).is_empty());
+    }
+}
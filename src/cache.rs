@@ -0,0 +1,160 @@
+//! Resumable audits: per-image results appended to a JSON Lines file
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::metrics::ScoredDetection;
+use crate::models::ImageResult;
+
+/// Cache file created in the dataset directory when no path is given
+pub const DEFAULT_CACHE_FILE: &str = ".gt-audit-cache.jsonl";
+
+/// First line of a cache file: the settings the cached results were produced with
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheHeader {
+    settings: String,
+}
+
+/// One audited image
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    path: PathBuf,
+    /// Not part of the serialized `ImageResult`, but needed for per-class stats
+    gt_classes: Vec<String>,
+    #[serde(default)]
+    gt_centers: Vec<(f32, f32)>,
+    #[serde(default)]
+    detections: Option<Vec<ScoredDetection>>,
+    #[serde(default)]
+    match_ious: Vec<f32>,
+    result: ImageResult,
+}
+
+/// Append-only store of finished image results
+pub struct ResultCache {
+    path: PathBuf,
+    writer: Mutex<File>,
+}
+
+impl ResultCache {
+    /// Open or create the cache at `path`, returning results already in it
+    ///
+    /// A cache written with different `settings` is discarded, as is a
+    /// truncated last line left behind by a crash.
+    pub fn open(path: &Path, settings: &str) -> Result<(Self, HashMap<PathBuf, ImageResult>)> {
+        let mut cached = HashMap::new();
+
+        if let Ok(file) = File::open(path) {
+            let mut lines = BufReader::new(file).lines();
+            let header: Option<CacheHeader> = lines
+                .next()
+                .and_then(|l| l.ok())
+                .and_then(|l| serde_json::from_str(&l).ok());
+
+            if header.is_some_and(|h| h.settings == settings) {
+                for line in lines.map_while(|l| l.ok()) {
+                    if let Ok(entry) = serde_json::from_str::<CacheEntry>(&line) {
+                        let mut result = entry.result;
+                        result.gt_classes = entry.gt_classes;
+                        result.gt_centers = entry.gt_centers;
+                        result.detections = entry.detections;
+                        result.match_ious = entry.match_ious;
+                        cached.insert(entry.path, result);
+                    }
+                }
+            }
+        }
+
+        // Rewrite the file so a stale or partly written cache does not linger
+        let mut file = File::create(path)
+            .with_context(|| format!("Failed to create cache: {}", path.display()))?;
+        writeln!(
+            file,
+            "{}",
+            serde_json::to_string(&CacheHeader {
+                settings: settings.to_string()
+            })?
+        )?;
+        for (image_path, result) in &cached {
+            write_entry(&mut file, image_path, result)?;
+        }
+
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok((
+            Self {
+                path: path.to_path_buf(),
+                writer: Mutex::new(file),
+            },
+            cached,
+        ))
+    }
+
+    /// Record a finished image; safe to call from worker threads
+    pub fn append(&self, image_path: &Path, result: &ImageResult) -> Result<()> {
+        let mut file = self
+            .writer
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock cache: {}", e))?;
+        write_entry(&mut file, image_path, result)
+    }
+
+    /// Delete the cache once the audit completed
+    pub fn finish(self) -> Result<()> {
+        drop(self.writer);
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove cache: {}", self.path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn write_entry(file: &mut File, image_path: &Path, result: &ImageResult) -> Result<()> {
+    let entry = CacheEntry {
+        path: image_path.to_path_buf(),
+        gt_classes: result.gt_classes.clone(),
+        gt_centers: result.gt_centers.clone(),
+        detections: result.detections.clone(),
+        match_ious: result.match_ious.clone(),
+        result: result.clone(),
+    };
+    // One write per line keeps lines whole even if the process is killed
+    let line = format!("{}\n", serde_json::to_string(&entry)?);
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reopen_restores_results_only_for_same_settings() {
+        let path =
+            std::env::temp_dir().join(format!("gt-audit-cache-{}.jsonl", std::process::id()));
+        let mut result = ImageResult::new("a.jpg".to_string(), &[], 2);
+        result.gt_classes = vec!["car".to_string()];
+
+        let (cache, cached) = ResultCache::open(&path, "v1").unwrap();
+        assert!(cached.is_empty());
+        cache.append(Path::new("images/a.jpg"), &result).unwrap();
+        drop(cache);
+
+        let (cache, cached) = ResultCache::open(&path, "v1").unwrap();
+        let restored = &cached[Path::new("images/a.jpg")];
+        assert_eq!(restored.detection_count, 2);
+        assert_eq!(restored.gt_classes, vec!["car".to_string()]);
+        drop(cache);
+
+        let (cache, cached) = ResultCache::open(&path, "v2").unwrap();
+        assert!(cached.is_empty());
+        cache.finish().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,418 @@
+//! Mapping a model's class names onto the dataset's
+//!
+//! A model trained on another dataset (e.g. COCO) numbers its classes its own
+//! way. Its class names come from the ONNX metadata ultralytics embeds, or from
+//! `--model-classes`, and each is matched to a dataset class by name, falling
+//! back to groups of synonyms.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// What happens to detections of model classes without a dataset counterpart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum UnmappedClasses {
+    /// Drop them
+    Ignore,
+    /// Report them as possibly missing from the dataset's classes
+    #[default]
+    Report,
+}
+
+impl std::str::FromStr for UnmappedClasses {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "ignore" => Ok(Self::Ignore),
+            "report" => Ok(Self::Report),
+            other => anyhow::bail!(
+                "Unknown unmapped class handling '{}' (expected ignore or report)",
+                other
+            ),
+        }
+    }
+}
+
+/// Synonym groups used without `--synonyms`, suited to fashion and person datasets
+const BUILTIN_SYNONYMS: &[&[&str]] = &[
+    &[
+        "clothing", "dress", "suit", "jacket", "coat", "top", "shirt", "blouse",
+    ],
+    &[
+        "footwear",
+        "boot",
+        "shoe",
+        "sandal",
+        "high heels",
+        "sneaker",
+    ],
+    &[
+        "bag",
+        "handbag",
+        "backpack",
+        "briefcase",
+        "luggage and bags",
+        "purse",
+    ],
+    &["pants", "jeans", "trousers", "shorts"],
+    &["person", "human", "man", "woman", "people", "boy", "girl"],
+];
+
+/// Groups of class names that count as the same class
+///
+/// A name belongs to a group when it contains, or is contained in, one of
+/// the group's names, ignoring case; `Winter Boot` falls in the group of
+/// `boot`. Two names are equivalent when they are equal or share a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassSynonyms {
+    /// Lowercased names of each group
+    groups: Vec<Vec<String>>,
+}
+
+/// A `--synonyms` file: a bare list of groups (JSON only) or a `groups` key
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SynonymFile {
+    Groups(Vec<Vec<String>>),
+    Table { groups: Vec<Vec<String>> },
+}
+
+impl ClassSynonyms {
+    pub fn new(groups: Vec<Vec<String>>) -> Self {
+        Self {
+            groups: groups
+                .into_iter()
+                .map(|group| group.iter().map(|name| name.to_lowercase()).collect())
+                .collect(),
+        }
+    }
+
+    /// The built-in groups, shared rather than rebuilt for every comparison
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceLock<ClassSynonyms> = OnceLock::new();
+        BUILTIN.get_or_init(Self::default)
+    }
+
+    /// Load groups from a TOML or JSON file
+    ///
+    /// TOML files hold `groups = [["car", "vehicle"], ...]`; JSON files hold
+    /// the same object or just the list of groups. The groups replace the
+    /// built-in ones rather than adding to them.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: SynonymFile = match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+            _ => serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?,
+        };
+        let groups = match file {
+            SynonymFile::Groups(groups) | SynonymFile::Table { groups } => groups,
+        };
+        if groups.is_empty() {
+            anyhow::bail!("No synonym groups in {}", path.display());
+        }
+        Ok(Self::new(groups))
+    }
+
+    /// The groups in `path` if given, else the built-in ones
+    pub fn from_config(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None => Ok(Self::builtin().clone()),
+        }
+    }
+
+    pub fn equivalent(&self, class1: &str, class2: &str) -> bool {
+        let c1 = class1.to_lowercase();
+        let c2 = class2.to_lowercase();
+
+        if c1 == c2 {
+            return true;
+        }
+
+        let in_group = |group: &[String], c: &str| {
+            group
+                .iter()
+                .any(|g| c.contains(g.as_str()) || g.contains(c))
+        };
+        self.groups
+            .iter()
+            .any(|group| in_group(group, &c1) && in_group(group, &c2))
+    }
+
+    /// The candidate `name` stands for: an exact match ignoring case, `_`
+    /// and `-`, else the first candidate sharing a synonym group
+    pub fn find_match<'a>(&self, name: &str, candidates: &'a [String]) -> Option<&'a String> {
+        candidates
+            .iter()
+            .find(|candidate| normalize(candidate) == normalize(name))
+            .or_else(|| {
+                candidates
+                    .iter()
+                    .find(|candidate| self.equivalent(name, candidate))
+            })
+    }
+}
+
+impl Default for ClassSynonyms {
+    fn default() -> Self {
+        Self::new(
+            BUILTIN_SYNONYMS
+                .iter()
+                .map(|group| group.iter().map(|name| name.to_string()).collect())
+                .collect(),
+        )
+    }
+}
+
+/// Where a model's class names came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassSource {
+    /// The `names` entry of the ONNX metadata
+    Metadata,
+    /// The `--model-classes` file
+    File,
+    /// Neither was available, so the model is assumed to use the dataset's classes
+    Dataset,
+}
+
+impl std::fmt::Display for ClassSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClassSource::Metadata => write!(f, "ONNX metadata"),
+            ClassSource::File => write!(f, "--model-classes"),
+            ClassSource::Dataset => write!(f, "dataset"),
+        }
+    }
+}
+
+/// Dataset class each model class is reported as
+#[derive(Debug, Clone)]
+pub struct ClassMapping {
+    pub source: ClassSource,
+    /// Model class names by class index
+    pub model_classes: Vec<String>,
+    /// Dataset class name for each model class, if one matched
+    pub targets: Vec<Option<String>>,
+    /// Dataset classes no model class maps to; the model never detects them
+    pub uncovered: Vec<String>,
+}
+
+impl ClassMapping {
+    /// The model's classes are the dataset's, in the same order
+    pub fn identity(dataset_classes: Vec<String>) -> Self {
+        Self {
+            source: ClassSource::Dataset,
+            targets: dataset_classes.iter().cloned().map(Some).collect(),
+            model_classes: dataset_classes,
+            uncovered: Vec::new(),
+        }
+    }
+
+    /// Match each model class to a dataset class
+    ///
+    /// Names match when they are equal ignoring case, `_` and `-`. Model
+    /// classes without an exact match fall back to the synonym groups (e.g.
+    /// `handbag` to `bag`), taking the first dataset class in the group.
+    pub fn build(
+        source: ClassSource,
+        model_classes: Vec<String>,
+        dataset_classes: &[String],
+        synonyms: &ClassSynonyms,
+    ) -> Self {
+        let targets: Vec<Option<String>> = model_classes
+            .iter()
+            .map(|model| synonyms.find_match(model, dataset_classes).cloned())
+            .collect();
+        let uncovered = dataset_classes
+            .iter()
+            .filter(|dataset| !targets.iter().flatten().any(|t| t == *dataset))
+            .cloned()
+            .collect();
+
+        Self {
+            source,
+            model_classes,
+            targets,
+            uncovered,
+        }
+    }
+
+    /// Model classes without a dataset counterpart
+    pub fn unmapped(&self) -> Vec<&str> {
+        self.model_classes
+            .iter()
+            .zip(&self.targets)
+            .filter(|(_, target)| target.is_none())
+            .map(|(model, _)| model.as_str())
+            .collect()
+    }
+
+    /// Model classes reported under a different dataset class name
+    pub fn renamed(&self) -> Vec<(&str, &str)> {
+        self.model_classes
+            .iter()
+            .zip(&self.targets)
+            .filter_map(|(model, target)| {
+                target
+                    .as_deref()
+                    .filter(|t| t != model)
+                    .map(|t| (model.as_str(), t))
+            })
+            .collect()
+    }
+
+    /// Name a detection of model class `index` is reported under
+    pub fn class_name(&self, index: usize) -> String {
+        match (self.targets.get(index), self.model_classes.get(index)) {
+            (Some(Some(target)), _) => target.clone(),
+            (_, Some(model)) => model.clone(),
+            _ => format!("class_{}", index),
+        }
+    }
+
+    /// Whether `class_name` is a model class without a dataset counterpart
+    pub fn is_unmapped(&self, class_name: &str) -> bool {
+        self.model_classes
+            .iter()
+            .zip(&self.targets)
+            .any(|(model, target)| target.is_none() && model == class_name)
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.to_lowercase()
+        .replace(['_', '-'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Class names from the `names` ONNX metadata ultralytics writes
+///
+/// The value is a Python dict literal such as `{0: 'person', 1: 'bicycle'}`,
+/// which also parses as a YAML flow mapping.
+pub fn parse_metadata_names(value: &str) -> Result<Vec<String>> {
+    let names: BTreeMap<usize, String> =
+        serde_yaml::from_str(value).context("Invalid class names in model metadata")?;
+    Ok(indexed_names(names))
+}
+
+/// Class names from a `--model-classes` file
+///
+/// YAML files may hold a `names` list or mapping as in `data.yaml`, or be the
+/// list or mapping itself. Any other file lists one class per line.
+pub fn load_class_file(path: &Path) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let is_yaml = matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("yaml" | "yml")
+    );
+    let names: Vec<String> = if is_yaml {
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        let names = yaml.get("names").cloned().unwrap_or(yaml);
+        if names.is_sequence() {
+            serde_yaml::from_value(names)?
+        } else {
+            indexed_names(
+                serde_yaml::from_value(names)
+                    .with_context(|| format!("No class names in {}", path.display()))?,
+            )
+        }
+    } else {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    if names.is_empty() {
+        anyhow::bail!("No class names in {}", path.display());
+    }
+    Ok(names)
+}
+
+/// Names ordered by class index, with gaps named `class_N`
+fn indexed_names(names: BTreeMap<usize, String>) -> Vec<String> {
+    let len = names.keys().next_back().map_or(0, |max| max + 1);
+    (0..len)
+        .map(|i| {
+            names
+                .get(&i)
+                .cloned()
+                .unwrap_or_else(|| format!("class_{}", i))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn metadata_names_parse_as_python_dict() {
+        let names = parse_metadata_names("{0: 'person', 1: 'traffic light', 3: \"dog\"}").unwrap();
+        assert_eq!(
+            names,
+            strings(&["person", "traffic light", "class_2", "dog"])
+        );
+        assert!(parse_metadata_names("not a dict").is_err());
+    }
+
+    #[test]
+    fn mapping_matches_names_and_equivalents() {
+        let mapping = ClassMapping::build(
+            ClassSource::Metadata,
+            strings(&["person", "traffic light", "handbag", "fire hydrant"]),
+            &strings(&["Traffic_Light", "bag", "person", "bus"]),
+            ClassSynonyms::builtin(),
+        );
+        assert_eq!(mapping.class_name(0), "person");
+        assert_eq!(mapping.class_name(1), "Traffic_Light");
+        assert_eq!(mapping.class_name(2), "bag");
+        assert_eq!(mapping.class_name(3), "fire hydrant");
+        assert_eq!(mapping.class_name(7), "class_7");
+
+        assert_eq!(mapping.unmapped(), vec!["fire hydrant"]);
+        assert!(mapping.is_unmapped("fire hydrant"));
+        assert!(!mapping.is_unmapped("person"));
+        assert_eq!(mapping.uncovered, strings(&["bus"]));
+        assert_eq!(
+            mapping.renamed(),
+            vec![("traffic light", "Traffic_Light"), ("handbag", "bag")]
+        );
+    }
+
+    #[test]
+    fn synonyms_load_from_toml_and_replace_builtin_groups() {
+        let path =
+            std::env::temp_dir().join(format!("gt-audit-synonyms-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "groups = [[\"car\", \"Vehicle\", \"auto\"], [\"pedestrian\", \"person\"]]\n",
+        )
+        .unwrap();
+        let synonyms = ClassSynonyms::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(synonyms.equivalent("vehicle", "Car"));
+        assert!(synonyms.equivalent("Pedestrian", "person"));
+        assert!(!synonyms.equivalent("car", "person"));
+        assert!(!synonyms.equivalent("Dress", "Clothing"));
+        assert!(ClassSynonyms::builtin().equivalent("Dress", "Clothing"));
+    }
+}
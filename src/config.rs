@@ -0,0 +1,351 @@
+//! TOML configuration for repeatable validate runs
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Config file picked up from the dataset or working directory when `--config` is not given
+pub const DEFAULT_CONFIG_FILE: &str = "gt-audit.toml";
+
+/// Commented config file written by `gt-audit config init`
+pub const TEMPLATE: &str = r#"# gt-audit configuration
+#
+# Every key mirrors a `gt-audit validate` flag, with `-` written as `_`.
+# Uncomment the keys you need; flags given on the command line override them.
+# `validate` picks this file up from the dataset directory or the working
+# directory, or from `--config <FILE>`.
+
+## Dataset
+# dataset = "./my-dataset"
+# split = "val"
+# splits = "all"                  # instead of `split`: "all" or e.g. "train,val"
+# classes_file = "./classes.txt"  # class names for `--images`
+# include_images = "*.jpg"        # comma-separated globs
+# exclude_images = "drafts/*"
+# exclude_classes = "ignore,unknown"
+# strict = false                  # fail on class names declared under several ids
+
+## Detection
+# method = "yolo"                 # zero-shot, grounding-dino, yolo, vlm or ensemble
+# backend = "heuristic"           # zero-shot backend: heuristic or grounding-dino
+# model = "./model.onnx"
+# model_format = "auto"           # yolov8, yolov5, end2end or auto
+# model_classes = "./classes.yaml"
+# unmapped_classes = "report"     # ignore or report
+# synonyms = "./synonyms.toml"
+# device = "cpu"                  # cpu, cuda or cuda:N
+# vlm_endpoint = "http://localhost:11434/v1"
+# vlm_model = "llava"
+# batch_size = 1
+# workers = 8
+# workers_io = 4
+
+## Thresholds
+# confidence = 0.25
+# iou = 0.5
+# text_threshold = 0.25
+# soft_nms = false
+# soft_nms_sigma = 0.5
+# nms_mode = "per-class"          # or agnostic, to suppress boxes across classes
+# blur_threshold = 100.0
+# brightness_min = 20.0
+# brightness_max = 235.0
+# min_box_pixels = 16.0
+# min_box_side = 2.0
+# max_box_fraction = 0.95
+# quality_checks = false          # single-color images, blurry or blank GT regions
+# min_color_std = 2.0
+# crop_blur_threshold = 20.0
+# min_gt_confidence = 0.5
+# assume_pixel_coords = false     # rescale pixel-coordinate label files
+# apply_exif_rotation = false     # rotate images by their EXIF orientation
+# compare_exif_orientation = false  # rerun the model on tagged images rotated
+# box_outlier_mads = 6.0         # off unless set; reads every label file
+
+## Checks
+# check_calibration = false
+# check_duplicates = false
+
+## Sampling
+# sample = 0                      # 0 audits every image
+# sample_strategy = "uniform"     # uniform or stratified
+# min_per_class = 20
+# stratified_sample = 500         # instead of `sample`: an even share per class
+# seed = 42
+
+## Reports
+# output = "audit.html"           # or a list; json, html, xml, md or sarif by extension
+# format = "html"                 # json, html, xml (or junit), markdown, sarif; or one per output
+# filter_severity = "high"
+# filter_type = "class_mismatch"
+# min_confidence_report = 0.4
+# report_detail = "flagged"       # summary, flagged or full
+# max_report_images = 20
+# report_top = 100                # only the worst flagged images in JSON and HTML reports
+# iou_buckets = 10
+# label_swap_min_share = 0.3
+# heatmap_grid = 20
+# sort_output = "by-total-issues" # or priority, by-high-severity, by-medium-severity, by-filename
+# priority_weights = "high=3,medium=1.5,low=0.5,confidence=2,classes=0.5"
+# export_worklist = "worklist.txt"
+# top = 500
+# group_by_class = false
+# github = false
+# embed_thumbnails = false
+# junit_flagged_only = false
+# junit_medium_as_failure = false
+# streaming_output = "results.jsonl"
+
+## Resuming and baselines
+# cache_file = "./.gt-audit-cache.jsonl"
+# no_cache = false
+# baseline = "./gt-audit-baseline.json"
+# update_baseline = false
+# write_suppressions = "suppressions.yaml"
+
+## Failure policy
+# policy = "./policy.yaml"
+# fail_on_high = 0
+# fail_on_medium = 10
+# fail_on_low = 100
+# fail_on_total = 100
+# fail_on_missing_labels = false
+
+## Output and logging
+# quiet = false
+# verbose = false
+# log_format = "text"             # text or json
+# print_summary = "json"
+# explain = false
+# no_color = false
+# watch = false
+# serve_metrics = ":9091"
+"#;
+
+/// Validate options read from a TOML file
+///
+/// Every key mirrors the matching `validate` flag (with `-` written as `_`).
+/// Keys left out fall back to the CLI defaults, and flags given on the
+/// command line always take precedence over the file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub dataset: Option<PathBuf>,
+    pub split: Option<String>,
+    pub splits: Option<String>,
+    pub classes_file: Option<PathBuf>,
+    pub method: Option<String>,
+    pub backend: Option<String>,
+    pub model: Option<PathBuf>,
+    pub model_format: Option<String>,
+    pub model_classes: Option<PathBuf>,
+    pub unmapped_classes: Option<String>,
+    pub synonyms: Option<PathBuf>,
+    pub confidence: Option<f32>,
+    pub iou: Option<f32>,
+    pub text_threshold: Option<f32>,
+    pub vlm_endpoint: Option<String>,
+    pub vlm_model: Option<String>,
+    pub soft_nms: Option<bool>,
+    pub soft_nms_sigma: Option<f32>,
+    pub nms_mode: Option<String>,
+    pub device: Option<String>,
+    pub batch_size: Option<usize>,
+    pub blur_threshold: Option<f32>,
+    pub brightness_min: Option<f32>,
+    pub brightness_max: Option<f32>,
+    pub min_box_pixels: Option<f32>,
+    pub min_box_side: Option<f32>,
+    pub max_box_fraction: Option<f32>,
+    pub quality_checks: Option<bool>,
+    pub min_color_std: Option<f32>,
+    pub crop_blur_threshold: Option<f32>,
+    pub check_calibration: Option<bool>,
+    pub check_duplicates: Option<bool>,
+    pub min_gt_confidence: Option<f32>,
+    pub assume_pixel_coords: Option<bool>,
+    pub apply_exif_rotation: Option<bool>,
+    pub compare_exif_orientation: Option<bool>,
+    pub box_outlier_mads: Option<f32>,
+    pub exclude_classes: Option<String>,
+    pub strict: Option<bool>,
+    pub output: Option<OneOrMany<PathBuf>>,
+    pub format: Option<OneOrMany<String>>,
+    pub filter_severity: Option<String>,
+    pub filter_type: Option<String>,
+    pub min_confidence_report: Option<f32>,
+    pub report_detail: Option<String>,
+    pub max_report_images: Option<usize>,
+    pub report_top: Option<usize>,
+    pub iou_buckets: Option<usize>,
+    pub label_swap_min_share: Option<f32>,
+    pub heatmap_grid: Option<usize>,
+    pub sort_output: Option<String>,
+    pub priority_weights: Option<String>,
+    pub export_worklist: Option<PathBuf>,
+    pub top: Option<usize>,
+    pub group_by_class: Option<bool>,
+    pub github: Option<bool>,
+    pub embed_thumbnails: Option<bool>,
+    pub junit_flagged_only: Option<bool>,
+    pub junit_medium_as_failure: Option<bool>,
+    pub include_images: Option<String>,
+    pub exclude_images: Option<String>,
+    pub sample: Option<usize>,
+    pub sample_strategy: Option<String>,
+    pub min_per_class: Option<usize>,
+    pub stratified_sample: Option<usize>,
+    pub seed: Option<u64>,
+    pub cache_file: Option<PathBuf>,
+    pub no_cache: Option<bool>,
+    pub streaming_output: Option<PathBuf>,
+    pub baseline: Option<PathBuf>,
+    pub update_baseline: Option<bool>,
+    pub write_suppressions: Option<PathBuf>,
+    pub policy: Option<PathBuf>,
+    pub fail_on_high: Option<usize>,
+    pub fail_on_medium: Option<usize>,
+    pub fail_on_low: Option<usize>,
+    pub fail_on_total: Option<usize>,
+    pub fail_on_missing_labels: Option<bool>,
+    pub workers: Option<usize>,
+    pub workers_io: Option<usize>,
+    pub watch: Option<bool>,
+    pub serve_metrics: Option<String>,
+    pub quiet: Option<bool>,
+    pub print_summary: Option<String>,
+    pub explain: Option<bool>,
+    pub no_color: Option<bool>,
+    pub verbose: Option<bool>,
+    pub log_format: Option<String>,
+}
+
+/// Value of a key that takes either one item or a list, such as `output`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::One(item) => vec![item],
+            Self::Many(items) => items,
+        }
+    }
+}
+
+impl Config {
+    /// Parse a config file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config: {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config: {}", path.display()))
+    }
+
+    /// Load the explicit config, or else `gt-audit.toml` from the dataset
+    /// directory or the working directory, whichever is found first
+    pub fn discover(explicit: Option<&Path>, dataset: Option<&Path>) -> Result<Option<Self>> {
+        if let Some(path) = explicit {
+            return Self::load(path).map(Some);
+        }
+        let candidates = dataset
+            .map(|dir| dir.join(DEFAULT_CONFIG_FILE))
+            .into_iter()
+            .chain([PathBuf::from(DEFAULT_CONFIG_FILE)]);
+        for path in candidates {
+            if path.is_file() {
+                return Self::load(&path).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Options a run was started with, recorded in its report
+    ///
+    /// The keys set in this file, overridden by `command_line`: the flags
+    /// given on the command line, keyed like the file.
+    pub fn effective(&self, command_line: BTreeMap<String, Value>) -> BTreeMap<String, Value> {
+        let mut options: BTreeMap<String, Value> = match serde_json::to_value(self) {
+            Ok(Value::Object(keys)) => keys.into_iter().filter(|(_, v)| !v.is_null()).collect(),
+            _ => BTreeMap::new(),
+        };
+        options.extend(command_line);
+        options
+    }
+}
+
+/// Write `TEMPLATE` to `path`, refusing to replace an existing file unless `force`
+pub fn write_template(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        anyhow::bail!(
+            "{} already exists (use --force to overwrite)",
+            path.display()
+        );
+    }
+    std::fs::write(path, TEMPLATE)
+        .with_context(|| format!("Failed to write config: {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_partial_config() {
+        let cfg: Config = toml::from_str(
+            r#"
+            dataset = "./data"
+            confidence = 0.4
+            soft_nms = true
+            fail_on_high = 0
+            output = ["audit.json", "audit.html"]
+            format = "json"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(cfg.dataset, Some(PathBuf::from("./data")));
+        assert_eq!(cfg.confidence, Some(0.4));
+        assert_eq!(cfg.soft_nms, Some(true));
+        assert_eq!(cfg.fail_on_high, Some(0));
+        assert!(cfg.method.is_none());
+        assert_eq!(
+            cfg.output.unwrap().into_vec(),
+            [PathBuf::from("audit.json"), PathBuf::from("audit.html")]
+        );
+        assert_eq!(cfg.format.unwrap().into_vec(), ["json"]);
+    }
+
+    #[test]
+    fn template_covers_every_key() {
+        // Uncommenting every example must give a valid config that sets each key
+        let uncommented: String = TEMPLATE
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| line.contains(" = "))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let cfg: Config = toml::from_str(&uncommented).unwrap();
+        let effective = cfg.effective(BTreeMap::new());
+        let keys = serde_json::to_value(Config::default())
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .len();
+        assert_eq!(effective.len(), keys);
+
+        // As written, the template sets nothing
+        let cfg: Config = toml::from_str(TEMPLATE).unwrap();
+        assert!(cfg.effective(BTreeMap::new()).is_empty());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(toml::from_str::<Config>("confidance = 0.4").is_err());
+    }
+}
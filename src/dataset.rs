@@ -1,63 +1,238 @@
-//! YOLO dataset loading
+//! YOLO dataset loading, conversion between YOLO, COCO and VOC, and export
+//! of audited annotations
 
 use anyhow::{Context, Result};
-use std::collections::HashMap;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
-use crate::models::{Annotation, BoundingBox};
+use crate::models::{
+    Annotation, AreaBucket, AuditResult, BoundingBox, ClassStats, DatasetStats, IssueSeverity,
+};
+use crate::report::xml_escape;
+
+/// Candidate locations of the dataset YAML, in lookup order
+const YAML_NAMES: [&str; 3] = ["dataset.yaml", "data/dataset.yaml", "data.yaml"];
+
+/// File extensions recognised as images
+const IMAGE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "webp", "bmp"];
+
+/// An image directory and the label directory mirroring it
+#[derive(Debug, Clone)]
+pub struct ImageSource {
+    pub images_dir: PathBuf,
+    pub labels_dir: PathBuf,
+}
 
 pub struct YoloDataset {
     pub path: PathBuf,
     pub class_names: HashMap<i32, String>,
+    /// Primary image directory (the first of `sources`)
     pub images_dir: PathBuf,
+    /// Primary label directory (the first of `sources`)
     pub labels_dir: PathBuf,
+    /// Every image/label directory pair of the selected split
+    pub sources: Vec<ImageSource>,
+    /// Images to audit instead of everything below `sources`
+    pub image_files: Option<Vec<PathBuf>>,
 }
 
 impl YoloDataset {
+    /// Load the default split
+    ///
+    /// When the dataset YAML declares `train`/`val`/`test` keys, the first of
+    /// `val`, `train`, `test` is used; otherwise the directory layout is guessed.
     pub fn load(path: &Path) -> Result<Self> {
-        let path = path.to_path_buf();
+        let sources = match Self::yaml_sources(path, None)? {
+            Some(sources) => sources,
+            None => {
+                let (images_dir, labels_dir) = Self::detect_structure(path)?;
+                vec![ImageSource {
+                    images_dir,
+                    labels_dir,
+                }]
+            }
+        };
+
+        Self::from_sources(path, sources)
+    }
+
+    /// Load a single named split (e.g. `train`, `val`, `test`)
+    pub fn load_split(path: &Path, split: &str) -> Result<Self> {
+        let sources = match Self::yaml_sources(path, Some(split))? {
+            Some(sources) => sources,
+            None => {
+                let (images_dir, labels_dir) =
+                    Self::split_dirs(path, split).with_context(|| {
+                        format!("Split '{}' not found in {}", split, path.display())
+                    })?;
+                vec![ImageSource {
+                    images_dir,
+                    labels_dir,
+                }]
+            }
+        };
+
+        Self::from_sources(path, sources)
+    }
+
+    fn from_sources(path: &Path, sources: Vec<ImageSource>) -> Result<Self> {
+        let class_names = Self::load_class_names(path)?;
+        let primary = sources[0].clone();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            class_names,
+            images_dir: primary.images_dir,
+            labels_dir: primary.labels_dir,
+            sources,
+            image_files: None,
+        })
+    }
+
+    /// A dataset of just the given images, e.g. the files staged in a commit
+    ///
+    /// Each image's label is looked up in the directory mirroring its own with
+    /// the last `images` component replaced by `labels`, or next to the image
+    /// if there is none. Class names come from `classes_file` (a `classes.txt`
+    /// or a YAML with `names`) when given, else from the dataset root
+    /// (`image_files_root`).
+    pub fn from_image_files(images: &[PathBuf], classes_file: Option<&Path>) -> Result<Self> {
+        if images.is_empty() {
+            anyhow::bail!("No images given");
+        }
+        let mut sources: Vec<ImageSource> = Vec::new();
+        for image in images {
+            if !image.is_file() {
+                anyhow::bail!("Image not found: {}", image.display());
+            }
+            let images_dir = image.parent().unwrap_or(Path::new("")).to_path_buf();
+            if !sources.iter().any(|s| s.images_dir == images_dir) {
+                sources.push(ImageSource {
+                    labels_dir: labels_dir_for(&images_dir),
+                    images_dir,
+                });
+            }
+        }
 
-        // Detect structure and load class names
-        let (images_dir, labels_dir) = Self::detect_structure(&path)?;
-        let class_names = Self::load_class_names(&path)?;
+        let path = image_files_root(images);
+        let class_names = match classes_file {
+            Some(file) => crate::class_map::load_class_file(file)?
+                .into_iter()
+                .enumerate()
+                .map(|(id, name)| (id as i32, name))
+                .collect(),
+            None => Self::load_class_names(&path)?,
+        };
+        let mut image_files = images.to_vec();
+        image_files.sort();
+        image_files.dedup();
+        let primary = sources[0].clone();
 
         Ok(Self {
             path,
             class_names,
-            images_dir,
-            labels_dir,
+            images_dir: primary.images_dir,
+            labels_dir: primary.labels_dir,
+            sources,
+            image_files: Some(image_files),
         })
     }
 
-    fn detect_structure(path: &Path) -> Result<(PathBuf, PathBuf)> {
-        // Try standard YOLO structure: images/val, labels/val
-        for split in ["val", "train", "test", ""] {
-            let img_dir = if split.is_empty() {
-                path.join("images")
-            } else {
-                path.join("images").join(split)
+    /// Resolve split directories from the `path`/`train`/`val`/`test` keys of the dataset YAML
+    ///
+    /// Returns `None` when no YAML declares any split, so callers can fall
+    /// back to guessing the layout. Relative paths are resolved against
+    /// `path:`, which is itself relative to the YAML's directory.
+    fn yaml_sources(path: &Path, split: Option<&str>) -> Result<Option<Vec<ImageSource>>> {
+        for name in YAML_NAMES {
+            let yaml_path = path.join(name);
+            if !yaml_path.exists() {
+                continue;
+            }
+            let content = fs::read_to_string(&yaml_path)
+                .with_context(|| format!("Failed to read {}", yaml_path.display()))?;
+            let yaml: serde_yaml::Value = serde_yaml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", yaml_path.display()))?;
+
+            if !SPLITS.iter().any(|s| yaml.get(s).is_some()) {
+                continue;
+            }
+
+            let yaml_dir = yaml_path.parent().unwrap_or(path);
+            let root = match yaml.get("path").and_then(|p| p.as_str()) {
+                Some(p) => yaml_dir.join(p),
+                None => yaml_dir.to_path_buf(),
             };
 
-            let lbl_dir = if split.is_empty() {
-                path.join("labels")
-            } else {
-                path.join("labels").join(split)
+            let split = match split {
+                Some(s) => s,
+                None => ["val", "train", "test"]
+                    .into_iter()
+                    .find(|s| yaml.get(s).is_some())
+                    .unwrap_or("val"),
             };
 
-            // Also check data/val/labels structure
-            let lbl_dir_alt = if split.is_empty() {
-                path.join("data").join("labels")
-            } else {
-                path.join("data").join(split).join("labels")
+            let entries: Vec<&str> = match yaml.get(split) {
+                Some(serde_yaml::Value::String(dir)) => vec![dir.as_str()],
+                Some(serde_yaml::Value::Sequence(dirs)) => {
+                    dirs.iter().filter_map(|d| d.as_str()).collect()
+                }
+                _ => anyhow::bail!(
+                    "Split '{}' is not declared in {}",
+                    split,
+                    yaml_path.display()
+                ),
             };
 
-            if img_dir.exists() {
-                if lbl_dir.exists() {
-                    return Ok((img_dir, lbl_dir));
-                } else if lbl_dir_alt.exists() {
-                    return Ok((img_dir, lbl_dir_alt));
+            let mut sources = Vec::new();
+            for entry in entries {
+                let mut images_dir = root.join(entry);
+                // Roboflow exports point one level above the dataset, as in
+                // `train: ../train/images`, for trainers run from a subfolder
+                let unparented = Path::new(entry)
+                    .components()
+                    .skip_while(|c| *c == Component::ParentDir)
+                    .collect::<PathBuf>();
+                if !images_dir.is_dir() && root.join(&unparented).is_dir() {
+                    images_dir = root.join(unparented);
                 }
+                if !images_dir.is_dir() {
+                    anyhow::bail!(
+                        "Image directory for split '{}' not found: {} (from {})",
+                        split,
+                        images_dir.display(),
+                        yaml_path.display()
+                    );
+                }
+                let labels_dir = labels_dir_for(&images_dir);
+                sources.push(ImageSource {
+                    images_dir,
+                    labels_dir,
+                });
+            }
+
+            if sources.is_empty() {
+                anyhow::bail!(
+                    "Split '{}' in {} lists no directories",
+                    split,
+                    yaml_path.display()
+                );
+            }
+            return Ok(Some(sources));
+        }
+
+        Ok(None)
+    }
+
+    fn detect_structure(path: &Path) -> Result<(PathBuf, PathBuf)> {
+        // Try standard YOLO structure: images/val, labels/val, or the
+        // Roboflow one: valid/images, valid/labels
+        for split in ["val", "train", "test", ""] {
+            if let Some(dirs) = Self::split_dirs(path, split) {
+                return Ok(dirs);
             }
         }
 
@@ -72,17 +247,60 @@ impl YoloDataset {
         )
     }
 
+    /// Locate the image and label directories of a split, if present
+    fn split_dirs(path: &Path, split: &str) -> Option<(PathBuf, PathBuf)> {
+        let img_dir = if split.is_empty() {
+            path.join("images")
+        } else {
+            path.join("images").join(split)
+        };
+
+        let lbl_dir = if split.is_empty() {
+            path.join("labels")
+        } else {
+            path.join("labels").join(split)
+        };
+
+        // Also check data/val/labels structure
+        let lbl_dir_alt = if split.is_empty() {
+            path.join("data").join("labels")
+        } else {
+            path.join("data").join(split).join("labels")
+        };
+
+        if img_dir.exists() {
+            if lbl_dir.exists() {
+                return Some((img_dir, lbl_dir));
+            } else if lbl_dir_alt.exists() {
+                return Some((img_dir, lbl_dir_alt));
+            }
+        }
+
+        // Roboflow exports: train/images, train/labels
+        if !split.is_empty() {
+            for dir in split_dir_names(split) {
+                let dir = path.join(dir);
+                let (img_dir, lbl_dir) = (dir.join("images"), dir.join("labels"));
+                if img_dir.exists() && lbl_dir.exists() {
+                    return Some((img_dir, lbl_dir));
+                }
+            }
+        }
+
+        None
+    }
+
     fn load_class_names(path: &Path) -> Result<HashMap<i32, String>> {
         let mut class_names = HashMap::new();
 
-        // Try dataset.yaml in multiple locations
-        let yaml_paths = [
-            path.join("dataset.yaml"),
-            path.join("data").join("dataset.yaml"),
-            path.join("data.yaml"),
-        ];
-
-        for yaml_path in yaml_paths {
+        // Try dataset.yaml in multiple locations, then the data.yaml Roboflow
+        // puts in each split folder
+        let split_yamls = SPLITS
+            .iter()
+            .flat_map(|split| split_dir_names(split))
+            .map(|dir| path.join(dir).join("data.yaml"));
+        let yaml_paths = YAML_NAMES.map(|name| path.join(name));
+        for yaml_path in yaml_paths.into_iter().chain(split_yamls) {
             if !yaml_path.exists() {
                 continue;
             }
@@ -130,7 +348,7 @@ impl YoloDataset {
             }
         }
 
-        eprintln!("Warning: No class names found, using class IDs");
+        tracing::warn!("No class names found, using class IDs");
         Ok(class_names)
     }
 
@@ -138,54 +356,120 @@ impl YoloDataset {
         self.get_images().len()
     }
 
+    /// All images of every source, including nested subfolders
+    ///
+    /// A dataset built from `from_image_files` has just those images.
     pub fn get_images(&self) -> Vec<PathBuf> {
+        if let Some(images) = &self.image_files {
+            return images.clone();
+        }
         let mut images = Vec::new();
-
-        if let Ok(entries) = fs::read_dir(&self.images_dir) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if let Some(ext) = path.extension() {
-                    let ext = ext.to_string_lossy().to_lowercase();
-                    if ["jpg", "jpeg", "png", "webp", "bmp"].contains(&ext.as_str()) {
-                        images.push(path);
-                    }
-                }
-            }
+        for source in &self.sources {
+            collect_images(&source.images_dir, &mut images);
         }
-
         images.sort();
         images
     }
 
+    /// Source whose image directory contains `image_path`
+    fn source_for(&self, image_path: &Path) -> Option<&ImageSource> {
+        self.sources
+            .iter()
+            .find(|s| image_path.starts_with(&s.images_dir))
+    }
+
+    /// Label file for an image, mirroring its subpath under the labels directory
+    ///
+    /// `images/val/scene01/frame0001.jpg` maps to `labels/val/scene01/frame0001.txt`,
+    /// so equal stems in different subfolders never share a label file.
     pub fn get_label_path(&self, image_path: &Path) -> PathBuf {
-        let stem = image_path.file_stem().unwrap_or_default();
-        self.labels_dir.join(format!("{}.txt", stem.to_string_lossy()))
+        match self.source_for(image_path) {
+            Some(source) => {
+                let relative = image_path
+                    .strip_prefix(&source.images_dir)
+                    .unwrap_or(image_path);
+                source.labels_dir.join(relative).with_extension("txt")
+            }
+            None => {
+                let stem = image_path.file_stem().unwrap_or_default();
+                self.labels_dir
+                    .join(format!("{}.txt", stem.to_string_lossy()))
+            }
+        }
+    }
+
+    /// Image whose label file is `label_path`, the inverse of `get_label_path`
+    ///
+    /// Returns `None` if the label lies outside every label directory or no
+    /// image with a known extension exists for it.
+    pub fn image_for_label(&self, label_path: &Path) -> Option<PathBuf> {
+        self.sources.iter().find_map(|source| {
+            let relative = label_path.strip_prefix(&source.labels_dir).ok()?;
+            let base = source.images_dir.join(relative);
+            IMAGE_EXTENSIONS
+                .iter()
+                .flat_map(|ext| [ext.to_string(), ext.to_uppercase()])
+                .map(|ext| base.with_extension(ext))
+                .find(|path| path.is_file())
+        })
+    }
+
+    /// Image path relative to its image directory, with `/` separators
+    ///
+    /// Used as the image name in reports so nested images stay distinguishable.
+    pub fn image_key(&self, image_path: &Path) -> String {
+        match self.source_for(image_path) {
+            Some(source) => image_path
+                .strip_prefix(&source.images_dir)
+                .unwrap_or(image_path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            None => image_path
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Image named `key` in reports, the inverse of `image_key`
+    pub fn image_path(&self, key: &str) -> Option<PathBuf> {
+        self.sources
+            .iter()
+            .map(|source| source.images_dir.join(key))
+            .find(|path| path.is_file())
     }
 
     pub fn load_annotations(&self, image_path: &Path) -> Vec<Annotation> {
+        self.load_labels(image_path).0
+    }
+
+    /// Parse the label file of an image, keeping rows that fail to parse
+    ///
+    /// Rows are either boxes (`class x y w h`), segmentation polygons
+    /// (`class x1 y1 ... xn yn`, at least three points) or a box followed by
+    /// its polygon; an annotation with only a polygon gets the enclosing box.
+    pub fn load_labels(&self, image_path: &Path) -> (Vec<Annotation>, Vec<LabelError>) {
         let label_path = self.get_label_path(image_path);
 
         if !label_path.exists() {
-            return Vec::new();
+            return (Vec::new(), Vec::new());
         }
 
         let content = match fs::read_to_string(&label_path) {
             Ok(c) => c,
-            Err(_) => return Vec::new(),
+            Err(_) => return (Vec::new(), Vec::new()),
         };
 
         let mut annotations = Vec::new();
+        let mut errors = Vec::new();
 
         for (line_num, line) in content.lines().enumerate() {
-            let parts: Vec<&str> = line.trim().split_whitespace().collect();
-            if parts.len() >= 5 {
-                if let (Ok(class_id), Ok(x), Ok(y), Ok(w), Ok(h)) = (
-                    parts[0].parse::<i32>(),
-                    parts[1].parse::<f32>(),
-                    parts[2].parse::<f32>(),
-                    parts[3].parse::<f32>(),
-                    parts[4].parse::<f32>(),
-                ) {
+            let line_num = line_num + 1;
+            match parse_label_row(line) {
+                Ok(None) => {}
+                Ok(Some((class_id, bbox, polygon, gt_confidence))) => {
                     let class_name = self
                         .class_names
                         .get(&class_id)
@@ -195,14 +479,122 @@ impl YoloDataset {
                     annotations.push(Annotation {
                         class_id,
                         class_name,
-                        bbox: BoundingBox::new(x, y, w, h),
-                        line_num: line_num + 1,
+                        bbox,
+                        line_num,
+                        polygon,
+                        gt_confidence,
                     });
                 }
+                Err(reason) => errors.push(LabelError {
+                    line_num,
+                    content: line.trim().to_string(),
+                    reason,
+                }),
             }
         }
 
-        annotations
+        (annotations, errors)
+    }
+
+    /// Number of label rows across the dataset that fail to parse
+    pub fn count_label_errors(&self) -> usize {
+        self.get_images()
+            .iter()
+            .map(|path| self.load_labels(path).1.len())
+            .sum()
+    }
+
+    /// Check the declared class names for duplicates and gaps in the ids
+    ///
+    /// Names are compared ignoring case. A gap usually means a class was
+    /// deleted while labels still use the old id.
+    pub fn class_name_check(&self) -> ClassNameReport {
+        let mut ids: Vec<i32> = self.class_names.keys().copied().collect();
+        ids.sort();
+
+        let mut by_name: Vec<(String, Vec<i32>)> = Vec::new();
+        for id in &ids {
+            let name = &self.class_names[id];
+            let key = name.trim().to_lowercase();
+            match by_name
+                .iter_mut()
+                .find(|(first, _)| first.trim().to_lowercase() == key)
+            {
+                Some((_, same)) => same.push(*id),
+                None => by_name.push((name.clone(), vec![*id])),
+            }
+        }
+
+        let declared: HashSet<i32> = ids.iter().copied().collect();
+        let max_id = ids.last().copied().unwrap_or(-1);
+        ClassNameReport {
+            duplicates: by_name
+                .into_iter()
+                .filter(|(_, ids)| ids.len() > 1)
+                .collect(),
+            missing_ids: (0..max_id).filter(|id| !declared.contains(id)).collect(),
+        }
+    }
+
+    /// Cross-reference the image and label directories
+    ///
+    /// Finds label files no image maps to, images without a label file and
+    /// images whose label file has no rows. `classes.txt` files are not labels
+    /// and never count as orphaned.
+    pub fn consistency_check(&self) -> ConsistencyReport {
+        let mut report = ConsistencyReport::default();
+        for image_path in self.get_images() {
+            let label_path = self.get_label_path(&image_path);
+            if !label_path.is_file() {
+                report.missing_labels.push(image_path);
+            } else if fs::read_to_string(&label_path).is_ok_and(|c| c.trim().is_empty()) {
+                report.empty_labels.push(image_path);
+            }
+        }
+
+        // Other images' labels sit next to the given images' ones
+        if self.image_files.is_some() {
+            return report;
+        }
+
+        let mut labels = Vec::new();
+        for source in &self.sources {
+            collect_files(&source.labels_dir, &["txt"], &mut labels);
+        }
+        labels.sort();
+        labels.dedup();
+        report.orphaned_labels = labels
+            .into_iter()
+            .filter(|path| path.file_name().is_some_and(|name| name != "classes.txt"))
+            .filter(|path| self.image_for_label(path).is_none())
+            .collect();
+        report
+    }
+
+    /// Whether labels may use `class_id`
+    ///
+    /// Any non-negative id is accepted when no class names were found, since
+    /// there is nothing to check against.
+    pub fn is_valid_class_id(&self, class_id: i32) -> bool {
+        class_id >= 0 && (self.class_names.is_empty() || self.class_names.contains_key(&class_id))
+    }
+
+    /// Lowest and highest declared class id
+    pub fn class_id_range(&self) -> Option<(i32, i32)> {
+        let min = self.class_names.keys().min()?;
+        let max = self.class_names.keys().max()?;
+        Some((*min, *max))
+    }
+
+    /// Class ids used in label files, with their annotation counts
+    pub fn label_class_ids(&self) -> BTreeMap<i32, usize> {
+        let mut ids = BTreeMap::new();
+        for image_path in self.get_images() {
+            for ann in self.load_annotations(&image_path) {
+                *ids.entry(ann.class_id).or_insert(0) += 1;
+            }
+        }
+        ids
     }
 
     pub fn get_class_name(&self, class_id: i32) -> String {
@@ -211,4 +603,1116 @@ impl YoloDataset {
             .cloned()
             .unwrap_or_else(|| format!("class_{}", class_id))
     }
+
+    /// Images containing at least one box of each class, keyed by class name
+    ///
+    /// An image with several classes is listed under each of them.
+    pub fn split_by_class(&self) -> HashMap<String, Vec<PathBuf>> {
+        self.images_by_class(&self.annotations_per_image(|| {}))
+    }
+
+    /// Compute per-class annotation statistics over all images
+    pub fn compute_stats(&self) -> DatasetStats {
+        self.compute_stats_with(|| {})
+    }
+
+    /// Like `compute_stats`, calling `on_image` as each label file is read
+    ///
+    /// Label files are read in parallel, so `on_image` is called from worker
+    /// threads.
+    pub fn compute_stats_with(&self, on_image: impl Fn() + Sync) -> DatasetStats {
+        self.stats_of(&self.annotations_per_image(on_image))
+    }
+
+    /// `compute_stats` and `split_by_class` together, reading each label file once
+    pub fn compute_stats_and_split_by_class(
+        &self,
+    ) -> (DatasetStats, HashMap<String, Vec<PathBuf>>) {
+        let per_image = self.annotations_per_image(|| {});
+        (self.stats_of(&per_image), self.images_by_class(&per_image))
+    }
+
+    /// Every image with its annotations, label files read in parallel
+    fn annotations_per_image(&self, on_image: impl Fn() + Sync) -> Vec<(PathBuf, Vec<Annotation>)> {
+        self.get_images()
+            .into_par_iter()
+            .map(|image_path| {
+                let annotations = self.load_annotations(&image_path);
+                on_image();
+                (image_path, annotations)
+            })
+            .collect()
+    }
+
+    fn images_by_class(
+        &self,
+        per_image: &[(PathBuf, Vec<Annotation>)],
+    ) -> HashMap<String, Vec<PathBuf>> {
+        let mut by_class: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for (image_path, annotations) in per_image {
+            let class_ids: BTreeSet<i32> = annotations.iter().map(|a| a.class_id).collect();
+            for class_id in class_ids {
+                by_class
+                    .entry(self.get_class_name(class_id))
+                    .or_default()
+                    .push(image_path.clone());
+            }
+        }
+        by_class
+    }
+
+    fn stats_of(&self, per_image: &[(PathBuf, Vec<Annotation>)]) -> DatasetStats {
+        let mut widths: BTreeMap<i32, Vec<f32>> = BTreeMap::new();
+        let mut heights: BTreeMap<i32, Vec<f32>> = BTreeMap::new();
+        let mut areas: BTreeMap<i32, Vec<f32>> = BTreeMap::new();
+        let mut image_counts: BTreeMap<i32, usize> = BTreeMap::new();
+        let mut total_annotations = 0;
+        let mut images_without_annotations = 0;
+        let mut images_over_50_annotations = 0;
+        let mut area_counts = [0; AREA_BUCKETS.len()];
+
+        for (_, annotations) in per_image {
+            total_annotations += annotations.len();
+
+            if annotations.is_empty() {
+                images_without_annotations += 1;
+            } else if annotations.len() > 50 {
+                images_over_50_annotations += 1;
+            }
+
+            let mut seen: Vec<i32> = Vec::new();
+            for ann in annotations {
+                let area = ann.bbox.area();
+                widths.entry(ann.class_id).or_default().push(ann.bbox.w);
+                heights.entry(ann.class_id).or_default().push(ann.bbox.h);
+                areas.entry(ann.class_id).or_default().push(area);
+                if let Some(bucket) = AREA_BUCKETS.iter().position(|(upper, _)| area < *upper) {
+                    area_counts[bucket] += 1;
+                }
+                if !seen.contains(&ann.class_id) {
+                    seen.push(ann.class_id);
+                    *image_counts.entry(ann.class_id).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let total_images = per_image.len();
+        let mut classes = Vec::new();
+
+        for (class_id, ws) in &widths {
+            let hs = &heights[class_id];
+            let areas = &areas[class_id];
+            let image_count = image_counts.get(class_id).copied().unwrap_or(0);
+
+            let (width_mean, width_std) = mean_std(ws);
+            let (height_mean, height_std) = mean_std(hs);
+            let (area_mean, area_std) = mean_std(areas);
+
+            classes.push(ClassStats {
+                class_id: *class_id,
+                class_name: self.get_class_name(*class_id),
+                annotation_count: ws.len(),
+                image_count,
+                image_pct: if total_images > 0 {
+                    image_count as f32 / total_images as f32 * 100.0
+                } else {
+                    0.0
+                },
+                width_mean,
+                width_std,
+                height_mean,
+                height_std,
+                area_mean,
+                area_std,
+                area_min: areas.iter().copied().fold(f32::INFINITY, f32::min),
+                area_max: areas.iter().copied().fold(0.0, f32::max),
+            });
+        }
+        classes.sort_by_key(|c| (std::cmp::Reverse(c.annotation_count), c.class_id));
+
+        let counts = per_image.iter().map(|(_, annotations)| annotations.len());
+        let class_imbalance_ratio = match (classes.first(), classes.last()) {
+            (Some(most), Some(least)) => {
+                most.annotation_count as f32 / least.annotation_count as f32
+            }
+            _ => 0.0,
+        };
+
+        DatasetStats {
+            dataset_path: self.path.to_string_lossy().to_string(),
+            total_images,
+            total_annotations,
+            avg_annotations_per_image: if total_images > 0 {
+                total_annotations as f32 / total_images as f32
+            } else {
+                0.0
+            },
+            min_annotations_per_image: counts.clone().min().unwrap_or(0),
+            max_annotations_per_image: counts.max().unwrap_or(0),
+            images_without_annotations,
+            images_over_50_annotations,
+            class_imbalance_ratio,
+            area_buckets: AREA_BUCKETS
+                .iter()
+                .zip(area_counts)
+                .map(|((_, range), count)| AreaBucket {
+                    range: range.to_string(),
+                    count,
+                })
+                .collect(),
+            classes,
+        }
+    }
+}
+
+/// Label directory for an image directory, following the ultralytics convention
+///
+/// The last `images` path component is replaced by `labels`; without one, labels
+/// are expected next to the images.
+fn labels_dir_for(images_dir: &Path) -> PathBuf {
+    let components: Vec<_> = images_dir.components().collect();
+    match components.iter().rposition(|c| c.as_os_str() == "images") {
+        Some(idx) => components
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if i == idx {
+                    Path::new("labels")
+                } else {
+                    Path::new(c.as_os_str())
+                }
+            })
+            .collect(),
+        None => images_dir.to_path_buf(),
+    }
+}
+
+/// Dataset root of a list of images: the directory above the first image's
+/// last `images` component, else the first image's directory
+pub fn image_files_root(images: &[PathBuf]) -> PathBuf {
+    let Some(dir) = images.first().and_then(|image| image.parent()) else {
+        return PathBuf::from(".");
+    };
+    let root = match dir
+        .ancestors()
+        .find(|a| a.file_name().is_some_and(|n| n == "images"))
+    {
+        Some(images_dir) => images_dir.parent().unwrap_or(Path::new("")),
+        None => dir,
+    };
+    if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        root.to_path_buf()
+    }
+}
+
+/// Recursively collect image files below `dir`
+fn collect_images(dir: &Path, images: &mut Vec<PathBuf>) {
+    collect_files(dir, &IMAGE_EXTENSIONS, images);
+}
+
+/// Recursively collect files below `dir` with one of `extensions` (lowercase)
+pub(crate) fn collect_files(dir: &Path, extensions: &[&str], files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, extensions, files);
+        } else if let Some(ext) = path.extension() {
+            let ext = ext.to_string_lossy().to_lowercase();
+            if extensions.contains(&ext.as_str()) {
+                files.push(path);
+            }
+        }
+    }
+}
+
+/// Standard split names, in audit order
+pub const SPLITS: [&str; 3] = ["train", "val", "test"];
+
+/// Folder names a split may have, since Roboflow calls `val` "valid"
+fn split_dir_names(split: &str) -> Vec<&str> {
+    match split {
+        "val" => vec!["val", "valid"],
+        split => vec![split],
+    }
+}
+
+/// Several splits of one dataset audited together
+pub struct MultiSplitDataset {
+    pub splits: HashMap<String, YoloDataset>,
+}
+
+impl MultiSplitDataset {
+    /// Load splits from a `--splits` value: `all` or a comma-separated list
+    ///
+    /// `all` loads whichever standard splits exist; explicitly named splits
+    /// must all be present.
+    pub fn load(path: &Path, spec: &str) -> Result<Self> {
+        let mut splits = HashMap::new();
+
+        if spec.trim() == "all" {
+            for split in SPLITS {
+                if let Ok(dataset) = YoloDataset::load_split(path, split) {
+                    splits.insert(split.to_string(), dataset);
+                }
+            }
+            if splits.is_empty() {
+                anyhow::bail!("No train/val/test splits found in {}", path.display());
+            }
+        } else {
+            for split in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+                splits.insert(split.to_string(), YoloDataset::load_split(path, split)?);
+            }
+            if splits.is_empty() {
+                anyhow::bail!("No splits given in --splits");
+            }
+        }
+
+        Ok(Self { splits })
+    }
+
+    /// Split names in a stable order: standard splits first, then the rest alphabetically
+    pub fn split_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.splits.keys().cloned().collect();
+        names.sort_by_key(|n| {
+            (
+                SPLITS.iter().position(|s| s == n).unwrap_or(SPLITS.len()),
+                n.clone(),
+            )
+        });
+        names
+    }
+}
+
+/// Label files and images that don't pair up, from `YoloDataset::consistency_check`
+#[derive(Debug, Clone, Default)]
+pub struct ConsistencyReport {
+    /// Label files without an image, e.g. left behind after images were deleted
+    pub orphaned_labels: Vec<PathBuf>,
+    /// Images without a label file
+    pub missing_labels: Vec<PathBuf>,
+    /// Images whose label file exists but has no rows
+    ///
+    /// Trainers treat these like missing labels, but an empty file usually
+    /// says the image was reviewed and holds no objects.
+    pub empty_labels: Vec<PathBuf>,
+}
+
+impl ConsistencyReport {
+    pub fn is_empty(&self) -> bool {
+        self.orphaned_labels.is_empty()
+            && self.missing_labels.is_empty()
+            && self.empty_labels.is_empty()
+    }
+}
+
+/// Problems with a dataset's class names, from `YoloDataset::class_name_check`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassNameReport {
+    /// Names declared under several ids, ignoring case, with those ids
+    pub duplicates: Vec<(String, Vec<i32>)>,
+    /// Ids below the highest declared one that have no name
+    pub missing_ids: Vec<i32>,
+}
+
+impl ClassNameReport {
+    pub fn is_empty(&self) -> bool {
+        self.duplicates.is_empty() && self.missing_ids.is_empty()
+    }
+
+    /// One message per duplicated name, and one for the missing ids
+    pub fn warnings(&self) -> Vec<String> {
+        let join = |ids: &[i32]| {
+            ids.iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut warnings: Vec<String> = self
+            .duplicates
+            .iter()
+            .map(|(name, ids)| format!("Class '{}' is declared under ids {}", name, join(ids)))
+            .collect();
+        if !self.missing_ids.is_empty() {
+            warnings.push(format!(
+                "No class name for ids {}; was a class deleted while labels still use its id?",
+                join(&self.missing_ids)
+            ));
+        }
+        warnings
+    }
+}
+
+/// Label row that could not be parsed
+#[derive(Debug, Clone)]
+pub struct LabelError {
+    pub line_num: usize,
+    /// The row as written, without surrounding whitespace
+    pub content: String,
+    pub reason: String,
+}
+
+/// Class id, box, polygon points and stored confidence of a label row
+type LabelRow = (i32, BoundingBox, Option<Vec<(f32, f32)>>, Option<f32>);
+
+/// Least IoU between a row's leading box and the box enclosing the points after
+/// it for the row to be read as a box followed by a polygon
+const BOX_POLYGON_MIN_IOU: f32 = 0.7;
+
+/// Parse one label row; blank rows yield `None`
+///
+/// A fifth value after the box is the confidence of a pre-annotation.
+fn parse_label_row(line: &str) -> std::result::Result<Option<LabelRow>, String> {
+    let mut parts = line.split_whitespace();
+    let Some(class_field) = parts.next() else {
+        return Ok(None);
+    };
+    let class_id = class_field
+        .parse::<i32>()
+        .map_err(|_| format!("Invalid class id '{}'", class_field))?;
+
+    let coords = parts
+        .map(|p| {
+            p.parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .ok_or_else(|| format!("Non-numeric coordinate '{}'", p))
+        })
+        .collect::<std::result::Result<Vec<f32>, String>>()?;
+
+    match coords.len() {
+        4 | 5 => {
+            let confidence = coords.get(4).copied();
+            if let Some(c) = confidence.filter(|c| !(0.0..=1.0).contains(c)) {
+                return Err(format!("Confidence {} is outside 0-1", c));
+            }
+            Ok(Some((
+                class_id,
+                BoundingBox::new(coords[0], coords[1], coords[2], coords[3]),
+                None,
+                confidence,
+            )))
+        }
+        n if n >= 6 && n % 2 == 0 => {
+            if let Some((bbox, points)) = box_with_polygon(&coords) {
+                return Ok(Some((class_id, bbox, Some(points), None)));
+            }
+            let points: Vec<(f32, f32)> = coords.chunks(2).map(|c| (c[0], c[1])).collect();
+            Ok(Some((
+                class_id,
+                BoundingBox::from_points(&points),
+                Some(points),
+                None,
+            )))
+        }
+        n => Err(format!(
+            "Expected 4 box values (plus an optional confidence) or an even number (at least 6) of polygon coordinates, found {}",
+            n
+        )),
+    }
+}
+
+/// Read `coords` as `x y w h` followed by polygon points, if the two agree
+///
+/// Polygon-only rows with five or more points have the same shape, so the box
+/// must roughly match the polygon's extent for the row to be read this way.
+fn box_with_polygon(coords: &[f32]) -> Option<(BoundingBox, Vec<(f32, f32)>)> {
+    if coords.len() < 10 {
+        return None;
+    }
+    let bbox = BoundingBox::new(coords[0], coords[1], coords[2], coords[3]);
+    let points: Vec<(f32, f32)> = coords[4..].chunks(2).map(|c| (c[0], c[1])).collect();
+    (bbox.iou(&BoundingBox::from_points(&points)) >= BOX_POLYGON_MIN_IOU).then_some((bbox, points))
+}
+
+/// How a label file's boxes deviate from normalized `x_center y_center width height`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoordinateMistake {
+    /// Coordinates in pixels rather than fractions of the image size
+    Pixels,
+    /// Corners `x1 y1 x2 y2` written where center and size belong
+    Corners,
+}
+
+impl CoordinateMistake {
+    /// Guess from the annotations of one label file; `None` when they look normalized
+    ///
+    /// Pixels: most coordinates exceed 1. Corners: every box lies within 0-1
+    /// with its "width" and "height" above its "center", as `x2 > x1` always
+    /// is, and most boxes read that way reach past the image edge.
+    pub fn detect(annotations: &[Annotation]) -> Option<Self> {
+        let values: Vec<f32> = annotations
+            .iter()
+            .flat_map(|a| [a.bbox.x, a.bbox.y, a.bbox.w, a.bbox.h])
+            .collect();
+        if values.is_empty() {
+            return None;
+        }
+        if values.iter().filter(|v| **v > 1.0).count() * 2 > values.len() {
+            return Some(Self::Pixels);
+        }
+
+        let boxes: Vec<&BoundingBox> = annotations
+            .iter()
+            .filter(|a| a.polygon.is_none())
+            .map(|a| &a.bbox)
+            .collect();
+        let corner_like = boxes.iter().all(|b| {
+            [b.x, b.y, b.w, b.h].iter().all(|v| (0.0..=1.0).contains(v)) && b.w > b.x && b.h > b.y
+        });
+        let past_edge = boxes
+            .iter()
+            .filter(|b| {
+                let (_, _, x2, y2) = b.to_xyxy();
+                x2 > 1.0 || y2 > 1.0
+            })
+            .count();
+        (!boxes.is_empty() && corner_like && past_edge * 2 > boxes.len()).then_some(Self::Corners)
+    }
+}
+
+/// Scale boxes and polygons given in pixels of a `width` x `height` image to 0-1
+pub fn normalize_pixel_coords(annotations: &mut [Annotation], width: u32, height: u32) {
+    let (w, h) = (width.max(1) as f32, height.max(1) as f32);
+    for ann in annotations {
+        let bbox = &mut ann.bbox;
+        *bbox = BoundingBox::new(bbox.x / w, bbox.y / h, bbox.w / w, bbox.h / h);
+        if let Some(points) = &mut ann.polygon {
+            for (x, y) in points {
+                *x /= w;
+                *y /= h;
+            }
+        }
+    }
+}
+
+/// Upper bounds of the box area ranges in `DatasetStats::area_buckets`, as
+/// fractions of the image; NaN areas fall in none
+const AREA_BUCKETS: [(f32, &str); 5] = [
+    (0.001, "<0.1%"),
+    (0.01, "0.1-1%"),
+    (0.1, "1-10%"),
+    (0.5, "10-50%"),
+    (f32::INFINITY, ">50%"),
+];
+
+/// Population mean and standard deviation
+fn mean_std(values: &[f32]) -> (f32, f32) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+    (mean, var.sqrt())
+}
+
+/// Annotation formats `gt-audit convert` reads and writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    /// `images/` and `labels/*.txt` with normalized boxes, plus `classes.txt`
+    Yolo,
+    /// A single `instances.json` with pixel boxes, images under `images/`
+    Coco,
+    /// Pascal VOC: `Annotations/*.xml` and `JPEGImages/`
+    Voc,
+}
+
+impl std::str::FromStr for DatasetFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "yolo" => Ok(Self::Yolo),
+            "coco" => Ok(Self::Coco),
+            "voc" => Ok(Self::Voc),
+            other => anyhow::bail!(
+                "Unknown dataset format '{}' (expected yolo, coco or voc)",
+                other
+            ),
+        }
+    }
+}
+
+/// COCO file name used when reading and writing
+pub const COCO_FILE: &str = "instances.json";
+
+/// One image and its annotations, independent of the on-disk format
+#[derive(Debug, Clone)]
+pub struct LabeledImage {
+    /// Path relative to the images directory, with `/` separators
+    pub key: String,
+    /// Image file to copy into the converted dataset
+    pub source: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// Boxes are normalized; `class_id` indexes `LabeledDataset::class_names`
+    pub annotations: Vec<Annotation>,
+}
+
+/// A dataset in the interchange form used for format conversion
+#[derive(Debug, Clone, Default)]
+pub struct LabeledDataset {
+    pub class_names: Vec<String>,
+    pub images: Vec<LabeledImage>,
+}
+
+impl LabeledDataset {
+    pub fn load(path: &Path, format: DatasetFormat) -> Result<Self> {
+        match format {
+            DatasetFormat::Yolo => Self::load_yolo(path),
+            DatasetFormat::Coco => Self::load_coco(path),
+            DatasetFormat::Voc => Self::load_voc(path),
+        }
+    }
+
+    pub fn annotation_count(&self) -> usize {
+        self.images.iter().map(|i| i.annotations.len()).sum()
+    }
+
+    fn load_yolo(path: &Path) -> Result<Self> {
+        Self::from_yolo(&YoloDataset::load(path)?)
+    }
+
+    /// Read the image sizes and annotations of a loaded YOLO dataset
+    pub fn from_yolo(dataset: &YoloDataset) -> Result<Self> {
+        let max_id = dataset.class_names.keys().copied().max().unwrap_or(-1);
+
+        let mut images = Vec::new();
+        for image_path in dataset.get_images() {
+            let (width, height) = image::image_dimensions(&image_path)
+                .with_context(|| format!("Failed to read image: {}", image_path.display()))?;
+            let mut annotations = dataset.load_annotations(&image_path);
+            // Negative ids have no class to convert to
+            annotations.retain(|a| a.class_id >= 0);
+            images.push(LabeledImage {
+                key: dataset.image_key(&image_path),
+                source: image_path,
+                width,
+                height,
+                annotations,
+            });
+        }
+
+        // Label ids beyond the declared classes keep their generated names
+        let max_id = images
+            .iter()
+            .flat_map(|i| &i.annotations)
+            .map(|a| a.class_id)
+            .fold(max_id, i32::max);
+        let class_names = (0..=max_id).map(|id| dataset.get_class_name(id)).collect();
+
+        Ok(Self {
+            class_names,
+            images,
+        })
+    }
+
+    fn load_coco(path: &Path) -> Result<Self> {
+        let json_path = if path.is_dir() {
+            path.join(COCO_FILE)
+        } else {
+            path.to_path_buf()
+        };
+        let root = json_path.parent().unwrap_or(Path::new("."));
+        let content = fs::read_to_string(&json_path)
+            .with_context(|| format!("Failed to read COCO file: {}", json_path.display()))?;
+        let coco: CocoFile = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse COCO file: {}", json_path.display()))?;
+
+        let mut categories = coco.categories;
+        categories.sort_by_key(|c| c.id);
+        let class_ids: HashMap<i64, i32> = categories
+            .iter()
+            .enumerate()
+            .map(|(idx, c)| (c.id, idx as i32))
+            .collect();
+        let class_names: Vec<String> = categories.into_iter().map(|c| c.name).collect();
+
+        let mut by_image: HashMap<i64, Vec<&CocoAnnotation>> = HashMap::new();
+        for ann in &coco.annotations {
+            by_image.entry(ann.image_id).or_default().push(ann);
+        }
+
+        let mut images = Vec::new();
+        for image in &coco.images {
+            let (w, h) = (image.width as f32, image.height as f32);
+            let mut annotations = Vec::new();
+            for ann in by_image.remove(&image.id).unwrap_or_default() {
+                let class_id = *class_ids.get(&ann.category_id).with_context(|| {
+                    format!(
+                        "Annotation {} has unknown category {}",
+                        ann.id, ann.category_id
+                    )
+                })?;
+                let [x, y, bw, bh] = ann.bbox;
+                // A malformed polygon (odd length, under three points) is
+                // dropped; the box alone still describes the object
+                let polygon = ann
+                    .segmentation
+                    .as_ref()
+                    .and_then(|s| s.first())
+                    .filter(|p| p.len() >= 6 && p.len() % 2 == 0)
+                    .map(|p| p.chunks(2).map(|c| (c[0] / w, c[1] / h)).collect());
+                annotations.push(Annotation {
+                    class_id,
+                    class_name: class_names[class_id as usize].clone(),
+                    bbox: BoundingBox::new((x + bw / 2.0) / w, (y + bh / 2.0) / h, bw / w, bh / h),
+                    line_num: annotations.len() + 1,
+                    polygon,
+                    gt_confidence: ann.score,
+                });
+            }
+
+            let nested = root.join("images").join(&image.file_name);
+            images.push(LabeledImage {
+                key: image.file_name.replace('\\', "/"),
+                source: if nested.exists() {
+                    nested
+                } else {
+                    root.join(&image.file_name)
+                },
+                width: image.width,
+                height: image.height,
+                annotations,
+            });
+        }
+
+        Ok(Self {
+            class_names,
+            images,
+        })
+    }
+
+    fn load_voc(path: &Path) -> Result<Self> {
+        let annotations_dir = path.join("Annotations");
+        let mut xml_paths: Vec<PathBuf> = fs::read_dir(&annotations_dir)
+            .with_context(|| {
+                format!(
+                    "Annotation directory not found: {}",
+                    annotations_dir.display()
+                )
+            })?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("xml"))
+            .collect();
+        xml_paths.sort();
+
+        // Boxes per file as (class name, xmin, ymin, xmax, ymax)
+        type VocObject = (String, f32, f32, f32, f32);
+        let mut parsed: Vec<(String, u32, u32, Vec<VocObject>)> = Vec::new();
+        for xml_path in &xml_paths {
+            let xml = fs::read_to_string(xml_path)
+                .with_context(|| format!("Failed to read {}", xml_path.display()))?;
+            let context = || format!("Malformed VOC annotation: {}", xml_path.display());
+            let filename = xml_text(&xml, "filename").with_context(context)?;
+            let size = xml_element(&xml, "size").with_context(context)?;
+            let width: u32 = xml_number(size, "width").with_context(context)?;
+            let height: u32 = xml_number(size, "height").with_context(context)?;
+
+            let mut objects = Vec::new();
+            for object in xml_elements(&xml, "object") {
+                let name = xml_text(object, "name").with_context(context)?;
+                let bndbox = xml_element(object, "bndbox").with_context(context)?;
+                objects.push((
+                    name,
+                    xml_number(bndbox, "xmin").with_context(context)?,
+                    xml_number(bndbox, "ymin").with_context(context)?,
+                    xml_number(bndbox, "xmax").with_context(context)?,
+                    xml_number(bndbox, "ymax").with_context(context)?,
+                ));
+            }
+            parsed.push((filename, width, height, objects));
+        }
+
+        let mut class_names: Vec<String> = parsed
+            .iter()
+            .flat_map(|(_, _, _, objects)| objects.iter().map(|o| o.0.clone()))
+            .collect();
+        class_names.sort();
+        class_names.dedup();
+
+        let images = parsed
+            .into_iter()
+            .map(|(filename, width, height, objects)| {
+                let (w, h) = (width as f32, height as f32);
+                let annotations = objects
+                    .into_iter()
+                    .enumerate()
+                    .map(|(idx, (name, xmin, ymin, xmax, ymax))| Annotation {
+                        class_id: class_names.binary_search(&name).unwrap_or(0) as i32,
+                        class_name: name,
+                        bbox: BoundingBox::new(
+                            (xmin + xmax) / 2.0 / w,
+                            (ymin + ymax) / 2.0 / h,
+                            (xmax - xmin) / w,
+                            (ymax - ymin) / h,
+                        ),
+                        line_num: idx + 1,
+                        polygon: None,
+                        gt_confidence: None,
+                    })
+                    .collect();
+                LabeledImage {
+                    source: path.join("JPEGImages").join(&filename),
+                    key: filename,
+                    width,
+                    height,
+                    annotations,
+                }
+            })
+            .collect();
+
+        Ok(Self {
+            class_names,
+            images,
+        })
+    }
+}
+
+/// Writes a `LabeledDataset` in one annotation format
+pub trait DatasetWriter {
+    /// Write annotations and copies of the images under `output`
+    fn write(&self, dataset: &LabeledDataset, output: &Path) -> Result<()>;
+}
+
+/// Writer for the format
+pub fn writer_for(format: DatasetFormat) -> Box<dyn DatasetWriter> {
+    match format {
+        DatasetFormat::Yolo => Box::new(YoloWriter),
+        DatasetFormat::Coco => Box::new(CocoWriter),
+        DatasetFormat::Voc => Box::new(VocWriter),
+    }
+}
+
+/// Copy an image to `dest`, creating parent directories
+fn copy_image(image: &LabeledImage, dest: &Path) -> Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(&image.source, dest)
+        .with_context(|| format!("Failed to copy image: {}", image.source.display()))?;
+    Ok(())
+}
+
+/// `images/`, `labels/` and `classes.txt`, readable by `YoloDataset::load`
+pub struct YoloWriter;
+
+impl DatasetWriter for YoloWriter {
+    fn write(&self, dataset: &LabeledDataset, output: &Path) -> Result<()> {
+        fs::create_dir_all(output)?;
+        fs::write(
+            output.join("classes.txt"),
+            dataset.class_names.join("\n") + "\n",
+        )?;
+
+        for image in &dataset.images {
+            copy_image(image, &output.join("images").join(&image.key))?;
+            let label_path = output
+                .join("labels")
+                .join(Path::new(&image.key).with_extension("txt"));
+            write_yolo_labels(&image.annotations, &label_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write `annotations` as a YOLO label file, one `Annotation::to_yolo_line`
+/// per row, creating parent directories
+pub fn write_yolo_labels(annotations: &[Annotation], path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content: String = annotations
+        .iter()
+        .map(|ann| ann.to_yolo_line() + "\n")
+        .collect();
+    fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// A single `instances.json` with images copied under `images/`
+pub struct CocoWriter;
+
+impl DatasetWriter for CocoWriter {
+    fn write(&self, dataset: &LabeledDataset, output: &Path) -> Result<()> {
+        fs::create_dir_all(output)?;
+        for image in &dataset.images {
+            copy_image(image, &output.join("images").join(&image.key))?;
+        }
+        write_coco(dataset, &output.join(COCO_FILE))
+    }
+}
+
+/// Write the annotations of `dataset` to a COCO JSON file
+///
+/// Image `file_name`s are the image keys, relative to the images directory.
+pub fn write_coco(dataset: &LabeledDataset, path: &Path) -> Result<()> {
+    let mut coco = CocoFile {
+        images: Vec::new(),
+        annotations: Vec::new(),
+        categories: dataset
+            .class_names
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| CocoCategory {
+                id: idx as i64 + 1,
+                name: name.clone(),
+            })
+            .collect(),
+    };
+
+    for (idx, image) in dataset.images.iter().enumerate() {
+        let image_id = idx as i64 + 1;
+        let (w, h) = (image.width as f32, image.height as f32);
+        coco.images.push(CocoImage {
+            id: image_id,
+            file_name: image.key.clone(),
+            width: image.width,
+            height: image.height,
+        });
+        for ann in &image.annotations {
+            let (x1, y1, _, _) = ann.bbox.to_xyxy();
+            let bbox = [x1 * w, y1 * h, ann.bbox.w * w, ann.bbox.h * h];
+            coco.annotations.push(CocoAnnotation {
+                id: coco.annotations.len() as i64 + 1,
+                image_id,
+                category_id: ann.class_id as i64 + 1,
+                bbox,
+                area: Some(bbox[2] * bbox[3]),
+                iscrowd: Some(0),
+                segmentation: ann
+                    .polygon
+                    .as_ref()
+                    .map(|points| vec![points.iter().flat_map(|(x, y)| [x * w, y * h]).collect()]),
+                score: ann.gt_confidence,
+            });
+        }
+    }
+
+    fs::write(path, serde_json::to_string_pretty(&coco)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Totals reported after an export
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    pub images_written: usize,
+    /// Flagged images left out by `exclude_flagged`
+    pub images_skipped: usize,
+    pub annotations_written: usize,
+    /// Annotations dropped for a high-severity issue on their label line
+    pub annotations_stripped: usize,
+}
+
+/// Exports the annotations of a dataset that an audit found no fault with
+///
+/// Images are matched to the audit's results by their key relative to the
+/// images directory.
+pub struct DatasetExporter<'a> {
+    dataset: &'a YoloDataset,
+    result: &'a AuditResult,
+    /// Leave flagged images out entirely, rather than only stripping the
+    /// annotations with high-severity issues
+    exclude_flagged: bool,
+}
+
+impl<'a> DatasetExporter<'a> {
+    pub fn new(dataset: &'a YoloDataset, result: &'a AuditResult, exclude_flagged: bool) -> Self {
+        Self {
+            dataset,
+            result,
+            exclude_flagged,
+        }
+    }
+
+    /// The dataset with flagged images or annotations removed
+    pub fn clean_dataset(&self) -> Result<(LabeledDataset, ExportSummary)> {
+        let mut dataset = LabeledDataset::from_yolo(self.dataset)?;
+        let mut summary = ExportSummary::default();
+
+        let flagged: HashMap<&str, HashSet<usize>> = self
+            .result
+            .flagged_images()
+            .into_iter()
+            .map(|image| {
+                let lines = image
+                    .issues
+                    .iter()
+                    .filter(|issue| issue.severity == IssueSeverity::High)
+                    .filter_map(|issue| issue.line_num)
+                    .collect();
+                (image.filename.as_str(), lines)
+            })
+            .collect();
+
+        dataset.images.retain_mut(|image| {
+            let Some(lines) = flagged.get(image.key.as_str()) else {
+                return true;
+            };
+            if self.exclude_flagged {
+                summary.images_skipped += 1;
+                return false;
+            }
+            let before = image.annotations.len();
+            image
+                .annotations
+                .retain(|ann| !lines.contains(&ann.line_num));
+            summary.annotations_stripped += before - image.annotations.len();
+            true
+        });
+
+        summary.images_written = dataset.images.len();
+        summary.annotations_written = dataset.annotation_count();
+        Ok((dataset, summary))
+    }
+
+    /// Write the clean annotations to a COCO JSON file
+    pub fn write_coco(&self, path: &Path) -> Result<ExportSummary> {
+        let (dataset, summary) = self.clean_dataset()?;
+        write_coco(&dataset, path)?;
+        Ok(summary)
+    }
+}
+
+/// Pascal VOC `Annotations/*.xml` with images copied to `JPEGImages/`
+///
+/// Image names are flattened to their file name, as VOC has no subdirectories.
+pub struct VocWriter;
+
+impl DatasetWriter for VocWriter {
+    fn write(&self, dataset: &LabeledDataset, output: &Path) -> Result<()> {
+        let annotations_dir = output.join("Annotations");
+        fs::create_dir_all(&annotations_dir)?;
+
+        for image in &dataset.images {
+            let filename = Path::new(&image.key)
+                .file_name()
+                .map(|f| f.to_string_lossy().to_string())
+                .unwrap_or_else(|| image.key.clone());
+            copy_image(image, &output.join("JPEGImages").join(&filename))?;
+
+            let (w, h) = (image.width as f32, image.height as f32);
+            let mut xml = String::from("<annotation>\n");
+            xml.push_str("  <folder>JPEGImages</folder>\n");
+            xml.push_str(&format!(
+                "  <filename>{}</filename>\n",
+                xml_escape(&filename)
+            ));
+            xml.push_str(&format!(
+                "  <size>\n    <width>{}</width>\n    <height>{}</height>\n    <depth>3</depth>\n  </size>\n",
+                image.width, image.height
+            ));
+            for ann in &image.annotations {
+                let (x1, y1, x2, y2) = ann.bbox.to_xyxy();
+                xml.push_str(&format!(
+                    "  <object>\n    <name>{}</name>\n    <difficult>0</difficult>\n    <bndbox>\n      <xmin>{:.1}</xmin>\n      <ymin>{:.1}</ymin>\n      <xmax>{:.1}</xmax>\n      <ymax>{:.1}</ymax>\n    </bndbox>\n  </object>\n",
+                    xml_escape(&ann.class_name),
+                    x1 * w,
+                    y1 * h,
+                    x2 * w,
+                    y2 * h
+                ));
+            }
+            xml.push_str("</annotation>\n");
+
+            let xml_path = annotations_dir.join(Path::new(&filename).with_extension("xml"));
+            fs::write(xml_path, xml)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CocoFile {
+    images: Vec<CocoImage>,
+    annotations: Vec<CocoAnnotation>,
+    categories: Vec<CocoCategory>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CocoImage {
+    id: i64,
+    file_name: String,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CocoAnnotation {
+    id: i64,
+    image_id: i64,
+    category_id: i64,
+    /// `[x, y, width, height]` in pixels, from the top-left corner
+    bbox: [f32; 4],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    area: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    iscrowd: Option<u8>,
+    /// Polygons as flat `[x1, y1, x2, y2, ...]` pixel lists; RLE masks are not supported
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_polygons"
+    )]
+    segmentation: Option<Vec<Vec<f32>>>,
+    /// Confidence of annotations bootstrapped from model predictions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    score: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CocoCategory {
+    id: i64,
+    name: String,
+}
+
+/// Read polygon segmentations, ignoring RLE masks
+fn deserialize_polygons<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<Vec<Vec<f32>>>, D::Error> {
+    let value = serde_json::Value::deserialize(deserializer)?;
+    Ok(serde_json::from_value(value).ok())
+}
+
+/// The elements named `tag` in a flat XML document, as their inner text
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let inner = &rest[start + open.len()..];
+        let Some(end) = inner.find(&close) else {
+            break;
+        };
+        elements.push(&inner[..end]);
+        rest = &inner[end + close.len()..];
+    }
+    elements
+}
+
+fn xml_element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    xml_elements(xml, tag).into_iter().next()
+}
+
+/// Unescaped text of the first element named `tag`
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    xml_element(xml, tag).map(|text| {
+        text.trim()
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    })
+}
+
+fn xml_number<T: std::str::FromStr>(xml: &str, tag: &str) -> Option<T> {
+    xml_text(xml, tag)?.parse().ok()
 }
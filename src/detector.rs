@@ -1,155 +1,472 @@
 //! Detection methods for ground truth validation
 
 use anyhow::{Context, Result};
+use base64::Engine;
 use image::{DynamicImage, GenericImageView};
 use ndarray::{Array4, ArrayD};
 use ort::session::{builder::GraphOptimizationLevel, Session};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::class_map::{self, ClassMapping, ClassSource, ClassSynonyms, UnmappedClasses};
+use crate::model_info::ModelInfo;
+use crate::models::{BoundingBox, Detection};
+use crate::policy::SeverityPolicy;
+
+/// Non-maximum suppression strategy
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub enum NmsMethod {
+    /// Drop boxes overlapping a higher-scoring box of the same class
+    #[default]
+    Hard,
+    /// Gaussian Soft-NMS: decay overlapping scores by `exp(-iou^2 / sigma)`
+    Soft { sigma: f32 },
+}
+
+/// Which overlapping detections NMS lets suppress each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum NmsMode {
+    /// Only boxes of the same class
+    #[default]
+    PerClass,
+    /// Boxes of any class, keeping the most confident: for models that emit
+    /// several classes on one object (say `car` and `truck`), or datasets of
+    /// mutually exclusive classes
+    Agnostic,
+}
+
+impl std::str::FromStr for NmsMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "per-class" => Ok(Self::PerClass),
+            "agnostic" => Ok(Self::Agnostic),
+            other => anyhow::bail!(
+                "Unknown NMS mode '{}' (expected per-class or agnostic)",
+                other
+            ),
+        }
+    }
+}
+
+/// Hardware the ONNX session runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Device {
+    #[default]
+    Cpu,
+    /// NVIDIA GPU via the CUDA execution provider (requires the `cuda` feature)
+    Cuda { device_id: u32 },
+}
+
+impl std::str::FromStr for Device {
+    type Err = anyhow::Error;
+
+    /// Parse `cpu`, `cuda` or `cuda:<id>`
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "cpu" => Ok(Self::Cpu),
+            "cuda" => Ok(Self::Cuda { device_id: 0 }),
+            other => match other.strip_prefix("cuda:") {
+                Some(id) => Ok(Self::Cuda {
+                    device_id: id
+                        .parse()
+                        .with_context(|| format!("Invalid CUDA device id: {}", id))?,
+                }),
+                None => anyhow::bail!("Unknown device '{}' (expected cpu, cuda or cuda:N)", s),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Device {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Device::Cpu => write!(f, "cpu"),
+            Device::Cuda { device_id } => write!(f, "cuda:{}", device_id),
+        }
+    }
+}
+
+/// Detector behind the `zero-shot` method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum ZeroShotBackend {
+    /// Label sanity checks and image heuristics, no model
+    #[default]
+    Heuristic,
+    /// Grounding DINO prompted with the dataset's class names
+    GroundingDino,
+}
+
+impl std::str::FromStr for ZeroShotBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "heuristic" => Ok(Self::Heuristic),
+            "grounding-dino" => Ok(Self::GroundingDino),
+            other => anyhow::bail!(
+                "Unknown zero-shot backend '{}' (expected heuristic or grounding-dino)",
+                other
+            ),
+        }
+    }
+}
+
+/// Output layout of a YOLO ONNX export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum ModelFormat {
+    /// YOLOv8/v11: `[batch, 4 + classes, boxes]` with class scores only
+    #[default]
+    YoloV8,
+    /// YOLOv5: `[batch, boxes, 5 + classes]` with an objectness score at index 4
+    YoloV5,
+    /// NMS inside the graph (`nms=True` exports, YOLOv10, RT-DETR):
+    /// `[batch, boxes, 6]` rows of `x1, y1, x2, y2, score, class`
+    EndToEnd,
+}
+
+impl ModelFormat {
+    /// Channels before the class scores; end-to-end rows carry a class id instead
+    pub(crate) fn class_offset(self) -> Option<usize> {
+        match self {
+            ModelFormat::YoloV8 => Some(4),
+            ModelFormat::YoloV5 => Some(5),
+            ModelFormat::EndToEnd => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ModelFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelFormat::YoloV8 => write!(f, "yolov8"),
+            ModelFormat::YoloV5 => write!(f, "yolov5"),
+            ModelFormat::EndToEnd => write!(f, "end2end"),
+        }
+    }
+}
 
-use crate::models::{
-    Annotation, BoundingBox, Detection, ImageResult, Issue, IssueSeverity, IssueType,
-};
+impl std::str::FromStr for ModelFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "yolov8" | "v8" => Ok(Self::YoloV8),
+            "yolov5" | "v5" => Ok(Self::YoloV5),
+            "end2end" => Ok(Self::EndToEnd),
+            other => anyhow::bail!(
+                "Unknown model format '{}' (expected yolov8, yolov5 or end2end)",
+                other
+            ),
+        }
+    }
+}
 
 /// Configuration for detectors
+#[derive(Debug, Clone)]
 pub struct DetectorConfig {
     pub confidence_threshold: f32,
     pub iou_threshold: f32,
     pub model_path: Option<PathBuf>,
+    /// Output layout of the YOLO model; detected from its output shape if `None`
+    pub model_format: Option<ModelFormat>,
+    pub nms_method: NmsMethod,
+    /// Whether NMS suppresses boxes of other classes
+    pub nms_mode: NmsMode,
+    pub device: Device,
+    /// Images per inference call; independent of the device
+    pub batch_size: usize,
+    /// Laplacian variance below which an image is flagged as blurry (0 disables)
+    pub blur_threshold: f32,
+    /// Mean luma below which an image is flagged as too dark (0 disables)
+    pub brightness_min: f32,
+    /// Mean luma above which an image is flagged as overexposed (255 disables)
+    pub brightness_max: f32,
+    /// GT box area in pixels below which the box is flagged as tiny (0 disables)
+    pub min_box_pixels: f32,
+    /// GT box side in pixels below which the box is flagged as tiny (0 disables)
+    pub min_box_side: f32,
+    /// Fraction of the image a GT box may cover before it is flagged (1 disables)
+    pub max_box_fraction: f32,
+    /// Flag single-color images, and GT boxes on blurry or single-color regions
+    pub quality_checks: bool,
+    /// Pixel standard deviation below which an image or box region counts as a single color
+    pub min_color_std: f32,
+    /// Laplacian variance below which a GT box region is flagged as blurry
+    pub crop_blur_threshold: f32,
+    /// Severity given to each issue type
+    pub severity_policy: SeverityPolicy,
+    /// Flag images whose detection confidences are suspiciously uniform (YOLO only)
+    pub check_confidence_calibration: bool,
+    /// Class names the model's detections are never attributed to
+    pub exclude_classes: Vec<String>,
+    /// Score a class's prompt tokens need for Grounding DINO to assign it
+    pub text_threshold: f32,
+    /// File naming the model's classes, overriding its ONNX metadata (YOLO only)
+    pub model_classes: Option<PathBuf>,
+    /// Detections of model classes the dataset lacks: dropped or reported
+    pub unmapped_classes: UnmappedClasses,
+    /// File of class synonym groups replacing the built-in ones
+    pub class_synonyms: Option<PathBuf>,
+    /// Base URL of the OpenAI-compatible API the `vlm` method calls
+    pub vlm_endpoint: String,
+    /// Vision model the `vlm` method asks for
+    pub vlm_model: String,
+    /// Rotate images as their EXIF orientation tag says before checking them
+    pub apply_exif_rotation: bool,
+    /// Also run the detector on EXIF-tagged images in the other orientation
+    pub compare_exif_orientation: bool,
 }
 
 /// Trait for detection methods
+///
+/// Detectors only find objects; matching them against the labels and
+/// deciding what is an issue is left to the `Auditor`.
 pub trait Detector: Send {
-    fn audit_image(
+    /// Objects in `image`; `class_names` are the dataset classes to look
+    /// for, which detectors with a fixed set of classes ignore
+    fn detect(&self, image: &DynamicImage, class_names: &[String]) -> Result<Vec<Detection>>;
+
+    /// Name recorded as the audit's method, with the model where there is
+    /// one so reports of different models tell apart
+    fn name(&self) -> String;
+
+    /// Detections for several images, one result per image
+    ///
+    /// Detectors that benefit from batched inference override this; the
+    /// default runs the images one at a time.
+    fn detect_batch(
         &self,
-        image_path: &Path,
-        annotations: &[Annotation],
-        class_names: &HashMap<i32, String>,
-    ) -> Result<ImageResult>;
+        images: &[DynamicImage],
+        class_names: &[String],
+    ) -> Vec<Result<Vec<Detection>>> {
+        images
+            .iter()
+            .map(|image| self.detect(image, class_names))
+            .collect()
+    }
 
-    fn detect(&self, image: &DynamicImage, class_names: &[String]) -> Result<Vec<Detection>>;
+    /// Whether `class_name` is a model class the dataset has no class for
+    fn is_unmapped(&self, _class_name: &str) -> bool {
+        false
+    }
 }
 
 /// YOLO-based detector using ONNX Runtime
 pub struct YoloDetector {
     session: Mutex<Session>,
     config: DetectorConfig,
-    model_class_names: Vec<String>,
+    class_mapping: ClassMapping,
+    /// Per model class index, whether the class is excluded
+    excluded: Vec<bool>,
+    /// Input `(width, height)` images are resized to
+    input_size: (u32, u32),
+    /// Output layout, as configured or detected
+    format: ModelFormat,
+    device: Device,
+    device_warning: Option<String>,
+    output_warning: Option<String>,
 }
 
 impl YoloDetector {
-    pub fn new(config: DetectorConfig, model_class_names: Vec<String>) -> Result<Self> {
+    /// Load the model and map its classes onto `dataset_class_names`
+    ///
+    /// The model's class names come from `config.model_classes`, else from
+    /// the ONNX metadata; without either, the model is assumed to predict the
+    /// dataset's classes in order. The input resolution comes from the model
+    /// as well (see `ModelInfo::input_size`).
+    pub fn new(config: DetectorConfig, dataset_class_names: Vec<String>) -> Result<Self> {
         let model_path = config
             .model_path
             .as_ref()
             .context("Model path required for YOLO detector")?;
 
-        println!("   Loading ONNX model: {}", model_path.display());
+        let synonyms = ClassSynonyms::from_config(config.class_synonyms.as_deref())?;
+        let (session, device, device_warning) = load_session(model_path, config.device)?;
+        let info = ModelInfo::read(&session);
 
-        let session = Session::builder()?
-            .with_optimization_level(GraphOptimizationLevel::Level3)?
-            .with_intra_threads(4)?
-            .commit_from_file(model_path)?;
+        let model_classes = match &config.model_classes {
+            Some(path) => Some((ClassSource::File, class_map::load_class_file(path)?)),
+            None => info
+                .class_names()
+                .transpose()?
+                .map(|names| (ClassSource::Metadata, names)),
+        };
+        let format = config
+            .model_format
+            .or(info.likely_format())
+            .unwrap_or_default();
+        let output_warning = output_warning(
+            &info,
+            format,
+            model_classes.as_ref(),
+            dataset_class_names.len(),
+        );
+        let class_mapping = match model_classes {
+            Some((source, names)) => {
+                ClassMapping::build(source, names, &dataset_class_names, &synonyms)
+            }
+            None => ClassMapping::identity(dataset_class_names),
+        };
 
-        println!("   Model loaded successfully");
+        let excluded = (0..class_mapping.model_classes.len())
+            .map(|c| {
+                let unmapped = class_mapping.targets[c].is_none();
+                config
+                    .exclude_classes
+                    .contains(&class_mapping.class_name(c))
+                    || (unmapped && config.unmapped_classes == UnmappedClasses::Ignore)
+            })
+            .collect();
 
         Ok(Self {
             session: Mutex::new(session),
             config,
-            model_class_names,
+            class_mapping,
+            excluded,
+            input_size: info.input_size(),
+            format,
+            device,
+            device_warning,
+            output_warning,
         })
     }
 
-    fn preprocess_image(&self, image: &DynamicImage) -> Array4<f32> {
-        let img_size = 640u32;
-        let resized = image.resize_exact(img_size, img_size, image::imageops::FilterType::Triangle);
-        let rgb = resized.to_rgb8();
+    /// How the model's classes map onto the dataset's
+    pub fn class_mapping(&self) -> &ClassMapping {
+        &self.class_mapping
+    }
+
+    /// Device the session actually runs on
+    pub fn device(&self) -> Device {
+        self.device
+    }
+
+    /// Why the requested device was replaced by the CPU, if it was
+    pub fn device_warning(&self) -> Option<&str> {
+        self.device_warning.as_deref()
+    }
 
-        let mut input = Array4::<f32>::zeros((1, 3, img_size as usize, img_size as usize));
+    /// How the model's output disagrees with the configured format, its class
+    /// names or the dataset, if it does
+    pub fn output_warning(&self) -> Option<&str> {
+        self.output_warning.as_deref()
+    }
 
-        for (x, y, pixel) in rgb.enumerate_pixels() {
-            input[[0, 0, y as usize, x as usize]] = pixel[0] as f32 / 255.0;
-            input[[0, 1, y as usize, x as usize]] = pixel[1] as f32 / 255.0;
-            input[[0, 2, y as usize, x as usize]] = pixel[2] as f32 / 255.0;
+    /// Input `(width, height)` images are resized to
+    pub fn input_size(&self) -> (u32, u32) {
+        self.input_size
+    }
+
+    /// Resize and normalize images into one NCHW input tensor
+    fn preprocess_images(&self, images: &[DynamicImage]) -> Array4<f32> {
+        let (width, height) = self.input_size;
+        let mut input = Array4::<f32>::zeros((images.len(), 3, height as usize, width as usize));
+
+        for (b, image) in images.iter().enumerate() {
+            tracing::trace!(
+                original = ?image.dimensions(),
+                input = ?(width, height),
+                "Resizing image for the model"
+            );
+            let resized = image.resize_exact(width, height, image::imageops::FilterType::Triangle);
+            let rgb = resized.to_rgb8();
+
+            for (x, y, pixel) in rgb.enumerate_pixels() {
+                input[[b, 0, y as usize, x as usize]] = pixel[0] as f32 / 255.0;
+                input[[b, 1, y as usize, x as usize]] = pixel[1] as f32 / 255.0;
+                input[[b, 2, y as usize, x as usize]] = pixel[2] as f32 / 255.0;
+            }
         }
 
         input
     }
 
+    /// Run the model once over a batch of images
+    fn run_batch(&self, images: &[DynamicImage]) -> Result<Vec<Vec<Detection>>> {
+        if images.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let input = self.preprocess_images(images);
+
+        // Create shape and flattened data for ort
+        let shape: Vec<i64> = input.shape().iter().map(|&x| x as i64).collect();
+        let data: Vec<f32> = input.into_raw_vec_and_offset().0;
+
+        // Create input tensor from shape and data
+        tracing::trace!(shape = ?shape, "Input tensor");
+        let input_tensor = ort::value::Tensor::from_array((shape.clone(), data))?;
+
+        // Run inference (lock the session for thread safety)
+        let mut session = self
+            .session
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock session: {}", e))?;
+        let outputs = session.run(ort::inputs!["images" => input_tensor])?;
+
+        // Get output tensor
+        let binding = outputs["output0"].try_extract_tensor::<f32>()?;
+        let (out_shape, out_data) = binding;
+        tracing::trace!(shape = ?&out_shape[..], "Output tensor");
+
+        // Convert to ndarray for processing
+        let output = ArrayD::from_shape_vec(
+            out_shape.iter().map(|&x| x as usize).collect::<Vec<_>>(),
+            out_data.to_vec(),
+        )?;
+
+        if output.ndim() == 3 && output.shape()[0] != images.len() {
+            anyhow::bail!(
+                "Model returned {} results for a batch of {} images; export it with a dynamic batch axis or use --batch-size 1",
+                output.shape()[0],
+                images.len()
+            );
+        }
+
+        Ok((0..images.len())
+            .map(|b| {
+                let (width, height) = images[b].dimensions();
+                self.postprocess_detections(&output, b, width, height)
+            })
+            .collect())
+    }
+
     fn postprocess_detections(
         &self,
         output: &ArrayD<f32>,
+        batch_idx: usize,
         _orig_width: u32,
         _orig_height: u32,
     ) -> Vec<Detection> {
-        let mut detections = Vec::new();
-        let conf_threshold = self.config.confidence_threshold;
-
-        // YOLOv8 output format: [1, num_classes + 4, num_detections]
-        // Transpose to [num_detections, num_classes + 4]
-        let output = output.view();
-
-        if output.ndim() < 2 {
-            return detections;
-        }
-
-        let shape = output.shape();
-        let num_classes = if shape.len() == 3 {
-            shape[1] - 4 // [1, classes+4, detections]
+        let detections = decode_output(
+            output,
+            batch_idx,
+            self.format,
+            self.input_size,
+            self.config.confidence_threshold,
+            &self.excluded,
+            |c| self.class_mapping.class_name(c),
+        );
+
+        // End-to-end exports already ran NMS inside the graph
+        if self.format == ModelFormat::EndToEnd {
+            detections
         } else {
-            return detections;
-        };
-
-        // Process each detection
-        let num_detections = shape[2];
-        for i in 0..num_detections {
-            // Get box coordinates (x_center, y_center, width, height)
-            let x = output[[0, 0, i]];
-            let y = output[[0, 1, i]];
-            let w = output[[0, 2, i]];
-            let h = output[[0, 3, i]];
-
-            // Find class with highest confidence
-            // Note: YOLOv8/v11 ONNX exports already apply sigmoid internally
-            let mut max_conf = 0.0f32;
-            let mut max_class = 0usize;
-            for c in 0..num_classes {
-                let conf = output[[0, 4 + c, i]];
-                if conf > max_conf {
-                    max_conf = conf;
-                    max_class = c;
-                }
-            }
-
-            if max_conf >= conf_threshold {
-                // Normalize coordinates to [0, 1]
-                let norm_x = x / 640.0;
-                let norm_y = y / 640.0;
-                let norm_w = w / 640.0;
-                let norm_h = h / 640.0;
-
-                let class_name = self
-                    .model_class_names
-                    .get(max_class)
-                    .cloned()
-                    .unwrap_or_else(|| format!("class_{}", max_class));
-
-                detections.push(Detection {
-                    class_name,
-                    confidence: max_conf,
-                    bbox: BoundingBox::new(norm_x, norm_y, norm_w, norm_h),
-                });
-            }
+            suppress(&self.config, detections)
         }
-
-        // Apply NMS
-        detections = self.non_max_suppression(detections);
-        detections
     }
 
-    fn non_max_suppression(&self, mut detections: Vec<Detection>) -> Vec<Detection> {
-        detections.sort_by(|a, b| b.confidence.partial_cmp(&a.confidence).unwrap());
+    /// Greedy NMS; boxes of other classes only suppress each other in agnostic `mode`
+    fn non_max_suppression(
+        mut detections: Vec<Detection>,
+        iou_threshold: f32,
+        mode: NmsMode,
+    ) -> Vec<Detection> {
+        detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
 
         let mut keep = Vec::new();
         let mut suppressed = vec![false; detections.len()];
@@ -164,9 +481,10 @@ impl YoloDetector {
                 if suppressed[j] {
                     continue;
                 }
-                if detections[i].class_name == detections[j].class_name {
+                if mode == NmsMode::Agnostic || detections[i].class_name == detections[j].class_name
+                {
                     let iou = detections[i].bbox.iou(&detections[j].bbox);
-                    if iou > self.config.iou_threshold {
+                    if iou > iou_threshold {
                         suppressed[j] = true;
                     }
                 }
@@ -176,300 +494,499 @@ impl YoloDetector {
         keep
     }
 
-    fn classes_equivalent(class1: &str, class2: &str) -> bool {
-        let c1 = class1.to_lowercase();
-        let c2 = class2.to_lowercase();
-
-        if c1 == c2 {
-            return true;
-        }
-
-        let groups: &[&[&str]] = &[
-            &[
-                "clothing", "dress", "suit", "jacket", "coat", "top", "shirt", "blouse",
-            ],
-            &["footwear", "boot", "shoe", "sandal", "high heels", "sneaker"],
-            &[
-                "bag",
-                "handbag",
-                "backpack",
-                "briefcase",
-                "luggage and bags",
-                "purse",
-            ],
-            &["pants", "jeans", "trousers", "shorts"],
-            &["person", "human", "man", "woman", "people", "boy", "girl"],
-        ];
+    /// Gaussian Soft-NMS (Bodla et al., 2017)
+    ///
+    /// Instead of discarding boxes that overlap a higher-scoring box of the
+    /// same class (any class in agnostic `mode`), their confidence is decayed by
+    /// `exp(-iou^2 / sigma)`. Boxes whose decayed confidence falls below
+    /// `score_threshold` are dropped.
+    fn soft_nms(
+        mut detections: Vec<Detection>,
+        sigma: f32,
+        score_threshold: f32,
+        mode: NmsMode,
+    ) -> Vec<Detection> {
+        let mut keep = Vec::new();
 
-        for group in groups {
-            let in_group1 = group.iter().any(|&g| c1.contains(g) || g.contains(&c1));
-            let in_group2 = group.iter().any(|&g| c2.contains(g) || g.contains(&c2));
-            if in_group1 && in_group2 {
-                return true;
+        while !detections.is_empty() {
+            let best_idx = detections
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.confidence.total_cmp(&b.confidence))
+                .map(|(i, _)| i)
+                .unwrap();
+            let best = detections.swap_remove(best_idx);
+
+            for det in detections.iter_mut() {
+                if mode == NmsMode::Agnostic || det.class_name == best.class_name {
+                    let iou = best.bbox.iou(&det.bbox);
+                    det.confidence *= (-(iou * iou) / sigma).exp();
+                }
             }
+            detections.retain(|d| d.confidence >= score_threshold);
+
+            keep.push(best);
         }
 
-        false
+        keep
     }
 }
 
 impl Detector for YoloDetector {
-    fn audit_image(
-        &self,
-        image_path: &Path,
-        annotations: &[Annotation],
-        _class_names: &HashMap<i32, String>,
-    ) -> Result<ImageResult> {
-        let filename = image_path
-            .file_name()
-            .map(|f| f.to_string_lossy().to_string())
+    fn name(&self) -> String {
+        let model = self
+            .config
+            .model_path
+            .as_deref()
+            .and_then(Path::file_name)
             .unwrap_or_default();
+        format!("yolo:{}", model.to_string_lossy())
+    }
 
-        // Load image
-        let image = image::open(image_path).context("Failed to load image")?;
-        let (_orig_width, _orig_height) = image.dimensions();
+    fn detect(&self, image: &DynamicImage, _class_names: &[String]) -> Result<Vec<Detection>> {
+        Ok(self
+            .run_batch(std::slice::from_ref(image))?
+            .pop()
+            .unwrap_or_default())
+    }
 
-        // Run detection
-        let detections = self.detect(&image, &[])?;
+    fn detect_batch(
+        &self,
+        images: &[DynamicImage],
+        _class_names: &[String],
+    ) -> Vec<Result<Vec<Detection>>> {
+        match self.run_batch(images) {
+            Ok(detections) => detections.into_iter().map(Ok).collect(),
+            Err(e) => {
+                let msg = e.to_string();
+                images
+                    .iter()
+                    .map(|_| Err(anyhow::anyhow!("Batched inference failed: {}", msg)))
+                    .collect()
+            }
+        }
+    }
 
-        let mut result = ImageResult::new(filename.clone(), annotations.len(), detections.len());
+    fn is_unmapped(&self, class_name: &str) -> bool {
+        self.class_mapping.is_unmapped(class_name)
+    }
+}
 
-        // Track matched GT annotations
-        let mut matched_gt: Vec<bool> = vec![false; annotations.len()];
+/// Create an ONNX Runtime session on the requested device
+///
+/// Returns the device actually used and, when it differs from the requested
+/// one, why.
+pub(crate) fn load_session(
+    model_path: &Path,
+    requested: Device,
+) -> Result<(Session, Device, Option<String>)> {
+    let (device, device_warning) = resolve_device(requested);
+
+    #[allow(unused_mut)]
+    let mut builder = Session::builder()?
+        .with_optimization_level(GraphOptimizationLevel::Level3)?
+        .with_intra_threads(4)?;
+
+    #[cfg(feature = "cuda")]
+    if let Device::Cuda { device_id } = device {
+        builder = builder.with_execution_providers([ort::ep::CUDA::default()
+            .with_device_id(device_id as i32)
+            .build()])?;
+    }
 
-        // Check each detection against GT
-        for det in &detections {
-            let mut best_iou = 0.0f32;
-            let mut best_gt_idx: Option<usize> = None;
+    let session = builder.commit_from_file(model_path)?;
 
-            for (idx, ann) in annotations.iter().enumerate() {
-                let iou = det.bbox.iou(&ann.bbox);
-                if iou > best_iou {
-                    best_iou = iou;
-                    best_gt_idx = Some(idx);
-                }
-            }
+    Ok((session, device, device_warning))
+}
 
-            if best_iou >= 0.3 {
-                if let Some(gt_idx) = best_gt_idx {
-                    let gt = &annotations[gt_idx];
-                    matched_gt[gt_idx] = true;
-
-                    // Check for class mismatch
-                    if !Self::classes_equivalent(&det.class_name, &gt.class_name) {
-                        result.add_issue(Issue {
-                            image: filename.clone(),
-                            severity: IssueSeverity::High,
-                            issue_type: IssueType::ClassMismatch,
-                            description: format!(
-                                "Model detects '{}' ({:.1}%), GT says '{}'",
-                                det.class_name,
-                                det.confidence * 100.0,
-                                gt.class_name
-                            ),
-                            gt_class: Some(gt.class_name.clone()),
-                            detected_class: Some(det.class_name.clone()),
-                            confidence: Some(det.confidence),
-                            iou: Some(best_iou),
-                            explanation: None,
-                            line_num: Some(gt.line_num),
-                        });
-                    }
-                }
-            } else {
-                // Detection with no matching GT - possible missing label
-                result.add_issue(Issue {
-                    image: filename.clone(),
-                    severity: IssueSeverity::Medium,
-                    issue_type: IssueType::MissingLabel,
-                    description: format!(
-                        "Model detects '{}' ({:.1}%) with no matching GT",
-                        det.class_name,
-                        det.confidence * 100.0
-                    ),
-                    gt_class: None,
-                    detected_class: Some(det.class_name.clone()),
-                    confidence: Some(det.confidence),
-                    iou: None,
-                    explanation: None,
-                    line_num: None,
-                });
+/// Detections in one image's rows of a YOLO output tensor, before NMS
+///
+/// Box coordinates are in input pixels and are normalized by `input_size`.
+/// Excluded classes can't claim a box, so they produce no detections.
+fn decode_output(
+    output: &ArrayD<f32>,
+    batch_idx: usize,
+    format: ModelFormat,
+    input_size: (u32, u32),
+    conf_threshold: f32,
+    excluded: &[bool],
+    class_name: impl Fn(usize) -> String,
+) -> Vec<Detection> {
+    let mut detections = Vec::new();
+    let input_width = input_size.0 as f32;
+    let input_height = input_size.1 as f32;
+
+    let shape = output.shape();
+    if shape.len() != 3 {
+        return detections;
+    }
+
+    let Some(offset) = format.class_offset() else {
+        // End-to-end output format: [batch, num_detections, 6] rows of
+        // x1, y1, x2, y2, score, class id, padded with zero-score rows
+        if shape[2] < 6 {
+            return detections;
+        }
+        for i in 0..shape[1] {
+            let value = |channel: usize| output[[batch_idx, i, channel]];
+            let confidence = value(4);
+            let class = value(5);
+            if confidence <= 0.0 || confidence < conf_threshold || class < 0.0 {
+                continue;
+            }
+            let class = class.round() as usize;
+            if excluded.get(class) == Some(&true) {
+                continue;
             }
+
+            let x1 = value(0) / input_width;
+            let y1 = value(1) / input_height;
+            let x2 = value(2) / input_width;
+            let y2 = value(3) / input_height;
+            detections.push(Detection {
+                class_name: class_name(class),
+                confidence,
+                bbox: BoundingBox::new((x1 + x2) / 2.0, (y1 + y2) / 2.0, x2 - x1, y2 - y1),
+            });
         }
+        return detections;
+    };
+
+    // YOLOv8 output format: [batch, 4 + num_classes, num_detections]
+    // YOLOv5 output format: [batch, num_detections, 5 + num_classes]
+    let transposed = format == ModelFormat::YoloV8;
+    let (num_classes, num_detections) = if transposed {
+        (shape[1].saturating_sub(offset), shape[2])
+    } else {
+        (shape[2].saturating_sub(offset), shape[1])
+    };
+    let value = |i: usize, channel: usize| {
+        if transposed {
+            output[[batch_idx, channel, i]]
+        } else {
+            output[[batch_idx, i, channel]]
+        }
+    };
+
+    // Process each detection
+    for i in 0..num_detections {
+        // Get box coordinates (x_center, y_center, width, height)
+        let x = value(i, 0);
+        let y = value(i, 1);
+        let w = value(i, 2);
+        let h = value(i, 3);
+
+        // YOLOv5 scales class probabilities by the box's objectness
+        let objectness = if format == ModelFormat::YoloV5 {
+            value(i, 4)
+        } else {
+            1.0
+        };
 
-        // Check for phantom GT (GT with no detection)
-        for (idx, ann) in annotations.iter().enumerate() {
-            if !matched_gt[idx] {
-                result.add_issue(Issue {
-                    image: filename.clone(),
-                    severity: IssueSeverity::Low,
-                    issue_type: IssueType::SpuriousLabel,
-                    description: format!("GT has '{}' but model detects nothing there", ann.class_name),
-                    gt_class: Some(ann.class_name.clone()),
-                    detected_class: None,
-                    confidence: None,
-                    iou: None,
-                    explanation: None,
-                    line_num: Some(ann.line_num),
-                });
+        // Find class with highest confidence
+        // Note: YOLO ONNX exports already apply sigmoid internally
+        let mut max_conf = 0.0f32;
+        let mut max_class = 0usize;
+        for c in 0..num_classes {
+            if excluded.get(c) == Some(&true) {
+                continue;
+            }
+            let conf = objectness * value(i, offset + c);
+            if conf > max_conf {
+                max_conf = conf;
+                max_class = c;
             }
         }
 
-        Ok(result)
+        if max_conf >= conf_threshold {
+            // Normalize coordinates to [0, 1]
+            let norm_x = x / input_width;
+            let norm_y = y / input_height;
+            let norm_w = w / input_width;
+            let norm_h = h / input_height;
+
+            detections.push(Detection {
+                class_name: class_name(max_class),
+                confidence: max_conf,
+                bbox: BoundingBox::new(norm_x, norm_y, norm_w, norm_h),
+            });
+        }
     }
 
-    fn detect(&self, image: &DynamicImage, _class_names: &[String]) -> Result<Vec<Detection>> {
-        let (orig_width, orig_height) = image.dimensions();
-
-        // Preprocess
-        let input = self.preprocess_image(image);
-
-        // Create shape and flattened data for ort
-        let shape: Vec<i64> = input.shape().iter().map(|&x| x as i64).collect();
-        let data: Vec<f32> = input.into_raw_vec_and_offset().0;
-
-        // Create input tensor from shape and data
-        let input_tensor = ort::value::Tensor::from_array((shape.clone(), data))?;
-
-        // Run inference (lock the session for thread safety)
-        let mut session = self.session.lock().map_err(|e| anyhow::anyhow!("Failed to lock session: {}", e))?;
-        let outputs = session.run(ort::inputs!["images" => input_tensor])?;
-
-        // Get output tensor
-        let binding = outputs["output0"].try_extract_tensor::<f32>()?;
-        let (out_shape, out_data) = binding;
+    detections
+}
 
-        // Convert to ndarray for processing
-        let output = ArrayD::from_shape_vec(
-            out_shape.iter().map(|&x| x as usize).collect::<Vec<_>>(),
-            out_data.to_vec()
-        )?;
+/// Warning for a model whose output doesn't fit the configured `format`, or
+/// whose class count doesn't match its class names or the dataset
+///
+/// Without class names the model's outputs are read as dataset class ids, so
+/// a different count mislabels every detection.
+fn output_warning(
+    info: &ModelInfo,
+    format: ModelFormat,
+    model_classes: Option<&(ClassSource, Vec<String>)>,
+    dataset_classes: usize,
+) -> Option<String> {
+    if let Some(likely) = info.likely_format().filter(|f| *f != format) {
+        return Some(format!(
+            "Model output looks like the {} layout but --model-format is {}; detections will be garbage",
+            likely, format
+        ));
+    }
+    let output_classes = info.output_classes(format);
+    match (model_classes, output_classes) {
+        (Some((source, names)), Some(n)) if names.len() != n => Some(format!(
+            "Model outputs scores for {} classes but {} names {}; detections past the last name are reported as class_N",
+            n,
+            source,
+            names.len()
+        )),
+        (None, Some(n)) if n != dataset_classes => Some(format!(
+            "Model outputs scores for {} classes but the dataset declares {}, and the model has no class names to match them by; \
+             detections will be reported under the wrong classes. Pass --model-classes with the model's class list",
+            n, dataset_classes
+        )),
+        (Some((source, names)), _) if names.len() != dataset_classes => Some(format!(
+            "Model has {} classes ({}) but the dataset declares {}; classes are matched by name",
+            names.len(),
+            source,
+            dataset_classes
+        )),
+        _ => None,
+    }
+}
 
-        // Postprocess
-        let detections = self.postprocess_detections(&output, orig_width, orig_height);
+/// Pick the device to use, falling back to CPU when CUDA is unavailable
+fn resolve_device(requested: Device) -> (Device, Option<String>) {
+    match requested {
+        Device::Cpu => (Device::Cpu, None),
+        #[cfg(feature = "cuda")]
+        Device::Cuda { .. } => {
+            use ort::ep::ExecutionProvider;
+            match ort::ep::CUDA::default().is_available() {
+                Ok(true) => (requested, None),
+                Ok(false) => (
+                    Device::Cpu,
+                    Some("CUDA execution provider is not available, falling back to CPU".into()),
+                ),
+                Err(e) => (
+                    Device::Cpu,
+                    Some(format!(
+                        "Could not query CUDA support ({}), falling back to CPU",
+                        e
+                    )),
+                ),
+            }
+        }
+        #[cfg(not(feature = "cuda"))]
+        Device::Cuda { .. } => (
+            Device::Cpu,
+            Some("gt-audit was built without the `cuda` feature, falling back to CPU".into()),
+        ),
+    }
+}
 
-        Ok(detections)
+/// Apply the NMS strategy of `config` to raw detections
+pub(crate) fn suppress(config: &DetectorConfig, detections: Vec<Detection>) -> Vec<Detection> {
+    match config.nms_method {
+        NmsMethod::Hard => {
+            YoloDetector::non_max_suppression(detections, config.iou_threshold, config.nms_mode)
+        }
+        NmsMethod::Soft { sigma } => YoloDetector::soft_nms(
+            detections,
+            sigma,
+            config.confidence_threshold,
+            config.nms_mode,
+        ),
     }
 }
 
-/// Zero-shot detector using basic heuristics (fallback when no model provided)
-pub struct ZeroShotDetector {
+/// Instruction sent with every image to the `vlm` method
+const VLM_PROMPT: &str =
+    "List all objects visible as JSON: [{class, x_center, y_center, width, height}]";
+
+/// Environment variable holding the API key for the `vlm` method
+pub const VLM_KEY_VAR: &str = "GT_AUDIT_VLM_KEY";
+
+/// Time allowed to connect to the VLM endpoint
+const VLM_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time allowed for the VLM's reply; large models on a CPU can take a while
+const VLM_READ_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Vision-language model behind an OpenAI-compatible chat completions API
+///
+/// Works with local servers such as Ollama (`http://localhost:11434/v1`) as
+/// well as hosted ones. Each image is sent as a JPEG, and the boxes listed in
+/// the reply go through the same matching as a YOLO model's detections.
+pub struct VlmDetector {
+    agent: ureq::Agent,
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
     config: DetectorConfig,
 }
 
-impl ZeroShotDetector {
+impl VlmDetector {
     pub fn new(config: DetectorConfig) -> Result<Self> {
-        println!("   Using heuristic-based validation (no model provided)");
-        println!("   For better results, use --model with a trained YOLO model");
-        Ok(Self { config })
+        Ok(Self {
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(VLM_CONNECT_TIMEOUT)
+                .timeout_read(VLM_READ_TIMEOUT)
+                .build(),
+            endpoint: config.vlm_endpoint.trim_end_matches('/').to_string(),
+            api_key: std::env::var(VLM_KEY_VAR)
+                .ok()
+                .filter(|key| !key.is_empty()),
+            model: config.vlm_model.clone(),
+            config,
+        })
     }
-}
-
-impl Detector for ZeroShotDetector {
-    fn audit_image(
-        &self,
-        image_path: &Path,
-        annotations: &[Annotation],
-        _class_names: &HashMap<i32, String>,
-    ) -> Result<ImageResult> {
-        let filename = image_path
-            .file_name()
-            .map(|f| f.to_string_lossy().to_string())
-            .unwrap_or_default();
-
-        let mut result = ImageResult::new(filename.clone(), annotations.len(), 0);
-
-        // Load image
-        let image = match image::open(image_path) {
-            Ok(img) => img,
-            Err(e) => {
-                result.add_issue(Issue {
-                    image: filename,
-                    severity: IssueSeverity::High,
-                    issue_type: IssueType::ClassMismatch,
-                    description: format!("Failed to load image: {}", e),
-                    gt_class: None,
-                    detected_class: None,
-                    confidence: None,
-                    iou: None,
-                    explanation: None,
-                    line_num: None,
-                });
-                return Ok(result);
-            }
-        };
 
-        // Validate each annotation
-        for ann in annotations {
-            // Check bbox validity
-            let bbox_valid = ann.bbox.w > 0.0
-                && ann.bbox.h > 0.0
-                && ann.bbox.w <= 1.0
-                && ann.bbox.h <= 1.0
-                && ann.bbox.x >= 0.0
-                && ann.bbox.y >= 0.0
-                && ann.bbox.x <= 1.0
-                && ann.bbox.y <= 1.0
-                && (ann.bbox.x - ann.bbox.w / 2.0) >= -0.01
-                && (ann.bbox.y - ann.bbox.h / 2.0) >= -0.01;
-
-            if !bbox_valid {
-                result.add_issue(Issue {
-                    image: filename.clone(),
-                    severity: IssueSeverity::High,
-                    issue_type: IssueType::Localization,
-                    description: format!(
-                        "Invalid bbox for '{}': x={:.3}, y={:.3}, w={:.3}, h={:.3}",
-                        ann.class_name, ann.bbox.x, ann.bbox.y, ann.bbox.w, ann.bbox.h
-                    ),
-                    gt_class: Some(ann.class_name.clone()),
-                    detected_class: None,
-                    confidence: None,
-                    iou: None,
-                    explanation: Some("Bounding box coordinates out of valid range".to_string()),
-                    line_num: Some(ann.line_num),
-                });
-            }
+    /// The fixed prompt, plus the dataset's class names so replies use them
+    fn prompt(class_names: &[String]) -> String {
+        if class_names.is_empty() {
+            VLM_PROMPT.to_string()
+        } else {
+            format!(
+                "{}\nUse these class names where they apply: {}.",
+                VLM_PROMPT,
+                class_names.join(", ")
+            )
         }
+    }
 
-        // Check for no annotations
-        if annotations.is_empty() {
-            let (width, height) = image.dimensions();
-            if width > 100 && height > 100 {
-                result.add_issue(Issue {
-                    image: filename.clone(),
-                    severity: IssueSeverity::Low,
-                    issue_type: IssueType::SpuriousLabel,
-                    description: "Image has no annotations".to_string(),
-                    gt_class: None,
-                    detected_class: None,
-                    confidence: None,
-                    iou: None,
-                    explanation: None,
-                    line_num: None,
-                });
-            }
+    /// Send one JPEG image with `prompt` and return the reply text
+    fn request(&self, jpeg: &[u8], prompt: &str) -> Result<String> {
+        let url = format!("{}/chat/completions", self.endpoint);
+        let image_url = format!(
+            "data:image/jpeg;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(jpeg)
+        );
+        let body = serde_json::json!({
+            "model": self.model,
+            "temperature": 0,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": prompt },
+                    { "type": "image_url", "image_url": { "url": image_url } },
+                ],
+            }],
+        });
+
+        let mut request = self.agent.post(&url);
+        if let Some(key) = &self.api_key {
+            request = request.set("Authorization", &format!("Bearer {}", key));
         }
+        let response: serde_json::Value = request
+            .send_json(body)
+            .with_context(|| format!("VLM request to {} failed", url))?
+            .into_json()
+            .context("VLM response is not JSON")?;
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string)
+            .with_context(|| format!("VLM response has no message content: {}", response))
+    }
+}
 
-        Ok(result)
+impl Detector for VlmDetector {
+    fn detect(&self, image: &DynamicImage, class_names: &[String]) -> Result<Vec<Detection>> {
+        let mut jpeg = Vec::new();
+        image
+            .to_rgb8()
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg),
+                image::ImageFormat::Jpeg,
+            )
+            .context("Failed to encode image as JPEG")?;
+
+        let reply = self.request(&jpeg, &Self::prompt(class_names))?;
+        let (width, height) = image.dimensions();
+        let mut detections = parse_vlm_reply(&reply, width, height)?;
+        tracing::trace!("VLM reply: {} ({} objects)", reply, detections.len());
+        detections.retain(|d| {
+            d.confidence >= self.config.confidence_threshold
+                && !self.config.exclude_classes.contains(&d.class_name)
+        });
+        Ok(suppress(&self.config, detections))
     }
 
-    fn detect(&self, _image: &DynamicImage, _class_names: &[String]) -> Result<Vec<Detection>> {
-        Ok(Vec::new())
+    fn name(&self) -> String {
+        format!("vlm:{}", self.model)
     }
 }
 
-/// Download CLIP model for zero-shot detection
-pub fn download_clip_model() -> Result<()> {
-    let cache_dir = dirs::cache_dir()
+/// Object as listed in a VLM reply
+#[derive(Deserialize)]
+struct VlmObject {
+    #[serde(alias = "label", alias = "name")]
+    class: String,
+    x_center: f32,
+    y_center: f32,
+    width: f32,
+    height: f32,
+    #[serde(default)]
+    confidence: Option<f32>,
+}
+
+/// Detections in a VLM reply, which may wrap its JSON array in prose or a code fence
+///
+/// Coordinates are read as normalized unless one exceeds 1, in which case all
+/// are taken as pixels of the `width` x `height` image. Objects without a
+/// confidence get 1.
+fn parse_vlm_reply(reply: &str, width: u32, height: u32) -> Result<Vec<Detection>> {
+    let json = reply
+        .find('[')
+        .zip(reply.rfind(']'))
+        .filter(|(start, end)| start < end)
+        .map(|(start, end)| &reply[start..=end])
+        .with_context(|| format!("VLM reply has no JSON list of objects: {}", reply))?;
+    let objects: Vec<VlmObject> = serde_json::from_str(json)
+        .with_context(|| format!("VLM reply is not a list of objects: {}", json))?;
+
+    let pixels = objects.iter().any(|o| {
+        [o.x_center, o.y_center, o.width, o.height]
+            .iter()
+            .any(|&v| v > 1.0)
+    });
+    let (sx, sy) = if pixels {
+        (width.max(1) as f32, height.max(1) as f32)
+    } else {
+        (1.0, 1.0)
+    };
+    Ok(objects
+        .into_iter()
+        .map(|o| Detection {
+            class_name: o.class,
+            confidence: o.confidence.unwrap_or(1.0),
+            bbox: BoundingBox::new(
+                o.x_center / sx,
+                o.y_center / sy,
+                o.width / sx,
+                o.height / sy,
+            ),
+        })
+        .collect())
+}
+
+/// Directory downloaded models are kept in
+pub fn model_cache_dir() -> PathBuf {
+    dirs::cache_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("gt-audit")
-        .join("models");
+        .join("models")
+}
+
+/// Download CLIP model for zero-shot detection
+pub fn download_clip_model() -> Result<()> {
+    let cache_dir = model_cache_dir();
 
     std::fs::create_dir_all(&cache_dir)?;
 
-    println!("   Model cache directory: {}", cache_dir.display());
-    println!("   For now, use --model with your trained YOLO model");
+    tracing::info!("   Model cache directory: {}", cache_dir.display());
+    tracing::info!("   For now, use --model with your trained YOLO model");
 
     Ok(())
 }
@@ -477,14 +994,213 @@ pub fn download_clip_model() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ndarray::IxDyn;
 
     #[test]
     fn test_class_equivalence() {
-        assert!(YoloDetector::classes_equivalent("Dress", "Clothing"));
-        assert!(YoloDetector::classes_equivalent("Boot", "Footwear"));
-        assert!(!YoloDetector::classes_equivalent("Boot", "Dress"));
-        assert!(YoloDetector::classes_equivalent("person", "Person"));
-        assert!(YoloDetector::classes_equivalent("Man", "Person"));
+        let synonyms = ClassSynonyms::builtin();
+        assert!(synonyms.equivalent("Dress", "Clothing"));
+        assert!(synonyms.equivalent("Boot", "Footwear"));
+        assert!(!synonyms.equivalent("Boot", "Dress"));
+        assert!(synonyms.equivalent("person", "Person"));
+        assert!(synonyms.equivalent("Man", "Person"));
+    }
+
+    /// (cx, cy, w, h, class, score) in input pixels of a 640x320 model
+    type TestBox = (f32, f32, f32, f32, usize, f32);
+
+    /// One image's model output holding `boxes` in the layout of `format`
+    fn model_output(boxes: &[TestBox], num_classes: usize, format: ModelFormat) -> ArrayD<f32> {
+        let column = |i: usize, channel: usize, offset: usize| {
+            let (cx, cy, w, h, class, score) = boxes[i];
+            match channel {
+                0 => cx,
+                1 => cy,
+                2 => w,
+                3 => h,
+                c if c == offset + class => score,
+                _ => 0.0,
+            }
+        };
+        let n = boxes.len();
+        match format {
+            ModelFormat::YoloV8 => ArrayD::from_shape_fn(IxDyn(&[1, 4 + num_classes, n]), |ix| {
+                column(ix[2], ix[1], 4)
+            }),
+            // Objectness 0.5 halves the doubled class scores back
+            ModelFormat::YoloV5 => {
+                ArrayD::from_shape_fn(IxDyn(&[1, n, 5 + num_classes]), |ix| match ix[2] {
+                    4 => 0.5,
+                    c => column(ix[1], c, 5) * if c >= 5 { 2.0 } else { 1.0 },
+                })
+            }
+            // A trailing zero-score row pads the end-to-end output
+            ModelFormat::EndToEnd => ArrayD::from_shape_fn(IxDyn(&[1, n + 1, 6]), |ix| {
+                let Some(&(cx, cy, w, h, class, score)) = boxes.get(ix[1]) else {
+                    return 0.0;
+                };
+                [
+                    cx - w / 2.0,
+                    cy - h / 2.0,
+                    cx + w / 2.0,
+                    cy + h / 2.0,
+                    score,
+                    class as f32,
+                ][ix[2]]
+            }),
+        }
+    }
+
+    fn decode_test_output(
+        boxes: &[TestBox],
+        format: ModelFormat,
+        excluded: &[bool],
+    ) -> Vec<Detection> {
+        let output = model_output(boxes, 2, format);
+        decode_output(&output, 0, format, (640, 320), 0.25, excluded, |c| {
+            format!("class_{}", c)
+        })
+    }
+
+    fn assert_same_detections(got: &[Detection], want: &[Detection]) {
+        assert_eq!(got.len(), want.len());
+        for (got, want) in got.iter().zip(want) {
+            assert_eq!(got.class_name, want.class_name);
+            assert!((got.confidence - want.confidence).abs() < 1e-6);
+            let (got, want) = (got.bbox.to_xyxy(), want.bbox.to_xyxy());
+            assert!((got.0 - want.0).abs() < 1e-6 && (got.1 - want.1).abs() < 1e-6);
+            assert!((got.2 - want.2).abs() < 1e-6 && (got.3 - want.3).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_output_layouts_decode_alike() {
+        let boxes = [
+            (320.0, 160.0, 64.0, 32.0, 1, 0.9),
+            (160.0, 80.0, 32.0, 16.0, 0, 0.6),
+            (100.0, 100.0, 10.0, 10.0, 0, 0.1),
+        ];
+        let expected = decode_test_output(&boxes, ModelFormat::YoloV8, &[]);
+        assert_eq!(expected.len(), 2);
+        assert_eq!(expected[0].class_name, "class_1");
+        let (x1, y1, x2, y2) = expected[0].bbox.to_xyxy();
+        assert!((x1 - 0.45).abs() < 1e-6 && (y1 - 0.45).abs() < 1e-6);
+        assert!((x2 - 0.55).abs() < 1e-6 && (y2 - 0.55).abs() < 1e-6);
+
+        for format in [ModelFormat::YoloV5, ModelFormat::EndToEnd] {
+            assert_same_detections(&decode_test_output(&boxes, format, &[]), &expected);
+
+            let kept = decode_test_output(&boxes, format, &[false, true]);
+            assert_eq!(kept.len(), 1);
+            assert_eq!(kept[0].class_name, "class_0");
+        }
+    }
+
+    #[test]
+    fn test_v5_and_v8_agree_after_nms() {
+        // The second box overlaps the first and is suppressed
+        let boxes = [
+            (320.0, 160.0, 64.0, 32.0, 1, 0.9),
+            (322.0, 161.0, 64.0, 32.0, 1, 0.8),
+            (160.0, 80.0, 32.0, 16.0, 0, 0.6),
+        ];
+        let nms = |format| {
+            YoloDetector::non_max_suppression(
+                decode_test_output(&boxes, format, &[]),
+                0.5,
+                NmsMode::PerClass,
+            )
+        };
+        let v8 = nms(ModelFormat::YoloV8);
+        assert_eq!(v8.len(), 2);
+        assert!((v8[0].confidence - 0.9).abs() < 1e-6);
+        assert_same_detections(&nms(ModelFormat::YoloV5), &v8);
+    }
+
+    #[test]
+    fn test_agnostic_nms_suppresses_other_classes() {
+        let det = |class: &str, conf: f32, x: f32| Detection {
+            class_name: class.to_string(),
+            confidence: conf,
+            bbox: BoundingBox::new(x, 0.5, 0.2, 0.2),
+        };
+        // A car also seen as a truck, and a truck elsewhere
+        let overlapping = || {
+            vec![
+                det("truck", 0.8, 0.51),
+                det("car", 0.9, 0.5),
+                det("truck", 0.7, 0.1),
+            ]
+        };
+        let classes = |kept: Vec<Detection>| -> Vec<String> {
+            kept.into_iter().map(|d| d.class_name).collect()
+        };
+
+        let kept = YoloDetector::non_max_suppression(overlapping(), 0.5, NmsMode::PerClass);
+        assert_eq!(classes(kept), ["car", "truck", "truck"]);
+        let kept = YoloDetector::non_max_suppression(overlapping(), 0.5, NmsMode::Agnostic);
+        assert_eq!(classes(kept), ["car", "truck"]);
+
+        let kept = YoloDetector::soft_nms(overlapping(), 0.5, 0.25, NmsMode::PerClass);
+        assert_eq!(kept.len(), 3);
+        let kept = YoloDetector::soft_nms(overlapping(), 0.5, 0.25, NmsMode::Agnostic);
+        assert_eq!(classes(kept), ["car", "truck"]);
+
+        assert_eq!("agnostic".parse::<NmsMode>().unwrap(), NmsMode::Agnostic);
+        assert!("cross-class".parse::<NmsMode>().is_err());
+    }
+
+    #[test]
+    fn test_device_parsing() {
+        assert_eq!("cpu".parse::<Device>().unwrap(), Device::Cpu);
+        assert_eq!(
+            "cuda".parse::<Device>().unwrap(),
+            Device::Cuda { device_id: 0 }
+        );
+        assert_eq!(
+            "cuda:1".parse::<Device>().unwrap(),
+            Device::Cuda { device_id: 1 }
+        );
+        assert!("cuda:x".parse::<Device>().is_err());
+        assert!("tpu".parse::<Device>().is_err());
+    }
+
+    #[test]
+    fn test_soft_nms_decays_overlapping_boxes() {
+        let det = |conf: f32, x: f32| Detection {
+            class_name: "person".to_string(),
+            confidence: conf,
+            bbox: BoundingBox::new(x, 0.5, 0.2, 0.2),
+        };
+
+        // Heavily overlapping box survives with a decayed score
+        let kept = YoloDetector::soft_nms(
+            vec![det(0.9, 0.5), det(0.8, 0.52)],
+            0.5,
+            0.1,
+            NmsMode::PerClass,
+        );
+        assert_eq!(kept.len(), 2);
+        assert!((kept[0].confidence - 0.9).abs() < 1e-6);
+        assert!(kept[1].confidence < 0.8);
+
+        // Non-overlapping box keeps its score
+        let kept = YoloDetector::soft_nms(
+            vec![det(0.9, 0.2), det(0.8, 0.8)],
+            0.5,
+            0.1,
+            NmsMode::PerClass,
+        );
+        assert!((kept[1].confidence - 0.8).abs() < 1e-6);
+
+        // Decayed below threshold gets dropped
+        let kept = YoloDetector::soft_nms(
+            vec![det(0.9, 0.5), det(0.3, 0.5)],
+            0.5,
+            0.25,
+            NmsMode::PerClass,
+        );
+        assert_eq!(kept.len(), 1);
     }
 
     #[test]
@@ -496,4 +1212,23 @@ mod tests {
         let box3 = BoundingBox::new(0.0, 0.0, 0.2, 0.2);
         assert!(box1.iou(&box3) < 0.1);
     }
+
+    #[test]
+    fn test_parse_vlm_reply() {
+        let reply = "Here are the objects:\n```json\n[{\"class\": \"car\", \"x_center\": 0.5, \"y_center\": 0.4, \"width\": 0.2, \"height\": 0.1}]\n```";
+        let detections = parse_vlm_reply(reply, 640, 480).unwrap();
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].class_name, "car");
+        assert_eq!(detections[0].confidence, 1.0);
+        assert_eq!(detections[0].bbox.x, 0.5);
+
+        // Pixel coordinates are normalized by the image size
+        let reply = r#"[{"label": "dog", "x_center": 320, "y_center": 240, "width": 64, "height": 48, "confidence": 0.8}]"#;
+        let detections = parse_vlm_reply(reply, 640, 480).unwrap();
+        assert_eq!(detections[0].class_name, "dog");
+        assert_eq!(detections[0].confidence, 0.8);
+        assert_eq!((detections[0].bbox.x, detections[0].bbox.h), (0.5, 0.1));
+
+        assert!(parse_vlm_reply("I see a cat.", 640, 480).is_err());
+    }
 }
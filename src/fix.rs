@@ -0,0 +1,687 @@
+//! Safe automatic label corrections, and the suggested fixes of a report
+//! exported as patched label files for review
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::dataset::{self, YoloDataset};
+use crate::models::{Annotation, AuditResult, BoundingBox, SuggestedFix};
+
+/// Directory (relative to the dataset root) where originals are backed up
+pub const BACKUP_DIR: &str = ".gt-audit-backup";
+
+/// Options controlling which fixes are applied and how
+pub struct FixOptions {
+    pub remove_zero_area: bool,
+    pub dry_run: bool,
+    pub backup: bool,
+}
+
+/// Totals reported after a fix run
+#[derive(Debug, Default)]
+pub struct FixSummary {
+    pub files_scanned: usize,
+    pub files_modified: usize,
+    pub lines_modified: usize,
+    pub lines_removed: usize,
+    /// Lines added by patch files (`apply_patches` only)
+    pub lines_added: usize,
+    /// Unified diff of each file a dry run would change, for the caller to show
+    pub diffs: Vec<String>,
+}
+
+/// Totals reported after exporting suggested fixes
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    pub files_written: usize,
+    pub fixes: usize,
+    /// Images whose fixes could not be exported, with the reason
+    pub skipped: Vec<String>,
+}
+
+/// What happens to a single line of a label file
+#[derive(Debug, Clone, PartialEq)]
+enum LineEdit {
+    Keep,
+    Replace(String),
+    Remove,
+}
+
+/// One line of a diff between two versions of a file
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DiffLine<'a> {
+    Context(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// Apply fixes to the label files of `images` (all dataset images if `None`)
+pub fn fix_dataset(
+    dataset: &YoloDataset,
+    images: Option<Vec<PathBuf>>,
+    options: &FixOptions,
+) -> Result<FixSummary> {
+    let images = images.unwrap_or_else(|| dataset.get_images());
+    let mut summary = FixSummary::default();
+
+    for image_path in &images {
+        let label_path = dataset.get_label_path(image_path);
+        if !label_path.exists() {
+            continue;
+        }
+        summary.files_scanned += 1;
+
+        let content = fs::read_to_string(&label_path)
+            .with_context(|| format!("Failed to read {}", label_path.display()))?;
+        let lines: Vec<&str> = content.lines().collect();
+        let edits = plan_edits(&lines, &dataset.class_names, options.remove_zero_area);
+
+        if edits.iter().all(|e| *e == LineEdit::Keep) {
+            continue;
+        }
+
+        summary.files_modified += 1;
+        for edit in &edits {
+            match edit {
+                LineEdit::Keep => {}
+                LineEdit::Replace(_) => summary.lines_modified += 1,
+                LineEdit::Remove => summary.lines_removed += 1,
+            }
+        }
+
+        if options.dry_run {
+            summary
+                .diffs
+                .push(unified_diff(&label_path, &edit_diff(&lines, &edits)));
+            continue;
+        }
+
+        if options.backup {
+            backup_file(&dataset.path, &label_path)?;
+        }
+
+        let mut fixed = String::new();
+        for (line, edit) in lines.iter().zip(&edits) {
+            match edit {
+                LineEdit::Keep => fixed.push_str(line),
+                LineEdit::Replace(new) => fixed.push_str(new),
+                LineEdit::Remove => continue,
+            }
+            fixed.push('\n');
+        }
+        write_atomic(&label_path, &fixed)?;
+    }
+
+    Ok(summary)
+}
+
+/// Decide the edit for every line of a label file
+fn plan_edits(
+    lines: &[&str],
+    class_names: &HashMap<i32, String>,
+    remove_zero_area: bool,
+) -> Vec<LineEdit> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut edits = Vec::with_capacity(lines.len());
+
+    for (i, line) in lines.iter().enumerate() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        // A sixth value is a stored confidence, carried over unchanged
+        let confidence = match parts.len() {
+            6 => parts[5].parse::<f32>().ok(),
+            _ => None,
+        };
+        let parsed = if parts.len() == 5 || confidence.is_some() {
+            match (
+                parts[0].parse::<i32>(),
+                parts[1].parse::<f32>(),
+                parts[2].parse::<f32>(),
+                parts[3].parse::<f32>(),
+                parts[4].parse::<f32>(),
+            ) {
+                (Ok(c), Ok(x), Ok(y), Ok(w), Ok(h)) => Some((c, x, y, w, h)),
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        // Leave anything we don't understand untouched
+        let Some((class_id, x, y, w, h)) = parsed else {
+            edits.push(LineEdit::Keep);
+            continue;
+        };
+
+        if !class_names.is_empty() && !class_names.contains_key(&class_id) {
+            edits.push(LineEdit::Remove);
+            continue;
+        }
+
+        // Clamp the box corners into the image
+        let x1 = (x - w / 2.0).clamp(0.0, 1.0);
+        let y1 = (y - h / 2.0).clamp(0.0, 1.0);
+        let x2 = (x + w / 2.0).clamp(0.0, 1.0);
+        let y2 = (y + h / 2.0).clamp(0.0, 1.0);
+        let (nw, nh) = (x2 - x1, y2 - y1);
+
+        if remove_zero_area && (nw <= 0.0 || nh <= 0.0) {
+            edits.push(LineEdit::Remove);
+            continue;
+        }
+
+        let clamped = (x1, y1, x2, y2) != (x - w / 2.0, y - h / 2.0, x + w / 2.0, y + h / 2.0);
+        let new_line = if clamped {
+            Annotation {
+                class_id,
+                class_name: class_names.get(&class_id).cloned().unwrap_or_default(),
+                bbox: BoundingBox::new(x1 + nw / 2.0, y1 + nh / 2.0, nw, nh),
+                line_num: i + 1,
+                polygon: None,
+                gt_confidence: confidence,
+            }
+            .to_yolo_line()
+        } else {
+            line.trim().to_string()
+        };
+
+        if !seen.insert(new_line.clone()) {
+            edits.push(LineEdit::Remove);
+        } else if clamped {
+            edits.push(LineEdit::Replace(new_line));
+        } else {
+            edits.push(LineEdit::Keep);
+        }
+    }
+
+    edits
+}
+
+/// The diff the edits of `lines` make
+fn edit_diff<'a>(lines: &[&'a str], edits: &'a [LineEdit]) -> Vec<DiffLine<'a>> {
+    let mut diff = Vec::with_capacity(lines.len());
+    for (line, edit) in lines.iter().zip(edits) {
+        match edit {
+            LineEdit::Keep => diff.push(DiffLine::Context(line)),
+            LineEdit::Replace(new) => {
+                diff.push(DiffLine::Removed(line));
+                diff.push(DiffLine::Added(new));
+            }
+            LineEdit::Remove => diff.push(DiffLine::Removed(line)),
+        }
+    }
+    diff
+}
+
+/// Shortest diff turning `old` into `new`, from their longest common subsequence
+fn line_diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    // common[i][j]: length of the LCS of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push(DiffLine::Context(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            diff.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    diff
+}
+
+/// Render a diff in unified format with three lines of context
+fn unified_diff(path: &Path, diff: &[DiffLine]) -> String {
+    const CONTEXT: usize = 3;
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path.display(), path.display());
+
+    // Group changed lines into hunks whose context windows overlap
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in diff
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Context(_)))
+    {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(diff.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    for (start, end) in hunks {
+        // Lines of each file before the hunk
+        let old_before = diff[..start]
+            .iter()
+            .filter(|l| !matches!(l, DiffLine::Added(_)))
+            .count();
+        let new_before = diff[..start]
+            .iter()
+            .filter(|l| !matches!(l, DiffLine::Removed(_)))
+            .count();
+
+        let mut body = String::new();
+        let mut old_len = 0;
+        let mut new_len = 0;
+        for line in &diff[start..end] {
+            match line {
+                DiffLine::Context(text) => {
+                    body.push_str(&format!(" {}\n", text));
+                    old_len += 1;
+                    new_len += 1;
+                }
+                DiffLine::Removed(text) => {
+                    body.push_str(&format!("-{}\n", text));
+                    old_len += 1;
+                }
+                DiffLine::Added(text) => {
+                    body.push_str(&format!("+{}\n", text));
+                    new_len += 1;
+                }
+            }
+        }
+
+        // An empty range starts at the line before it
+        let first = |before: usize, len: usize| if len == 0 { before } else { before + 1 };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            first(old_before, old_len),
+            old_len,
+            first(new_before, new_len),
+            new_len
+        ));
+        out.push_str(&body);
+    }
+
+    out
+}
+
+/// Label file `content` with `fixes` applied, each with the line of its issue
+///
+/// Lines deleted by one fix are left alone by the others. Added boxes go at
+/// the end. Classes are looked up by name in `class_ids`.
+pub fn apply_suggestions(
+    content: &str,
+    fixes: &[(Option<usize>, &SuggestedFix)],
+    class_ids: &HashMap<String, i32>,
+) -> Result<String> {
+    let class_id = |name: &str| {
+        class_ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("No dataset class '{}'", name))
+    };
+    let mut lines: Vec<Option<String>> = content.lines().map(|l| Some(l.to_string())).collect();
+    let mut added = Vec::new();
+
+    for (line_num, fix) in fixes {
+        if let SuggestedFix::AddBox { bbox, class } = fix {
+            let annotation = Annotation {
+                class_id: class_id(class)?,
+                class_name: class.clone(),
+                bbox: bbox.clone(),
+                line_num: 0,
+                polygon: None,
+                gt_confidence: None,
+            };
+            added.push(annotation.to_yolo_line());
+            continue;
+        }
+        let line = line_num
+            .and_then(|n| lines.get_mut(n.checked_sub(1)?))
+            .with_context(|| format!("Line {:?} is not in the label file", line_num))?;
+        let Some(text) = line else {
+            continue;
+        };
+        match fix {
+            SuggestedFix::ChangeClass { to } => {
+                let rest = text
+                    .trim()
+                    .split_once(char::is_whitespace)
+                    .map(|(_, rest)| rest.to_string())
+                    .unwrap_or_default();
+                *text = format!("{} {}", class_id(to)?, rest.trim_start());
+            }
+            SuggestedFix::DeleteLine => *line = None,
+            SuggestedFix::AddBox { .. } => unreachable!(),
+        }
+    }
+
+    let mut out = String::new();
+    for line in lines.into_iter().flatten().chain(added) {
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Write the suggested fixes of `result` to `out_dir` as patched copies of
+/// the label files, at their paths relative to `dataset_root`
+///
+/// Each file holds the whole proposed label content, ready to review and
+/// apply with `apply_patches`. Suppressed issues are left out.
+pub fn export_fixes(
+    result: &AuditResult,
+    dataset_root: &Path,
+    out_dir: &Path,
+) -> Result<ExportSummary> {
+    let mut summary = ExportSummary::default();
+    let mut datasets: HashMap<Option<String>, YoloDataset> = HashMap::new();
+
+    for image in result.flagged_images() {
+        let fixes: Vec<(Option<usize>, &SuggestedFix)> = image
+            .issues
+            .iter()
+            .filter(|i| !i.suppressed)
+            .filter_map(|i| i.suggested_fix.as_ref().map(|fix| (i.line_num, fix)))
+            .collect();
+        if fixes.is_empty() {
+            continue;
+        }
+
+        let dataset = match datasets.entry(image.split.clone()) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => entry.insert(match &image.split {
+                Some(split) => YoloDataset::load_split(dataset_root, split)?,
+                None => YoloDataset::load(dataset_root)?,
+            }),
+        };
+        let Some(image_path) = dataset.image_path(&image.filename) else {
+            summary.skipped.push(format!(
+                "{}: image not found in the dataset",
+                image.filename
+            ));
+            continue;
+        };
+        let label_path = dataset.get_label_path(&image_path);
+        let content = match fs::read_to_string(&label_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", label_path.display()))
+            }
+        };
+        let class_ids: HashMap<String, i32> = dataset
+            .class_names
+            .iter()
+            .map(|(id, name)| (name.clone(), *id))
+            .collect();
+        let patched = match apply_suggestions(&content, &fixes, &class_ids) {
+            Ok(patched) => patched,
+            Err(e) => {
+                summary.skipped.push(format!("{}: {:#}", image.filename, e));
+                continue;
+            }
+        };
+
+        let relative = label_path
+            .strip_prefix(dataset_root)
+            .unwrap_or_else(|_| Path::new(label_path.file_name().unwrap_or_default()));
+        let patch_path = out_dir.join(relative);
+        if let Some(parent) = patch_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&patch_path, patched)
+            .with_context(|| format!("Failed to write {}", patch_path.display()))?;
+        summary.files_written += 1;
+        summary.fixes += fixes.len();
+    }
+
+    Ok(summary)
+}
+
+/// Replace the label files of the dataset at `dataset_root` with the patch
+/// files below `patch_dir`, as written by `export_fixes`
+pub fn apply_patches(
+    dataset_root: &Path,
+    patch_dir: &Path,
+    options: &FixOptions,
+) -> Result<FixSummary> {
+    if !patch_dir.is_dir() {
+        anyhow::bail!("Patch directory not found: {}", patch_dir.display());
+    }
+    let mut patches = Vec::new();
+    dataset::collect_files(patch_dir, &["txt"], &mut patches);
+    patches.sort();
+    let mut summary = FixSummary::default();
+
+    for patch_path in &patches {
+        let relative = patch_path.strip_prefix(patch_dir).unwrap_or(patch_path);
+        let label_path = dataset_root.join(relative);
+        summary.files_scanned += 1;
+
+        let patched = fs::read_to_string(patch_path)
+            .with_context(|| format!("Failed to read {}", patch_path.display()))?;
+        let current = match fs::read_to_string(&label_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", label_path.display()))
+            }
+        };
+        let old: Vec<&str> = current.lines().collect();
+        let new: Vec<&str> = patched.lines().collect();
+        if old == new {
+            continue;
+        }
+
+        let diff = line_diff(&old, &new);
+        summary.files_modified += 1;
+        for line in &diff {
+            match line {
+                DiffLine::Context(_) => {}
+                DiffLine::Removed(_) => summary.lines_removed += 1,
+                DiffLine::Added(_) => summary.lines_added += 1,
+            }
+        }
+
+        if options.dry_run {
+            summary.diffs.push(unified_diff(&label_path, &diff));
+            continue;
+        }
+        if options.backup && label_path.exists() {
+            backup_file(dataset_root, &label_path)?;
+        }
+        if let Some(parent) = label_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        write_atomic(&label_path, &patched)?;
+    }
+
+    Ok(summary)
+}
+
+/// Copy a label file into the backup directory, mirroring its dataset path
+///
+/// An existing backup is kept: it holds the labels from before the first
+/// fix, which a later run must not replace with already-fixed ones.
+fn backup_file(dataset_root: &Path, label_path: &Path) -> Result<()> {
+    let relative = label_path
+        .strip_prefix(dataset_root)
+        .unwrap_or_else(|_| Path::new(label_path.file_name().unwrap_or_default()));
+    let backup_path = dataset_root.join(BACKUP_DIR).join(relative);
+    if backup_path.exists() {
+        return Ok(());
+    }
+
+    if let Some(parent) = backup_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::copy(label_path, &backup_path)
+        .with_context(|| format!("Failed to back up {}", label_path.display()))?;
+    Ok(())
+}
+
+/// Write via a temporary sibling file and rename so readers never see a partial file
+fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let tmp_path = path.with_extension("txt.gt-audit-tmp");
+    fs::write(&tmp_path, content)
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> HashMap<i32, String> {
+        HashMap::from([(0, "person".to_string()), (1, "car".to_string())])
+    }
+
+    #[test]
+    fn test_plan_edits() {
+        let lines = [
+            "0 0.5 0.5 0.2 0.2",
+            "0 0.5 0.5 0.2 0.2",
+            "7 0.5 0.5 0.2 0.2",
+            "1 0.95 0.5 0.2 0.2",
+            "1 0.5 0.5 0.0 0.2",
+            "not a label",
+            "1 0.95 0.5 0.2 0.2 0.35",
+        ];
+        let edits = plan_edits(&lines, &names(), true);
+
+        assert_eq!(edits[0], LineEdit::Keep);
+        assert_eq!(edits[1], LineEdit::Remove);
+        assert_eq!(edits[2], LineEdit::Remove);
+        assert_eq!(
+            edits[3],
+            LineEdit::Replace("1 0.925000 0.500000 0.150000 0.200000".to_string())
+        );
+        assert_eq!(edits[4], LineEdit::Remove);
+        assert_eq!(edits[5], LineEdit::Keep);
+        assert_eq!(
+            edits[6],
+            LineEdit::Replace("1 0.925000 0.500000 0.150000 0.200000 0.35".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        let lines = [
+            "0 0.5 0.5 0.2 0.2",
+            "0 0.5 0.5 0.2 0.2",
+            "1 0.1 0.1 0.1 0.1",
+        ];
+        let edits = [LineEdit::Keep, LineEdit::Remove, LineEdit::Keep];
+        let diff = unified_diff(Path::new("a.txt"), &edit_diff(&lines, &edits));
+
+        assert!(diff.contains("@@ -1,3 +1,2 @@"));
+        assert!(diff.contains("-0 0.5 0.5 0.2 0.2\n"));
+
+        let diff = line_diff(&["a", "b", "c"], &["a", "B", "c", "d"]);
+        assert_eq!(
+            diff,
+            [
+                DiffLine::Context("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Added("B"),
+                DiffLine::Context("c"),
+                DiffLine::Added("d"),
+            ]
+        );
+        let diff = unified_diff(Path::new("new.txt"), &line_diff(&[], &["a"]));
+        assert!(diff.contains("@@ -0,0 +1,1 @@\n+a\n"));
+    }
+
+    #[test]
+    fn suggestions_patch_label_content() {
+        let content = "0 0.5 0.5 0.2 0.2\n0 0.5 0.5 0.2 0.2\n1 0.2 0.2 0.1 0.1 0.9\n";
+        let class_ids = HashMap::from([("person".to_string(), 0), ("car".to_string(), 1)]);
+        let change = SuggestedFix::ChangeClass {
+            to: "person".to_string(),
+        };
+        let add = SuggestedFix::AddBox {
+            bbox: BoundingBox::new(0.7, 0.7, 0.1, 0.2),
+            class: "car".to_string(),
+        };
+        let fixes = [
+            (Some(1), &SuggestedFix::DeleteLine),
+            (Some(1), &change),
+            (Some(3), &change),
+            (None, &add),
+        ];
+        let patched = apply_suggestions(content, &fixes, &class_ids).unwrap();
+        assert_eq!(
+            patched,
+            "0 0.5 0.5 0.2 0.2\n0 0.2 0.2 0.1 0.1 0.9\n1 0.700000 0.700000 0.100000 0.200000\n"
+        );
+
+        let unknown = SuggestedFix::ChangeClass {
+            to: "bus".to_string(),
+        };
+        assert!(apply_suggestions(content, &[(Some(1), &unknown)], &class_ids).is_err());
+        assert!(apply_suggestions(content, &[(Some(9), &change)], &class_ids).is_err());
+    }
+
+    #[test]
+    fn dry_runs_return_diffs_without_writing() {
+        let root = std::env::temp_dir().join(format!("gt-audit-dry-run-{}", std::process::id()));
+        fs::create_dir_all(root.join("images/val")).unwrap();
+        fs::create_dir_all(root.join("labels/val")).unwrap();
+        fs::write(root.join("classes.txt"), "person\n").unwrap();
+        fs::write(root.join("images/val/a.png"), b"").unwrap();
+        let label_path = root.join("labels/val/a.txt");
+        let content = "0 0.5 0.5 0.2 0.2\n0 0.5 0.5 0.2 0.2\n";
+        fs::write(&label_path, content).unwrap();
+
+        let dataset = YoloDataset::load(&root).unwrap();
+        let options = FixOptions {
+            remove_zero_area: true,
+            dry_run: true,
+            backup: false,
+        };
+        let summary = fix_dataset(&dataset, None, &options).unwrap();
+        assert_eq!(summary.lines_removed, 1);
+        assert_eq!(summary.diffs.len(), 1);
+        assert!(summary.diffs[0].contains("-0 0.5 0.5 0.2 0.2\n"));
+        assert_eq!(fs::read_to_string(&label_path).unwrap(), content);
+
+        let patch_dir = root.join("patches");
+        fs::create_dir_all(patch_dir.join("labels/val")).unwrap();
+        fs::write(patch_dir.join("labels/val/a.txt"), "0 0.5 0.5 0.2 0.2\n").unwrap();
+        let summary = apply_patches(&root, &patch_dir, &options).unwrap();
+        assert_eq!(summary.diffs.len(), 1);
+        assert!(summary.diffs[0].contains("@@ -1,2 +1,1 @@"));
+        assert_eq!(fs::read_to_string(&label_path).unwrap(), content);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn backups_keep_the_first_original() {
+        let root = std::env::temp_dir().join(format!("gt-audit-backup-{}", std::process::id()));
+        fs::create_dir_all(root.join("labels")).unwrap();
+        let label_path = root.join("labels/a.txt");
+        let backup_path = root.join(BACKUP_DIR).join("labels/a.txt");
+
+        fs::write(&label_path, "original").unwrap();
+        backup_file(&root, &label_path).unwrap();
+        fs::write(&label_path, "fixed").unwrap();
+        backup_file(&root, &label_path).unwrap();
+        let backup = fs::read_to_string(&backup_path).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(backup, "original");
+    }
+}
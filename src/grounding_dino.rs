@@ -0,0 +1,496 @@
+//! Grounding DINO open-vocabulary detector
+//!
+//! Runs an ONNX export of Grounding DINO prompted with the dataset's class
+//! names, so labels can be checked without a model trained on the dataset.
+//! The export must follow the Hugging Face layout: inputs `pixel_values`,
+//! `input_ids`, `token_type_ids`, `attention_mask` and `pixel_mask`, outputs
+//! `logits` (one score per query and prompt token) and `pred_boxes`
+//! (normalized center x, y, width, height).
+
+use anyhow::{Context, Result};
+use image::{DynamicImage, GenericImageView};
+use ort::session::Session;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::detector::{self, Detector, DetectorConfig};
+use crate::models::{BoundingBox, Detection};
+
+/// Where `gt-audit download grounding-dino` fetches the model from
+pub const MODEL_URL: &str =
+    "https://huggingface.co/onnx-community/grounding-dino-tiny-ONNX/resolve/main/onnx/model.onnx";
+
+/// BERT vocabulary of the model's text encoder
+pub const VOCAB_URL: &str =
+    "https://huggingface.co/IDEA-Research/grounding-dino-tiny/resolve/main/vocab.txt";
+
+/// File names in the model cache
+const MODEL_FILE: &str = "grounding-dino.onnx";
+const VOCAB_FILE: &str = "grounding-dino-vocab.txt";
+
+/// Longest prompt the text encoder accepts; longer class lists are split across prompts
+const MAX_TEXT_LEN: usize = 256;
+
+/// Shorter image side after resizing, as in the reference preprocessing
+const SHORTEST_EDGE: f32 = 800.0;
+
+/// Upper bound on the longer image side after resizing
+const LONGEST_EDGE: f32 = 1333.0;
+
+const IMAGENET_MEAN: [f32; 3] = [0.485, 0.456, 0.406];
+const IMAGENET_STD: [f32; 3] = [0.229, 0.224, 0.225];
+
+/// Text a class is prompted with
+///
+/// Underscores become spaces and case is folded, so `Traffic_Light` is
+/// prompted as `traffic light`.
+pub fn class_phrase(class_name: &str) -> String {
+    class_name
+        .replace('_', " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Full prompt text for a list of classes: each phrase ends with ` .`
+///
+/// For example `["car", "traffic_light"]` gives `car . traffic light .`.
+pub fn prompt_text(class_names: &[String]) -> String {
+    class_names
+        .iter()
+        .map(|name| format!("{} .", class_phrase(name)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A tokenized prompt and the tokens of each class in it
+#[derive(Debug)]
+struct Prompt {
+    input_ids: Vec<i64>,
+    /// Class name and the range of its phrase in `input_ids`
+    spans: Vec<(String, Range<usize>)>,
+}
+
+/// WordPiece tokenizer for an uncased BERT vocabulary
+struct Tokenizer {
+    vocab: HashMap<String, i64>,
+    cls: i64,
+    sep: i64,
+    unk: i64,
+    period: i64,
+}
+
+impl Tokenizer {
+    fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read vocabulary {}", path.display()))?;
+        Self::from_tokens(text.lines())
+    }
+
+    /// Build from vocabulary entries, one per token id
+    fn from_tokens<'a>(tokens: impl IntoIterator<Item = &'a str>) -> Result<Self> {
+        let vocab: HashMap<String, i64> = tokens
+            .into_iter()
+            .enumerate()
+            .map(|(id, token)| (token.to_string(), id as i64))
+            .collect();
+        let id = |token: &str| {
+            vocab
+                .get(token)
+                .copied()
+                .with_context(|| format!("Vocabulary has no '{}' token", token))
+        };
+        let (cls, sep, unk, period) = (id("[CLS]")?, id("[SEP]")?, id("[UNK]")?, id(".")?);
+        Ok(Self {
+            vocab,
+            cls,
+            sep,
+            unk,
+            period,
+        })
+    }
+
+    /// Token ids of a phrase: split on whitespace and punctuation, then into word pieces
+    fn encode(&self, phrase: &str) -> Vec<i64> {
+        let mut ids = Vec::new();
+        for word in basic_tokens(&phrase.to_lowercase()) {
+            self.word_pieces(&word, &mut ids);
+        }
+        ids
+    }
+
+    /// Greedy longest-match WordPiece; a word that can't be covered becomes `[UNK]`
+    fn word_pieces(&self, word: &str, ids: &mut Vec<i64>) {
+        let chars: Vec<char> = word.chars().collect();
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let piece = (start + 1..=chars.len()).rev().find_map(|end| {
+                let text: String = chars[start..end].iter().collect();
+                let key = if start == 0 {
+                    text
+                } else {
+                    format!("##{}", text)
+                };
+                self.vocab.get(&key).map(|&id| (id, end))
+            });
+            match piece {
+                Some((id, end)) => {
+                    pieces.push(id);
+                    start = end;
+                }
+                None => {
+                    ids.push(self.unk);
+                    return;
+                }
+            }
+        }
+        ids.extend(pieces);
+    }
+
+    /// Prompts covering `class_names`, each within `MAX_TEXT_LEN` tokens
+    fn prompts(&self, class_names: &[String]) -> Vec<Prompt> {
+        let mut prompts = Vec::new();
+        let mut current = self.empty_prompt();
+        for name in class_names {
+            let mut tokens = self.encode(&class_phrase(name));
+            if tokens.is_empty() {
+                continue;
+            }
+            // Room for [CLS], the phrase's period and the closing [SEP]
+            tokens.truncate(MAX_TEXT_LEN - 3);
+            if current.input_ids.len() + tokens.len() + 2 > MAX_TEXT_LEN {
+                let full = std::mem::replace(&mut current, self.empty_prompt());
+                prompts.push(self.finish_prompt(full));
+            }
+            let start = current.input_ids.len();
+            current.input_ids.extend(tokens);
+            current
+                .spans
+                .push((name.clone(), start..current.input_ids.len()));
+            current.input_ids.push(self.period);
+        }
+        if !current.spans.is_empty() {
+            prompts.push(self.finish_prompt(current));
+        }
+        prompts
+    }
+
+    fn empty_prompt(&self) -> Prompt {
+        Prompt {
+            input_ids: vec![self.cls],
+            spans: Vec::new(),
+        }
+    }
+
+    fn finish_prompt(&self, mut prompt: Prompt) -> Prompt {
+        prompt.input_ids.push(self.sep);
+        prompt
+    }
+}
+
+/// Split on whitespace and make every punctuation character its own token
+fn basic_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in text.split_whitespace() {
+        let mut current = String::new();
+        for c in word.chars() {
+            if c.is_ascii_punctuation() {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(c.to_string());
+            } else {
+                current.push(c);
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+    }
+    tokens
+}
+
+/// Class and confidence of one query from its per-token logits
+///
+/// The query is kept when some class token scores at least `box_threshold`.
+/// It goes to the class whose tokens score highest on average, provided that
+/// average reaches `text_threshold`, so a box matching only "light" in
+/// "traffic light" is not reported as a traffic light. The confidence is the
+/// best token score of that class.
+fn assign_class(
+    spans: &[(String, Range<usize>)],
+    logits: &[f32],
+    box_threshold: f32,
+    text_threshold: f32,
+) -> Option<(String, f32)> {
+    let score = |i: usize| logits.get(i).map_or(0.0, |l| 1.0 / (1.0 + (-l).exp()));
+
+    let mut best: Option<(&String, f32, f32)> = None;
+    let mut box_score = 0.0f32;
+    for (name, range) in spans {
+        let scores: Vec<f32> = range.clone().map(score).collect();
+        let max = scores.iter().copied().fold(0.0, f32::max);
+        let mean = scores.iter().sum::<f32>() / scores.len().max(1) as f32;
+        box_score = box_score.max(max);
+        if mean >= text_threshold && best.is_none_or(|(_, m, _)| mean > m) {
+            best = Some((name, mean, max));
+        }
+    }
+
+    if box_score < box_threshold {
+        return None;
+    }
+    best.map(|(name, _, max)| (name.clone(), max))
+}
+
+/// Open-vocabulary detector prompted with the dataset's class names
+pub struct GroundingDinoDetector {
+    session: Mutex<Session>,
+    config: DetectorConfig,
+    tokenizer: Tokenizer,
+    device_warning: Option<String>,
+}
+
+impl GroundingDinoDetector {
+    /// Load the model given by `config.model_path`, or the downloaded one
+    ///
+    /// The vocabulary is read from a `vocab.txt` next to the model when there
+    /// is one, otherwise from the model cache.
+    pub fn new(config: DetectorConfig) -> Result<Self> {
+        let (model_path, vocab_path) = model_files(config.model_path.as_deref())?;
+        let tokenizer = Tokenizer::load(&vocab_path)?;
+        let (session, _, device_warning) = detector::load_session(&model_path, config.device)?;
+        Ok(Self {
+            session: Mutex::new(session),
+            config,
+            tokenizer,
+            device_warning,
+        })
+    }
+
+    /// Why the requested device was replaced by the CPU, if it was
+    pub fn device_warning(&self) -> Option<&str> {
+        self.device_warning.as_deref()
+    }
+
+    /// Resize keeping the aspect ratio and normalize into a CHW buffer
+    fn preprocess(image: &DynamicImage) -> (u32, u32, Vec<f32>) {
+        let (width, height) = image.dimensions();
+        let scale = (SHORTEST_EDGE / width.min(height).max(1) as f32)
+            .min(LONGEST_EDGE / width.max(height).max(1) as f32);
+        let w = ((width as f32 * scale).round() as u32).max(1);
+        let h = ((height as f32 * scale).round() as u32).max(1);
+        let rgb = image
+            .resize_exact(w, h, image::imageops::FilterType::Triangle)
+            .to_rgb8();
+
+        let plane = (w * h) as usize;
+        let mut data = vec![0.0f32; 3 * plane];
+        for (x, y, pixel) in rgb.enumerate_pixels() {
+            let i = (y * w + x) as usize;
+            for c in 0..3 {
+                data[c * plane + i] =
+                    (pixel[c] as f32 / 255.0 - IMAGENET_MEAN[c]) / IMAGENET_STD[c];
+            }
+        }
+        (w, h, data)
+    }
+
+    /// Run the model once for one prompt
+    fn run(&self, image: &DynamicImage, prompt: &Prompt) -> Result<Vec<Detection>> {
+        use ort::value::Tensor;
+
+        let (w, h, pixels) = Self::preprocess(image);
+        let len = prompt.input_ids.len();
+        let text_shape = vec![1i64, len as i64];
+
+        let pixel_values = Tensor::from_array((vec![1i64, 3, h as i64, w as i64], pixels))?;
+        let pixel_mask =
+            Tensor::from_array((vec![1i64, h as i64, w as i64], vec![1i64; (w * h) as usize]))?;
+        let input_ids = Tensor::from_array((text_shape.clone(), prompt.input_ids.clone()))?;
+        let token_type_ids = Tensor::from_array((text_shape.clone(), vec![0i64; len]))?;
+        let attention_mask = Tensor::from_array((text_shape, vec![1i64; len]))?;
+
+        // Run inference (lock the session for thread safety)
+        let mut session = self
+            .session
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to lock session: {}", e))?;
+        let outputs = session.run(ort::inputs![
+            "pixel_values" => pixel_values,
+            "input_ids" => input_ids,
+            "token_type_ids" => token_type_ids,
+            "attention_mask" => attention_mask,
+            "pixel_mask" => pixel_mask,
+        ])?;
+
+        let (logits_shape, logits) = outputs["logits"].try_extract_tensor::<f32>()?;
+        let (_, boxes) = outputs["pred_boxes"].try_extract_tensor::<f32>()?;
+        if logits_shape.len() != 3 {
+            anyhow::bail!(
+                "Unexpected Grounding DINO logits shape {:?}; expected [batch, queries, tokens]",
+                logits_shape
+            );
+        }
+        let queries = logits_shape[1] as usize;
+        let tokens = logits_shape[2] as usize;
+
+        let mut detections = Vec::new();
+        for q in 0..queries {
+            let Some((class_name, confidence)) = assign_class(
+                &prompt.spans,
+                &logits[q * tokens..(q + 1) * tokens],
+                self.config.confidence_threshold,
+                self.config.text_threshold,
+            ) else {
+                continue;
+            };
+            let b = &boxes[q * 4..q * 4 + 4];
+            detections.push(Detection {
+                class_name,
+                confidence,
+                bbox: BoundingBox::new(b[0], b[1], b[2], b[3]),
+            });
+        }
+        Ok(detections)
+    }
+}
+
+impl Detector for GroundingDinoDetector {
+    fn detect(&self, image: &DynamicImage, class_names: &[String]) -> Result<Vec<Detection>> {
+        let mut detections = Vec::new();
+        for prompt in self.tokenizer.prompts(class_names) {
+            detections.extend(self.run(image, &prompt)?);
+        }
+        Ok(detector::suppress(&self.config, detections))
+    }
+
+    fn name(&self) -> String {
+        "grounding-dino".to_string()
+    }
+}
+
+/// Model and vocabulary paths: an explicit model or the cached download
+fn model_files(model_path: Option<&Path>) -> Result<(PathBuf, PathBuf)> {
+    let cache_dir = detector::model_cache_dir();
+    let model = model_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| cache_dir.join(MODEL_FILE));
+    let vocab = model_path
+        .map(|p| p.with_file_name("vocab.txt"))
+        .filter(|p| p.exists())
+        .unwrap_or_else(|| cache_dir.join(VOCAB_FILE));
+
+    for path in [&model, &vocab] {
+        if !path.exists() {
+            anyhow::bail!(
+                "Grounding DINO file not found: {} (run `gt-audit download grounding-dino`)",
+                path.display()
+            );
+        }
+    }
+    Ok((model, vocab))
+}
+
+/// Download the model and its vocabulary into the model cache
+///
+/// Files already in the cache are kept. Returns the cache directory.
+pub fn download() -> Result<PathBuf> {
+    let cache_dir = detector::model_cache_dir();
+    std::fs::create_dir_all(&cache_dir)
+        .with_context(|| format!("Failed to create {}", cache_dir.display()))?;
+
+    for (url, file) in [(VOCAB_URL, VOCAB_FILE), (MODEL_URL, MODEL_FILE)] {
+        let path = cache_dir.join(file);
+        if !path.exists() {
+            download_file(url, &path)?;
+        }
+    }
+    Ok(cache_dir)
+}
+
+/// Stream `url` into `path` via a temporary file, so an interrupted download
+/// is never mistaken for a finished one
+fn download_file(url: &str, path: &Path) -> Result<()> {
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("Failed to download {}", url))?;
+
+    let partial = path.with_extension("part");
+    let mut file = std::fs::File::create(&partial)
+        .with_context(|| format!("Failed to create {}", partial.display()))?;
+    if let Err(e) = std::io::copy(&mut response.into_reader(), &mut file) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e).with_context(|| format!("Failed to download {}", url));
+    }
+    std::fs::rename(&partial, path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokenizer() -> Tokenizer {
+        Tokenizer::from_tokens([
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", ".", "-", "car", "traffic", "light", "t", "shirt",
+            "bi", "##cycle",
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn prompt_uses_phrases_ending_in_periods() {
+        let classes = vec!["car".to_string(), "Traffic_Light".to_string()];
+        assert_eq!(prompt_text(&classes), "car . traffic light .");
+
+        let prompts = tokenizer().prompts(&classes);
+        assert_eq!(prompts.len(), 1);
+        // [CLS] car . traffic light . [SEP]
+        assert_eq!(prompts[0].input_ids, vec![2, 6, 4, 7, 8, 4, 3]);
+        assert_eq!(prompts[0].spans[0], ("car".to_string(), 1..2));
+        assert_eq!(prompts[0].spans[1], ("Traffic_Light".to_string(), 3..5));
+    }
+
+    #[test]
+    fn word_pieces_and_punctuation() {
+        let tok = tokenizer();
+        assert_eq!(tok.encode("bicycle"), vec![11, 12]);
+        assert_eq!(tok.encode("T-Shirt"), vec![9, 5, 10]);
+        assert_eq!(tok.encode("zebra car"), vec![1, 6]);
+    }
+
+    #[test]
+    fn long_class_lists_are_split_across_prompts() {
+        let classes = vec!["traffic light".to_string(); 100];
+        let prompts = tokenizer().prompts(&classes);
+        assert!(prompts.len() > 1);
+        assert!(prompts.iter().all(|p| p.input_ids.len() <= MAX_TEXT_LEN));
+        assert_eq!(prompts.iter().map(|p| p.spans.len()).sum::<usize>(), 100);
+    }
+
+    #[test]
+    fn multi_word_classes_need_all_their_tokens() {
+        let spans = vec![
+            ("car".to_string(), 1..2),
+            ("traffic light".to_string(), 3..5),
+        ];
+        // Logits for [CLS] car . traffic light . [SEP]: only "light" scores high
+        let logits = [-9.0, -9.0, -9.0, -9.0, 3.0, -9.0, -9.0];
+        assert_eq!(assign_class(&spans, &logits, 0.35, 0.6), None);
+
+        let logits = [-9.0, -9.0, -9.0, 2.0, 3.0, -9.0, -9.0];
+        let (class, confidence) = assign_class(&spans, &logits, 0.35, 0.6).unwrap();
+        assert_eq!(class, "traffic light");
+        assert!(confidence > 0.95);
+
+        // Below the box threshold nothing is kept, whatever the text scores
+        let logits = [-9.0, -1.0, -9.0, -9.0, -9.0, -9.0, -9.0];
+        assert_eq!(assign_class(&spans, &logits, 0.35, 0.1), None);
+    }
+}
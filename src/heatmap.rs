@@ -0,0 +1,97 @@
+//! Spatial distribution of GT boxes: how often box centers fall in each cell
+//! of a grid laid over the image
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::ImageResult;
+
+/// Cells per side of the grid used in reports by default
+pub const DEFAULT_GRID: usize = 20;
+
+/// Count of GT box centers per grid cell, in normalized image coordinates
+///
+/// Serializes as the bare 2D array, one row per horizontal band from the top
+/// of the image down.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SpatialHeatmap {
+    counts: Vec<Vec<usize>>,
+}
+
+impl SpatialHeatmap {
+    /// An empty `grid` x `grid` heatmap
+    pub fn new(grid: usize) -> Self {
+        let grid = grid.max(1);
+        Self {
+            counts: vec![vec![0; grid]; grid],
+        }
+    }
+
+    /// Bin the GT boxes of every result, or `None` if there are none
+    pub fn from_results<'a>(
+        results: impl IntoIterator<Item = &'a ImageResult>,
+        grid: usize,
+    ) -> Option<Self> {
+        let mut heatmap = Self::new(grid);
+        for result in results {
+            for &(x, y) in &result.gt_centers {
+                heatmap.add(x, y);
+            }
+        }
+        (heatmap.total() > 0).then_some(heatmap)
+    }
+
+    /// Count a point; points outside the image land in the nearest edge cell
+    pub fn add(&mut self, x: f32, y: f32) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        let grid = self.grid();
+        let cell = |v: f32| ((v * grid as f32) as usize).min(grid - 1);
+        self.counts[cell(y.max(0.0))][cell(x.max(0.0))] += 1;
+    }
+
+    /// Cells per side
+    pub fn grid(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Counts indexed by `[row][column]`
+    pub fn counts(&self) -> &[Vec<usize>] {
+        &self.counts
+    }
+
+    /// Points binned
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    /// Count of the fullest cell
+    pub fn max(&self) -> usize {
+        self.counts.iter().flatten().copied().max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn centers_are_binned_by_row_and_column() {
+        let mut heatmap = SpatialHeatmap::new(4);
+        heatmap.add(0.1, 0.9);
+        heatmap.add(0.5, 0.5);
+        heatmap.add(0.55, 0.6);
+        heatmap.add(1.0, -0.2);
+
+        assert_eq!(heatmap.counts()[3][0], 1);
+        assert_eq!(heatmap.counts()[2][2], 2);
+        assert_eq!(heatmap.counts()[0][3], 1);
+        assert_eq!(heatmap.total(), 4);
+        assert_eq!(heatmap.max(), 2);
+        assert_eq!(
+            serde_json::to_value(SpatialHeatmap::new(2)).unwrap(),
+            serde_json::json!([[0, 0], [0, 0]])
+        );
+    }
+}
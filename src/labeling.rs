@@ -0,0 +1,513 @@
+//! Flagged images of a report exported as Label Studio or CVAT tasks
+//!
+//! Each task shows an image with its current labels as pre-annotations, so
+//! reviewers correct boxes instead of drawing them again, and carries the
+//! image's issues along. Segmentation polygons are exported as their
+//! enclosing boxes.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+use crate::class_map::ClassSynonyms;
+use crate::dataset::YoloDataset;
+use crate::models::{Annotation, AuditResult, ImageResult, Issue, IssueSeverity};
+use crate::report::xml_escape;
+
+/// Labeling tools `gt-audit export` writes tasks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskFormat {
+    /// A JSON list of tasks with predictions, for Label Studio's import
+    LabelStudio,
+    /// A "CVAT for images 1.1" annotations XML
+    Cvat,
+}
+
+impl std::str::FromStr for TaskFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "label-studio" => Ok(Self::LabelStudio),
+            "cvat" => Ok(Self::Cvat),
+            other => anyhow::bail!(
+                "Unknown task format '{}' (expected label-studio or cvat)",
+                other
+            ),
+        }
+    }
+}
+
+/// Names the tasks must use to match a Label Studio labeling config
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelConfig {
+    /// `name` of the `<RectangleLabels>` control
+    pub from_name: String,
+    /// `name` of the `<Image>` object the control labels
+    pub to_name: String,
+    /// Task data key the image URL goes under (`$image` is `image`)
+    pub data_key: String,
+    /// `value`s of the control's `<Label>`s; dataset classes are mapped onto these
+    pub labels: Vec<String>,
+}
+
+impl Default for LabelConfig {
+    /// The names of Label Studio's object detection template
+    fn default() -> Self {
+        Self {
+            from_name: "label".to_string(),
+            to_name: "image".to_string(),
+            data_key: "image".to_string(),
+            labels: Vec::new(),
+        }
+    }
+}
+
+impl LabelConfig {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid label config: {}", path.display()))
+    }
+
+    /// Read the first `<RectangleLabels>` of a labeling config and its image
+    pub fn parse(xml: &str) -> Result<Self> {
+        let mut config = Self::default();
+        let mut control = None;
+        let mut images = HashMap::new();
+        let mut inside = false;
+
+        for tag in xml.split('<').skip(1) {
+            let tag = tag.split('>').next().unwrap_or_default();
+            let element = tag.split_whitespace().next().unwrap_or_default();
+            match element {
+                "RectangleLabels" if control.is_none() => {
+                    let name = attribute(tag, "name").context("<RectangleLabels> has no name")?;
+                    let to_name =
+                        attribute(tag, "toName").context("<RectangleLabels> has no toName")?;
+                    control = Some((name, to_name));
+                    inside = !tag.trim_end().ends_with('/');
+                }
+                "/RectangleLabels" => inside = false,
+                "Label" if inside => {
+                    if let Some(value) = attribute(tag, "value") {
+                        config.labels.push(value);
+                    }
+                }
+                "Image" => {
+                    if let (Some(name), Some(value)) =
+                        (attribute(tag, "name"), attribute(tag, "value"))
+                    {
+                        images.insert(name, value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (from_name, to_name) = control.context("No <RectangleLabels> control")?;
+        let value = images
+            .get(&to_name)
+            .with_context(|| format!("No <Image> named '{}'", to_name))?;
+        config.data_key = value.trim_start_matches('$').to_string();
+        config.from_name = from_name;
+        config.to_name = to_name;
+        Ok(config)
+    }
+}
+
+/// Value of attribute `name` in the inside of an XML tag, unescaped
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    while let Some(pos) = rest.find(name) {
+        let preceded_by_space = rest[..pos].ends_with(char::is_whitespace);
+        rest = &rest[pos + name.len()..];
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        if !preceded_by_space {
+            continue;
+        }
+        let value = value.trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &value[1..];
+        let end = value.find(quote)?;
+        return Some(
+            value[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+    }
+    None
+}
+
+/// Totals reported after exporting tasks
+#[derive(Debug, Default)]
+pub struct TaskSummary {
+    pub tasks: usize,
+    pub boxes: usize,
+    /// Dataset classes without a label in the labeling config, exported under
+    /// their own name
+    pub unmapped_classes: Vec<String>,
+    /// Images that could not be exported, with the reason
+    pub skipped: Vec<String>,
+}
+
+/// A flagged image ready to be written as a task
+struct TaskImage<'a> {
+    result: &'a ImageResult,
+    /// Image URL, or its path when no prefix was given
+    url: String,
+    /// Path relative to the dataset root, with `/` separators
+    name: String,
+    width: u32,
+    height: u32,
+    /// Annotations with class names mapped to labels
+    annotations: Vec<Annotation>,
+    /// Issues not suppressed by `.gtauditignore`
+    issues: Vec<&'a Issue>,
+}
+
+/// Exports the images a report flagged as tasks for a labeling tool
+///
+/// Images are found by their key and split in the dataset the report was
+/// made from, like `export-fixes` does.
+pub struct TaskExporter<'a> {
+    result: &'a AuditResult,
+    dataset_root: &'a Path,
+    /// URL prefix, or a template where `{path}` stands for the image path
+    images_prefix: Option<&'a str>,
+    label_config: &'a LabelConfig,
+    synonyms: &'a ClassSynonyms,
+}
+
+impl<'a> TaskExporter<'a> {
+    pub fn new(
+        result: &'a AuditResult,
+        dataset_root: &'a Path,
+        images_prefix: Option<&'a str>,
+        label_config: &'a LabelConfig,
+        synonyms: &'a ClassSynonyms,
+    ) -> Self {
+        Self {
+            result,
+            dataset_root,
+            images_prefix,
+            label_config,
+            synonyms,
+        }
+    }
+
+    pub fn write(&self, format: TaskFormat, path: &Path) -> Result<TaskSummary> {
+        let (images, mut summary) = self.task_images()?;
+        let content = match format {
+            TaskFormat::LabelStudio => self.label_studio_json(&images)?,
+            TaskFormat::Cvat => self.cvat_xml(&images),
+        };
+        fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        summary.tasks = images.len();
+        summary.boxes = images.iter().map(|i| i.annotations.len()).sum();
+        Ok(summary)
+    }
+
+    fn task_images(&self) -> Result<(Vec<TaskImage<'a>>, TaskSummary)> {
+        let mut summary = TaskSummary::default();
+        let mut datasets: HashMap<Option<String>, YoloDataset> = HashMap::new();
+        let mut images = Vec::new();
+
+        for image in self.result.flagged_images() {
+            let issues: Vec<&Issue> = image.issues.iter().filter(|i| !i.suppressed).collect();
+            if issues.is_empty() {
+                continue;
+            }
+
+            let dataset = match datasets.entry(image.split.clone()) {
+                std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(match &image.split {
+                        Some(split) => YoloDataset::load_split(self.dataset_root, split)?,
+                        None => YoloDataset::load(self.dataset_root)?,
+                    })
+                }
+            };
+            let Some(image_path) = dataset.image_path(&image.filename) else {
+                summary.skipped.push(format!(
+                    "{}: image not found in the dataset",
+                    image.filename
+                ));
+                continue;
+            };
+            let (width, height) = match image::image_dimensions(&image_path) {
+                Ok(size) => size,
+                Err(e) => {
+                    summary.skipped.push(format!("{}: {}", image.filename, e));
+                    continue;
+                }
+            };
+
+            let mut annotations = dataset.load_annotations(&image_path);
+            annotations.retain(|a| a.class_id >= 0);
+            for annotation in &mut annotations {
+                annotation.class_name = self.label_for(&annotation.class_name, &mut summary);
+            }
+
+            let name = image_path
+                .strip_prefix(self.dataset_root)
+                .unwrap_or(&image_path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            let url = match self.images_prefix {
+                Some(prefix) => image_url(prefix, &name),
+                None => image_path.to_string_lossy().to_string(),
+            };
+            images.push(TaskImage {
+                result: image,
+                url,
+                name,
+                width,
+                height,
+                annotations,
+                issues,
+            });
+        }
+        Ok((images, summary))
+    }
+
+    /// Label of the labeling config a dataset class goes under
+    ///
+    /// Matched the way model classes are matched to dataset classes, so the
+    /// same `--synonyms` file serves both.
+    fn label_for(&self, class_name: &str, summary: &mut TaskSummary) -> String {
+        if self.label_config.labels.is_empty() {
+            return class_name.to_string();
+        }
+        match self
+            .synonyms
+            .find_match(class_name, &self.label_config.labels)
+        {
+            Some(label) => label.clone(),
+            None => {
+                if !summary.unmapped_classes.iter().any(|c| c == class_name) {
+                    summary.unmapped_classes.push(class_name.to_string());
+                }
+                class_name.to_string()
+            }
+        }
+    }
+
+    fn label_studio_json(&self, images: &[TaskImage]) -> Result<String> {
+        let tasks: Vec<LabelStudioTask> = images
+            .iter()
+            .map(|image| LabelStudioTask {
+                data: BTreeMap::from([(self.label_config.data_key.as_str(), image.url.clone())]),
+                predictions: vec![LabelStudioPrediction {
+                    model_version: "ground truth",
+                    result: image
+                        .annotations
+                        .iter()
+                        .map(|ann| {
+                            let (x1, y1, x2, y2) = ann.bbox.to_xyxy();
+                            LabelStudioRegion {
+                                id: format!("line{}", ann.line_num),
+                                from_name: &self.label_config.from_name,
+                                to_name: &self.label_config.to_name,
+                                kind: "rectanglelabels",
+                                original_width: image.width,
+                                original_height: image.height,
+                                image_rotation: 0,
+                                value: RectangleValue {
+                                    x: x1 * 100.0,
+                                    y: y1 * 100.0,
+                                    width: (x2 - x1) * 100.0,
+                                    height: (y2 - y1) * 100.0,
+                                    rotation: 0.0,
+                                    rectanglelabels: vec![ann.class_name.clone()],
+                                },
+                            }
+                        })
+                        .collect(),
+                }],
+                meta: TaskMeta {
+                    image: &image.result.filename,
+                    split: image.result.split.as_deref(),
+                    relabel_priority: image.result.relabel_priority,
+                    issues: &image.issues,
+                },
+            })
+            .collect();
+        Ok(serde_json::to_string_pretty(&tasks)?)
+    }
+
+    fn cvat_xml(&self, images: &[TaskImage]) -> String {
+        let mut labels = self.label_config.labels.clone();
+        for ann in images.iter().flat_map(|i| &i.annotations) {
+            if !labels.contains(&ann.class_name) {
+                labels.push(ann.class_name.clone());
+            }
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<annotations>\n");
+        xml.push_str("  <version>1.1</version>\n");
+        xml.push_str("  <meta>\n    <task>\n      <name>gt-audit relabeling</name>\n");
+        xml.push_str(&format!("      <size>{}</size>\n", images.len()));
+        xml.push_str("      <labels>\n");
+        for label in &labels {
+            xml.push_str(&format!(
+                "        <label>\n          <name>{}</name>\n        </label>\n",
+                xml_escape(label)
+            ));
+        }
+        xml.push_str("      </labels>\n    </task>\n  </meta>\n");
+
+        for (id, image) in images.iter().enumerate() {
+            xml.push_str(&format!(
+                "  <image id=\"{}\" name=\"{}\" width=\"{}\" height=\"{}\">\n",
+                id,
+                xml_escape(&image.name),
+                image.width,
+                image.height
+            ));
+            // CVAT has no place for free text on an image, so issues go in comments
+            for issue in &image.issues {
+                let line = issue
+                    .line_num
+                    .map(|n| format!(" (line {})", n))
+                    .unwrap_or_default();
+                let severity = match issue.severity {
+                    IssueSeverity::High => "high",
+                    IssueSeverity::Medium => "medium",
+                    IssueSeverity::Low => "low",
+                };
+                let comment = format!(
+                    "{} {}{}: {}",
+                    severity, issue.issue_type, line, issue.description
+                );
+                xml.push_str(&format!(
+                    "    <!-- {} -->\n",
+                    xml_escape(&comment).replace("--", "- -")
+                ));
+            }
+            let (w, h) = (image.width as f32, image.height as f32);
+            for ann in &image.annotations {
+                let (x1, y1, x2, y2) = ann.bbox.to_xyxy();
+                xml.push_str(&format!(
+                    "    <box label=\"{}\" source=\"manual\" occluded=\"0\" xtl=\"{:.2}\" ytl=\"{:.2}\" xbr=\"{:.2}\" ybr=\"{:.2}\" z_order=\"0\">\n    </box>\n",
+                    xml_escape(&ann.class_name),
+                    x1 * w,
+                    y1 * h,
+                    x2 * w,
+                    y2 * h
+                ));
+            }
+            xml.push_str("  </image>\n");
+        }
+        xml.push_str("</annotations>\n");
+        xml
+    }
+}
+
+/// URL of an image: `{path}` in the prefix replaced, or the path appended
+fn image_url(prefix: &str, path: &str) -> String {
+    if prefix.contains("{path}") {
+        prefix.replace("{path}", path)
+    } else {
+        format!("{}/{}", prefix.trim_end_matches('/'), path)
+    }
+}
+
+#[derive(Serialize)]
+struct LabelStudioTask<'a> {
+    data: BTreeMap<&'a str, String>,
+    predictions: Vec<LabelStudioPrediction<'a>>,
+    meta: TaskMeta<'a>,
+}
+
+#[derive(Serialize)]
+struct LabelStudioPrediction<'a> {
+    model_version: &'static str,
+    result: Vec<LabelStudioRegion<'a>>,
+}
+
+#[derive(Serialize)]
+struct LabelStudioRegion<'a> {
+    id: String,
+    from_name: &'a str,
+    to_name: &'a str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    original_width: u32,
+    original_height: u32,
+    image_rotation: u32,
+    value: RectangleValue,
+}
+
+/// A box in percent of the image size, from the top-left corner
+#[derive(Serialize)]
+struct RectangleValue {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    rotation: f32,
+    rectanglelabels: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct TaskMeta<'a> {
+    image: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    split: Option<&'a str>,
+    relabel_priority: f32,
+    issues: &'a [&'a Issue],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_config_names_control_and_image() {
+        let config = LabelConfig::parse(
+            r#"<View>
+  <Image name="img" value="$photo" zoom="true"/>
+  <RectangleLabels name="box" toName="img">
+    <Label value="Car" background="green"/>
+    <Label value="Person &amp; Rider"/>
+  </RectangleLabels>
+  <Choices name="quality" toName="img">
+    <Choice value="Blurry"/>
+  </Choices>
+</View>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            LabelConfig {
+                from_name: "box".to_string(),
+                to_name: "img".to_string(),
+                data_key: "photo".to_string(),
+                labels: vec!["Car".to_string(), "Person & Rider".to_string()],
+            }
+        );
+        assert!(
+            LabelConfig::parse("<View><Image name=\"image\" value=\"$image\"/></View>").is_err()
+        );
+    }
+
+    #[test]
+    fn image_urls_fill_templates() {
+        assert_eq!(
+            image_url("https://bucket.example.com/data/", "images/val/a.jpg"),
+            "https://bucket.example.com/data/images/val/a.jpg"
+        );
+        assert_eq!(
+            image_url("https://cdn.example.com/{path}?sig=abc", "images/a.jpg"),
+            "https://cdn.example.com/images/a.jpg?sig=abc"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Duplicate image detection within and across dataset splits
+
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use crate::dataset::MultiSplitDataset;
+use crate::models::IssueSeverity;
+
+/// dHashes differing in at most this many bits are near-duplicates
+pub const NEAR_DUPLICATE_DISTANCE: u32 = 5;
+
+/// What gets hashed to decide whether two images are the same
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashMode {
+    /// Raw file bytes: fast, but re-encoded copies are not matched
+    #[default]
+    Bytes,
+    /// Decoded RGBA pixels: matches the same image saved in a different format
+    Pixels,
+}
+
+impl std::str::FromStr for HashMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "bytes" => Ok(Self::Bytes),
+            "pixels" => Ok(Self::Pixels),
+            other => anyhow::bail!("Unknown hash mode '{}' (expected bytes or pixels)", other),
+        }
+    }
+}
+
+/// One copy of a duplicated image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateFile {
+    pub split: String,
+    pub path: PathBuf,
+}
+
+/// Images sharing the same content hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub hash: String,
+    /// High when the copies span more than one split, Low otherwise
+    pub severity: IssueSeverity,
+    pub cross_split: bool,
+    pub files: Vec<DuplicateFile>,
+}
+
+/// Result of a leakage scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeakageReport {
+    pub hash_mode: HashMode,
+    pub images_hashed: usize,
+    /// Images that could not be read or decoded
+    pub images_skipped: usize,
+    pub groups: Vec<DuplicateGroup>,
+}
+
+impl LeakageReport {
+    /// Duplicate groups that leak between splits
+    pub fn cross_split_count(&self) -> usize {
+        self.groups.iter().filter(|g| g.cross_split).count()
+    }
+
+    /// Duplicate groups confined to a single split
+    pub fn intra_split_count(&self) -> usize {
+        self.groups.len() - self.cross_split_count()
+    }
+}
+
+/// Hash every image of every split and group identical ones
+///
+/// Images are hashed in parallel and only the 32-byte digest per image is
+/// kept, so memory stays proportional to the image count rather than the
+/// dataset size.
+pub fn find_duplicates(dataset: &MultiSplitDataset, mode: HashMode) -> Result<LeakageReport> {
+    let split_names = dataset.split_names();
+    let images: Vec<(usize, PathBuf)> = split_names
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, name)| {
+            dataset.splits[name]
+                .get_images()
+                .into_iter()
+                .map(move |path| (idx, path))
+        })
+        .collect();
+
+    let hashes: Vec<Option<blake3::Hash>> = images
+        .par_iter()
+        .map(|(_, path)| hash_image(path, mode).ok())
+        .collect();
+
+    let mut by_hash: HashMap<blake3::Hash, Vec<usize>> = HashMap::new();
+    for (i, hash) in hashes.iter().enumerate() {
+        if let Some(hash) = hash {
+            by_hash.entry(*hash).or_default().push(i);
+        }
+    }
+
+    let mut groups: Vec<DuplicateGroup> = by_hash
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(hash, members)| {
+            let first_split = images[members[0]].0;
+            let cross_split = members.iter().any(|&i| images[i].0 != first_split);
+            let mut files: Vec<DuplicateFile> = members
+                .iter()
+                .map(|&i| DuplicateFile {
+                    split: split_names[images[i].0].clone(),
+                    path: images[i].1.clone(),
+                })
+                .collect();
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+            DuplicateGroup {
+                hash: hash.to_hex().to_string(),
+                severity: if cross_split {
+                    IssueSeverity::High
+                } else {
+                    IssueSeverity::Low
+                },
+                cross_split,
+                files,
+            }
+        })
+        .collect();
+
+    // Leaks first, then the largest groups
+    groups.sort_by(|a, b| {
+        b.cross_split
+            .cmp(&a.cross_split)
+            .then(b.files.len().cmp(&a.files.len()))
+            .then(a.files[0].path.cmp(&b.files[0].path))
+    });
+
+    let images_skipped = hashes.iter().filter(|h| h.is_none()).count();
+    Ok(LeakageReport {
+        hash_mode: mode,
+        images_hashed: images.len() - images_skipped,
+        images_skipped,
+        groups,
+    })
+}
+
+/// Content hash of a single image
+fn hash_image(path: &Path, mode: HashMode) -> Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    match mode {
+        HashMode::Bytes => {
+            let mut reader = BufReader::new(
+                File::open(path).with_context(|| format!("Failed to open {}", path.display()))?,
+            );
+            let mut buf = [0u8; 64 * 1024];
+            loop {
+                let n = reader.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                hasher.update(&buf[..n]);
+            }
+        }
+        HashMode::Pixels => {
+            let img = image::open(path)
+                .with_context(|| format!("Failed to decode {}", path.display()))?
+                .to_rgba8();
+            hasher.update(&img.width().to_le_bytes());
+            hasher.update(&img.height().to_le_bytes());
+            hasher.update(img.as_raw());
+        }
+    }
+    Ok(hasher.finalize())
+}
+
+/// Difference hash of an image
+///
+/// The image is shrunk to a 9x8 grayscale thumbnail; each of the 64 bits
+/// records whether a pixel is darker than its right neighbour. Resizing,
+/// re-encoding and small edits change few bits.
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let thumb = image
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if thumb.get_pixel(x, y)[0] < thumb.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+/// Difference hash of an image file
+pub fn dhash_file(path: &Path) -> Result<u64> {
+    let image =
+        image::open(path).with_context(|| format!("Failed to decode {}", path.display()))?;
+    Ok(dhash(&image))
+}
+
+/// Pairs of hashes differing in at most `max_distance` bits
+///
+/// Returns `(i, j, distance)` with `i < j`, sorted. Each hash is cut into
+/// `max_distance + 1` bit ranges, and two hashes within the distance agree on
+/// at least one of them, so only hashes sharing a range value are compared
+/// instead of all pairs. `None` entries (unreadable images) are skipped.
+pub fn near_duplicates(hashes: &[Option<u64>], max_distance: u32) -> Vec<(usize, usize, u32)> {
+    let parts = (max_distance as usize + 1).min(64);
+    let ranges: Vec<(usize, u64)> = (0..parts)
+        .map(|p| {
+            let start = 64 * p / parts;
+            let width = 64 * (p + 1) / parts - start;
+            let mask = if width == 64 {
+                u64::MAX
+            } else {
+                (1u64 << width) - 1
+            };
+            (start, mask)
+        })
+        .collect();
+
+    let mut buckets: HashMap<(usize, u64), Vec<usize>> = HashMap::new();
+    for (i, hash) in hashes.iter().enumerate() {
+        let Some(hash) = hash else {
+            continue;
+        };
+        for (part, &(shift, mask)) in ranges.iter().enumerate() {
+            buckets
+                .entry((part, (hash >> shift) & mask))
+                .or_default()
+                .push(i);
+        }
+    }
+
+    let mut pairs = BTreeSet::new();
+    for members in buckets.values() {
+        for (k, &i) in members.iter().enumerate() {
+            for &j in &members[k + 1..] {
+                if let (Some(a), Some(b)) = (hashes[i], hashes[j]) {
+                    let distance = (a ^ b).count_ones();
+                    if distance <= max_distance {
+                        pairs.insert((i, j, distance));
+                    }
+                }
+            }
+        }
+    }
+    pairs.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn near_duplicates_finds_only_close_hashes() {
+        let base = 0xF0F0_1234_5678_9ABCu64;
+        let hashes = vec![
+            Some(base),
+            Some(base ^ 0b1011),    // 3 bits away
+            Some(!base),            // 64 bits away
+            None,                   // unreadable
+            Some(base ^ (1 << 63)), // 1 bit away
+        ];
+        assert_eq!(
+            near_duplicates(&hashes, NEAR_DUPLICATE_DISTANCE),
+            vec![(0, 1, 3), (0, 4, 1), (1, 4, 4)]
+        );
+        assert!(near_duplicates(&hashes, 0).is_empty());
+    }
+
+    #[test]
+    fn dhash_survives_resizing() {
+        let waves = image::RgbImage::from_fn(90, 80, |x, y| {
+            let v = (128.0 + 100.0 * (x as f32 / 9.0 + y as f32 / 13.0).sin()) as u8;
+            image::Rgb([v, v / 2, 255 - v])
+        });
+        let image = DynamicImage::ImageRgb8(waves);
+        let smaller = image.resize_exact(45, 40, image::imageops::FilterType::Triangle);
+        assert!((dhash(&image) ^ dhash(&smaller)).count_ones() <= NEAR_DUPLICATE_DISTANCE);
+    }
+}
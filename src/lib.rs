@@ -0,0 +1,59 @@
+//! Fast ground truth label validation for object detection datasets
+//!
+//! gt-audit runs a detector over a YOLO-format dataset and flags labels the
+//! detector disagrees with. The same pipeline behind the `gt-audit` CLI is
+//! available programmatically:
+//!
+//! ```no_run
+//! use gt_audit::{run_audit, AuditOptions};
+//!
+//! let result = run_audit(AuditOptions {
+//!     dataset_path: "./my-dataset".into(),
+//!     model_path: Some("./model.onnx".into()),
+//!     ..Default::default()
+//! })?;
+//!
+//! println!("{} issues in {} images", result.total_issues(), result.images_with_issues());
+//! for image in result.flagged_images() {
+//!     println!("{}: {} issues", image.filename, image.issues.len());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod audit;
+pub mod auditor;
+pub mod baseline;
+pub mod box_stats;
+pub mod cache;
+pub mod class_map;
+pub mod config;
+pub mod dataset;
+pub mod detector;
+pub mod fix;
+pub mod grounding_dino;
+pub mod heatmap;
+pub mod labeling;
+pub mod leakage;
+pub mod logging;
+pub mod metrics;
+pub mod model_info;
+pub mod models;
+pub mod policy;
+pub mod priority;
+pub mod prometheus;
+pub mod report;
+pub mod sampling;
+pub mod stream;
+pub mod suppress;
+pub mod watch;
+
+pub use audit::{run_audit, run_audit_with, AuditObserver, AuditOptions, NoopObserver};
+pub use auditor::Auditor;
+pub use dataset::{MultiSplitDataset, YoloDataset};
+pub use detector::{
+    Detector, DetectorConfig, Device, ModelFormat, NmsMethod, YoloDetector, ZeroShotBackend,
+};
+pub use grounding_dino::GroundingDinoDetector;
+pub use models::{
+    AuditResult, AuditSummary, ImageResult, Issue, IssueSeverity, IssueType, PerClassStats,
+};
@@ -0,0 +1,122 @@
+//! Log output for the CLI, built on `tracing`
+//!
+//! Events go to stderr. While an audit's progress bar is showing, each line is
+//! written with the bar suspended so the two never garble each other, even
+//! when rayon workers log concurrently.
+
+use anyhow::Result;
+use indicatif::ProgressBar;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::fmt::{self, MakeWriter};
+use tracing_subscriber::prelude::*;
+
+/// How log lines are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Plain messages, with levels and timestamps once debug output is on
+    #[default]
+    Text,
+    /// One JSON object per event, for log pipelines
+    Json,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => anyhow::bail!("Unknown log format '{}' (expected text or json)", other),
+        }
+    }
+}
+
+/// Level for `-q` and the number of `-v` flags given
+///
+/// Info by default, debug with `-v` and trace with `-vv`; quiet keeps only
+/// warnings and errors.
+pub fn level(verbose: u8, quiet: bool) -> LevelFilter {
+    match (quiet, verbose) {
+        (true, _) => LevelFilter::WARN,
+        (false, 0) => LevelFilter::INFO,
+        (false, 1) => LevelFilter::DEBUG,
+        (false, _) => LevelFilter::TRACE,
+    }
+}
+
+/// Install the global subscriber
+///
+/// Only gt-audit's own events go beyond warnings, so debug output isn't
+/// drowned out by dependencies such as ONNX Runtime. Later calls are no-ops.
+pub fn init(level: LevelFilter, format: LogFormat) {
+    let filter = Targets::new()
+        .with_target("gt_audit", level)
+        .with_default(level.min(LevelFilter::WARN));
+    let layer = match format {
+        LogFormat::Json => fmt::layer().json().with_writer(ProgressWriter).boxed(),
+        LogFormat::Text if level > LevelFilter::INFO => fmt::layer()
+            .with_writer(ProgressWriter)
+            .with_ansi(io::stderr().is_terminal())
+            .with_timer(fmt::time::uptime())
+            .boxed(),
+        LogFormat::Text => fmt::layer()
+            .with_writer(ProgressWriter)
+            .with_ansi(io::stderr().is_terminal())
+            .without_time()
+            .with_target(false)
+            .with_level(false)
+            .boxed(),
+    };
+    let _ = tracing_subscriber::registry()
+        .with(layer.with_filter(filter))
+        .try_init();
+}
+
+/// Progress bar that log lines are printed around, if one is showing
+static PROGRESS: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// Suspend `pb` while logging from now on, or stop with `None`
+pub fn set_progress_bar(pb: Option<ProgressBar>) {
+    if let Ok(mut progress) = PROGRESS.lock() {
+        *progress = pb;
+    }
+}
+
+/// Run `f`, which prints something, with the current progress bar hidden
+pub fn suspend_progress<R>(f: impl FnOnce() -> R) -> R {
+    let pb = PROGRESS.lock().ok().and_then(|pb| pb.clone());
+    match pb {
+        Some(pb) => pb.suspend(f),
+        None => f(),
+    }
+}
+
+/// Stderr writer that hides the current progress bar while a line is written
+struct ProgressWriter;
+
+impl<'a> MakeWriter<'a> for ProgressWriter {
+    type Writer = ProgressWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        ProgressWriter
+    }
+}
+
+impl Write for ProgressWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The formatter hands over each event as one buffer
+        let pb = PROGRESS.lock().ok().and_then(|pb| pb.clone());
+        match pb {
+            Some(pb) => pb.suspend(|| io::stderr().write_all(buf))?,
+            None => io::stderr().write_all(buf)?,
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
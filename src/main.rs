@@ -1,19 +1,73 @@
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
 use indicatif::{ProgressBar, ProgressStyle};
-use rayon::prelude::*;
-use std::path::PathBuf;
-use std::time::Instant;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Once, OnceLock};
+use tracing::{info, warn};
 
-mod dataset;
-mod detector;
-mod models;
-mod report;
+use gt_audit::audit::{self, run_audit_with, AuditObserver, AuditOptions};
+use gt_audit::baseline::Baseline;
+use gt_audit::box_stats::BoxStats;
+use gt_audit::cache::DEFAULT_CACHE_FILE;
+use gt_audit::class_map::{ClassMapping, ClassSource, ClassSynonyms, UnmappedClasses};
+use gt_audit::config::{self, Config, OneOrMany, DEFAULT_CONFIG_FILE};
+use gt_audit::dataset::{
+    self, DatasetExporter, DatasetFormat, LabeledDataset, MultiSplitDataset, YoloDataset,
+};
+use gt_audit::detector::{self, Device, ModelFormat, NmsMethod, NmsMode, ZeroShotBackend};
+use gt_audit::fix;
+use gt_audit::grounding_dino;
+use gt_audit::heatmap;
+use gt_audit::labeling::{LabelConfig, TaskExporter, TaskFormat};
+use gt_audit::leakage::{self, HashMode};
+use gt_audit::logging::{self, LogFormat};
+use gt_audit::metrics;
+use gt_audit::model_info::ModelInfo;
+use gt_audit::models::{
+    AuditResult, AuditSummary, ClassStats, ImageResult, IssueSeverity, IssueType, SortOrder,
+    DEFAULT_LABEL_SWAP_MIN_SHARE,
+};
+use gt_audit::policy::{self, PolicyFile};
+use gt_audit::priority::{self, PriorityWeights};
+use gt_audit::prometheus::MetricsServer;
+use gt_audit::report::{
+    HtmlReporter, JsonReporter, JunitReporter, MarkdownReporter, ReportDetail, Reporter,
+    SarifReporter,
+};
+use gt_audit::sampling::SampleStrategy;
+use gt_audit::suppress::{self, Suppressions, SUPPRESSIONS_FILE};
+use gt_audit::watch;
 
-use dataset::YoloDataset;
-use detector::{Detector, DetectorConfig, YoloDetector, ZeroShotDetector};
-use models::AuditResult;
-use report::{HtmlReporter, JsonReporter, Reporter};
+/// Whether human-oriented output goes to stderr, leaving stdout to `--print-summary`
+static HUMAN_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Set by the first Ctrl-C during an audit, which then stops after the images in flight
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Set once the audit is over, after which Ctrl-C exits right away
+static AUDIT_DONE: AtomicBool = AtomicBool::new(false);
+
+/// Exit code of an interrupted audit, as for a process killed by SIGINT
+const EXIT_INTERRUPTED: u8 = 130;
+
+/// `println!` for the human-oriented output of `validate`
+macro_rules! say {
+    ($($arg:tt)*) => {
+        logging::suspend_progress(|| {
+            if HUMAN_TO_STDERR.load(Ordering::Relaxed) {
+                eprintln!($($arg)*)
+            } else {
+                println!($($arg)*)
+            }
+        })
+    };
+}
 
 #[derive(Parser)]
 #[command(name = "gt-audit")]
@@ -26,40 +80,317 @@ struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Validate ground truth labels
     Validate {
         /// Path to dataset (YOLO format)
         #[arg(value_name = "DATASET")]
-        dataset: PathBuf,
+        dataset: Option<PathBuf>,
+
+        /// Load options from a TOML file (defaults to gt-audit.toml in DATASET or ./ if present)
+        #[arg(long, value_name = "FILE")]
+        config: Option<PathBuf>,
+
+        /// Split to audit (e.g. train, val, test), as declared in data.yaml
+        #[arg(long, conflicts_with = "splits")]
+        split: Option<String>,
+
+        /// Splits to audit together: "all" or a comma-separated list (e.g. train,val)
+        #[arg(long)]
+        splits: Option<String>,
+
+        /// Audit just these images instead of DATASET (e.g. the files of a commit)
+        #[arg(long, value_name = "IMAGE", num_args = 1.., conflicts_with_all = ["dataset", "split", "splits"])]
+        images: Vec<PathBuf>,
+
+        /// Class names for --images: classes.txt or a YAML with `names` [default: found at the dataset root]
+        #[arg(
+            long,
+            value_name = "FILE",
+            visible_alias = "classes-yaml",
+            requires = "images"
+        )]
+        classes_file: Option<PathBuf>,
+
+        /// Detection method: zero-shot, grounding-dino, vlm, byom, ensemble [default: zero-shot]
+        #[arg(short, long)]
+        method: Option<String>,
 
-        /// Detection method: zero-shot, vlm, byom
-        #[arg(short, long, default_value = "zero-shot")]
-        method: String,
+        /// Zero-shot backend: heuristic or grounding-dino [default: heuristic]
+        #[arg(long)]
+        backend: Option<String>,
 
-        /// Path to custom model (for byom method)
+        /// Path to custom model (for byom method, or a Grounding DINO export)
         #[arg(long)]
         model: Option<PathBuf>,
 
-        /// Confidence threshold for detections
-        #[arg(short, long, default_value = "0.25")]
-        confidence: f32,
+        /// Output layout of the model: yolov8 (also v11), yolov5, end2end or auto [default: auto]
+        #[arg(long, alias = "model-arch", value_name = "FORMAT")]
+        model_format: Option<String>,
+
+        /// Model class names (YAML or one per line), if not in the ONNX metadata
+        #[arg(long, value_name = "FILE")]
+        model_classes: Option<PathBuf>,
+
+        /// Detections of model classes the dataset lacks: ignore or report [default: report]
+        #[arg(long, value_name = "MODE")]
+        unmapped_classes: Option<String>,
 
-        /// IoU threshold for matching
-        #[arg(long, default_value = "0.5")]
-        iou: f32,
+        /// Class synonym groups (TOML or JSON), replacing the built-in ones
+        #[arg(long, value_name = "FILE")]
+        synonyms: Option<PathBuf>,
 
-        /// Output file (json or html based on extension)
+        /// Confidence threshold for detections [default: 0.25]
         #[arg(short, long)]
-        output: Option<PathBuf>,
+        confidence: Option<f32>,
+
+        /// IoU threshold for matching [default: 0.5]
+        #[arg(long)]
+        iou: Option<f32>,
+
+        /// Score a class's prompt tokens need to label a Grounding DINO box [default: 0.25]
+        #[arg(long)]
+        text_threshold: Option<f32>,
+
+        /// Base URL of the OpenAI-compatible API for --method vlm [default: http://localhost:11434/v1]
+        #[arg(long)]
+        vlm_endpoint: Option<String>,
+
+        /// Vision model requested from the VLM endpoint [default: llava]
+        #[arg(long)]
+        vlm_model: Option<String>,
+
+        /// Use Gaussian Soft-NMS instead of hard NMS
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        soft_nms: Option<bool>,
+
+        /// Sigma for Soft-NMS Gaussian decay [default: 0.5]
+        #[arg(long)]
+        soft_nms_sigma: Option<f32>,
+
+        /// Which overlapping boxes NMS suppresses: per-class, or agnostic to suppress across classes [default: per-class]
+        #[arg(long, value_name = "MODE")]
+        nms_mode: Option<String>,
+
+        /// Inference device: cpu, cuda or cuda:N [default: cpu]
+        #[arg(long)]
+        device: Option<Device>,
+
+        /// Images per inference call (the model needs a dynamic batch axis) [default: 1]
+        #[arg(long)]
+        batch_size: Option<usize>,
+
+        /// Flag images whose Laplacian variance is below this as blurry (0 disables) [default: 100]
+        #[arg(long)]
+        blur_threshold: Option<f32>,
+
+        /// Flag images whose mean luma is below this as too dark (0 disables) [default: 20]
+        #[arg(long)]
+        brightness_min: Option<f32>,
+
+        /// Flag images whose mean luma is above this as overexposed (255 disables) [default: 235]
+        #[arg(long)]
+        brightness_max: Option<f32>,
+
+        /// Flag GT boxes with a smaller area in pixels (zero-shot only; 0 disables) [default: 16]
+        #[arg(long)]
+        min_box_pixels: Option<f32>,
+
+        /// Flag GT boxes with a shorter side in pixels (zero-shot only; 0 disables) [default: 2]
+        #[arg(long)]
+        min_box_side: Option<f32>,
+
+        /// Flag GT boxes covering more of the image than this (zero-shot only; 1 disables) [default: 0.95]
+        #[arg(long)]
+        max_box_fraction: Option<f32>,
+
+        /// Flag single-color images, and GT boxes on blurry or single-color regions (zero-shot only)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        quality_checks: Option<bool>,
+
+        /// Pixel standard deviation below which --quality-checks counts an image or box as one color [default: 2]
+        #[arg(long)]
+        min_color_std: Option<f32>,
+
+        /// Flag GT boxes whose region has a Laplacian variance below this with --quality-checks [default: 20]
+        #[arg(long)]
+        crop_blur_threshold: Option<f32>,
+
+        /// Flag images whose detection confidences are nearly identical (YOLO only)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        check_calibration: Option<bool>,
+
+        /// Flag near-duplicate images (perceptual hash distance of 5 bits or less)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        check_duplicates: Option<bool>,
+
+        /// Flag GT boxes whose stored confidence (sixth label column) is below this
+        #[arg(long, value_name = "F")]
+        min_gt_confidence: Option<f32>,
+
+        /// Rescale label files that look like pixel coordinates by the image size, instead of reporting them
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        assume_pixel_coords: Option<bool>,
+
+        /// Rotate images as their EXIF orientation tag says before checking them
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        apply_exif_rotation: Option<bool>,
+
+        /// Run the model on EXIF-tagged images in the other orientation too, to tell which one the labels match (one more inference per tagged image)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        compare_exif_orientation: Option<bool>,
+
+        /// Flag GT boxes whose area or aspect ratio is this many MADs from their class's median (6 is a good start); reads every label file first [default: off]
+        #[arg(long, value_name = "MADS")]
+        box_outlier_mads: Option<f32>,
+
+        /// Comma-separated class names to leave out of validation (e.g. ignore_region)
+        #[arg(long, value_name = "CLASSES")]
+        exclude_classes: Option<String>,
+
+        /// Fail when the dataset declares a class name under several ids, rather than warn
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        strict: Option<bool>,
+
+        /// Output file (json, html, xml, md or sarif based on extension); repeat
+        /// for several reports, or use `-` for stdout
+        #[arg(short, long)]
+        output: Vec<PathBuf>,
+
+        /// Report format, overriding the output file extension; once for every
+        /// output, or once per `--output` in the same order
+        #[arg(long, value_parser = ["json", "html", "xml", "junit", "markdown", "sarif"])]
+        format: Vec<String>,
+
+        /// Only write issues of this severity to the output file: high, medium or low
+        #[arg(long, value_name = "SEVERITY")]
+        filter_severity: Option<IssueSeverity>,
+
+        /// Only write issues of this type to the output file (e.g. class_mismatch)
+        #[arg(long, value_name = "TYPE")]
+        filter_type: Option<IssueType>,
+
+        /// Leave out issues raised by detections less confident than this, from counts and reports
+        #[arg(long, value_name = "FLOAT")]
+        min_confidence_report: Option<f32>,
+
+        /// Per-image data in JSON reports: summary, flagged or full [default: flagged]
+        #[arg(long, value_name = "DETAIL")]
+        report_detail: Option<ReportDetail>,
+
+        /// Flagged images listed in detail in Markdown [default: 20] and HTML [default: 1000] reports
+        #[arg(long)]
+        max_report_images: Option<usize>,
+
+        /// Only the N worst flagged images in JSON and HTML reports, in --sort-output order [default: all]
+        #[arg(long, value_name = "N")]
+        report_top: Option<usize>,
+
+        /// Buckets of the IoU histogram of matched detections in reports [default: 10]
+        #[arg(long, value_name = "N")]
+        iou_buckets: Option<usize>,
+
+        /// Share of a class's annotations that mismatches to one other class must exceed to be reported as a label swap [default: 0.3]
+        #[arg(long, value_name = "F")]
+        label_swap_min_share: Option<f32>,
+
+        /// Cells per side of the spatial heatmap of GT box centers in reports [default: 20]
+        #[arg(long, value_name = "N")]
+        heatmap_grid: Option<usize>,
+
+        /// Order of flagged images in reports: by-total-issues, priority, by-high-severity, by-medium-severity or by-filename [default: by-total-issues]
+        #[arg(long, value_name = "ORDER")]
+        sort_output: Option<SortOrder>,
+
+        /// Relabel-priority weights, e.g. high=3,medium=1.5,low=0.5,confidence=2,classes=0.5 (the defaults)
+        #[arg(long, value_name = "WEIGHTS")]
+        priority_weights: Option<PriorityWeights>,
+
+        /// Write the paths of flagged images to this file, highest relabel priority first
+        #[arg(long, value_name = "FILE")]
+        export_worklist: Option<PathBuf>,
+
+        /// Only the N highest-priority images in --export-worklist [default: all]
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+
+        /// Write one --export-worklist file per class involved in issues
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        group_by_class: Option<bool>,
+
+        /// Use emoji severity markers in Markdown reports (GitHub flavor)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        github: Option<bool>,
+
+        /// Embed thumbnails of flagged images with their labels drawn in HTML reports
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        embed_thumbnails: Option<bool>,
+
+        /// Leave images without issues out of JUnit reports
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        junit_flagged_only: Option<bool>,
+
+        /// Report medium severity issues as JUnit failures rather than errors
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        junit_medium_as_failure: Option<bool>,
+
+        /// Only audit images matching these comma-separated globs (e.g. "frame_*.jpg")
+        #[arg(long, value_name = "GLOBS")]
+        include_images: Option<String>,
+
+        /// Skip images matching these comma-separated globs
+        #[arg(long, value_name = "GLOBS")]
+        exclude_images: Option<String>,
+
+        /// Number of images to sample (0 = all) [default: 0]
+        #[arg(long)]
+        sample: Option<usize>,
+
+        /// How to sample: uniform, or stratified to cover rare classes [default: uniform]
+        #[arg(long, value_name = "STRATEGY")]
+        sample_strategy: Option<SampleStrategy>,
+
+        /// Images each class should appear in with stratified sampling [default: 20]
+        #[arg(long, value_name = "N")]
+        min_per_class: Option<usize>,
+
+        /// Sample N images stratified by class, each class getting an even share by default
+        #[arg(long, value_name = "N", conflicts_with_all = ["sample", "sample_strategy"])]
+        stratified_sample: Option<usize>,
+
+        /// Random seed for sampling [default: 42]
+        #[arg(long)]
+        seed: Option<u64>,
+
+        /// Resume cache of finished images [default: <DATASET>/.gt-audit-cache.jsonl]
+        #[arg(long, value_name = "FILE", conflicts_with = "no_cache")]
+        cache_file: Option<PathBuf>,
+
+        /// Don't read or write the resume cache
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        no_cache: Option<bool>,
 
-        /// Number of images to sample (0 = all)
-        #[arg(long, default_value = "0")]
-        sample: usize,
+        /// Write each image result to this JSON Lines file as soon as it is audited
+        #[arg(long, value_name = "FILE")]
+        streaming_output: Option<PathBuf>,
 
-        /// Random seed for sampling
-        #[arg(long, default_value = "42")]
-        seed: u64,
+        /// Known issues (from --update-baseline) that don't count toward --fail-on-* thresholds
+        #[arg(long, value_name = "FILE")]
+        baseline: Option<PathBuf>,
+
+        /// Write the current issues to the --baseline file
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        update_baseline: Option<bool>,
+
+        /// Write a suppression rule for each unsuppressed issue to this file, to
+        /// prune by hand and save as .gtauditignore in the dataset root
+        #[arg(long, value_name = "FILE")]
+        write_suppressions: Option<PathBuf>,
+
+        /// YAML file with severities per issue type and CI fail thresholds
+        #[arg(long, value_name = "FILE")]
+        policy: Option<PathBuf>,
 
         /// Fail if high severity issues exceed threshold
         #[arg(long)]
@@ -69,13 +400,58 @@ enum Commands {
         #[arg(long)]
         fail_on_medium: Option<usize>,
 
+        /// Fail if low severity issues exceed threshold
+        #[arg(long)]
+        fail_on_low: Option<usize>,
+
+        /// Fail if issues of any severity exceed threshold
+        #[arg(long)]
+        fail_on_total: Option<usize>,
+
+        /// Fail if any image has no label file
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        fail_on_missing_labels: Option<bool>,
+
         /// Number of parallel workers
         #[arg(short = 'j', long)]
         workers: Option<usize>,
 
-        /// Verbose output
-        #[arg(short, long)]
-        verbose: bool,
+        /// Threads that load and decode images ahead of inference, leaving
+        /// --workers to inference [default: load on the inference threads]
+        #[arg(long, value_name = "N")]
+        workers_io: Option<usize>,
+
+        /// Keep running and re-audit images whenever their label files change
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        watch: Option<bool>,
+
+        /// Serve Prometheus metrics on ADDR (e.g. :9091) after the audit, until Ctrl-C
+        #[arg(long, value_name = "ADDR")]
+        serve_metrics: Option<String>,
+
+        /// Only log warnings and errors; no banner or progress bar
+        #[arg(short, long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        quiet: Option<bool>,
+
+        /// Print a machine-readable summary to stdout (json); other output goes to stderr
+        #[arg(long, value_name = "FORMAT", value_parser = ["json"])]
+        print_summary: Option<String>,
+
+        /// Explain what each issue type found means and how to fix it
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        explain: Option<bool>,
+
+        /// Print the summary without colors, e.g. for CI logs (also set by NO_COLOR)
+        #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+        no_color: Option<bool>,
+
+        /// Debug output per image with -v, tensor shapes and preprocessing with -vv
+        #[arg(short, long, action = clap::ArgAction::Count)]
+        verbose: u8,
+
+        /// Log line format: text or json [default: text]
+        #[arg(long, value_name = "FORMAT")]
+        log_format: Option<LogFormat>,
     },
 
     /// Download required models
@@ -85,130 +461,748 @@ enum Commands {
         model: String,
     },
 
-    /// Show information about a dataset
+    /// Show information about a dataset and/or an ONNX model
     Info {
+        /// Path to dataset
+        #[arg(value_name = "DATASET", required_unless_present = "model")]
+        dataset: Option<PathBuf>,
+
+        /// ONNX model whose metadata, input size and classes to show
+        #[arg(long, value_name = "MODEL")]
+        model: Option<PathBuf>,
+
+        /// Also write the dataset statistics as JSON
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Apply safe automatic corrections to label files
+    Fix {
+        /// Path to dataset (YOLO format)
+        #[arg(value_name = "DATASET")]
+        dataset: PathBuf,
+
+        /// Only fix label files of images flagged in a prior JSON report
+        #[arg(long)]
+        report: Option<PathBuf>,
+
+        /// Also delete boxes with zero width or height
+        #[arg(long)]
+        remove_zero_area: bool,
+
+        /// Show a unified diff per label file without writing changes
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Copy original label files to .gt-audit-backup/ before writing
+        #[arg(long)]
+        backup: bool,
+
+        /// Replace label files with the reviewed patch files in DIR, as written
+        /// by export-fixes, instead of the automatic corrections
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["report", "remove_zero_area"])]
+        from: Option<PathBuf>,
+    },
+
+    /// Write the fixes a report suggests as patched label files for review
+    ExportFixes {
+        /// JSON report of an audit
+        #[arg(value_name = "REPORT")]
+        report: PathBuf,
+
+        /// Directory to write patched label files to, mirroring the dataset
+        #[arg(short, long, value_name = "DIR")]
+        output: PathBuf,
+
+        /// Dataset the report was made from [default: the report's dataset path]
+        #[arg(long)]
+        dataset: Option<PathBuf>,
+    },
+
+    /// Find duplicate images within and across train/val/test splits
+    Leakage {
+        /// Path to dataset (YOLO format)
+        #[arg(value_name = "DATASET")]
+        dataset: PathBuf,
+
+        /// What to hash: bytes (file contents) or pixels (decoded image data)
+        #[arg(long, default_value = "bytes")]
+        hash: HashMode,
+
+        /// Output JSON file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Convert annotations between YOLO, COCO and Pascal VOC
+    Convert {
+        /// Dataset to read
+        #[arg(value_name = "INPUT")]
+        input: PathBuf,
+
+        /// Directory to write the converted dataset to
+        #[arg(value_name = "OUTPUT")]
+        output: PathBuf,
+
+        /// Format of INPUT: yolo, coco or voc
+        #[arg(long, default_value = "yolo")]
+        from: DatasetFormat,
+
+        /// Format to write: yolo, coco or voc
+        #[arg(long)]
+        to: DatasetFormat,
+    },
+
+    /// Export the annotations an audit didn't flag as COCO JSON, or the
+    /// images it flagged as Label Studio or CVAT tasks
+    Export {
+        /// Path to dataset (YOLO format)
+        #[arg(value_name = "DATASET")]
+        dataset: PathBuf,
+
+        /// JSON report of an audit of the dataset
+        #[arg(long)]
+        report: PathBuf,
+
+        /// What to write
+        #[arg(long, value_name = "FORMAT", default_value = "coco", value_parser = ["coco", "label-studio", "cvat"])]
+        to: String,
+
+        /// File to write [default: instances.json, tasks.json or annotations.xml]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Leave flagged images out entirely instead of only their high-severity annotations
+        #[arg(long)]
+        exclude_flagged: bool,
+
+        /// URL prefix of the images in tasks, or a template where {path} stands
+        /// for the image path (for pre-signed URLs) [default: local paths]
+        #[arg(long, value_name = "URL")]
+        images_prefix: Option<String>,
+
+        /// Label Studio labeling config whose labels class names are mapped to
+        #[arg(long, value_name = "FILE")]
+        label_config: Option<PathBuf>,
+
+        /// Class synonym groups (TOML or JSON), replacing the built-in ones
+        #[arg(long, value_name = "FILE")]
+        synonyms: Option<PathBuf>,
+    },
+
+    /// Show per-class annotation statistics for a dataset
+    Stats {
         /// Path to dataset
         #[arg(value_name = "DATASET")]
         dataset: PathBuf,
+
+        /// Output JSON file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Write one JSON file per class to this directory, listing the images
+        /// that contain the class; classes whose names map to the same file
+        /// name get a numeric suffix
+        #[arg(long, value_name = "DIR")]
+        per_class_report: Option<PathBuf>,
+    },
+
+    /// Manage gt-audit.toml config files
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Write a commented template listing every validate option
+    Init {
+        /// File to write
+        #[arg(value_name = "FILE", default_value = DEFAULT_CONFIG_FILE)]
+        path: PathBuf,
+
+        /// Overwrite the file if it exists
+        #[arg(long)]
+        force: bool,
     },
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// `validate` flags given on the command line, keyed like the config file
+///
+/// Numbers and booleans keep their type; other values stay strings.
+fn command_line_options(matches: &ArgMatches) -> BTreeMap<String, serde_json::Value> {
+    matches
+        .ids()
+        .map(|id| id.as_str())
+        .filter(|&id| id != "config" && matches.value_source(id) == Some(ValueSource::CommandLine))
+        .map(|id| {
+            let mut values: Vec<serde_json::Value> = matches
+                .get_raw(id)
+                .into_iter()
+                .flatten()
+                .map(|raw| {
+                    let raw = raw.to_string_lossy();
+                    match serde_json::from_str(&raw) {
+                        Ok(value @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_))) => {
+                            value
+                        }
+                        _ => serde_json::Value::String(raw.into_owned()),
+                    }
+                })
+                .collect();
+            // Counted flags such as -v carry no raw value
+            let value = match values.len() {
+                0 => serde_json::Value::Bool(true),
+                1 => values.remove(0),
+                _ => serde_json::Value::Array(values),
+            };
+            (id.to_string(), value)
+        })
+        .collect()
+}
+
+fn main() -> Result<ExitCode> {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // `validate` sets up logging once it has read its config file
+    if !matches!(cli.command, Commands::Validate { .. }) {
+        logging::init(logging::level(0, false), LogFormat::Text);
+    }
 
     match cli.command {
         Commands::Validate {
             dataset,
+            config,
+            split,
+            splits,
+            images,
+            classes_file,
             method,
+            backend,
             model,
+            model_format,
+            model_classes,
+            unmapped_classes,
+            synonyms,
             confidence,
             iou,
+            text_threshold,
+            vlm_endpoint,
+            vlm_model,
+            soft_nms,
+            soft_nms_sigma,
+            nms_mode,
+            device,
+            batch_size,
+            blur_threshold,
+            brightness_min,
+            brightness_max,
+            min_box_pixels,
+            min_box_side,
+            max_box_fraction,
+            quality_checks,
+            min_color_std,
+            crop_blur_threshold,
+            check_calibration,
+            check_duplicates,
+            min_gt_confidence,
+            assume_pixel_coords,
+            apply_exif_rotation,
+            compare_exif_orientation,
+            box_outlier_mads,
+            exclude_classes,
+            strict,
             output,
+            format,
+            filter_severity,
+            filter_type,
+            min_confidence_report,
+            report_detail,
+            max_report_images,
+            report_top,
+            iou_buckets,
+            label_swap_min_share,
+            heatmap_grid,
+            sort_output,
+            priority_weights,
+            export_worklist,
+            top,
+            group_by_class,
+            github,
+            embed_thumbnails,
+            junit_flagged_only,
+            junit_medium_as_failure,
+            include_images,
+            exclude_images,
             sample,
+            sample_strategy,
+            min_per_class,
+            stratified_sample,
             seed,
+            cache_file,
+            no_cache,
+            streaming_output,
+            baseline,
+            update_baseline,
+            write_suppressions,
+            policy,
             fail_on_high,
             fail_on_medium,
+            fail_on_low,
+            fail_on_total,
+            fail_on_missing_labels,
             workers,
+            workers_io,
+            watch,
+            serve_metrics,
+            quiet,
+            print_summary,
+            explain,
+            no_color,
             verbose,
+            log_format,
         } => {
+            // Flags given on the command line win over the config file
+            let images_root = (!images.is_empty()).then(|| dataset::image_files_root(&images));
+            let cfg = Config::discover(
+                config.as_deref(),
+                dataset.as_deref().or(images_root.as_deref()),
+            )?
+            .unwrap_or_default();
+            let effective_options = cfg.effective(
+                matches
+                    .subcommand_matches("validate")
+                    .map(command_line_options)
+                    .unwrap_or_default(),
+            );
+            let quiet = quiet.or(cfg.quiet).unwrap_or(false);
+            let verbose = verbose.max(cfg.verbose.unwrap_or(false) as u8);
+            let log_format = match log_format {
+                Some(format) => format,
+                None => cfg
+                    .log_format
+                    .as_deref()
+                    .map(str::parse)
+                    .transpose()?
+                    .unwrap_or_default(),
+            };
+            logging::init(logging::level(verbose, quiet), log_format);
+            // Images are audited against the dataset they belong to
+            let dataset = match images_root {
+                Some(root) => root,
+                None => dataset.or(cfg.dataset).context(
+                    "No dataset given: pass DATASET or --images, or set `dataset` in the config file",
+                )?,
+            };
+            let (split, splits) = if split.is_some() || splits.is_some() {
+                (split, splits)
+            } else {
+                (cfg.split, cfg.splits)
+            };
+            let device = match device {
+                Some(device) => device,
+                None => cfg.device.as_deref().unwrap_or("cpu").parse()?,
+            };
+            let (sample, sample_strategy, min_per_class) =
+                match stratified_sample.or(cfg.stratified_sample) {
+                    Some(n) => (
+                        n,
+                        SampleStrategy::Stratified,
+                        min_per_class.or(cfg.min_per_class),
+                    ),
+                    None => (
+                        sample.or(cfg.sample).unwrap_or(0),
+                        match sample_strategy {
+                            Some(strategy) => strategy,
+                            None => cfg
+                                .sample_strategy
+                                .as_deref()
+                                .unwrap_or("uniform")
+                                .parse()?,
+                        },
+                        Some(min_per_class.or(cfg.min_per_class).unwrap_or(20)),
+                    ),
+                };
+            let globs = |patterns: Option<String>| -> Vec<String> {
+                patterns
+                    .map(|p| {
+                        p.split(',')
+                            .map(str::trim)
+                            .filter(|s| !s.is_empty())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+            let cache_file = if no_cache.or(cfg.no_cache).unwrap_or(false) {
+                None
+            } else {
+                Some(
+                    cache_file
+                        .or(cfg.cache_file)
+                        .unwrap_or_else(|| dataset.join(DEFAULT_CACHE_FILE)),
+                )
+            };
             run_validate(
                 dataset,
-                method,
-                model,
-                confidence,
-                iou,
-                output,
+                split,
+                splits,
+                images,
+                classes_file.or(cfg.classes_file),
+                method.or(cfg.method).unwrap_or_else(|| "zero-shot".into()),
+                backend
+                    .or(cfg.backend)
+                    .as_deref()
+                    .map(str::parse)
+                    .transpose()?
+                    .unwrap_or_default(),
+                model.or(cfg.model),
+                model_format
+                    .or(cfg.model_format)
+                    .as_deref()
+                    .filter(|f| *f != "auto")
+                    .map(str::parse)
+                    .transpose()?,
+                model_classes.or(cfg.model_classes),
+                unmapped_classes
+                    .or(cfg.unmapped_classes)
+                    .as_deref()
+                    .map(str::parse)
+                    .transpose()?
+                    .unwrap_or_default(),
+                synonyms.or(cfg.synonyms),
+                confidence.or(cfg.confidence).unwrap_or(0.25),
+                iou.or(cfg.iou).unwrap_or(0.5),
+                text_threshold.or(cfg.text_threshold).unwrap_or(0.25),
+                vlm_endpoint
+                    .or(cfg.vlm_endpoint)
+                    .unwrap_or_else(|| "http://localhost:11434/v1".to_string()),
+                vlm_model
+                    .or(cfg.vlm_model)
+                    .unwrap_or_else(|| "llava".to_string()),
+                soft_nms.or(cfg.soft_nms).unwrap_or(false),
+                soft_nms_sigma.or(cfg.soft_nms_sigma).unwrap_or(0.5),
+                nms_mode
+                    .or(cfg.nms_mode)
+                    .as_deref()
+                    .map(str::parse)
+                    .transpose()?
+                    .unwrap_or_default(),
+                device,
+                batch_size.or(cfg.batch_size).unwrap_or(1),
+                blur_threshold.or(cfg.blur_threshold).unwrap_or(100.0),
+                brightness_min.or(cfg.brightness_min).unwrap_or(20.0),
+                brightness_max.or(cfg.brightness_max).unwrap_or(235.0),
+                min_box_pixels.or(cfg.min_box_pixels).unwrap_or(16.0),
+                min_box_side.or(cfg.min_box_side).unwrap_or(2.0),
+                max_box_fraction.or(cfg.max_box_fraction).unwrap_or(0.95),
+                quality_checks.or(cfg.quality_checks).unwrap_or(false),
+                min_color_std.or(cfg.min_color_std).unwrap_or(2.0),
+                crop_blur_threshold
+                    .or(cfg.crop_blur_threshold)
+                    .unwrap_or(20.0),
+                check_calibration.or(cfg.check_calibration).unwrap_or(false),
+                check_duplicates.or(cfg.check_duplicates).unwrap_or(false),
+                min_gt_confidence.or(cfg.min_gt_confidence),
+                assume_pixel_coords
+                    .or(cfg.assume_pixel_coords)
+                    .unwrap_or(false),
+                apply_exif_rotation
+                    .or(cfg.apply_exif_rotation)
+                    .unwrap_or(false),
+                compare_exif_orientation
+                    .or(cfg.compare_exif_orientation)
+                    .unwrap_or(false),
+                box_outlier_mads.or(cfg.box_outlier_mads).unwrap_or(0.0),
+                globs(exclude_classes.or(cfg.exclude_classes)),
+                strict.or(cfg.strict).unwrap_or(false),
+                if output.is_empty() {
+                    cfg.output.map(OneOrMany::into_vec).unwrap_or_default()
+                } else {
+                    output
+                },
+                if format.is_empty() {
+                    cfg.format.map(OneOrMany::into_vec).unwrap_or_default()
+                } else {
+                    format
+                },
+                filter_severity.or(cfg.filter_severity.as_deref().map(str::parse).transpose()?),
+                filter_type.or(cfg.filter_type.as_deref().map(str::parse).transpose()?),
+                min_confidence_report.or(cfg.min_confidence_report),
+                report_detail
+                    .or(cfg.report_detail.as_deref().map(str::parse).transpose()?)
+                    .unwrap_or_default(),
+                max_report_images.or(cfg.max_report_images),
+                report_top.or(cfg.report_top),
+                iou_buckets
+                    .or(cfg.iou_buckets)
+                    .unwrap_or(metrics::DEFAULT_IOU_BUCKETS),
+                label_swap_min_share
+                    .or(cfg.label_swap_min_share)
+                    .unwrap_or(DEFAULT_LABEL_SWAP_MIN_SHARE),
+                heatmap_grid
+                    .or(cfg.heatmap_grid)
+                    .unwrap_or(heatmap::DEFAULT_GRID),
+                sort_output
+                    .or(cfg.sort_output.as_deref().map(str::parse).transpose()?)
+                    .unwrap_or_default(),
+                priority_weights
+                    .or(cfg
+                        .priority_weights
+                        .as_deref()
+                        .map(str::parse)
+                        .transpose()?)
+                    .unwrap_or_default(),
+                export_worklist.or(cfg.export_worklist),
+                top.or(cfg.top),
+                group_by_class.or(cfg.group_by_class).unwrap_or(false),
+                github.or(cfg.github).unwrap_or(false),
+                embed_thumbnails.or(cfg.embed_thumbnails).unwrap_or(false),
+                junit_flagged_only
+                    .or(cfg.junit_flagged_only)
+                    .unwrap_or(false),
+                junit_medium_as_failure
+                    .or(cfg.junit_medium_as_failure)
+                    .unwrap_or(false),
+                globs(include_images.or(cfg.include_images)),
+                globs(exclude_images.or(cfg.exclude_images)),
                 sample,
-                seed,
-                fail_on_high,
-                fail_on_medium,
-                workers,
-                verbose,
+                sample_strategy,
+                min_per_class,
+                seed.or(cfg.seed).unwrap_or(42),
+                cache_file,
+                streaming_output.or(cfg.streaming_output),
+                baseline.or(cfg.baseline),
+                update_baseline.or(cfg.update_baseline).unwrap_or(false),
+                write_suppressions.or(cfg.write_suppressions),
+                policy.or(cfg.policy),
+                fail_on_high.or(cfg.fail_on_high),
+                fail_on_medium.or(cfg.fail_on_medium),
+                fail_on_low.or(cfg.fail_on_low),
+                fail_on_total.or(cfg.fail_on_total),
+                fail_on_missing_labels
+                    .or(cfg.fail_on_missing_labels)
+                    .unwrap_or(false),
+                workers.or(cfg.workers),
+                workers_io.or(cfg.workers_io),
+                watch.or(cfg.watch).unwrap_or(false),
+                serve_metrics.or(cfg.serve_metrics),
+                quiet,
+                print_summary.or(cfg.print_summary),
+                explain.or(cfg.explain).unwrap_or(false),
+                no_color.or(cfg.no_color).unwrap_or(false),
+                effective_options,
             )
         }
+        Commands::Config {
+            action: ConfigAction::Init { path, force },
+        } => {
+            config::write_template(&path, force)?;
+            println!("✅ Config template written to {}", path.display());
+            Ok(ExitCode::SUCCESS)
+        }
         Commands::Download { model } => run_download(model),
-        Commands::Info { dataset } => run_info(dataset),
+        Commands::Info {
+            dataset,
+            model,
+            output,
+        } => {
+            if let Some(model) = &model {
+                run_model_info(model, dataset.as_deref())?;
+            }
+            match dataset {
+                Some(dataset) => {
+                    if model.is_some() {
+                        println!();
+                    }
+                    run_info(dataset, output)
+                }
+                None => Ok(ExitCode::SUCCESS),
+            }
+        }
+        Commands::Fix {
+            dataset,
+            report,
+            remove_zero_area,
+            dry_run,
+            backup,
+            from,
+        } => run_fix(
+            dataset,
+            report,
+            from,
+            fix::FixOptions {
+                remove_zero_area,
+                dry_run,
+                backup,
+            },
+        ),
+        Commands::ExportFixes {
+            report,
+            output,
+            dataset,
+        } => run_export_fixes(report, output, dataset),
+        Commands::Leakage {
+            dataset,
+            hash,
+            output,
+        } => run_leakage(dataset, hash, output),
+        Commands::Convert {
+            input,
+            output,
+            from,
+            to,
+        } => run_convert(input, output, from, to),
+        Commands::Export {
+            dataset,
+            report,
+            to,
+            output,
+            exclude_flagged,
+            images_prefix,
+            label_config,
+            synonyms,
+        } => match to.as_str() {
+            "coco" => {
+                if images_prefix.is_some() || label_config.is_some() || synonyms.is_some() {
+                    anyhow::bail!(
+                        "--images-prefix, --label-config and --synonyms only apply to --to label-studio or cvat"
+                    );
+                }
+                run_export(dataset, report, output, exclude_flagged)
+            }
+            to => {
+                if exclude_flagged {
+                    anyhow::bail!("--exclude-flagged only applies to --to coco");
+                }
+                run_export_tasks(
+                    dataset,
+                    report,
+                    to.parse()?,
+                    output,
+                    images_prefix,
+                    label_config,
+                    synonyms,
+                )
+            }
+        },
+        Commands::Stats {
+            dataset,
+            output,
+            per_class_report,
+        } => run_stats(dataset, output, per_class_report),
     }
 }
 
 #[allow(clippy::too_many_arguments)]
 fn run_validate(
     dataset_path: PathBuf,
+    split: Option<String>,
+    splits: Option<String>,
+    images: Vec<PathBuf>,
+    classes_file: Option<PathBuf>,
     method: String,
+    backend: ZeroShotBackend,
     model_path: Option<PathBuf>,
+    model_format: Option<ModelFormat>,
+    model_classes: Option<PathBuf>,
+    unmapped_classes: UnmappedClasses,
+    class_synonyms: Option<PathBuf>,
     confidence: f32,
     iou_threshold: f32,
-    output: Option<PathBuf>,
+    text_threshold: f32,
+    vlm_endpoint: String,
+    vlm_model: String,
+    soft_nms: bool,
+    soft_nms_sigma: f32,
+    nms_mode: NmsMode,
+    device: Device,
+    batch_size: usize,
+    blur_threshold: f32,
+    brightness_min: f32,
+    brightness_max: f32,
+    min_box_pixels: f32,
+    min_box_side: f32,
+    max_box_fraction: f32,
+    quality_checks: bool,
+    min_color_std: f32,
+    crop_blur_threshold: f32,
+    check_calibration: bool,
+    check_duplicates: bool,
+    min_gt_confidence: Option<f32>,
+    assume_pixel_coords: bool,
+    apply_exif_rotation: bool,
+    compare_exif_orientation: bool,
+    box_outlier_mads: f32,
+    exclude_classes: Vec<String>,
+    strict: bool,
+    output: Vec<PathBuf>,
+    format: Vec<String>,
+    filter_severity: Option<IssueSeverity>,
+    filter_type: Option<IssueType>,
+    min_confidence_report: Option<f32>,
+    report_detail: ReportDetail,
+    max_report_images: Option<usize>,
+    report_top: Option<usize>,
+    iou_buckets: usize,
+    label_swap_min_share: f32,
+    heatmap_grid: usize,
+    sort_output: SortOrder,
+    priority_weights: PriorityWeights,
+    export_worklist: Option<PathBuf>,
+    top: Option<usize>,
+    group_by_class: bool,
+    github: bool,
+    embed_thumbnails: bool,
+    junit_flagged_only: bool,
+    junit_medium_as_failure: bool,
+    include_images: Vec<String>,
+    exclude_images: Vec<String>,
     sample: usize,
+    sample_strategy: SampleStrategy,
+    min_per_class: Option<usize>,
     seed: u64,
+    cache_file: Option<PathBuf>,
+    stream_output: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    update_baseline: bool,
+    write_suppressions: Option<PathBuf>,
+    policy_file: Option<PathBuf>,
     fail_on_high: Option<usize>,
     fail_on_medium: Option<usize>,
+    fail_on_low: Option<usize>,
+    fail_on_total: Option<usize>,
+    fail_on_missing_labels: bool,
     workers: Option<usize>,
-    _verbose: bool,
-) -> Result<()> {
-    let start = Instant::now();
-
-    println!("╔══════════════════════════════════════════════════════════╗");
-    println!("║               gt-audit - Ground Truth Validator          ║");
-    println!("╚══════════════════════════════════════════════════════════╝");
-    println!();
-
-    // Load dataset
-    println!("📂 Loading dataset: {}", dataset_path.display());
-    let dataset = YoloDataset::load(&dataset_path)?;
-    println!("   Classes: {}", dataset.class_names.len());
-    println!("   Images: {}", dataset.image_count());
-
-    // Get images to process
-    let mut images = dataset.get_images();
-    if sample > 0 && sample < images.len() {
-        use rand::seq::SliceRandom;
-        use rand::SeedableRng;
-        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
-        images.shuffle(&mut rng);
-        images.truncate(sample);
-        println!("   Sampled: {} images (seed={})", sample, seed);
+    workers_io: Option<usize>,
+    watch: bool,
+    serve_metrics: Option<String>,
+    quiet: bool,
+    print_summary: Option<String>,
+    explain: bool,
+    no_color: bool,
+    effective_options: BTreeMap<String, serde_json::Value>,
+) -> Result<ExitCode> {
+    // Checked up front, so a bad path doesn't surface only after the audit
+    let reports = report_outputs(output, format)?;
+    let report_to_stdout = reports.iter().any(|(path, _)| is_stdout(path));
+    if report_to_stdout && print_summary.is_some() {
+        anyhow::bail!("--print-summary and `--output -` both write to stdout; use only one");
     }
-
-    // Initialize detector
-    println!();
-
-    // Get class names sorted by ID for the model
-    let mut model_class_names: Vec<String> = Vec::new();
-    let mut class_ids: Vec<i32> = dataset.class_names.keys().copied().collect();
-    class_ids.sort();
-    for id in class_ids {
-        model_class_names.push(dataset.class_names.get(&id).cloned().unwrap_or_default());
+    if print_summary.is_some() || report_to_stdout {
+        HUMAN_TO_STDERR.store(true, Ordering::Relaxed);
+    }
+    // Soft-NMS decays scores by exp(-iou² / sigma); hard NMS never reads it
+    if soft_nms && (soft_nms_sigma.is_nan() || soft_nms_sigma <= 0.0) {
+        anyhow::bail!("--soft-nms-sigma must be positive, got {}", soft_nms_sigma);
+    }
+    if !quiet {
+        say!("╔══════════════════════════════════════════════════════════╗");
+        say!("║               gt-audit - Ground Truth Validator          ║");
+        say!("╚══════════════════════════════════════════════════════════╝");
+        say!();
     }
-
-    // Determine which method to use
-    let effective_method = if model_path.is_some() {
-        "yolo".to_string()
-    } else {
-        method.clone()
-    };
-
-    println!("🔍 Initializing detector: {}", effective_method);
-    let config = DetectorConfig {
-        confidence_threshold: confidence,
-        iou_threshold,
-        model_path: model_path.clone(),
-    };
-
-    let detector: Box<dyn Detector + Sync> = match effective_method.as_str() {
-        "yolo" | "byom" => {
-            if config.model_path.is_none() {
-                anyhow::bail!("YOLO/BYOM method requires --model path to ONNX model");
-            }
-            Box::new(YoloDetector::new(config, model_class_names)?)
-        }
-        "zero-shot" => Box::new(ZeroShotDetector::new(config)?),
-        "vlm" => {
-            println!("   VLM method requires GPU, checking...");
-            anyhow::bail!("VLM method not yet implemented - coming soon");
-        }
-        _ => anyhow::bail!("Unknown method: {}. Use: zero-shot, vlm, yolo", method),
-    };
 
     // Set up parallelism
     if let Some(w) = workers {
@@ -218,153 +1212,1368 @@ fn run_validate(
             .ok();
     }
 
-    // Progress bar
-    let pb = ProgressBar::new(images.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")?
-            .progress_chars("#>-"),
-    );
-
-    // Process images in parallel
-    println!();
-    println!("🔬 Auditing {} images...", images.len());
+    let policy_file = match &policy_file {
+        Some(path) => PolicyFile::load(path)?,
+        None => PolicyFile::default(),
+    };
+    // Load the baseline up front so a bad path fails before the audit runs
+    let known_issues = match (&baseline, update_baseline) {
+        (Some(baseline_path), false) => Some(Baseline::load(baseline_path)?),
+        (None, true) => anyhow::bail!("--update-baseline requires --baseline <FILE>"),
+        _ => None,
+    };
+    let suppressions = Suppressions::discover(&dataset_path)?;
+    if !quiet {
+        if let Some(suppressions) = &suppressions {
+            say!(
+                "🔇 {} suppression rules from {}",
+                suppressions.len(),
+                SUPPRESSIONS_FILE
+            );
+        }
+        say!("📂 Loading dataset: {}", dataset_path.display());
+    }
 
-    let results: Vec<_> = images
-        .par_iter()
-        .map(|img_path| {
-            let annotations = dataset.load_annotations(img_path);
-            let result = detector.audit_image(img_path, &annotations, &dataset.class_names);
-            pb.inc(1);
-            result
-        })
-        .collect();
+    let options = AuditOptions {
+        dataset_path,
+        split,
+        splits,
+        images,
+        classes_file,
+        method,
+        backend,
+        model_path,
+        model_format,
+        model_classes,
+        unmapped_classes,
+        class_synonyms,
+        confidence_threshold: confidence,
+        iou_threshold,
+        text_threshold,
+        vlm_endpoint,
+        vlm_model,
+        nms_method: if soft_nms {
+            NmsMethod::Soft {
+                sigma: soft_nms_sigma,
+            }
+        } else {
+            NmsMethod::Hard
+        },
+        nms_mode,
+        device,
+        batch_size,
+        io_workers: workers_io,
+        blur_threshold,
+        brightness_min,
+        brightness_max,
+        min_box_pixels,
+        min_box_side,
+        max_box_fraction,
+        quality_checks,
+        min_color_std,
+        crop_blur_threshold,
+        check_calibration,
+        check_duplicates,
+        min_gt_confidence,
+        assume_pixel_coords,
+        apply_exif_rotation,
+        compare_exif_orientation,
+        box_outlier_mads,
+        exclude_classes,
+        strict,
+        include_images,
+        exclude_images,
+        sample,
+        sample_strategy,
+        min_per_class,
+        seed,
+        cache_file,
+        stream_output,
+        min_confidence_report,
+        baseline: known_issues.clone(),
+        suppressions: suppressions.clone(),
+        severity_policy: policy_file.severity.clone(),
+    };
 
-    pb.finish_with_message("Done!");
+    ctrlc::set_handler(|| {
+        if AUDIT_DONE.load(Ordering::SeqCst) || INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(EXIT_INTERRUPTED.into());
+        }
+        say!("🛑 Interrupted: finishing the images in progress (Ctrl-C again to quit now)");
+    })
+    .context("Failed to install the Ctrl-C handler")?;
 
-    // Build audit result
-    let mut audit_result = AuditResult::new(
-        dataset_path.to_string_lossy().to_string(),
-        effective_method.clone(),
-        confidence,
-        iou_threshold,
-        dataset.image_count(),
-        images.len(),
-    );
+    let observer = CliObserver::new(options.model_path.clone(), quiet);
+    let mut audit_result = run_audit_with(options.clone(), &observer)?;
+    audit_result.config = effective_options;
+    AUDIT_DONE.store(true, Ordering::SeqCst);
+    audit_result.set_iou_buckets(iou_buckets);
+    audit_result.set_label_swap_min_share(label_swap_min_share);
+    audit_result.set_heatmap_grid(heatmap_grid);
+    audit_result.set_priority_weights(priority_weights);
+    audit_result.set_sort_order(sort_output);
 
-    for result in results.into_iter().flatten() {
-        audit_result.add_image_result(result);
+    let active_baseline = match &baseline {
+        Some(_) if update_baseline && audit_result.partial => {
+            say!("⚠️  Baseline not updated, since the audit was interrupted");
+            None
+        }
+        Some(baseline_path) if update_baseline => {
+            let baseline = Baseline::from_result(&audit_result);
+            baseline.save(baseline_path)?;
+            baseline.apply(&mut audit_result);
+            if !quiet {
+                say!(
+                    "📌 Baseline updated: {} ({} issues)",
+                    baseline_path.display(),
+                    baseline.fingerprints.len()
+                );
+            }
+            Some(baseline)
+        }
+        _ => known_issues,
+    };
+    if let Some(path) = &write_suppressions {
+        std::fs::write(path, suppress::template(&audit_result)?)
+            .with_context(|| format!("Failed to write suppressions: {}", path.display()))?;
+        if !quiet {
+            say!("🔇 Suppression rules written: {}", path.display());
+        }
     }
 
     // Print summary
-    println!();
-    println!("╔══════════════════════════════════════════════════════════╗");
-    println!("║                      AUDIT SUMMARY                       ║");
-    println!("╚══════════════════════════════════════════════════════════╝");
-    println!();
-    println!("  Total images:       {}", audit_result.total_images);
-    println!("  Images audited:     {}", audit_result.images_audited);
-    println!("  Images with issues: {}", audit_result.images_with_issues());
-    println!("  Total issues:       {}", audit_result.total_issues());
-    println!();
-    println!("  By severity:");
-    println!("    🔴 High:   {}", audit_result.high_count());
-    println!("    🟡 Medium: {}", audit_result.medium_count());
-    println!("    ⚪ Low:    {}", audit_result.low_count());
-    println!();
-    println!("  By type:");
-    for (issue_type, count) in audit_result.issues_by_type() {
-        println!("    {}: {}", issue_type, count);
+    say!();
+    say!("╔══════════════════════════════════════════════════════════╗");
+    say!("║                      AUDIT SUMMARY                       ║");
+    say!("╚══════════════════════════════════════════════════════════╝");
+    say!();
+    if audit_result.partial {
+        say!(
+            "  ⚠️  INTERRUPTED: {} images were not audited; results are partial",
+            audit_result.images_skipped
+        );
+        say!();
     }
-    println!();
-    println!("  Time: {:.2}s", start.elapsed().as_secs_f64());
+    say!("  Total images:       {}", audit_result.total_images);
+    say!("  Images audited:     {}", audit_result.images_audited);
+    say!(
+        "  Images with issues: {}",
+        audit_result.images_with_issues()
+    );
+    say!(
+        "  Images clean:       {} ({:.1}%)",
+        audit_result.images_clean(),
+        audit_result.clean_rate() * 100.0
+    );
+    if audit_result.summary.failed_images > 0 {
+        say!(
+            "  Images failed:      {}",
+            audit_result.summary.failed_images
+        );
+    }
+    say!("  Total issues:       {}", audit_result.total_issues());
+    if !audit_result.dataset_issues.is_empty() {
+        say!(
+            "    Dataset-level:    {}",
+            audit_result.dataset_issues.len()
+        );
+    }
+    let summary = &audit_result.summary;
+    if summary.baselined_issues + summary.suppressed_issues > 0 {
+        say!("    New:              {}", audit_result.new_issue_count());
+    }
+    if summary.baselined_issues > 0 {
+        say!("    Baselined:        {}", summary.baselined_issues);
+    }
+    if summary.suppressed_issues > 0 {
+        say!("    Suppressed:       {}", summary.suppressed_issues);
+    }
+    if !audit_result.label_swaps.is_empty() {
+        say!();
+        for swap in &audit_result.label_swaps {
+            say!("  ⚠️  {}", swap);
+        }
+    }
+    say!();
+    say!("  By severity:");
+    say!("    🔴 High:   {}", audit_result.high_count());
+    say!("    🟡 Medium: {}", audit_result.medium_count());
+    say!("    ⚪ Low:    {}", audit_result.low_count());
+    say!();
+    say!("  By type:");
+    let human_out_is_terminal = if HUMAN_TO_STDERR.load(Ordering::Relaxed) {
+        std::io::stderr().is_terminal()
+    } else {
+        std::io::stdout().is_terminal()
+    };
+    let color = !no_color && std::env::var_os("NO_COLOR").is_none() && human_out_is_terminal;
+    for line in audit_result.to_summary_table(color).lines() {
+        say!("    {}", line);
+    }
+    if explain {
+        let found: Vec<IssueType> = audit_result
+            .issues_by_type()
+            .iter()
+            .filter_map(|(issue_type, _)| issue_type.parse().ok())
+            .collect();
+        if !found.is_empty() {
+            say!();
+            say!("  What these mean:");
+            for issue_type in found {
+                say!(
+                    "    [{}] {} ({}): {}",
+                    issue_type.short_code(),
+                    issue_type.display_name(),
+                    issue_type,
+                    issue_type.help_text()
+                );
+            }
+        }
+    }
+    if !audit_result.by_split.is_empty() {
+        say!();
+        say!("  By split:");
+        let mut splits: Vec<_> = audit_result.by_split.iter().collect();
+        splits.sort_by_key(|(name, _)| {
+            gt_audit::dataset::SPLITS
+                .iter()
+                .position(|s| s == name)
+                .unwrap_or(gt_audit::dataset::SPLITS.len())
+        });
+        for (name, summary) in splits {
+            say!(
+                "    {}: {} images audited, {} with issues, {} issues",
+                name,
+                summary.images_audited,
+                summary.images_with_issues,
+                summary.total_issues
+            );
+        }
+    }
+    if !audit_result.summary.confusion_matrix.is_empty() {
+        say!();
+        say!("  Top class confusions (GT → detected):");
+        for entry in audit_result.summary.confusion_matrix.iter().take(5) {
+            say!(
+                "    {} → {}: {}",
+                entry.gt_class,
+                entry.detected_class,
+                entry.count
+            );
+        }
+    }
+    if let Some(metrics) = &audit_result.metrics {
+        say!();
+        say!(
+            "  Detection metrics (conf {}, IoU {}):",
+            audit_result.confidence_threshold,
+            audit_result.iou_threshold
+        );
+        say!(
+            "    Precision: {:.3}  Recall: {:.3}  F1: {:.3}",
+            metrics.precision,
+            metrics.recall,
+            metrics.f1
+        );
+        if let Some(map50) = metrics.map50 {
+            say!("    mAP@0.5:   {:.3}", map50);
+        }
+    }
+    say!();
+    say!("  Time: {:.2}s", audit_result.elapsed_secs);
+
+    let policy = policy_file.failure_policy(
+        fail_on_high,
+        fail_on_medium,
+        fail_on_low,
+        fail_on_total,
+        fail_on_missing_labels,
+    );
 
     // Save output
-    if let Some(output_path) = &output {
-        let ext = output_path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("json");
-
-        match ext {
-            "html" => {
-                let reporter = HtmlReporter::new();
-                reporter.generate(&audit_result, output_path)?;
-                println!("📄 HTML report saved: {}", output_path.display());
+    let markdown = MarkdownReporter {
+        max_images: max_report_images.unwrap_or(20),
+        github,
+        policy: policy.clone(),
+    };
+    let json = JsonReporter {
+        detail: report_detail,
+        max_images: report_top,
+    };
+    let mut html = HtmlReporter::new();
+    if embed_thumbnails {
+        html = html.with_thumbnails(audit::load_datasets(&options)?);
+    }
+    if let Some(max_images) = max_report_images.into_iter().chain(report_top).min() {
+        html = html.with_max_images(max_images);
+    }
+    let junit = JunitReporter {
+        medium_as_failure: junit_medium_as_failure,
+        flagged_only: junit_flagged_only,
+        policy: policy.clone(),
+    };
+    for (output_path, format) in &reports {
+        let kind = save_report(
+            &audit_result,
+            output_path,
+            format.as_deref(),
+            filter_severity,
+            filter_type.as_ref(),
+            &json,
+            &markdown,
+            &html,
+            &junit,
+        )?;
+        if !quiet {
+            if is_stdout(output_path) {
+                say!("📄 {} written to stdout", kind);
+            } else {
+                say!("📄 {} saved: {}", kind, output_path.display());
             }
-            _ => {
-                let reporter = JsonReporter::new();
-                reporter.generate(&audit_result, output_path)?;
-                println!("📄 JSON report saved: {}", output_path.display());
+        }
+    }
+    let worklist_datasets = match &export_worklist {
+        Some(path) => {
+            let datasets = audit::load_datasets(&options)?;
+            let written = write_worklists(&audit_result, path, top, group_by_class, &datasets)?;
+            if !quiet {
+                if written.is_empty() {
+                    say!("📝 No worklist written: no class is involved in an issue");
+                } else {
+                    say!("📝 Worklist saved: {}", written.join(", "));
+                }
             }
+            datasets
         }
+        None => Vec::new(),
+    };
+    if audit_result.partial {
+        return Ok(ExitCode::from(EXIT_INTERRUPTED));
     }
 
-    // Check thresholds for CI
-    let mut exit_code = 0;
-
-    if let Some(threshold) = fail_on_high {
-        if audit_result.high_count() > threshold {
-            eprintln!(
-                "❌ FAIL: High severity issues ({}) exceed threshold ({})",
-                audit_result.high_count(),
-                threshold
+    let metrics_server = match &serve_metrics {
+        Some(addr) => {
+            let server = MetricsServer::start(addr, &audit_result)?;
+            if !quiet {
+                say!("📈 Serving Prometheus metrics on {}/metrics", addr);
+            }
+            Some(server)
+        }
+        None => None,
+    };
+
+    if watch {
+        if !quiet {
+            say!("👀 Watching label files for changes (Ctrl-C to exit)...");
+        }
+        watch::watch(&options, &mut audit_result, &observer, |result, changes| {
+            if let Some(min_confidence) = min_confidence_report {
+                result.drop_low_confidence_issues(min_confidence);
+            }
+            if let Some(known) = &active_baseline {
+                known.apply(result);
+            }
+            if let Some(suppressions) = &suppressions {
+                suppressions.apply(result);
+            }
+            for change in changes.iter().filter(|_| !quiet) {
+                if change.is_empty() {
+                    say!("   {}: no change", change.image);
+                    continue;
+                }
+                say!(
+                    "   {}: +{} -{} issues",
+                    change.image,
+                    change.added.len(),
+                    change.removed.len()
+                );
+                for issue in &change.added {
+                    say!("     + [{}] {}", issue.issue_type, issue.description);
+                }
+                for issue in &change.removed {
+                    say!("     - [{}] {}", issue.issue_type, issue.description);
+                }
+            }
+            say!(
+                "   Total issues: {} ({} high)",
+                result.total_issues(),
+                result.high_count()
             );
-            exit_code = 1;
+            if let Some(server) = &metrics_server {
+                server.update(result);
+            }
+            for (output_path, format) in &reports {
+                save_report(
+                    result,
+                    output_path,
+                    format.as_deref(),
+                    filter_severity,
+                    filter_type.as_ref(),
+                    &json,
+                    &markdown,
+                    &html,
+                    &junit,
+                )?;
+            }
+            if let Some(path) = &export_worklist {
+                write_worklists(result, path, top, group_by_class, &worklist_datasets)?;
+            }
+            Ok(())
+        })?;
+    } else if let Some(server) = metrics_server {
+        if !quiet {
+            say!("   Press Ctrl-C to stop");
         }
+        server.join();
     }
 
-    if let Some(threshold) = fail_on_medium {
-        let medium_plus = audit_result.high_count() + audit_result.medium_count();
-        if medium_plus > threshold {
-            eprintln!(
-                "❌ FAIL: Medium+ severity issues ({}) exceed threshold ({})",
-                medium_plus, threshold
-            );
-            exit_code = 1;
+    // Check thresholds for CI
+    let violations = policy::threshold_violations(&audit_result, &policy);
+    if print_summary.is_some() {
+        let summary = MachineSummary {
+            summary: &audit_result.summary,
+            elapsed_secs: audit_result.elapsed_secs,
+            passed: violations.is_empty(),
+            violations: &violations,
+        };
+        println!("{}", serde_json::to_string(&summary)?);
+    }
+    if !violations.is_empty() {
+        for violation in &violations {
+            say!("❌ FAIL: {}", violation);
         }
+        return Ok(ExitCode::FAILURE);
     }
 
-    if exit_code == 0 && (fail_on_high.is_some() || fail_on_medium.is_some()) {
-        println!("✅ PASS: Issue counts within thresholds");
+    if policy.is_active() {
+        say!("✅ PASS: Issue counts within thresholds");
     }
 
-    std::process::exit(exit_code);
+    Ok(ExitCode::SUCCESS)
 }
 
-fn run_download(model: String) -> Result<()> {
-    println!("📥 Downloading models...");
+/// What `--print-summary json` writes to stdout
+#[derive(Serialize)]
+struct MachineSummary<'a> {
+    #[serde(flatten)]
+    summary: &'a AuditSummary,
+    elapsed_secs: f64,
+    /// Whether the issue counts are within the failure thresholds
+    passed: bool,
+    violations: &'a [String],
+}
+
+/// Whether `path` stands for stdout (`-`)
+fn is_stdout(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Pair each output path with its `--format`, if any
+///
+/// A single format applies to every output; otherwise there must be one per
+/// output. Fails if a report's directory is missing or not writable, so the
+/// mistake shows before a long audit rather than after it.
+fn report_outputs(
+    outputs: Vec<PathBuf>,
+    formats: Vec<String>,
+) -> Result<Vec<(PathBuf, Option<String>)>> {
+    let formats: Vec<Option<String>> = match formats.len() {
+        0 => vec![None; outputs.len()],
+        1 => vec![formats.into_iter().next(); outputs.len()],
+        n if n == outputs.len() => formats.into_iter().map(Some).collect(),
+        n => anyhow::bail!(
+            "{} --format values for {} outputs: give one for all outputs, or one per output",
+            n,
+            outputs.len()
+        ),
+    };
+    if outputs.iter().filter(|path| is_stdout(path)).count() > 1 {
+        anyhow::bail!("Only one report can be written to stdout");
+    }
+    for (path, format) in outputs.iter().zip(&formats) {
+        report_format(path, format.as_deref())?;
+    }
+
+    for path in outputs.iter().filter(|path| !is_stdout(path)) {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if !dir.is_dir() {
+            anyhow::bail!("Output directory does not exist: {}", dir.display());
+        }
+        // Creating a file is the only reliable test for write access
+        let probe = dir.join(format!(".gt-audit-{}.tmp", std::process::id()));
+        std::fs::File::create(&probe)
+            .with_context(|| format!("Output directory is not writable: {}", dir.display()))?;
+        let _ = std::fs::remove_file(&probe);
+    }
+    Ok(outputs.into_iter().zip(formats).collect())
+}
+
+/// Write the flagged images of `result` to `path`, one path per line and
+/// highest relabel priority first
+///
+/// With `group_by_class`, writes `<stem>-<class>.<ext>` next to `path` for
+/// every class involved in issues instead. Images are listed by their path
+/// in `datasets` where found, else by their name in the report. Returns the
+/// files written, for messages.
+fn write_worklists(
+    result: &AuditResult,
+    path: &Path,
+    top: Option<usize>,
+    group_by_class: bool,
+    datasets: &[(Option<String>, YoloDataset)],
+) -> Result<Vec<String>> {
+    let write = |path: &Path, images: &[&ImageResult]| -> Result<()> {
+        let mut out = String::new();
+        for image in images {
+            let full_path = datasets
+                .iter()
+                .filter(|(split, _)| *split == image.split)
+                .find_map(|(_, dataset)| dataset.image_path(&image.filename));
+            match full_path {
+                Some(full_path) => out.push_str(&full_path.to_string_lossy()),
+                None => out.push_str(&image.filename),
+            }
+            out.push('\n');
+        }
+        std::fs::write(path, out)
+            .with_context(|| format!("Failed to write worklist: {}", path.display()))
+    };
+
+    if !group_by_class {
+        write(path, &priority::worklist(&result.image_results, top))?;
+        return Ok(vec![path.display().to_string()]);
+    }
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "worklist".to_string());
+    let mut written = Vec::new();
+    for (class, images) in priority::worklists_by_class(&result.image_results, top) {
+        let mut name = format!("{}-{}", stem, file_stem_for(&class));
+        if let Some(ext) = path.extension() {
+            name = format!("{}.{}", name, ext.to_string_lossy());
+        }
+        let class_path = path.with_file_name(name);
+        write(&class_path, &images)?;
+        written.push(class_path.display().to_string());
+    }
+    Ok(written)
+}
+
+/// Report format given by `format`, else by the extension of `path`
+///
+/// Stdout and paths without an extension get JSON; any other extension must
+/// name a supported format, so `-o issues.csv` fails rather than writing JSON.
+fn report_format<'a>(path: &'a Path, format: Option<&'a str>) -> Result<&'a str> {
+    let format = match format {
+        Some(format) => format,
+        None => match path.extension() {
+            Some(ext) => ext.to_str().unwrap_or_default(),
+            None => "json",
+        },
+    };
+    match format {
+        "json" | "html" | "xml" | "junit" | "sarif" | "md" | "markdown" => Ok(format),
+        other => anyhow::bail!(
+            "Unsupported report extension '.{}' for {}: use .json, .html, .xml, .sarif or .md, or pass --format",
+            other,
+            path.display()
+        ),
+    }
+}
+
+/// Write the report for `result`, picking the format from `format` or the file extension
+///
+/// `-` writes the report to stdout. Returns the kind of report written, for
+/// messages.
+#[allow(clippy::too_many_arguments)]
+fn save_report(
+    result: &AuditResult,
+    output_path: &Path,
+    format: Option<&str>,
+    filter_severity: Option<IssueSeverity>,
+    filter_type: Option<&IssueType>,
+    json: &JsonReporter,
+    markdown: &MarkdownReporter,
+    html: &HtmlReporter,
+    junit: &JunitReporter,
+) -> Result<&'static str> {
+    let mut filtered: Option<AuditResult> = None;
+    if let Some(severity) = filter_severity {
+        filtered = Some(result.filter_by_severity(severity));
+    }
+    if let Some(issue_type) = filter_type {
+        filtered = Some(
+            filtered
+                .as_ref()
+                .unwrap_or(result)
+                .filter_by_type(issue_type),
+        );
+    }
+    let result = filtered.as_ref().unwrap_or(result);
+
+    let sarif = SarifReporter::new();
+    let (reporter, kind): (&dyn Reporter, _) = match report_format(output_path, format)? {
+        "html" => (html, "HTML report"),
+        "xml" | "junit" => (junit, "JUnit XML report"),
+        "sarif" => (&sarif, "SARIF report"),
+        "md" | "markdown" => (markdown, "Markdown report"),
+        _ => (json, "JSON report"),
+    };
+    if is_stdout(output_path) {
+        print!("{}", reporter.render(result)?);
+    } else {
+        reporter
+            .generate(result, output_path)
+            .with_context(|| format!("Failed to write {}", output_path.display()))?;
+    }
+    Ok(kind)
+}
+
+/// Prints audit progress to the terminal
+struct CliObserver {
+    model_path: Option<PathBuf>,
+    /// Leave out the progress bar
+    quiet: bool,
+    classes_printed: Once,
+    pb: OnceLock<ProgressBar>,
+}
+
+impl CliObserver {
+    fn new(model_path: Option<PathBuf>, quiet: bool) -> Self {
+        Self {
+            model_path,
+            quiet,
+            classes_printed: Once::new(),
+            pb: OnceLock::new(),
+        }
+    }
+}
+
+impl AuditObserver for CliObserver {
+    fn on_dataset_loaded(&self, split: Option<&str>, dataset: &YoloDataset, images: usize) {
+        // Class names are shared by all splits
+        self.classes_printed
+            .call_once(|| info!("   Classes: {}", dataset.class_names.len()));
+        match split {
+            Some(name) => info!("   Images ({}): {}", name, images),
+            None => info!("   Images: {}", images),
+        }
+    }
+
+    fn on_sampled(&self, sample: usize, seed: u64) {
+        info!("   Sampled: {} images (seed={})", sample, seed);
+    }
+
+    fn on_filtered(&self, images: usize) {
+        info!("   Matching include/exclude patterns: {} images", images);
+    }
+
+    fn on_detector_init(&self, method: &str) {
+        info!("🔍 Initializing detector: {}", method);
+        match (method, &self.model_path) {
+            ("yolo" | "byom", Some(model_path)) => {
+                info!("   Loading ONNX model: {}", model_path.display());
+            }
+            ("zero-shot", _) => {
+                info!("   Using heuristic-based validation (no model provided)");
+                info!("   For better results, use --model with a trained YOLO model");
+            }
+            ("grounding-dino", _) => {
+                info!("   Prompting Grounding DINO with the dataset's class names");
+            }
+            ("vlm", _) => {
+                info!("   Asking a vision-language model for the objects in each image");
+            }
+            _ => {}
+        }
+    }
+
+    fn on_class_mapping(&self, mapping: &ClassMapping) {
+        if mapping.source == ClassSource::Dataset {
+            return;
+        }
+        info!(
+            "   Model classes ({}): {}, {} mapped to dataset classes",
+            mapping.source,
+            mapping.model_classes.len(),
+            mapping.model_classes.len() - mapping.unmapped().len()
+        );
+        for (model, dataset) in mapping.renamed() {
+            info!("     {} → {}", model, dataset);
+        }
+        let unmapped = mapping.unmapped();
+        if !unmapped.is_empty() {
+            info!("   Unmapped model classes: {}", unmapped.join(", "));
+        }
+        if !mapping.uncovered.is_empty() {
+            self.on_warning(&format!(
+                "The model has no class for: {}",
+                mapping.uncovered.join(", ")
+            ));
+        }
+    }
 
-    match model.as_str() {
-        "clip" | "all" => {
-            println!("   Downloading CLIP model...");
-            detector::download_clip_model()?;
-            println!("   ✓ CLIP model ready");
+    fn on_warning(&self, message: &str) {
+        warn!("   ⚠️  {}", message);
+    }
+
+    fn on_resumed(&self, images: usize) {
+        info!("♻️  Resuming: {} images restored from cache", images);
+    }
+
+    fn on_start(&self, images: usize) {
+        info!("🔬 Auditing {} images...", images);
+        if self.quiet {
+            return;
         }
-        "grounding-dino" => {
-            println!("   Downloading Grounding DINO model...");
-            println!("   ⚠ Grounding DINO - coming soon");
+
+        let pb = progress_bar(images);
+        logging::set_progress_bar(Some(pb.clone()));
+        self.pb.set(pb).ok();
+    }
+
+    fn on_image_done(&self, _image_path: &Path, _result: &Result<ImageResult>) {
+        if let Some(pb) = self.pb.get() {
+            pb.inc(1);
         }
-        _ => {
-            anyhow::bail!("Unknown model: {}. Use: clip, grounding-dino, all", model);
+    }
+
+    fn on_finish(&self, _result: &AuditResult) {
+        if let Some(pb) = self.pb.get() {
+            pb.finish_with_message("Done!");
         }
+        logging::set_progress_bar(None);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        INTERRUPTED.load(Ordering::SeqCst)
+    }
+}
+
+fn run_download(model: String) -> Result<ExitCode> {
+    println!("📥 Downloading models...");
+
+    if !matches!(model.as_str(), "clip" | "grounding-dino" | "all") {
+        anyhow::bail!("Unknown model: {}. Use: clip, grounding-dino, all", model);
+    }
+    if matches!(model.as_str(), "clip" | "all") {
+        println!("   Downloading CLIP model...");
+        detector::download_clip_model()?;
+        println!("   ✓ CLIP model ready");
+    }
+    if matches!(model.as_str(), "grounding-dino" | "all") {
+        println!("   Downloading Grounding DINO model...");
+        let cache_dir = grounding_dino::download()?;
+        println!("   ✓ Grounding DINO model ready in {}", cache_dir.display());
     }
 
     println!("✅ Done!");
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Progress bar over `len` items, in the style of the audit's
+fn progress_bar(len: usize) -> ProgressBar {
+    let pb = ProgressBar::new(len as u64);
+    if let Ok(style) = ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
+    {
+        pb.set_style(style.progress_chars("#>-"));
+    }
+    pb
 }
 
-fn run_info(dataset_path: PathBuf) -> Result<()> {
+fn run_info(dataset_path: PathBuf, output: Option<PathBuf>) -> Result<ExitCode> {
     let dataset = YoloDataset::load(&dataset_path)?;
 
+    let pb = progress_bar(dataset.image_count());
+    let stats = dataset.compute_stats_with(|| pb.inc(1));
+    pb.finish_and_clear();
+
     println!("Dataset: {}", dataset_path.display());
     println!("Images: {}", dataset.image_count());
     println!("Classes: {}", dataset.class_names.len());
+    println!(
+        "Annotations: {} ({}/{:.2}/{} min/mean/max per image)",
+        stats.total_annotations,
+        stats.min_annotations_per_image,
+        stats.avg_annotations_per_image,
+        stats.max_annotations_per_image
+    );
+    println!(
+        "Images without annotations: {}",
+        stats.images_without_annotations
+    );
+    if let (Some(most), Some(least)) = (stats.classes.first(), stats.classes.last()) {
+        println!(
+            "Class imbalance: {:.1}x ({} {} vs {} {})",
+            stats.class_imbalance_ratio,
+            most.class_name,
+            most.annotation_count,
+            least.class_name,
+            least.annotation_count
+        );
+    }
     println!();
     println!("Class names:");
     for (id, name) in &dataset.class_names {
         println!("  {}: {}", id, name);
     }
 
+    println!();
+    println!("Class ids in labels:");
+    let used = dataset.label_class_ids();
+    for (id, count) in &used {
+        if dataset.is_valid_class_id(*id) {
+            println!("  {}: {} annotations", id, count);
+        } else {
+            println!("  {}: {} annotations ⚠️  not declared", id, count);
+        }
+    }
+    let mut unused: Vec<i32> = dataset
+        .class_names
+        .keys()
+        .filter(|id| !used.contains_key(id))
+        .copied()
+        .collect();
+    if !unused.is_empty() {
+        unused.sort();
+        let unused: Vec<String> = unused.iter().map(|id| id.to_string()).collect();
+        println!("  Declared but unused: {}", unused.join(", "));
+    }
+
+    let splits = MultiSplitDataset::load(&dataset_path, "all").ok();
+
+    println!();
+    println!("Unparseable label lines:");
+    match &splits {
+        Some(splits) => {
+            for name in splits.split_names() {
+                println!("  {}: {}", name, splits.splits[&name].count_label_errors());
+            }
+        }
+        None => println!("  {}", dataset.count_label_errors()),
+    }
+
+    println!();
+    println!("Label file consistency:");
+    match &splits {
+        Some(splits) => {
+            for name in splits.split_names() {
+                println!("  {}:", name);
+                print_consistency(&splits.splits[&name], "    ");
+            }
+        }
+        None => print_consistency(&dataset, "  "),
+    }
+
+    if !stats.classes.is_empty() {
+        println!();
+        println!("Annotations per class:");
+        println!("  {:<20} {:>7} {:>7}", "Class", "Boxes", "Images");
+        for c in &stats.classes {
+            println!(
+                "  {:<20} {:>7} {:>7} ({:.1}%)",
+                c.class_name, c.annotation_count, c.image_count, c.image_pct
+            );
+        }
+
+        println!();
+        println!("Box area (share of the image):");
+        for bucket in &stats.area_buckets {
+            println!("  {:<8} {:>7}", bucket.range, bucket.count);
+        }
+    }
+
+    let box_stats = match &splits {
+        Some(splits) => BoxStats::from_datasets(splits.splits.values()),
+        None => BoxStats::from_datasets([&dataset]),
+    };
+    if !box_stats.is_empty() {
+        println!();
+        println!("Box sizes by class (median, 5th-95th percentile):");
+        println!(
+            "  {:<20} {:>7}  {:<24} Aspect ratio",
+            "Class", "Boxes", "Area"
+        );
+        for (name, stats) in &box_stats.classes {
+            let area = format!(
+                "{:.4} ({:.4}-{:.4})",
+                stats.area.median, stats.area.p5, stats.area.p95
+            );
+            let aspect = format!(
+                "{:.2} ({:.2}-{:.2})",
+                stats.aspect_ratio.median, stats.aspect_ratio.p5, stats.aspect_ratio.p95
+            );
+            println!("  {:<20} {:>7}  {:<24} {}", name, stats.count, area, aspect);
+        }
+    }
+
+    if let Some(output_path) = &output {
+        std::fs::write(output_path, serde_json::to_string_pretty(&stats)?)?;
+        println!();
+        println!("📄 JSON stats saved: {}", output_path.display());
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Print the counts of `YoloDataset::consistency_check` with a few example paths
+fn print_consistency(dataset: &YoloDataset, indent: &str) {
+    const EXAMPLES: usize = 5;
+    let report = dataset.consistency_check();
+    for (what, paths) in [
+        ("Orphaned label files (no image)", &report.orphaned_labels),
+        ("Images without a label file", &report.missing_labels),
+        ("Images with an empty label file", &report.empty_labels),
+    ] {
+        println!("{}{}: {}", indent, what, paths.len());
+        for path in paths.iter().take(EXAMPLES) {
+            let path = path.strip_prefix(&dataset.path).unwrap_or(path);
+            println!("{}  {}", indent, path.display());
+        }
+        if paths.len() > EXAMPLES {
+            println!("{}  ... and {} more", indent, paths.len() - EXAMPLES);
+        }
+    }
+}
+
+fn run_model_info(model_path: &Path, dataset_path: Option<&Path>) -> Result<()> {
+    let info = ModelInfo::load(model_path)?;
+
+    println!("Model: {}", model_path.display());
+    if let Some(producer) = &info.producer {
+        println!("Producer: {}", producer);
+    }
+    let shape = |dims: &[i64]| {
+        let dims: Vec<String> = dims
+            .iter()
+            .map(|d| {
+                if *d < 0 {
+                    "?".to_string()
+                } else {
+                    d.to_string()
+                }
+            })
+            .collect();
+        format!("[{}]", dims.join(", "))
+    };
+    for (name, dims) in &info.inputs {
+        println!("Input: {} {}", name, shape(dims));
+    }
+    for (name, dims) in &info.outputs {
+        println!("Output: {} {}", name, shape(dims));
+    }
+    let (width, height) = info.input_size();
+    println!("Input size: {}x{}", width, height);
+    let format = info.likely_format();
+    if let Some(format) = format {
+        println!("Output layout: {} (detected)", format);
+    }
+    if let Some(task) = info.custom.get("task") {
+        println!("Task: {}", task);
+    }
+
+    let output_classes = format.and_then(|f| info.output_classes(f));
+    let names = info.class_names().transpose()?;
+    match (&names, output_classes) {
+        (Some(names), _) => println!("Classes: {}", names.len()),
+        (None, Some(n)) => println!("Classes: {} (no names in metadata)", n),
+        (None, None) => println!("Classes: unknown"),
+    }
+    if let Some(names) = &names {
+        println!();
+        println!("Class names:");
+        for (id, name) in names.iter().enumerate() {
+            println!("  {}: {}", id, name);
+        }
+    }
+
+    let other: Vec<(&String, &String)> = info
+        .custom
+        .iter()
+        .filter(|(key, _)| !matches!(key.as_str(), "names" | "task"))
+        .collect();
+    if !other.is_empty() {
+        println!();
+        println!("Metadata:");
+        for (key, value) in other {
+            println!("  {}: {}", key, value);
+        }
+    }
+
+    if let Some(dataset_path) = dataset_path {
+        let dataset = YoloDataset::load(dataset_path)?;
+        let dataset_classes: Vec<String> = dataset.class_names.values().cloned().collect();
+        if let Some(names) = names {
+            let mapping = ClassMapping::build(
+                ClassSource::Metadata,
+                names,
+                &dataset_classes,
+                ClassSynonyms::builtin(),
+            );
+            println!();
+            println!(
+                "{} of {} model classes map to dataset classes",
+                mapping.model_classes.len() - mapping.unmapped().len(),
+                mapping.model_classes.len()
+            );
+            if !mapping.uncovered.is_empty() {
+                println!("  ⚠️  No model class for: {}", mapping.uncovered.join(", "));
+            }
+        } else if let Some(n) = output_classes.filter(|n| *n != dataset_classes.len()) {
+            println!();
+            println!(
+                "⚠️  Model scores {} classes but the dataset declares {}",
+                n,
+                dataset_classes.len()
+            );
+        }
+    }
+
     Ok(())
 }
+
+fn run_fix(
+    dataset_path: PathBuf,
+    report: Option<PathBuf>,
+    from: Option<PathBuf>,
+    options: fix::FixOptions,
+) -> Result<ExitCode> {
+    if let Some(patch_dir) = from {
+        let summary = fix::apply_patches(&dataset_path, &patch_dir, &options)?;
+        for diff in &summary.diffs {
+            print!("{}", diff);
+        }
+        print_fix_summary(&dataset_path, &summary, &options);
+        return Ok(ExitCode::SUCCESS);
+    }
+    let dataset = YoloDataset::load(&dataset_path)?;
+
+    // Restrict to flagged images when a prior report is given
+    let images = match &report {
+        Some(report_path) => {
+            let result = AuditResult::from_json(report_path)?;
+            Some(
+                result
+                    .flagged_images()
+                    .into_iter()
+                    .map(|img| dataset.images_dir.join(&img.filename))
+                    .collect(),
+            )
+        }
+        None => None,
+    };
+
+    let summary = fix::fix_dataset(&dataset, images, &options)?;
+    for diff in &summary.diffs {
+        print!("{}", diff);
+    }
+    print_fix_summary(&dataset_path, &summary, &options);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_fix_summary(dataset_path: &Path, summary: &fix::FixSummary, options: &fix::FixOptions) {
+    println!();
+    if options.dry_run {
+        println!("🔎 Dry run - no files written");
+    }
+    println!("  Label files scanned:  {}", summary.files_scanned);
+    println!("  Label files modified: {}", summary.files_modified);
+    println!("  Lines modified:       {}", summary.lines_modified);
+    println!("  Lines removed:        {}", summary.lines_removed);
+    if summary.lines_added > 0 {
+        println!("  Lines added:          {}", summary.lines_added);
+    }
+    if options.backup && !options.dry_run && summary.files_modified > 0 {
+        println!(
+            "  Backups: {}",
+            dataset_path.join(fix::BACKUP_DIR).display()
+        );
+    }
+}
+
+fn run_export_fixes(
+    report: PathBuf,
+    output: PathBuf,
+    dataset: Option<PathBuf>,
+) -> Result<ExitCode> {
+    let result = AuditResult::from_json(&report)?;
+    let dataset_path = dataset.unwrap_or_else(|| PathBuf::from(&result.dataset_path));
+    let summary = fix::export_fixes(&result, &dataset_path, &output)?;
+
+    for skipped in &summary.skipped {
+        warn!("Skipped {}", skipped);
+    }
+    println!("  Suggested fixes:      {}", summary.fixes);
+    println!("  Label files written:  {}", summary.files_written);
+    println!("✅ Patched labels written to {}", output.display());
+    println!(
+        "   Review them, then apply with: gt-audit fix {} --from {}",
+        dataset_path.display(),
+        output.display()
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_leakage(dataset_path: PathBuf, mode: HashMode, output: Option<PathBuf>) -> Result<ExitCode> {
+    let dataset = MultiSplitDataset::load(&dataset_path, "all")?;
+    println!("📂 Dataset: {}", dataset_path.display());
+    println!("   Splits: {}", dataset.split_names().join(", "));
+    println!();
+
+    let report = leakage::find_duplicates(&dataset, mode)?;
+
+    println!(
+        "🔍 Hashed {} images ({} unreadable)",
+        report.images_hashed, report.images_skipped
+    );
+    println!(
+        "   Cross-split duplicate groups: {}",
+        report.cross_split_count()
+    );
+    println!(
+        "   Intra-split duplicate groups: {}",
+        report.intra_split_count()
+    );
+
+    for group in &report.groups {
+        println!();
+        let marker = if group.cross_split { "🔴" } else { "⚪" };
+        println!(
+            "  {} {} copies ({})",
+            marker,
+            group.files.len(),
+            &group.hash[..group.hash.len().min(16)]
+        );
+        for file in &group.files {
+            println!("     [{}] {}", file.split, file.path.display());
+        }
+    }
+
+    if let Some(output_path) = &output {
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(output_path, json)?;
+        println!();
+        println!("📄 JSON report saved: {}", output_path.display());
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_convert(
+    input: PathBuf,
+    output: PathBuf,
+    from: DatasetFormat,
+    to: DatasetFormat,
+) -> Result<ExitCode> {
+    let dataset = LabeledDataset::load(&input, from)?;
+    println!(
+        "Read {} images, {} annotations, {} classes from {}",
+        dataset.images.len(),
+        dataset.annotation_count(),
+        dataset.class_names.len(),
+        input.display()
+    );
+
+    dataset::writer_for(to).write(&dataset, &output)?;
+    println!("✅ Converted dataset written to {}", output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_export(
+    dataset_path: PathBuf,
+    report: PathBuf,
+    output: Option<PathBuf>,
+    exclude_flagged: bool,
+) -> Result<ExitCode> {
+    let output = output.unwrap_or_else(|| PathBuf::from(dataset::COCO_FILE));
+    let dataset = YoloDataset::load(&dataset_path)?;
+    let result = AuditResult::from_json(&report)?;
+
+    let summary = DatasetExporter::new(&dataset, &result, exclude_flagged).write_coco(&output)?;
+
+    println!("  Images written:       {}", summary.images_written);
+    println!("  Annotations written:  {}", summary.annotations_written);
+    if exclude_flagged {
+        println!("  Flagged images left out: {}", summary.images_skipped);
+    } else {
+        println!("  Annotations stripped: {}", summary.annotations_stripped);
+    }
+    println!("✅ COCO annotations written to {}", output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_export_tasks(
+    dataset_path: PathBuf,
+    report: PathBuf,
+    format: TaskFormat,
+    output: Option<PathBuf>,
+    images_prefix: Option<String>,
+    label_config: Option<PathBuf>,
+    synonyms: Option<PathBuf>,
+) -> Result<ExitCode> {
+    let result = AuditResult::from_json(&report)?;
+    let label_config = match &label_config {
+        Some(path) => LabelConfig::load(path)?,
+        None => LabelConfig::default(),
+    };
+    let synonyms = ClassSynonyms::from_config(synonyms.as_deref())?;
+    let output = output.unwrap_or_else(|| {
+        PathBuf::from(match format {
+            TaskFormat::LabelStudio => "tasks.json",
+            TaskFormat::Cvat => "annotations.xml",
+        })
+    });
+
+    let summary = TaskExporter::new(
+        &result,
+        &dataset_path,
+        images_prefix.as_deref(),
+        &label_config,
+        &synonyms,
+    )
+    .write(format, &output)?;
+
+    for skipped in &summary.skipped {
+        warn!("Skipped {}", skipped);
+    }
+    if !summary.unmapped_classes.is_empty() {
+        warn!(
+            "No label in the labeling config for classes: {}",
+            summary.unmapped_classes.join(", ")
+        );
+    }
+    println!("  Tasks written:        {}", summary.tasks);
+    println!("  Pre-annotated boxes:  {}", summary.boxes);
+    println!("✅ Tasks written to {}", output.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_stats(
+    dataset_path: PathBuf,
+    output: Option<PathBuf>,
+    per_class_report: Option<PathBuf>,
+) -> Result<ExitCode> {
+    let dataset = YoloDataset::load(&dataset_path)?;
+    let (stats, by_class) = dataset.compute_stats_and_split_by_class();
+
+    println!("Dataset: {}", dataset_path.display());
+    println!("Images: {}", stats.total_images);
+    println!("Annotations: {}", stats.total_annotations);
+    println!(
+        "Avg annotations per image: {:.2}",
+        stats.avg_annotations_per_image
+    );
+    println!(
+        "Images with no annotations: {}",
+        stats.images_without_annotations
+    );
+    println!(
+        "Images with >50 annotations: {}",
+        stats.images_over_50_annotations
+    );
+    println!();
+    println!(
+        "  {:<4} {:<20} {:>7} {:>15} {:>15} {:>15} {:>15} {:>8} {:>8}",
+        "ID", "Class", "Count", "Images", "Width", "Height", "Area", "Min", "Max"
+    );
+    for c in &stats.classes {
+        println!(
+            "  {:<4} {:<20} {:>7} {:>7} ({:>4.1}%) {:>7.3}±{:<7.3} {:>7.3}±{:<7.3} {:>7.4}±{:<7.4} {:>8.4} {:>8.4}",
+            c.class_id,
+            c.class_name,
+            c.annotation_count,
+            c.image_count,
+            c.image_pct,
+            c.width_mean,
+            c.width_std,
+            c.height_mean,
+            c.height_std,
+            c.area_mean,
+            c.area_std,
+            c.area_min,
+            c.area_max,
+        );
+    }
+
+    if let Some(output_path) = &output {
+        let json = serde_json::to_string_pretty(&stats)?;
+        std::fs::write(output_path, json)?;
+        println!();
+        println!("📄 JSON stats saved: {}", output_path.display());
+    }
+
+    if let Some(dir) = &per_class_report {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let mut by_class: Vec<_> = by_class.into_iter().collect();
+        by_class.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut stems = HashSet::new();
+        for (class_name, images) in &by_class {
+            let report = ClassReport {
+                class_name,
+                image_count: images.len(),
+                stats: stats.classes.iter().find(|c| &c.class_name == class_name),
+                images: images.iter().map(|p| p.display().to_string()).collect(),
+            };
+            let stem = file_stem_for(class_name);
+            let unique = unique_stem(&stem, &mut stems);
+            if unique != stem {
+                println!(
+                    "⚠️  Class '{}' written to {}.json, since another class's file is {}.json",
+                    class_name, unique, stem
+                );
+            }
+            let path = dir.join(format!("{}.json", unique));
+            std::fs::write(&path, serde_json::to_string_pretty(&report)?)?;
+        }
+        println!();
+        println!(
+            "📄 Per-class reports saved: {} classes in {}",
+            by_class.len(),
+            dir.display()
+        );
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// What `stats --per-class-report` writes for each class
+#[derive(Serialize)]
+struct ClassReport<'a> {
+    class_name: &'a str,
+    image_count: usize,
+    stats: Option<&'a ClassStats>,
+    /// Images with at least one box of the class
+    images: Vec<String>,
+}
+
+/// `stem`, or `stem_2`, `stem_3`... if it is in `taken`, which it is then added to
+///
+/// Compared case-insensitively, as some file systems are.
+fn unique_stem(stem: &str, taken: &mut HashSet<String>) -> String {
+    let mut unique = stem.to_string();
+    let mut n = 1;
+    while !taken.insert(unique.to_lowercase()) {
+        n += 1;
+        unique = format!("{}_{}", stem, n);
+    }
+    unique
+}
+
+/// `name` with every character unsafe in a file name replaced by `_`
+fn file_stem_for(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,379 @@
+//! Detection quality metrics: precision, recall, F1 and AP per class, and how
+//! tightly matched detections overlap their GT boxes
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::models::{Annotation, Detection, ImageResult};
+
+/// IoU a detection needs with a GT box to count toward AP@0.5
+pub const AP_IOU: f32 = 0.5;
+
+/// Buckets of the IoU histogram unless configured otherwise
+pub const DEFAULT_IOU_BUCKETS: usize = 10;
+
+/// A model detection after matching against the GT of its image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoredDetection {
+    /// Class of the matched GT box, or the detected class if unmatched
+    pub class_name: String,
+    pub confidence: f32,
+    /// Matched a same-class GT box at the configured IoU threshold
+    pub true_positive: bool,
+    /// Matched a same-class GT box at `AP_IOU`
+    pub true_positive_50: bool,
+}
+
+/// Match detections to GT boxes, once at `iou_threshold` and once at `AP_IOU`
+///
+/// Detections are matched greedily in order of confidence, each taking the
+/// unmatched GT box of the same class (as decided by `same_class`) it
+/// overlaps most. Every GT box is matched at most once.
+pub fn score_detections(
+    annotations: &[Annotation],
+    detections: &[Detection],
+    iou_threshold: f32,
+    same_class: impl Fn(&str, &str) -> bool,
+) -> Vec<ScoredDetection> {
+    let at_threshold = greedy_match(annotations, detections, iou_threshold, &same_class);
+    let at_50 = greedy_match(annotations, detections, AP_IOU, &same_class);
+
+    detections
+        .iter()
+        .enumerate()
+        .map(|(i, det)| {
+            let class_name = at_threshold[i]
+                .or(at_50[i])
+                .map(|gt| annotations[gt].class_name.clone())
+                .unwrap_or_else(|| det.class_name.clone());
+            ScoredDetection {
+                class_name,
+                confidence: det.confidence,
+                true_positive: at_threshold[i].is_some(),
+                true_positive_50: at_50[i].is_some(),
+            }
+        })
+        .collect()
+}
+
+/// Index of the GT box each detection matched, if any
+fn greedy_match(
+    annotations: &[Annotation],
+    detections: &[Detection],
+    iou_threshold: f32,
+    same_class: &impl Fn(&str, &str) -> bool,
+) -> Vec<Option<usize>> {
+    let mut order: Vec<usize> = (0..detections.len()).collect();
+    order.sort_by(|&a, &b| {
+        detections[b]
+            .confidence
+            .total_cmp(&detections[a].confidence)
+    });
+
+    let mut taken = vec![false; annotations.len()];
+    let mut matches = vec![None; detections.len()];
+    for i in order {
+        let det = &detections[i];
+        let best = annotations
+            .iter()
+            .enumerate()
+            .filter(|(gt, ann)| !taken[*gt] && same_class(&det.class_name, &ann.class_name))
+            .map(|(gt, ann)| (gt, det.bbox.iou(&ann.bbox)))
+            .filter(|(_, iou)| *iou >= iou_threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((gt, _)) = best {
+            taken[gt] = true;
+            matches[i] = Some(gt);
+        }
+    }
+    matches
+}
+
+/// Detection metrics for one class
+///
+/// Ratios with a zero denominator are reported as 0. `ap50` is `None` for
+/// classes without GT instances, where recall is undefined.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClassMetrics {
+    pub gt_count: usize,
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+    pub precision: f32,
+    pub recall: f32,
+    pub f1: f32,
+    pub ap50: Option<f32>,
+}
+
+/// Detection metrics over all audited images
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DetectionMetrics {
+    /// Precision over all classes at the configured thresholds
+    pub precision: f32,
+    pub recall: f32,
+    pub f1: f32,
+    /// Mean of `ap50` over classes with GT instances
+    pub map50: Option<f32>,
+    pub per_class: BTreeMap<String, ClassMetrics>,
+}
+
+impl DetectionMetrics {
+    /// Metrics over the images whose detections were scored
+    ///
+    /// Returns `None` when no image was audited by a detector that produces
+    /// scored detections.
+    pub fn from_results<'a>(results: impl IntoIterator<Item = &'a ImageResult>) -> Option<Self> {
+        let mut gt_counts: BTreeMap<String, usize> = BTreeMap::new();
+        let mut scored: BTreeMap<String, Vec<&ScoredDetection>> = BTreeMap::new();
+        let mut any_scored = false;
+
+        for result in results {
+            let Some(detections) = &result.detections else {
+                continue;
+            };
+            any_scored = true;
+            for class_name in &result.gt_classes {
+                *gt_counts.entry(class_name.clone()).or_insert(0) += 1;
+            }
+            for det in detections {
+                scored.entry(det.class_name.clone()).or_default().push(det);
+            }
+        }
+        if !any_scored {
+            return None;
+        }
+
+        let mut classes: Vec<String> = gt_counts.keys().chain(scored.keys()).cloned().collect();
+        classes.sort();
+        classes.dedup();
+
+        let mut per_class = BTreeMap::new();
+        for class_name in classes {
+            let gt_count = gt_counts.get(&class_name).copied().unwrap_or(0);
+            let mut detections = scored.remove(&class_name).unwrap_or_default();
+            let true_positives = detections.iter().filter(|d| d.true_positive).count();
+            let false_positives = detections.len() - true_positives;
+
+            detections.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+            let hits: Vec<bool> = detections.iter().map(|d| d.true_positive_50).collect();
+
+            per_class.insert(
+                class_name,
+                ClassMetrics::new(gt_count, true_positives, false_positives, &hits),
+            );
+        }
+
+        let gt_total: usize = per_class.values().map(|c| c.gt_count).sum();
+        let tp_total: usize = per_class.values().map(|c| c.true_positives).sum();
+        let det_total: usize = per_class
+            .values()
+            .map(|c| c.true_positives + c.false_positives)
+            .sum();
+        let precision = ratio(tp_total, det_total);
+        let recall = ratio(tp_total, gt_total);
+
+        let aps: Vec<f32> = per_class.values().filter_map(|c| c.ap50).collect();
+        let map50 = (!aps.is_empty()).then(|| aps.iter().sum::<f32>() / aps.len() as f32);
+
+        Some(Self {
+            precision,
+            recall,
+            f1: f1(precision, recall),
+            map50,
+            per_class,
+        })
+    }
+}
+
+impl ClassMetrics {
+    /// `hits_50` holds, in descending confidence order, whether each
+    /// detection of the class matched at `AP_IOU`
+    fn new(
+        gt_count: usize,
+        true_positives: usize,
+        false_positives: usize,
+        hits_50: &[bool],
+    ) -> Self {
+        let precision = ratio(true_positives, true_positives + false_positives);
+        let recall = ratio(true_positives, gt_count);
+        Self {
+            gt_count,
+            true_positives,
+            false_positives,
+            false_negatives: gt_count.saturating_sub(true_positives),
+            precision,
+            recall,
+            f1: f1(precision, recall),
+            ap50: (gt_count > 0).then(|| average_precision(hits_50, gt_count)),
+        }
+    }
+}
+
+/// IoU of every detection matched to a GT box, counted in equal-width
+/// buckets from 0 to 1
+///
+/// Serializes as `[[low, high, count], ...]`, lowest bucket first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct IouHistogram {
+    buckets: Vec<(f32, f32, usize)>,
+}
+
+impl IouHistogram {
+    /// An empty histogram of `buckets` buckets
+    pub fn new(buckets: usize) -> Self {
+        let n = buckets.max(1);
+        Self {
+            buckets: (0..n)
+                .map(|i| (i as f32 / n as f32, (i + 1) as f32 / n as f32, 0))
+                .collect(),
+        }
+    }
+
+    /// Bin the match IoUs of every result, or `None` if there are none
+    pub fn from_results<'a>(
+        results: impl IntoIterator<Item = &'a ImageResult>,
+        buckets: usize,
+    ) -> Option<Self> {
+        let mut histogram = Self::new(buckets);
+        for result in results {
+            for &iou in &result.match_ious {
+                histogram.add(iou);
+            }
+        }
+        (histogram.total() > 0).then_some(histogram)
+    }
+
+    /// Count an IoU; 1.0 lands in the last bucket
+    pub fn add(&mut self, iou: f32) {
+        if !iou.is_finite() {
+            return;
+        }
+        let n = self.buckets.len();
+        let bucket = ((iou.max(0.0) * n as f32) as usize).min(n - 1);
+        self.buckets[bucket].2 += 1;
+    }
+
+    /// `(low, high, count)` of every bucket
+    pub fn buckets(&self) -> &[(f32, f32, usize)] {
+        &self.buckets
+    }
+
+    /// IoUs counted
+    pub fn total(&self) -> usize {
+        self.buckets.iter().map(|b| b.2).sum()
+    }
+
+    /// Count of the fullest bucket
+    pub fn max(&self) -> usize {
+        self.buckets.iter().map(|b| b.2).max().unwrap_or(0)
+    }
+}
+
+/// Area under the precision/recall curve with all-point interpolation
+fn average_precision(hits: &[bool], gt_count: usize) -> f32 {
+    let mut tp = 0;
+    let mut curve: Vec<(f32, f32)> = Vec::with_capacity(hits.len());
+    for (i, hit) in hits.iter().enumerate() {
+        if *hit {
+            tp += 1;
+        }
+        curve.push((ratio(tp, gt_count), ratio(tp, i + 1)));
+    }
+
+    // Each point takes the best precision reachable at equal or higher recall
+    for i in (0..curve.len().saturating_sub(1)).rev() {
+        curve[i].1 = curve[i].1.max(curve[i + 1].1);
+    }
+
+    let mut ap = 0.0;
+    let mut prev_recall = 0.0;
+    for (recall, precision) in curve {
+        ap += (recall - prev_recall) * precision;
+        prev_recall = recall;
+    }
+    ap
+}
+
+fn ratio(num: usize, den: usize) -> f32 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f32 / den as f32
+    }
+}
+
+fn f1(precision: f32, recall: f32) -> f32 {
+    if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scored(class_name: &str, confidence: f32, hit: bool) -> ScoredDetection {
+        ScoredDetection {
+            class_name: class_name.to_string(),
+            confidence,
+            true_positive: hit,
+            true_positive_50: hit,
+        }
+    }
+
+    #[test]
+    fn metrics_per_class_and_overall() {
+        let mut image = ImageResult::new("a.jpg".to_string(), &[], 4);
+        image.gt_classes = vec!["car".to_string(), "car".to_string(), "dog".to_string()];
+        image.detections = Some(vec![
+            scored("car", 0.9, true),
+            scored("car", 0.8, false),
+            scored("car", 0.7, true),
+            scored("cat", 0.6, false),
+        ]);
+
+        let metrics = DetectionMetrics::from_results([&image]).unwrap();
+        let car = &metrics.per_class["car"];
+        assert_eq!(
+            (car.true_positives, car.false_positives, car.false_negatives),
+            (2, 1, 0)
+        );
+        assert!((car.precision - 2.0 / 3.0).abs() < 1e-6);
+        assert_eq!(car.recall, 1.0);
+        // Recall 0.5 at precision 1, then 1.0 at precision 2/3
+        assert!((car.ap50.unwrap() - (0.5 + 0.5 * 2.0 / 3.0)).abs() < 1e-6);
+
+        assert_eq!(metrics.per_class["dog"].ap50, Some(0.0));
+        let cat = &metrics.per_class["cat"];
+        assert_eq!(cat.ap50, None);
+        assert_eq!(cat.recall, 0.0);
+
+        // Classes without GT stay out of the mean
+        let expected = (car.ap50.unwrap() + 0.0) / 2.0;
+        assert!((metrics.map50.unwrap() - expected).abs() < 1e-6);
+        assert!(!serde_json::to_string(&metrics).unwrap().contains("NaN"));
+
+        let unscored = ImageResult::new("b.jpg".to_string(), &[], 0);
+        assert!(DetectionMetrics::from_results([&unscored]).is_none());
+    }
+
+    #[test]
+    fn iou_histogram_buckets() {
+        let mut image = ImageResult::new("a.jpg".to_string(), &[], 3);
+        image.match_ious = vec![0.35, 0.92, 1.0, 0.98];
+        let histogram = IouHistogram::from_results([&image], 10).unwrap();
+        assert_eq!(histogram.total(), 4);
+        assert_eq!(histogram.buckets()[3], (0.3, 0.4, 1));
+        assert_eq!(histogram.buckets()[9], (0.9, 1.0, 3));
+        assert_eq!(
+            serde_json::to_string(&IouHistogram::new(2)).unwrap(),
+            "[[0.0,0.5,0],[0.5,1.0,0]]"
+        );
+
+        let unmatched = ImageResult::new("b.jpg".to_string(), &[], 0);
+        assert!(IouHistogram::from_results([&unmatched], 10).is_none());
+    }
+}
@@ -0,0 +1,183 @@
+//! What an ONNX model declares about itself
+//!
+//! Ultralytics exports record the training image size, task and class names
+//! as custom metadata; the graph's input and output shapes fill in what the
+//! metadata leaves out.
+
+use anyhow::Result;
+use ort::session::Session;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::class_map;
+use crate::detector::{Device, ModelFormat};
+
+/// Input resolution assumed when neither the metadata nor the input shape gives one
+pub const DEFAULT_INPUT_SIZE: u32 = 640;
+
+/// Most rows an end-to-end output is expected to have; YOLOv5 outputs for one
+/// class have six columns too, but thousands of rows
+const END_TO_END_MAX_BOXES: i64 = 1000;
+
+/// Metadata and tensor shapes of an ONNX model
+#[derive(Debug, Clone, Default)]
+pub struct ModelInfo {
+    /// Input names and shapes; dynamic axes are negative
+    pub inputs: Vec<(String, Vec<i64>)>,
+    /// Output names and shapes; dynamic axes are negative
+    pub outputs: Vec<(String, Vec<i64>)>,
+    pub producer: Option<String>,
+    /// Custom metadata entries such as `names`, `imgsz` and `task`
+    pub custom: BTreeMap<String, String>,
+}
+
+impl ModelInfo {
+    /// Read the metadata and shapes of a loaded session
+    pub fn read(session: &Session) -> Self {
+        let shapes = |outlets: &[ort::value::Outlet]| {
+            outlets
+                .iter()
+                .map(|outlet| {
+                    let shape = outlet
+                        .dtype()
+                        .tensor_shape()
+                        .map(|shape| shape.to_vec())
+                        .unwrap_or_default();
+                    (outlet.name().to_string(), shape)
+                })
+                .collect()
+        };
+
+        let mut info = Self {
+            inputs: shapes(session.inputs()),
+            outputs: shapes(session.outputs()),
+            ..Default::default()
+        };
+        if let Ok(metadata) = session.metadata() {
+            info.producer = metadata.producer().filter(|p| !p.is_empty());
+            for key in metadata.custom_keys().unwrap_or_default() {
+                if let Some(value) = metadata.custom(&key) {
+                    info.custom.insert(key, value);
+                }
+            }
+        }
+        info
+    }
+
+    /// Load a model on the CPU just to read its metadata
+    pub fn load(model_path: &Path) -> Result<Self> {
+        let (session, _, _) = crate::detector::load_session(model_path, Device::Cpu)?;
+        Ok(Self::read(&session))
+    }
+
+    /// Input `(width, height)` the model expects
+    ///
+    /// Taken from a fixed input shape, else from the `imgsz` metadata of
+    /// models exported with dynamic axes, else `DEFAULT_INPUT_SIZE` square.
+    pub fn input_size(&self) -> (u32, u32) {
+        self.input_shape_size()
+            .or_else(|| self.custom.get("imgsz").and_then(|v| parse_imgsz(v)))
+            .unwrap_or((DEFAULT_INPUT_SIZE, DEFAULT_INPUT_SIZE))
+    }
+
+    /// `(width, height)` of an NCHW first input whose spatial axes are fixed
+    fn input_shape_size(&self) -> Option<(u32, u32)> {
+        match self.inputs.first()?.1.as_slice() {
+            [_, _, h, w] if *h > 0 && *w > 0 => Some((*w as u32, *h as u32)),
+            _ => None,
+        }
+    }
+
+    /// Class names from the `names` metadata, if present
+    pub fn class_names(&self) -> Option<Result<Vec<String>>> {
+        self.custom
+            .get("names")
+            .map(|names| class_map::parse_metadata_names(names))
+    }
+
+    /// Number of classes the first output scores when read as `format`
+    pub fn output_classes(&self, format: ModelFormat) -> Option<usize> {
+        let channels = match (format, self.outputs.first()?.1.as_slice()) {
+            (ModelFormat::YoloV8, [_, channels, _]) => *channels,
+            (ModelFormat::YoloV5, [_, _, channels]) => *channels,
+            _ => return None,
+        };
+        let offset = format.class_offset()? as i64;
+        (channels > offset).then(|| (channels - offset) as usize)
+    }
+
+    /// Output layout suggested by the first output's shape
+    ///
+    /// Six columns over at most `END_TO_END_MAX_BOXES` rows are taken for
+    /// post-NMS rows. Otherwise exports score far more boxes than classes, so
+    /// the longer axis is taken to be the boxes.
+    pub fn likely_format(&self) -> Option<ModelFormat> {
+        match self.outputs.first()?.1.as_slice() {
+            [_, boxes, 6] if (1..=END_TO_END_MAX_BOXES).contains(boxes) => {
+                Some(ModelFormat::EndToEnd)
+            }
+            [_, a, b] if *a > 0 && *b > 0 && a < b => Some(ModelFormat::YoloV8),
+            [_, a, b] if *a > 0 && *b > 0 && a > b => Some(ModelFormat::YoloV5),
+            _ => None,
+        }
+    }
+}
+
+/// `(width, height)` from an `imgsz` value such as `[384, 640]` (height first) or `640`
+fn parse_imgsz(value: &str) -> Option<(u32, u32)> {
+    let sizes: Vec<u32> = serde_yaml::from_str(value)
+        .or_else(|_| serde_yaml::from_str(value).map(|size| vec![size]))
+        .ok()?;
+    match sizes.as_slice() {
+        [size] if *size > 0 => Some((*size, *size)),
+        [h, w] if *h > 0 && *w > 0 => Some((*w, *h)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(input: Vec<i64>, imgsz: Option<&str>) -> ModelInfo {
+        ModelInfo {
+            inputs: vec![("images".to_string(), input)],
+            outputs: vec![("output0".to_string(), vec![1, 84, 8400])],
+            custom: imgsz
+                .map(|v| BTreeMap::from([("imgsz".to_string(), v.to_string())]))
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn input_size_prefers_fixed_shape_then_metadata() {
+        assert_eq!(
+            info(vec![1, 3, 384, 640], Some("[640, 640]")).input_size(),
+            (640, 384)
+        );
+        assert_eq!(
+            info(vec![-1, 3, -1, -1], Some("[384, 640]")).input_size(),
+            (640, 384)
+        );
+        assert_eq!(
+            info(vec![-1, 3, -1, -1], Some("320")).input_size(),
+            (320, 320)
+        );
+        assert_eq!(
+            info(vec![-1, 3, -1, -1], Some("bogus")).input_size(),
+            (640, 640)
+        );
+        assert_eq!(info(vec![], None).input_size(), (640, 640));
+
+        let v8 = info(vec![], None);
+        assert_eq!(v8.likely_format(), Some(ModelFormat::YoloV8));
+        assert_eq!(v8.output_classes(ModelFormat::YoloV8), Some(80));
+        let v5 = ModelInfo {
+            outputs: vec![("output0".to_string(), vec![1, 25200, 85])],
+            ..Default::default()
+        };
+        assert_eq!(v5.likely_format(), Some(ModelFormat::YoloV5));
+        assert_eq!(v5.output_classes(ModelFormat::YoloV5), Some(80));
+    }
+}
@@ -1,7 +1,14 @@
 //! Data models for gt-audit
 
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use anyhow::Context;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::box_stats::BoxStats;
+use crate::heatmap::{SpatialHeatmap, DEFAULT_GRID};
+use crate::metrics::{DetectionMetrics, IouHistogram, ScoredDetection, DEFAULT_IOU_BUCKETS};
+use crate::priority::{PriorityScoring, PriorityWeights};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -11,6 +18,30 @@ pub enum IssueSeverity {
     Low,
 }
 
+impl std::str::FromStr for IssueSeverity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "high" => Ok(Self::High),
+            "medium" => Ok(Self::Medium),
+            "low" => Ok(Self::Low),
+            other => anyhow::bail!(
+                "Unknown severity '{}' (expected high, medium or low)",
+                other
+            ),
+        }
+    }
+}
+
+/// Kind of label problem
+///
+/// Up to 0.2, GT boxes with coordinates outside the image were reported as
+/// `Localization`. They are now `OutOfBounds`; `Localization` is reserved for
+/// a same-class detection whose box disagrees with the GT box (IoU below the
+/// matching threshold), or a GT box whose size or shape is far from the rest
+/// of its class. Filters or dashboards keyed on `localization` for
+/// coordinate errors should switch to `out_of_bounds`.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum IssueType {
@@ -18,6 +49,33 @@ pub enum IssueType {
     MissingLabel,
     SpuriousLabel,
     Localization,
+    OutOfBounds,
+    /// Image too blurry, dark or overexposed for its labels to be learnable
+    ImageQuality,
+    /// Label row that is neither a valid box nor a valid polygon
+    ParseError,
+    /// Class id that the dataset does not declare
+    InvalidClassId,
+    /// Same-class GT box almost identical to another one (IoU above 0.85)
+    DuplicateAnnotation,
+    /// Same-class GT boxes overlapping heavily (IoU 0.5 to 0.85), e.g. crowds or occlusion
+    AnnotationOverlap,
+    /// Image that is a near-copy of another audited image
+    DuplicateImage,
+    /// Image that could not be decoded or audited
+    ImageError,
+    /// Label file without an image, or image without a label file or with an empty one
+    DatasetConsistency,
+    /// GT box whose stored pre-annotation confidence is below `--min-gt-confidence`
+    SuspectPreAnnotation,
+    /// Label file whose boxes are in pixels or corner coordinates rather than
+    /// normalized center and size
+    CoordinateFormat,
+    /// GT box only a few pixels in size, or covering nearly the whole image
+    BoxSize,
+    /// Image with an EXIF orientation tag, whose boxes may be rotated
+    /// relative to the pixels the detector sees
+    ExifOrientation,
 }
 
 impl std::fmt::Display for IssueType {
@@ -27,11 +85,200 @@ impl std::fmt::Display for IssueType {
             IssueType::MissingLabel => write!(f, "missing_label"),
             IssueType::SpuriousLabel => write!(f, "spurious_label"),
             IssueType::Localization => write!(f, "localization"),
+            IssueType::OutOfBounds => write!(f, "out_of_bounds"),
+            IssueType::ImageQuality => write!(f, "image_quality"),
+            IssueType::ParseError => write!(f, "parse_error"),
+            IssueType::InvalidClassId => write!(f, "invalid_class_id"),
+            IssueType::DuplicateAnnotation => write!(f, "duplicate_annotation"),
+            IssueType::AnnotationOverlap => write!(f, "annotation_overlap"),
+            IssueType::DuplicateImage => write!(f, "duplicate_image"),
+            IssueType::ImageError => write!(f, "image_error"),
+            IssueType::DatasetConsistency => write!(f, "dataset_consistency"),
+            IssueType::SuspectPreAnnotation => write!(f, "suspect_pre_annotation"),
+            IssueType::CoordinateFormat => write!(f, "coordinate_format"),
+            IssueType::BoxSize => write!(f, "box_size"),
+            IssueType::ExifOrientation => write!(f, "exif_orientation"),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl IssueType {
+    /// Every issue type, in declaration order
+    pub const ALL: [IssueType; 17] = [
+        IssueType::ClassMismatch,
+        IssueType::MissingLabel,
+        IssueType::SpuriousLabel,
+        IssueType::Localization,
+        IssueType::OutOfBounds,
+        IssueType::ImageQuality,
+        IssueType::ParseError,
+        IssueType::InvalidClassId,
+        IssueType::DuplicateAnnotation,
+        IssueType::AnnotationOverlap,
+        IssueType::DuplicateImage,
+        IssueType::ImageError,
+        IssueType::DatasetConsistency,
+        IssueType::SuspectPreAnnotation,
+        IssueType::CoordinateFormat,
+        IssueType::BoxSize,
+        IssueType::ExifOrientation,
+    ];
+
+    /// Human-readable name for reports, e.g. "Class Mismatch"
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            IssueType::ClassMismatch => "Class Mismatch",
+            IssueType::MissingLabel => "Missing Label",
+            IssueType::SpuriousLabel => "Spurious Label",
+            IssueType::Localization => "Localization",
+            IssueType::OutOfBounds => "Out of Bounds",
+            IssueType::ImageQuality => "Image Quality",
+            IssueType::ParseError => "Parse Error",
+            IssueType::InvalidClassId => "Invalid Class Id",
+            IssueType::DuplicateAnnotation => "Duplicate Annotation",
+            IssueType::AnnotationOverlap => "Annotation Overlap",
+            IssueType::DuplicateImage => "Duplicate Image",
+            IssueType::ImageError => "Image Error",
+            IssueType::DatasetConsistency => "Dataset Consistency",
+            IssueType::SuspectPreAnnotation => "Suspect Pre-Annotation",
+            IssueType::CoordinateFormat => "Coordinate Format",
+            IssueType::BoxSize => "Box Size",
+            IssueType::ExifOrientation => "EXIF Orientation",
+        }
+    }
+
+    /// Two-letter code for compact table columns
+    pub fn short_code(&self) -> &'static str {
+        match self {
+            IssueType::ClassMismatch => "CM",
+            IssueType::MissingLabel => "ML",
+            IssueType::SpuriousLabel => "SL",
+            IssueType::Localization => "LO",
+            IssueType::OutOfBounds => "OB",
+            IssueType::ImageQuality => "IQ",
+            IssueType::ParseError => "PE",
+            IssueType::InvalidClassId => "IC",
+            IssueType::DuplicateAnnotation => "DA",
+            IssueType::AnnotationOverlap => "AO",
+            IssueType::DuplicateImage => "DI",
+            IssueType::ImageError => "IE",
+            IssueType::DatasetConsistency => "DC",
+            IssueType::SuspectPreAnnotation => "SP",
+            IssueType::CoordinateFormat => "CF",
+            IssueType::BoxSize => "BS",
+            IssueType::ExifOrientation => "EO",
+        }
+    }
+
+    /// What the issue means and how to fix it, in one sentence
+    pub fn help_text(&self) -> &'static str {
+        match self {
+            IssueType::ClassMismatch => {
+                "The model confidently detects a different class where the GT box is; check whether the label's class is wrong."
+            }
+            IssueType::MissingLabel => {
+                "The model detects an object that has no GT box; add the missing label or confirm it is a false positive."
+            }
+            IssueType::SpuriousLabel => {
+                "A GT box has no matching detection; remove it if nothing is there, or accept it as a hard example."
+            }
+            IssueType::Localization => {
+                "A GT box overlaps a same-class detection too little, or is far larger, smaller or differently shaped than its class usually is; tighten the box around the object."
+            }
+            IssueType::OutOfBounds => {
+                "A GT box extends outside the image or has no area; fix its coordinates or delete the row."
+            }
+            IssueType::ImageQuality => {
+                "The image is too blurry, dark or overexposed, or the model's scores on it are degenerate; consider dropping it from the dataset."
+            }
+            IssueType::ParseError => {
+                "A label row is neither a valid box nor a valid polygon; rewrite it as `class x y w h` in normalized coordinates."
+            }
+            IssueType::InvalidClassId => {
+                "A label uses a class id that data.yaml does not declare; fix the id or add the class to data.yaml."
+            }
+            IssueType::DuplicateAnnotation => {
+                "Two GT boxes of the same class are near-identical; delete one of them."
+            }
+            IssueType::AnnotationOverlap => {
+                "Two GT boxes of the same class overlap heavily; check they are separate objects, as in crowds or occlusion."
+            }
+            IssueType::DuplicateImage => {
+                "The image is a near-copy of another one; keep a single copy, and never one in each split."
+            }
+            IssueType::ImageError => {
+                "The image could not be decoded or audited; re-export or remove the file."
+            }
+            IssueType::DatasetConsistency => {
+                "A label file has no image, or an image has no or an empty label file; pair them up or confirm the image is a background."
+            }
+            IssueType::SuspectPreAnnotation => {
+                "The GT box was pre-annotated with a low confidence; have someone review it."
+            }
+            IssueType::CoordinateFormat => {
+                "The label file's boxes are in pixels or x1 y1 x2 y2 corners; convert them to normalized x_center y_center width height."
+            }
+            IssueType::BoxSize => {
+                "A GT box is only a few pixels across or covers nearly the whole image; usually a slip of the annotation tool, so fix or delete it."
+            }
+            IssueType::ExifOrientation => {
+                "The image file is stored rotated with an EXIF orientation tag; check the boxes line up with the rotation your training pipeline uses, and audit with the same --apply-exif-rotation choice."
+            }
+        }
+    }
+
+    /// Severity used when no severity policy overrides it
+    pub fn default_severity(&self) -> IssueSeverity {
+        match self {
+            IssueType::ClassMismatch
+            | IssueType::OutOfBounds
+            | IssueType::ParseError
+            | IssueType::InvalidClassId
+            | IssueType::ImageError
+            | IssueType::CoordinateFormat => IssueSeverity::High,
+            IssueType::MissingLabel
+            | IssueType::DuplicateAnnotation
+            | IssueType::DuplicateImage
+            | IssueType::BoxSize
+            | IssueType::ExifOrientation => IssueSeverity::Medium,
+            IssueType::SpuriousLabel
+            | IssueType::Localization
+            | IssueType::ImageQuality
+            | IssueType::AnnotationOverlap
+            | IssueType::DatasetConsistency
+            | IssueType::SuspectPreAnnotation => IssueSeverity::Low,
+        }
+    }
+}
+
+impl std::str::FromStr for IssueType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "class_mismatch" => Ok(Self::ClassMismatch),
+            "missing_label" => Ok(Self::MissingLabel),
+            "spurious_label" => Ok(Self::SpuriousLabel),
+            "localization" => Ok(Self::Localization),
+            "out_of_bounds" => Ok(Self::OutOfBounds),
+            "image_quality" => Ok(Self::ImageQuality),
+            "parse_error" => Ok(Self::ParseError),
+            "invalid_class_id" => Ok(Self::InvalidClassId),
+            "duplicate_annotation" => Ok(Self::DuplicateAnnotation),
+            "annotation_overlap" => Ok(Self::AnnotationOverlap),
+            "duplicate_image" => Ok(Self::DuplicateImage),
+            "image_error" => Ok(Self::ImageError),
+            "dataset_consistency" => Ok(Self::DatasetConsistency),
+            "suspect_pre_annotation" => Ok(Self::SuspectPreAnnotation),
+            "coordinate_format" => Ok(Self::CoordinateFormat),
+            "box_size" => Ok(Self::BoxSize),
+            "exif_orientation" => Ok(Self::ExifOrientation),
+            other => anyhow::bail!("Unknown issue type '{}'", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BoundingBox {
     pub x: f32,
     pub y: f32,
@@ -44,6 +291,19 @@ impl BoundingBox {
         Self { x, y, w, h }
     }
 
+    /// Axis-aligned box enclosing a set of (x, y) points
+    pub fn from_points(points: &[(f32, f32)]) -> Self {
+        let (mut x1, mut y1) = (f32::INFINITY, f32::INFINITY);
+        let (mut x2, mut y2) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for &(x, y) in points {
+            x1 = x1.min(x);
+            y1 = y1.min(y);
+            x2 = x2.max(x);
+            y2 = y2.max(y);
+        }
+        Self::new((x1 + x2) / 2.0, (y1 + y2) / 2.0, x2 - x1, y2 - y1)
+    }
+
     /// Convert to x1, y1, x2, y2 format (normalized)
     pub fn to_xyxy(&self) -> (f32, f32, f32, f32) {
         let x1 = self.x - self.w / 2.0;
@@ -53,6 +313,44 @@ impl BoundingBox {
         (x1, y1, x2, y2)
     }
 
+    /// Normalized area (0..1 for boxes inside the image)
+    pub fn area(&self) -> f32 {
+        self.w * self.h
+    }
+
+    /// Width over height; infinite for zero-height boxes
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.h == 0.0 {
+            f32::INFINITY
+        } else {
+            self.w / self.h
+        }
+    }
+
+    /// Absolute pixel corners (x1, y1, x2, y2), rounded and clamped to the image
+    pub fn to_pixel_coords(&self, img_w: u32, img_h: u32) -> (u32, u32, u32, u32) {
+        let (x1, y1, x2, y2) = self.to_xyxy();
+        let px = |v: f32, size: u32| (v * size as f32).round().clamp(0.0, size as f32) as u32;
+        (px(x1, img_w), px(y1, img_h), px(x2, img_w), px(y2, img_h))
+    }
+
+    /// Box from absolute pixel corners; the inverse of `to_pixel_coords`
+    pub fn from_pixel_coords(x1: u32, y1: u32, x2: u32, y2: u32, img_w: u32, img_h: u32) -> Self {
+        let (w, h) = (img_w.max(1) as f32, img_h.max(1) as f32);
+        let (x1, y1, x2, y2) = (x1 as f32, y1 as f32, x2 as f32, y2 as f32);
+        Self::new(
+            (x1 + x2) / 2.0 / w,
+            (y1 + y2) / 2.0 / h,
+            (x2 - x1) / w,
+            (y2 - y1) / h,
+        )
+    }
+
+    /// Area in pixels for an image of the given size
+    pub fn pixel_area(&self, img_w: u32, img_h: u32) -> f32 {
+        self.area() * img_w as f32 * img_h as f32
+    }
+
     /// Calculate IoU with another bounding box
     pub fn iou(&self, other: &BoundingBox) -> f32 {
         let (ax1, ay1, ax2, ay2) = self.to_xyxy();
@@ -64,9 +362,7 @@ impl BoundingBox {
         let y2 = ay2.min(by2);
 
         let inter = (x2 - x1).max(0.0) * (y2 - y1).max(0.0);
-        let area_a = (ax2 - ax1) * (ay2 - ay1);
-        let area_b = (bx2 - bx1) * (by2 - by1);
-        let union = area_a + area_b - inter;
+        let union = self.area() + other.area() - inter;
 
         if union > 0.0 {
             inter / union
@@ -82,6 +378,95 @@ pub struct Annotation {
     pub class_name: String,
     pub bbox: BoundingBox,
     pub line_num: usize,
+    /// Normalized polygon points, for segmentation labels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub polygon: Option<Vec<(f32, f32)>>,
+    /// Confidence from a sixth label column, written by tools that store
+    /// model predictions as pre-annotations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt_confidence: Option<f32>,
+}
+
+impl Annotation {
+    /// The annotation as a YOLO label row, without a trailing newline
+    ///
+    /// Boxes are `class x y w h` with six decimals, followed by the stored
+    /// confidence if there is one; segmentation annotations are written as
+    /// their polygon.
+    pub fn to_yolo_line(&self) -> String {
+        match &self.polygon {
+            Some(points) => {
+                let mut line = self.class_id.to_string();
+                for (x, y) in points {
+                    line.push_str(&format!(" {:.6} {:.6}", x, y));
+                }
+                line
+            }
+            None => {
+                let mut line = format!(
+                    "{} {:.6} {:.6} {:.6} {:.6}",
+                    self.class_id, self.bbox.x, self.bbox.y, self.bbox.w, self.bbox.h
+                );
+                if let Some(confidence) = self.gt_confidence {
+                    line.push_str(&format!(" {}", confidence));
+                }
+                line
+            }
+        }
+    }
+
+    /// Start an annotation of class 0 with an empty box on line 1
+    pub fn builder() -> AnnotationBuilder {
+        AnnotationBuilder {
+            annotation: Annotation {
+                class_id: 0,
+                class_name: String::new(),
+                bbox: BoundingBox::new(0.0, 0.0, 0.0, 0.0),
+                line_num: 1,
+                polygon: None,
+                gt_confidence: None,
+            },
+        }
+    }
+}
+
+/// Sets annotation fields one at a time, so tests only spell out what matters
+#[derive(Debug, Clone)]
+pub struct AnnotationBuilder {
+    annotation: Annotation,
+}
+
+impl AnnotationBuilder {
+    pub fn class(mut self, class_id: i32, class_name: impl Into<String>) -> Self {
+        self.annotation.class_id = class_id;
+        self.annotation.class_name = class_name.into();
+        self
+    }
+
+    /// Normalized center and size
+    pub fn bbox(mut self, x: f32, y: f32, w: f32, h: f32) -> Self {
+        self.annotation.bbox = BoundingBox::new(x, y, w, h);
+        self
+    }
+
+    pub fn line_num(mut self, line_num: usize) -> Self {
+        self.annotation.line_num = line_num;
+        self
+    }
+
+    pub fn polygon(mut self, points: Vec<(f32, f32)>) -> Self {
+        self.annotation.polygon = Some(points);
+        self
+    }
+
+    pub fn gt_confidence(mut self, confidence: f32) -> Self {
+        self.annotation.gt_confidence = Some(confidence);
+        self
+    }
+
+    pub fn build(self) -> Annotation {
+        self.annotation
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,6 +476,17 @@ pub struct Detection {
     pub bbox: BoundingBox,
 }
 
+impl Detection {
+    /// Detection agreeing exactly with `ann`, with full confidence
+    pub fn from_annotation(ann: &Annotation) -> Self {
+        Self {
+            class_name: ann.class_name.clone(),
+            confidence: 1.0,
+            bbox: ann.bbox.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Issue {
     pub image: String,
@@ -110,6 +506,132 @@ pub struct Issue {
     pub explanation: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub line_num: Option<usize>,
+    /// Second label line involved, for issues about a pair of annotations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other_line_num: Option<usize>,
+    /// Known issue recorded in a baseline; does not count toward failure thresholds
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub baselined: bool,
+    /// Accepted by a suppression rule; does not count toward failure thresholds
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suppressed: bool,
+    /// Label edit that would resolve the issue, when the evidence is strong
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggested_fix: Option<SuggestedFix>,
+}
+
+/// Machine-readable label edit proposed for an issue, applied by
+/// `gt-audit export-fixes` after review
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SuggestedFix {
+    /// Give the box on the issue's line another class
+    ChangeClass { to: String },
+    /// Delete the issue's line
+    DeleteLine,
+    /// Add a box the labels lack
+    AddBox { bbox: BoundingBox, class: String },
+}
+
+impl Issue {
+    /// Whether the issue counts toward failure thresholds: neither baselined nor suppressed
+    pub fn is_new(&self) -> bool {
+        !self.baselined && !self.suppressed
+    }
+
+    /// Start an issue with an empty description and no classes, scores or lines
+    pub fn builder(
+        image: impl Into<String>,
+        severity: IssueSeverity,
+        issue_type: IssueType,
+    ) -> IssueBuilder {
+        IssueBuilder {
+            issue: Issue {
+                image: image.into(),
+                severity,
+                issue_type,
+                description: String::new(),
+                gt_class: None,
+                detected_class: None,
+                confidence: None,
+                iou: None,
+                explanation: None,
+                line_num: None,
+                other_line_num: None,
+                baselined: false,
+                suppressed: false,
+                suggested_fix: None,
+            },
+        }
+    }
+}
+
+/// Sets issue fields one at a time, so each check only spells out what it knows
+#[derive(Debug, Clone)]
+pub struct IssueBuilder {
+    issue: Issue,
+}
+
+impl IssueBuilder {
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.issue.description = description.into();
+        self
+    }
+
+    pub fn gt_class(mut self, class: impl Into<String>) -> Self {
+        self.issue.gt_class = Some(class.into());
+        self
+    }
+
+    pub fn detected_class(mut self, class: impl Into<String>) -> Self {
+        self.issue.detected_class = Some(class.into());
+        self
+    }
+
+    pub fn confidence(mut self, confidence: f32) -> Self {
+        self.issue.confidence = Some(confidence);
+        self
+    }
+
+    pub fn iou(mut self, iou: f32) -> Self {
+        self.issue.iou = Some(iou);
+        self
+    }
+
+    pub fn line_num(mut self, line_num: usize) -> Self {
+        self.issue.line_num = Some(line_num);
+        self
+    }
+
+    pub fn other_line_num(mut self, line_num: usize) -> Self {
+        self.issue.other_line_num = Some(line_num);
+        self
+    }
+
+    pub fn explanation(mut self, explanation: impl Into<String>) -> Self {
+        self.issue.explanation = Some(explanation.into());
+        self
+    }
+
+    /// Accepts an `Option` too, for checks that only sometimes know a fix
+    pub fn suggested_fix(mut self, fix: impl Into<Option<SuggestedFix>>) -> Self {
+        self.issue.suggested_fix = fix.into();
+        self
+    }
+
+    pub fn suppressed(mut self) -> Self {
+        self.issue.suppressed = true;
+        self
+    }
+
+    pub fn baselined(mut self) -> Self {
+        self.issue.baselined = true;
+        self
+    }
+
+    pub fn build(self) -> Issue {
+        self.issue
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,15 +640,43 @@ pub struct ImageResult {
     pub gt_count: usize,
     pub detection_count: usize,
     pub issues: Vec<Issue>,
+    /// Dataset split the image belongs to, when auditing several splits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split: Option<String>,
+    /// Label file the issues' line numbers refer to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_file: Option<String>,
+    /// How urgently the image should be relabeled; see `crate::priority`
+    #[serde(default)]
+    pub relabel_priority: f32,
+    /// Class name of every GT annotation in the image
+    #[serde(skip)]
+    pub gt_classes: Vec<String>,
+    /// Normalized center of every GT box in the image
+    #[serde(skip)]
+    pub gt_centers: Vec<(f32, f32)>,
+    /// Model detections matched against the GT, for detectors that produce boxes
+    #[serde(skip)]
+    pub detections: Option<Vec<ScoredDetection>>,
+    /// IoU of each detection matched to a GT box
+    #[serde(skip)]
+    pub match_ious: Vec<f32>,
 }
 
 impl ImageResult {
-    pub fn new(filename: String, gt_count: usize, detection_count: usize) -> Self {
+    pub fn new(filename: String, annotations: &[Annotation], detection_count: usize) -> Self {
         Self {
             filename,
-            gt_count,
+            gt_count: annotations.len(),
             detection_count,
             issues: Vec::new(),
+            split: None,
+            label_file: None,
+            relabel_priority: 0.0,
+            gt_classes: annotations.iter().map(|a| a.class_name.clone()).collect(),
+            gt_centers: annotations.iter().map(|a| (a.bbox.x, a.bbox.y)).collect(),
+            detections: None,
+            match_ious: Vec::new(),
         }
     }
 
@@ -167,7 +717,383 @@ pub struct AuditSummary {
     pub images_with_issues: usize,
     pub total_issues: usize,
     pub by_severity: HashMap<String, usize>,
+    /// Like `by_severity`, but only issues neither baselined nor suppressed
+    #[serde(default)]
+    pub new_by_severity: HashMap<String, usize>,
+    /// Issues matched by a baseline
+    #[serde(default)]
+    pub baselined_issues: usize,
+    /// Issues accepted by a suppression rule
+    #[serde(default)]
+    pub suppressed_issues: usize,
+    /// Images that could not be decoded or audited (`image_error` issues)
+    #[serde(default)]
+    pub failed_images: usize,
     pub by_type: HashMap<String, usize>,
+    pub per_class: BTreeMap<String, ClassSummary>,
+    pub confusion_matrix: Vec<ConfusionEntry>,
+}
+
+impl AuditSummary {
+    /// Summary with no results yet
+    pub fn empty(total_images: usize, images_audited: usize) -> Self {
+        Self {
+            total_images,
+            images_audited,
+            images_with_issues: 0,
+            total_issues: 0,
+            by_severity: HashMap::new(),
+            new_by_severity: HashMap::new(),
+            baselined_issues: 0,
+            suppressed_issues: 0,
+            failed_images: 0,
+            by_type: HashMap::new(),
+            per_class: BTreeMap::new(),
+            confusion_matrix: Vec::new(),
+        }
+    }
+
+    /// Aggregate issue counts over a set of image results
+    pub fn from_results<'a>(
+        results: impl IntoIterator<Item = &'a ImageResult>,
+        total_images: usize,
+        images_audited: usize,
+    ) -> Self {
+        let mut summary = Self::empty(total_images, images_audited);
+        for result in results {
+            summary.add(result);
+        }
+        summary.sort_confusion_matrix();
+        summary
+    }
+
+    /// Count one more issue by severity and type
+    pub fn add_issue(&mut self, issue: &Issue) {
+        self.total_issues += 1;
+        let sev = match issue.severity {
+            IssueSeverity::High => "high",
+            IssueSeverity::Medium => "medium",
+            IssueSeverity::Low => "low",
+        };
+        *self.by_severity.entry(sev.to_string()).or_insert(0) += 1;
+        if issue.suppressed {
+            self.suppressed_issues += 1;
+        } else if issue.baselined {
+            self.baselined_issues += 1;
+        } else {
+            *self.new_by_severity.entry(sev.to_string()).or_insert(0) += 1;
+        }
+        *self
+            .by_type
+            .entry(issue.issue_type.to_string())
+            .or_insert(0) += 1;
+    }
+
+    /// Count one more image result
+    ///
+    /// New confusion pairs are appended unsorted; call
+    /// `sort_confusion_matrix` once all results are in.
+    pub fn add(&mut self, result: &ImageResult) {
+        if result.has_issues() {
+            self.images_with_issues += 1;
+        }
+        if result
+            .issues
+            .iter()
+            .any(|issue| issue.issue_type == IssueType::ImageError)
+        {
+            self.failed_images += 1;
+        }
+        for issue in &result.issues {
+            self.add_issue(issue);
+        }
+
+        // Per-class breakdown and confusion matrix
+        let per_class = &mut self.per_class;
+        let mut seen: Vec<&String> = Vec::new();
+        for class_name in &result.gt_classes {
+            let entry = per_class.entry(class_name.clone()).or_default();
+            entry.annotations += 1;
+            if !seen.contains(&class_name) {
+                seen.push(class_name);
+                entry.images += 1;
+            }
+        }
+
+        for issue in &result.issues {
+            // Attribute every issue to the GT class, or the detected one for missing labels
+            let class_name = issue.gt_class.as_ref().or(issue.detected_class.as_ref());
+            if let Some(class_name) = class_name {
+                let entry = per_class.entry(class_name.clone()).or_default();
+                entry.issues += 1;
+                match issue.severity {
+                    IssueSeverity::High => entry.high_issues += 1,
+                    IssueSeverity::Medium => entry.medium_issues += 1,
+                    IssueSeverity::Low => {}
+                }
+            }
+
+            match issue.issue_type {
+                IssueType::ClassMismatch => {
+                    if let (Some(gt), Some(det)) = (&issue.gt_class, &issue.detected_class) {
+                        per_class.entry(gt.clone()).or_default().class_mismatches += 1;
+                        match self
+                            .confusion_matrix
+                            .iter_mut()
+                            .find(|e| &e.gt_class == gt && &e.detected_class == det)
+                        {
+                            Some(entry) => entry.count += 1,
+                            None => self.confusion_matrix.push(ConfusionEntry {
+                                gt_class: gt.clone(),
+                                detected_class: det.clone(),
+                                count: 1,
+                            }),
+                        }
+                    }
+                }
+                IssueType::MissingLabel => {
+                    if let Some(det) = &issue.detected_class {
+                        per_class.entry(det.clone()).or_default().missing_labels += 1;
+                    }
+                }
+                IssueType::SpuriousLabel => {
+                    if let Some(gt) = &issue.gt_class {
+                        per_class.entry(gt.clone()).or_default().spurious_labels += 1;
+                    }
+                }
+                IssueType::Localization
+                | IssueType::OutOfBounds
+                | IssueType::ImageQuality
+                | IssueType::ParseError
+                | IssueType::InvalidClassId
+                | IssueType::DuplicateAnnotation
+                | IssueType::AnnotationOverlap
+                | IssueType::DuplicateImage
+                | IssueType::ImageError
+                | IssueType::DatasetConsistency
+                | IssueType::SuspectPreAnnotation
+                | IssueType::CoordinateFormat
+                | IssueType::BoxSize
+                | IssueType::ExifOrientation => {}
+            }
+        }
+    }
+
+    /// Order the confusion matrix by count, most frequent pair first
+    pub fn sort_confusion_matrix(&mut self) {
+        self.confusion_matrix.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| a.gt_class.cmp(&b.gt_class))
+                .then_with(|| a.detected_class.cmp(&b.detected_class))
+        });
+    }
+}
+
+/// Issue breakdown for a single GT class
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClassSummary {
+    pub images: usize,
+    pub annotations: usize,
+    /// Issues of any type whose GT (or else detected) class is this class
+    #[serde(default)]
+    pub issues: usize,
+    #[serde(default)]
+    pub high_issues: usize,
+    #[serde(default)]
+    pub medium_issues: usize,
+    pub class_mismatches: usize,
+    pub missing_labels: usize,
+    pub spurious_labels: usize,
+}
+
+/// Issue counts of one class, as listed in `AuditResult::per_class`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PerClassStats {
+    pub class_name: String,
+    /// GT boxes of the class
+    pub annotation_count: usize,
+    /// Issues whose GT class, or else detected class, is this class
+    pub issue_count: usize,
+    pub high_count: usize,
+    pub medium_count: usize,
+    pub class_mismatch_count: usize,
+    pub missing_label_count: usize,
+}
+
+impl PerClassStats {
+    /// Stats of every class in a summary's breakdown, keyed by class name
+    pub fn from_summary(summary: &AuditSummary) -> HashMap<String, PerClassStats> {
+        summary
+            .per_class
+            .iter()
+            .map(|(class_name, class)| {
+                let stats = PerClassStats {
+                    class_name: class_name.clone(),
+                    annotation_count: class.annotations,
+                    issue_count: class.issues,
+                    high_count: class.high_issues,
+                    medium_count: class.medium_issues,
+                    class_mismatch_count: class.class_mismatches,
+                    missing_label_count: class.missing_labels,
+                };
+                (class_name.clone(), stats)
+            })
+            .collect()
+    }
+}
+
+/// Number of times a GT class was detected as another class
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfusionEntry {
+    pub gt_class: String,
+    pub detected_class: String,
+    pub count: usize,
+}
+
+/// Default share of a class's annotations that mismatches to one other
+/// class must exceed for the pair to be reported as a possible label swap
+pub const DEFAULT_LABEL_SWAP_MIN_SHARE: f32 = 0.3;
+
+/// Mismatches a class pair needs before it can count as systematic
+pub const LABEL_SWAP_MIN_COUNT: usize = 5;
+
+/// Class pair whose labels look swapped or renamed wholesale, as when an
+/// annotation tool's class list was reordered
+///
+/// Found in the confusion matrix: many of `gt_class`'s annotations were
+/// detected as `detected_class`. When the reverse holds too, the pair is
+/// reported once, with `reverse_count` set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelSwap {
+    pub gt_class: String,
+    pub detected_class: String,
+    /// `ClassMismatch` issues from `gt_class` to `detected_class`
+    pub count: usize,
+    /// Fraction of `gt_class` annotations those issues account for
+    pub share: f32,
+    /// Mismatches from `detected_class` back to `gt_class`, if also systematic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverse_count: Option<usize>,
+}
+
+impl LabelSwap {
+    /// Systematic pairs in a summary's sorted confusion matrix, most frequent
+    /// first; mismatches must make up more than `min_share` of their class
+    pub fn find(summary: &AuditSummary, min_share: f32) -> Vec<LabelSwap> {
+        let share_of = |entry: &ConfusionEntry| -> Option<f32> {
+            let annotations = summary.per_class.get(&entry.gt_class)?.annotations;
+            let share = entry.count as f32 / annotations.max(1) as f32;
+            (entry.count >= LABEL_SWAP_MIN_COUNT && share > min_share).then_some(share)
+        };
+        let reverse = |entry: &ConfusionEntry| {
+            summary
+                .confusion_matrix
+                .iter()
+                .find(|e| e.gt_class == entry.detected_class && e.detected_class == entry.gt_class)
+        };
+
+        let mut swaps: Vec<LabelSwap> = Vec::new();
+        for entry in &summary.confusion_matrix {
+            let Some(share) = share_of(entry) else {
+                continue;
+            };
+            // The larger direction of a mutual swap comes first and covers both
+            if swaps.iter().any(|s| {
+                s.reverse_count.is_some()
+                    && s.gt_class == entry.detected_class
+                    && s.detected_class == entry.gt_class
+            }) {
+                continue;
+            }
+            swaps.push(LabelSwap {
+                gt_class: entry.gt_class.clone(),
+                detected_class: entry.detected_class.clone(),
+                count: entry.count,
+                share,
+                reverse_count: reverse(entry)
+                    .filter(|e| share_of(e).is_some())
+                    .map(|e| e.count),
+            });
+        }
+        swaps
+    }
+
+    /// Mismatches in both directions
+    pub fn instances(&self) -> usize {
+        self.count + self.reverse_count.unwrap_or(0)
+    }
+}
+
+impl std::fmt::Display for LabelSwap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let arrow = if self.reverse_count.is_some() {
+            "↔"
+        } else {
+            "→"
+        };
+        write!(
+            f,
+            "possible systematic label swap: '{}' {} '{}' ({} instances)",
+            self.gt_class,
+            arrow,
+            self.detected_class,
+            self.instances()
+        )
+    }
+}
+
+/// Order flagged images are listed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Highest relabel priority first
+    ByPriority,
+    /// Most issues first
+    #[default]
+    ByTotalIssues,
+    /// Most High issues first, then most Medium issues, then most issues
+    ByHighSeverity,
+    /// Most Medium issues first, then most High issues, then most issues
+    ByMediumSeverity,
+    /// By file name
+    ByFilename,
+}
+
+impl SortOrder {
+    /// Sort `images` in this order; ties keep their current order
+    pub fn sort(self, images: &mut [ImageResult]) {
+        match self {
+            Self::ByPriority => {
+                images.sort_by(|a, b| b.relabel_priority.total_cmp(&a.relabel_priority))
+            }
+            Self::ByTotalIssues => images.sort_by_key(|i| std::cmp::Reverse(i.issues.len())),
+            Self::ByHighSeverity => images.sort_by_key(|i| {
+                std::cmp::Reverse((i.high_count(), i.medium_count(), i.issues.len()))
+            }),
+            Self::ByMediumSeverity => images.sort_by_key(|i| {
+                std::cmp::Reverse((i.medium_count(), i.high_count(), i.issues.len()))
+            }),
+            Self::ByFilename => images.sort_by(|a, b| a.filename.cmp(&b.filename)),
+        }
+    }
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "priority" | "by-priority" => Ok(Self::ByPriority),
+            "by-total-issues" => Ok(Self::ByTotalIssues),
+            "by-high-severity" => Ok(Self::ByHighSeverity),
+            "by-medium-severity" => Ok(Self::ByMediumSeverity),
+            "by-filename" => Ok(Self::ByFilename),
+            other => anyhow::bail!(
+                "Unknown sort order '{}' (expected by-total-issues, priority, by-high-severity, by-medium-severity or by-filename)",
+                other
+            ),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -179,12 +1105,158 @@ pub struct AuditResult {
     pub method: String,
     pub confidence_threshold: f32,
     pub iou_threshold: f32,
+    /// Classes left out of validation
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_classes: Vec<String>,
     pub total_images: usize,
     pub images_audited: usize,
-    #[serde(skip)]
-    pub image_results: Vec<ImageResult>,
+    /// Wall-clock duration of the audit in seconds
+    #[serde(default)]
+    pub elapsed_secs: f64,
+    /// Set when the audit was interrupted before every selected image was audited
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
+    /// Selected images left unaudited by an interruption
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub images_skipped: usize,
+    /// Images in the audited splits without a label file, filtered out or not
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub missing_label_files: usize,
+    /// Flagged images of the audit, in JSON reports limited by `JsonReporter::max_images`
+    #[serde(default, skip_serializing)]
+    pub flagged_images_total: Option<usize>,
+    /// Set in JSON reports that list only some of the flagged images
+    #[serde(default, skip_serializing)]
+    pub flagged_images_truncated: bool,
+    /// Options the run was started with, from the config file and command line
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub config: BTreeMap<String, serde_json::Value>,
     pub summary: AuditSummary,
-    pub flagged_images: Vec<ImageResult>,
+    /// Issue counts per class, keyed by GT class (the detected class for
+    /// issues without one)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub per_class: HashMap<String, PerClassStats>,
+    /// Count of (GT class, detected class) pairs in `ClassMismatch` issues
+    #[serde(
+        default,
+        serialize_with = "serialize_confusion",
+        deserialize_with = "deserialize_confusion"
+    )]
+    pub class_confusion: HashMap<(String, String), usize>,
+    /// Per-split summaries when auditing several splits at once
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub by_split: HashMap<String, AuditSummary>,
+    /// Precision, recall and mAP, when the method scores model detections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<DetectionMetrics>,
+    /// Every audited image, each stored once, in `sort_order` after `finalize`
+    ///
+    /// Reports list only those with issues, as `flagged_images` (see
+    /// `flagged_images()`); a report read back without `image_results` has
+    /// only those.
+    #[serde(
+        default,
+        rename = "flagged_images",
+        serialize_with = "serialize_flagged"
+    )]
+    pub image_results: Vec<ImageResult>,
+    /// Issues about the dataset rather than one audited image, such as label
+    /// files without an image
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dataset_issues: Vec<Issue>,
+    /// Class pairs whose mismatches suggest labels were swapped wholesale
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub label_swaps: Vec<LabelSwap>,
+    /// Where GT box centers fall in image space, if any image had boxes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spatial_heatmap: Option<SpatialHeatmap>,
+    /// How tightly matched detections overlap their GT boxes, when the
+    /// method matches model detections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iou_histogram: Option<IouHistogram>,
+    /// Size and shape of GT boxes per class, over the whole dataset
+    #[serde(default, skip_serializing_if = "BoxStats::is_empty")]
+    pub box_stats: BoxStats,
+    /// How each image's `relabel_priority` was computed
+    #[serde(default)]
+    pub priority_scoring: PriorityScoring,
+    /// Order of `image_results`, kept whenever the summaries are recomputed
+    #[serde(skip)]
+    pub sort_order: SortOrder,
+    /// Buckets of `iou_histogram`, kept whenever the summaries are recomputed
+    #[serde(skip, default = "default_iou_buckets")]
+    pub iou_buckets: usize,
+    /// Share `label_swaps` are found with, kept whenever the summaries are recomputed
+    #[serde(skip, default = "default_label_swap_min_share")]
+    pub label_swap_min_share: f32,
+    /// Cells per side of `spatial_heatmap`, kept whenever the summaries are recomputed
+    #[serde(skip, default = "default_heatmap_grid")]
+    pub heatmap_grid: usize,
+}
+
+fn default_iou_buckets() -> usize {
+    DEFAULT_IOU_BUCKETS
+}
+
+fn default_label_swap_min_share() -> f32 {
+    DEFAULT_LABEL_SWAP_MIN_SHARE
+}
+
+fn default_heatmap_grid() -> usize {
+    DEFAULT_GRID
+}
+
+/// An image result with the fields its own serialization leaves out, as
+/// stored in full reports
+#[derive(Serialize, Deserialize)]
+struct FullImageResult {
+    #[serde(flatten)]
+    result: ImageResult,
+    #[serde(default)]
+    gt_classes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    gt_centers: Vec<(f32, f32)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detections: Option<Vec<ScoredDetection>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    match_ious: Vec<f32>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// Serialize the image results that have issues
+fn serialize_flagged<S: Serializer>(
+    results: &[ImageResult],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(results.iter().filter(|r| r.has_issues()))
+}
+
+/// Serialize confusion pairs as nested `{gt_class: {detected_class: count}}` objects
+fn serialize_confusion<S: Serializer>(
+    confusion: &HashMap<(String, String), usize>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut nested: BTreeMap<&str, BTreeMap<&str, usize>> = BTreeMap::new();
+    for ((gt, det), count) in confusion {
+        nested.entry(gt).or_default().insert(det, *count);
+    }
+    nested.serialize(serializer)
+}
+
+fn deserialize_confusion<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<(String, String), usize>, D::Error> {
+    let nested: HashMap<String, HashMap<String, usize>> = HashMap::deserialize(deserializer)?;
+    Ok(nested
+        .into_iter()
+        .flat_map(|(gt, dets)| {
+            dets.into_iter()
+                .map(move |(det, count)| ((gt.clone(), det), count))
+        })
+        .collect())
 }
 
 /// Version from Cargo.toml
@@ -207,60 +1279,368 @@ impl AuditResult {
             method,
             confidence_threshold,
             iou_threshold,
+            excluded_classes: Vec::new(),
             total_images,
             images_audited,
+            elapsed_secs: 0.0,
+            partial: false,
+            images_skipped: 0,
+            missing_label_files: 0,
+            flagged_images_total: None,
+            flagged_images_truncated: false,
+            config: BTreeMap::new(),
             image_results: Vec::new(),
-            summary: AuditSummary {
-                total_images,
-                images_audited,
-                images_with_issues: 0,
-                total_issues: 0,
-                by_severity: HashMap::new(),
-                by_type: HashMap::new(),
-            },
-            flagged_images: Vec::new(),
+            summary: AuditSummary::empty(total_images, images_audited),
+            per_class: HashMap::new(),
+            class_confusion: HashMap::new(),
+            by_split: HashMap::new(),
+            metrics: None,
+            dataset_issues: Vec::new(),
+            label_swaps: Vec::new(),
+            spatial_heatmap: None,
+            iou_histogram: None,
+            box_stats: BoxStats::default(),
+            priority_scoring: PriorityScoring::default(),
+            sort_order: SortOrder::default(),
+            iou_buckets: DEFAULT_IOU_BUCKETS,
+            label_swap_min_share: DEFAULT_LABEL_SWAP_MIN_SHARE,
+            heatmap_grid: DEFAULT_GRID,
+        }
+    }
+
+    /// Load a JSON report
+    ///
+    /// Reports that list every image (see `full_image_results`) have their
+    /// summaries recomputed from the images. Other reports only carry the
+    /// flagged images, so their stored summaries are kept as written, and
+    /// reports cut to the worst flagged images are refused: exports and fixes
+    /// would treat the rest as clean.
+    pub fn from_json(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read report: {}", path.display()))?;
+        let mut value: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse report: {}", path.display()))?;
+        let images = value
+            .as_object_mut()
+            .and_then(|o| o.remove("image_results"));
+        let mut result: AuditResult = serde_json::from_value(value)
+            .with_context(|| format!("Failed to parse report: {}", path.display()))?;
+
+        match images {
+            Some(images) => {
+                let images: Vec<FullImageResult> = serde_json::from_value(images)
+                    .with_context(|| format!("Failed to parse report: {}", path.display()))?;
+                result.image_results = images
+                    .into_iter()
+                    .map(|image| ImageResult {
+                        gt_classes: image.gt_classes,
+                        gt_centers: image.gt_centers,
+                        detections: image.detections,
+                        match_ious: image.match_ious,
+                        ..image.result
+                    })
+                    .collect();
+                result.update_summary();
+            }
+            None if result.flagged_images_truncated => anyhow::bail!(
+                "Report {} lists only {} of {} flagged images (--report-top); rerun the audit without it",
+                path.display(),
+                result.image_results.len(),
+                result.flagged_images_total.unwrap_or_default()
+            ),
+            None => {}
+        }
+        Ok(result)
+    }
+
+    /// Every image result, including the fields `from_json` needs to
+    /// recompute the summaries
+    pub fn full_image_results(&self) -> serde_json::Result<serde_json::Value> {
+        let images: Vec<FullImageResult> = self
+            .image_results
+            .iter()
+            .map(|r| FullImageResult {
+                result: r.clone(),
+                gt_classes: r.gt_classes.clone(),
+                gt_centers: r.gt_centers.clone(),
+                detections: r.detections.clone(),
+                match_ious: r.match_ious.clone(),
+            })
+            .collect();
+        serde_json::to_value(images)
+    }
+
+    /// Register a split so a per-split summary is kept for it
+    pub fn add_split(&mut self, split: String, total_images: usize, images_audited: usize) {
+        self.by_split
+            .insert(split, AuditSummary::empty(total_images, images_audited));
+    }
+
+    /// Add an issue about the dataset as a whole, counting it in the summary
+    pub fn add_dataset_issue(&mut self, issue: Issue) {
+        self.summary.add_issue(&issue);
+        self.dataset_issues.push(issue);
+    }
+
+    /// Image results with issues, in the report's `sort_order` once finalized
+    pub fn flagged_images(&self) -> Vec<&ImageResult> {
+        self.image_results
+            .iter()
+            .filter(|r| r.has_issues())
+            .collect()
+    }
+
+    /// The first `n` flagged images, the worst ones in the report's `sort_order`
+    pub fn worst_images(&self, n: usize) -> Vec<&ImageResult> {
+        self.image_results
+            .iter()
+            .filter(|r| r.has_issues())
+            .take(n)
+            .collect()
+    }
+
+    /// The `n` flagged images with the most issues of `severity`, most first
+    ///
+    /// Images without such an issue are left out; ties keep the order of
+    /// `flagged_images`.
+    pub fn worst_images_by_severity(&self, severity: IssueSeverity, n: usize) -> Vec<&ImageResult> {
+        let count = |image: &ImageResult| {
+            image
+                .issues
+                .iter()
+                .filter(|i| i.severity == severity)
+                .count()
+        };
+        let mut images: Vec<&ImageResult> = self
+            .image_results
+            .iter()
+            .filter(|image| count(image) > 0)
+            .collect();
+        images.sort_by_key(|image| std::cmp::Reverse(count(image)));
+        images.truncate(n);
+        images
+    }
+
+    /// Issues of every image, followed by the dataset-level ones
+    pub fn all_issues(&self) -> impl Iterator<Item = &Issue> {
+        self.image_results
+            .iter()
+            .flat_map(|r| &r.issues)
+            .chain(&self.dataset_issues)
+    }
+
+    /// Mark issues accepted by `is_known` as baselined and recompute the summaries
+    pub fn apply_baseline(&mut self, mut is_known: impl FnMut(&Issue) -> bool) {
+        self.mark_issues(|issue| issue.baselined = is_known(issue));
+    }
+
+    /// Mark issues accepted by `is_suppressed` as suppressed and recompute the summaries
+    pub fn apply_suppressions(&mut self, mut is_suppressed: impl FnMut(&Issue) -> bool) {
+        self.mark_issues(|issue| issue.suppressed = is_suppressed(issue));
+    }
+
+    fn mark_issues(&mut self, mut mark: impl FnMut(&mut Issue)) {
+        for result in &mut self.image_results {
+            for issue in &mut result.issues {
+                mark(issue);
+            }
+        }
+        for issue in &mut self.dataset_issues {
+            mark(issue);
+        }
+        self.update_summary();
+    }
+
+    /// Drop issues the detector raised with a confidence below `min_confidence`
+    ///
+    /// Unlike the filters below, this changes the result itself: every image
+    /// is kept, and the counts and summaries no longer include the dropped
+    /// issues. Issues without a confidence, such as label checks, are kept.
+    pub fn drop_low_confidence_issues(&mut self, min_confidence: f32) {
+        let keep = |issue: &Issue| issue.confidence.unwrap_or(1.0) >= min_confidence;
+        for result in &mut self.image_results {
+            result.issues.retain(keep);
         }
+        self.dataset_issues.retain(keep);
+        self.update_summary();
+    }
+
+    /// Copy of the result restricted to issues of one severity
+    ///
+    /// Images without such an issue are dropped, and the remaining images
+    /// keep only the matching issues.
+    pub fn filter_by_severity(&self, severity: IssueSeverity) -> AuditResult {
+        self.filter_issues(|issue| issue.severity == severity)
+    }
+
+    /// Copy of the result restricted to issues of one type, like `filter_by_severity`
+    pub fn filter_by_type(&self, issue_type: &IssueType) -> AuditResult {
+        self.filter_issues(|issue| issue.issue_type == *issue_type)
+    }
+
+    fn filter_issues(&self, keep: impl Fn(&Issue) -> bool) -> AuditResult {
+        let mut filtered = self.clone();
+        filtered.image_results = self
+            .image_results
+            .iter()
+            .filter_map(|r| {
+                let mut r = r.clone();
+                r.issues.retain(&keep);
+                r.has_issues().then_some(r)
+            })
+            .collect();
+        filtered.dataset_issues.retain(&keep);
+        filtered.update_summary();
+        // Metrics describe the model on the whole audit, not the selected issues
+        filtered.metrics = self.metrics.clone();
+        filtered.spatial_heatmap = self.spatial_heatmap.clone();
+        filtered.iou_histogram = self.iou_histogram.clone();
+        filtered
     }
 
+    /// Add an image result, updating the counts incrementally
+    ///
+    /// Call `finalize` after the last result to order `image_results` and the
+    /// confusion matrix and to compute the metrics.
     pub fn add_image_result(&mut self, result: ImageResult) {
-        if result.has_issues() {
-            self.flagged_images.push(result.clone());
+        self.summary.add(&result);
+        if let Some(summary) = result
+            .split
+            .as_ref()
+            .and_then(|split| self.by_split.get_mut(split))
+        {
+            summary.add(&result);
         }
         self.image_results.push(result);
+    }
+
+    /// Replace the result of an already audited image, or add it if new
+    ///
+    /// Images are matched by filename and split. Returns the previous result.
+    pub fn replace_image_result(&mut self, result: ImageResult) -> Option<ImageResult> {
+        let same_image = |r: &ImageResult| r.filename == result.filename && r.split == result.split;
+        let previous = match self.image_results.iter().position(same_image) {
+            Some(idx) => Some(std::mem::replace(&mut self.image_results[idx], result)),
+            None => {
+                self.images_audited += 1;
+                if let Some(summary) = result
+                    .split
+                    .as_ref()
+                    .and_then(|split| self.by_split.get_mut(split))
+                {
+                    summary.images_audited += 1;
+                }
+                self.image_results.push(result);
+                None
+            }
+        };
         self.update_summary();
+        previous
     }
 
-    fn update_summary(&mut self) {
-        self.summary.images_with_issues = self.image_results.iter().filter(|r| r.has_issues()).count();
-        self.summary.total_issues = self.image_results.iter().map(|r| r.issues.len()).sum();
+    /// Finish the aggregation after the last `add_image_result`
+    pub fn finalize(&mut self) {
+        self.summary.sort_confusion_matrix();
+        for summary in self.by_split.values_mut() {
+            summary.sort_confusion_matrix();
+        }
 
-        // Count by severity
-        let mut by_severity: HashMap<String, usize> = HashMap::new();
-        let mut by_type: HashMap<String, usize> = HashMap::new();
+        self.class_confusion = self
+            .summary
+            .confusion_matrix
+            .iter()
+            .map(|e| ((e.gt_class.clone(), e.detected_class.clone()), e.count))
+            .collect();
+        self.per_class = PerClassStats::from_summary(&self.summary);
+        self.label_swaps = LabelSwap::find(&self.summary, self.label_swap_min_share);
 
-        for result in &self.image_results {
-            for issue in &result.issues {
-                let sev = match issue.severity {
-                    IssueSeverity::High => "high",
-                    IssueSeverity::Medium => "medium",
-                    IssueSeverity::Low => "low",
-                };
-                *by_severity.entry(sev.to_string()).or_insert(0) += 1;
-                *by_type.entry(issue.issue_type.to_string()).or_insert(0) += 1;
-            }
+        self.metrics = DetectionMetrics::from_results(&self.image_results);
+        self.spatial_heatmap = SpatialHeatmap::from_results(&self.image_results, self.heatmap_grid);
+        self.iou_histogram = IouHistogram::from_results(&self.image_results, self.iou_buckets);
+
+        self.score_priorities();
+        self.sort_order.sort(&mut self.image_results);
+    }
+
+    /// Count match IoUs in `buckets` buckets from now on
+    pub fn set_iou_buckets(&mut self, buckets: usize) {
+        self.iou_buckets = buckets;
+        self.iou_histogram = IouHistogram::from_results(&self.image_results, buckets);
+    }
+
+    /// Find label swaps with `min_share` from now on
+    pub fn set_label_swap_min_share(&mut self, min_share: f32) {
+        self.label_swap_min_share = min_share;
+        self.label_swaps = LabelSwap::find(&self.summary, min_share);
+    }
+
+    /// Bin GT box centers on a `grid` x `grid` heatmap from now on
+    pub fn set_heatmap_grid(&mut self, grid: usize) {
+        self.heatmap_grid = grid;
+        self.spatial_heatmap = SpatialHeatmap::from_results(&self.image_results, grid);
+    }
+
+    /// Score relabel priorities with `weights` from now on
+    pub fn set_priority_weights(&mut self, weights: PriorityWeights) {
+        self.priority_scoring = PriorityScoring::new(weights);
+        self.score_priorities();
+        self.sort_order.sort(&mut self.image_results);
+    }
+
+    fn score_priorities(&mut self) {
+        let weights = self.priority_scoring.weights;
+        for image in &mut self.image_results {
+            image.relabel_priority = weights.score(image);
         }
+    }
 
-        self.summary.by_severity = by_severity;
-        self.summary.by_type = by_type;
+    /// List flagged images in `order` from now on
+    pub fn set_sort_order(&mut self, order: SortOrder) {
+        self.sort_order = order;
+        order.sort(&mut self.image_results);
+    }
 
-        // Sort flagged images by issue count
-        self.flagged_images.sort_by(|a, b| b.issues.len().cmp(&a.issues.len()));
+    /// Recompute every summary from `image_results` and `dataset_issues`
+    fn update_summary(&mut self) {
+        self.summary = AuditSummary::empty(self.total_images, self.images_audited);
+        for summary in self.by_split.values_mut() {
+            *summary = AuditSummary::empty(summary.total_images, summary.images_audited);
+        }
+        for result in &self.image_results {
+            self.summary.add(result);
+            if let Some(summary) = result
+                .split
+                .as_ref()
+                .and_then(|split| self.by_split.get_mut(split))
+            {
+                summary.add(result);
+            }
+        }
+        for issue in &self.dataset_issues {
+            self.summary.add_issue(issue);
+        }
+        self.finalize();
     }
 
     pub fn images_with_issues(&self) -> usize {
         self.summary.images_with_issues
     }
 
+    /// Audited images without any issue
+    pub fn images_clean(&self) -> usize {
+        self.images_audited
+            .saturating_sub(self.images_with_issues())
+    }
+
+    /// Fraction of audited images without any issue (0 when none were audited)
+    pub fn clean_rate(&self) -> f32 {
+        if self.images_audited == 0 {
+            0.0
+        } else {
+            self.images_clean() as f32 / self.images_audited as f32
+        }
+    }
+
     pub fn total_issues(&self) -> usize {
         self.summary.total_issues
     }
@@ -277,9 +1657,499 @@ impl AuditResult {
         *self.summary.by_severity.get("low").unwrap_or(&0)
     }
 
+    /// High severity issues neither baselined nor suppressed
+    pub fn new_high_count(&self) -> usize {
+        *self.summary.new_by_severity.get("high").unwrap_or(&0)
+    }
+
+    /// Medium severity issues neither baselined nor suppressed
+    pub fn new_medium_count(&self) -> usize {
+        *self.summary.new_by_severity.get("medium").unwrap_or(&0)
+    }
+
+    /// Low severity issues neither baselined nor suppressed
+    pub fn new_low_count(&self) -> usize {
+        *self.summary.new_by_severity.get("low").unwrap_or(&0)
+    }
+
+    /// Issues of any severity neither baselined nor suppressed
+    pub fn new_issue_count(&self) -> usize {
+        self.summary.new_by_severity.values().sum()
+    }
+
     pub fn issues_by_type(&self) -> Vec<(String, usize)> {
         let mut items: Vec<_> = self.summary.by_type.iter().map(|(k, v)| (k.clone(), *v)).collect();
         items.sort_by(|a, b| b.1.cmp(&a.1));
         items
     }
+
+    /// Issue counts by type and severity as an aligned ASCII table, most
+    /// frequent type first, with a total row
+    ///
+    /// With `color`, nonzero High and Medium counts are red and yellow.
+    pub fn to_summary_table(&self, color: bool) -> String {
+        let mut counts: BTreeMap<String, [usize; 3]> = BTreeMap::new();
+        let issues = self
+            .image_results
+            .iter()
+            .flat_map(|image| &image.issues)
+            .chain(&self.dataset_issues);
+        for issue in issues {
+            let column = match issue.severity {
+                IssueSeverity::High => 0,
+                IssueSeverity::Medium => 1,
+                IssueSeverity::Low => 2,
+            };
+            counts.entry(issue.issue_type.to_string()).or_default()[column] += 1;
+        }
+        let mut rows: Vec<(String, [usize; 3])> = counts.into_iter().collect();
+        rows.sort_by_key(|(_, c)| std::cmp::Reverse(c.iter().sum::<usize>()));
+        let mut total = [0; 3];
+        for (_, c) in &rows {
+            for (t, n) in total.iter_mut().zip(c) {
+                *t += n;
+            }
+        }
+        rows.push(("Total".to_string(), total));
+
+        let header = ["Issue Type", "High", "Medium", "Low", "Total"];
+        let cells: Vec<[String; 5]> = rows
+            .iter()
+            .map(|(name, [high, medium, low])| {
+                [
+                    name.clone(),
+                    high.to_string(),
+                    medium.to_string(),
+                    low.to_string(),
+                    (high + medium + low).to_string(),
+                ]
+            })
+            .collect();
+        let widths: Vec<usize> = (0..5)
+            .map(|i| {
+                cells
+                    .iter()
+                    .map(|row| row[i].len())
+                    .chain([header[i].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let rule: String = widths
+            .iter()
+            .map(|w| format!("+{}", "-".repeat(w + 2)))
+            .collect::<String>()
+            + "+\n";
+        let line = |row: [&str; 5], colors: [Option<&str>; 5]| -> String {
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                let padded = if i == 0 {
+                    format!("{:<w$}", cell, w = widths[i])
+                } else {
+                    format!("{:>w$}", cell, w = widths[i])
+                };
+                match colors[i] {
+                    Some(code) if color => line += &format!("| \x1b[{}m{}\x1b[0m ", code, padded),
+                    _ => line += &format!("| {} ", padded),
+                }
+            }
+            line + "|\n"
+        };
+
+        let mut table = rule.clone();
+        table += &line(header, [None; 5]);
+        table += &rule;
+        let last = cells.len() - 1;
+        for (i, row) in cells.iter().enumerate() {
+            if i == last {
+                table += &rule;
+            }
+            let (high, medium) = (rows[i].1[0], rows[i].1[1]);
+            let colors = [
+                None,
+                (high > 0).then_some("31"),
+                (medium > 0).then_some("33"),
+                None,
+                None,
+            ];
+            table += &line(row.each_ref().map(String::as_str), colors);
+        }
+        table + &rule
+    }
+}
+
+/// Annotation statistics for a single class
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassStats {
+    pub class_id: i32,
+    pub class_name: String,
+    pub annotation_count: usize,
+    pub image_count: usize,
+    /// Percentage of images containing at least one instance of the class
+    pub image_pct: f32,
+    pub width_mean: f32,
+    pub width_std: f32,
+    pub height_mean: f32,
+    pub height_std: f32,
+    pub area_mean: f32,
+    pub area_std: f32,
+    pub area_min: f32,
+    pub area_max: f32,
+}
+
+/// GT boxes whose area falls in one range of image fractions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AreaBucket {
+    /// Range of box area as a percentage of the image, e.g. `1-10%`
+    pub range: String,
+    pub count: usize,
+}
+
+/// Annotation statistics for a whole dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetStats {
+    pub dataset_path: String,
+    pub total_images: usize,
+    pub total_annotations: usize,
+    pub avg_annotations_per_image: f32,
+    pub min_annotations_per_image: usize,
+    pub max_annotations_per_image: usize,
+    pub images_without_annotations: usize,
+    pub images_over_50_annotations: usize,
+    /// Annotations of the most common class over those of the rarest (0 without annotations)
+    pub class_imbalance_ratio: f32,
+    pub area_buckets: Vec<AreaBucket>,
+    /// Most annotated class first
+    pub classes: Vec<ClassStats>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issue_types_have_unique_codes_and_parse_back() {
+        let codes: std::collections::HashSet<_> =
+            IssueType::ALL.iter().map(|t| t.short_code()).collect();
+        assert_eq!(codes.len(), IssueType::ALL.len());
+        for issue_type in IssueType::ALL {
+            assert_eq!(
+                issue_type.to_string().parse::<IssueType>().unwrap(),
+                issue_type
+            );
+            assert!(issue_type.help_text().ends_with('.'));
+        }
+        assert_eq!(IssueType::ClassMismatch.display_name(), "Class Mismatch");
+        assert_eq!(IssueType::ClassMismatch.short_code(), "CM");
+    }
+
+    #[test]
+    fn bbox_area_and_aspect_ratio() {
+        let bbox = BoundingBox::new(0.5, 0.5, 0.2, 0.1);
+        assert!((bbox.area() - 0.02).abs() < 1e-6);
+        assert!((bbox.aspect_ratio() - 2.0).abs() < 1e-6);
+        assert!((bbox.pixel_area(640, 480) - 6144.0).abs() < 1e-2);
+        assert_eq!(
+            BoundingBox::new(0.5, 0.5, 0.2, 0.0).aspect_ratio(),
+            f32::INFINITY
+        );
+    }
+
+    #[test]
+    fn bbox_pixel_coords_round_trip_and_clamp() {
+        let bbox = BoundingBox::from_pixel_coords(64, 48, 192, 144, 640, 480);
+        assert!((bbox.x - 0.2).abs() < 1e-6 && (bbox.y - 0.2).abs() < 1e-6);
+        assert!((bbox.w - 0.2).abs() < 1e-6 && (bbox.h - 0.2).abs() < 1e-6);
+        assert_eq!(bbox.to_pixel_coords(640, 480), (64, 48, 192, 144));
+
+        // Boxes reaching past the edges stop at the image bounds
+        let outside = BoundingBox::new(0.95, 0.05, 0.2, 0.2);
+        assert_eq!(outside.to_pixel_coords(100, 100), (85, 0, 100, 15));
+    }
+
+    #[test]
+    fn test_class_confusion_serializes_nested() {
+        let mut result = AuditResult::new(String::new(), String::new(), 0.25, 0.5, 0, 0);
+        result
+            .class_confusion
+            .insert(("jacket".to_string(), "coat".to_string()), 3);
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["class_confusion"]["jacket"]["coat"], 3);
+
+        let back: AuditResult = serde_json::from_value(json).unwrap();
+        assert_eq!(back.class_confusion, result.class_confusion);
+    }
+
+    #[test]
+    fn filter_by_severity_strips_other_issues() {
+        let issue = |severity, issue_type| Issue::builder("a.jpg", severity, issue_type).build();
+        let mut result = AuditResult::new(String::new(), String::new(), 0.25, 0.5, 3, 3);
+        let mut mixed = ImageResult::new("a.jpg".to_string(), &[], 0);
+        mixed.add_issue(issue(IssueSeverity::High, IssueType::ClassMismatch));
+        mixed.add_issue(issue(IssueSeverity::Low, IssueType::OutOfBounds));
+        let mut low = ImageResult::new("b.jpg".to_string(), &[], 0);
+        low.add_issue(issue(IssueSeverity::Low, IssueType::OutOfBounds));
+        result.add_image_result(mixed);
+        result.add_image_result(low);
+        result.add_image_result(ImageResult::new("c.jpg".to_string(), &[], 0));
+
+        let high = result.filter_by_severity(IssueSeverity::High);
+        assert_eq!(high.flagged_images().len(), 1);
+        assert_eq!(high.total_issues(), 1);
+        assert_eq!(high.low_count(), 0);
+
+        let oob = result.filter_by_type(&IssueType::OutOfBounds);
+        assert_eq!(oob.images_with_issues(), 2);
+        assert_eq!(oob.high_count(), 0);
+        assert_eq!(result.total_issues(), 3);
+    }
+
+    #[test]
+    fn low_confidence_issues_are_dropped_from_counts() {
+        let issue = || {
+            Issue::builder("a.jpg", IssueSeverity::Medium, IssueType::MissingLabel)
+                .detected_class("car")
+        };
+        let mut result = AuditResult::new(String::new(), String::new(), 0.15, 0.5, 2, 2);
+        let mut weak = ImageResult::new("a.jpg".to_string(), &[], 2);
+        weak.add_issue(issue().confidence(0.2).build());
+        let mut mixed = ImageResult::new("b.jpg".to_string(), &[], 1);
+        mixed.add_issue(issue().confidence(0.9).build());
+        mixed.add_issue(issue().build());
+        result.add_image_result(weak);
+        result.add_image_result(mixed);
+        result.finalize();
+
+        result.drop_low_confidence_issues(0.4);
+        assert_eq!(result.total_issues(), 2);
+        assert_eq!(result.images_with_issues(), 1);
+        assert_eq!(result.images_clean(), 1);
+        assert_eq!(result.image_results.len(), 2);
+    }
+
+    #[test]
+    fn flagged_images_follow_the_sort_order() {
+        let image = |name: &str, severities: &[IssueSeverity]| {
+            let mut image = ImageResult::new(name.to_string(), &[], 0);
+            for severity in severities {
+                image.add_issue(Issue::builder(name, *severity, IssueType::MissingLabel).build());
+            }
+            image
+        };
+        use IssueSeverity::{High, Low, Medium};
+        let mut result = AuditResult::new(String::new(), String::new(), 0.25, 0.5, 3, 3);
+        result.add_image_result(image("c.jpg", &[Low, Low, Low]));
+        result.add_image_result(image("a.jpg", &[High, Medium]));
+        result.add_image_result(image("b.jpg", &[Medium, Medium]));
+        result.finalize();
+        let order = |result: &AuditResult| -> Vec<String> {
+            result
+                .flagged_images()
+                .iter()
+                .map(|i| i.filename.clone())
+                .collect()
+        };
+        assert_eq!(order(&result), ["c.jpg", "a.jpg", "b.jpg"]);
+
+        result.set_sort_order("priority".parse().unwrap());
+        assert_eq!(order(&result), ["a.jpg", "b.jpg", "c.jpg"]);
+        result.set_sort_order(SortOrder::ByHighSeverity);
+        assert_eq!(order(&result), ["a.jpg", "b.jpg", "c.jpg"]);
+        result.set_sort_order(SortOrder::ByMediumSeverity);
+        assert_eq!(order(&result), ["b.jpg", "a.jpg", "c.jpg"]);
+
+        // The order survives recomputing the summaries
+        result.set_sort_order("by-filename".parse().unwrap());
+        let filtered = result.filter_by_type(&IssueType::MissingLabel);
+        assert_eq!(order(&filtered), ["a.jpg", "b.jpg", "c.jpg"]);
+
+        let names = |images: Vec<&ImageResult>| -> Vec<String> {
+            images.iter().map(|i| i.filename.clone()).collect()
+        };
+        assert_eq!(names(result.worst_images(2)), ["a.jpg", "b.jpg"]);
+        assert_eq!(result.worst_images(10).len(), 3);
+        assert_eq!(
+            names(result.worst_images_by_severity(Medium, 5)),
+            ["b.jpg", "a.jpg"]
+        );
+        assert!(result.worst_images_by_severity(High, 0).is_empty());
+    }
+
+    #[test]
+    fn summary_table_counts_types_by_severity() {
+        let issue = |severity, issue_type| Issue::builder("a.jpg", severity, issue_type).build();
+        let mut image = ImageResult::new("a.jpg".to_string(), &[], 0);
+        image.add_issue(issue(IssueSeverity::High, IssueType::ClassMismatch));
+        image.add_issue(issue(IssueSeverity::Low, IssueType::SpuriousLabel));
+        image.add_issue(issue(IssueSeverity::Low, IssueType::SpuriousLabel));
+        let mut result = AuditResult::new(String::new(), String::new(), 0.25, 0.5, 1, 1);
+        result.add_image_result(image);
+        result.finalize();
+
+        let table = result.to_summary_table(false);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "+----------------+------+--------+-----+-------+");
+        assert_eq!(lines[1], "| Issue Type     | High | Medium | Low | Total |");
+        assert_eq!(lines[3], "| spurious_label |    0 |      0 |   2 |     2 |");
+        assert_eq!(lines[4], "| class_mismatch |    1 |      0 |   0 |     1 |");
+        assert_eq!(lines[6], "| Total          |    1 |      0 |   2 |     3 |");
+        assert_eq!(lines.len(), 8);
+
+        let colored = result.to_summary_table(true);
+        assert!(colored.contains("| \x1b[31m   1\x1b[0m |"));
+        assert!(!table.contains('\x1b'));
+    }
+
+    #[test]
+    fn per_class_counts_issues_by_gt_or_detected_class() {
+        let issue = |severity, gt: Option<&str>, det: Option<&str>| {
+            let mut issue = Issue::builder("a.jpg", severity, IssueType::ClassMismatch).build();
+            issue.gt_class = gt.map(str::to_string);
+            issue.detected_class = det.map(str::to_string);
+            issue
+        };
+        let mut image = ImageResult::new("a.jpg".to_string(), &[], 0);
+        image.add_issue(issue(IssueSeverity::High, Some("jacket"), Some("coat")));
+        image.add_issue(issue(IssueSeverity::Medium, None, Some("coat")));
+        image.add_issue(issue(IssueSeverity::Low, Some("jacket"), None));
+        image.add_issue(issue(IssueSeverity::Low, None, None));
+
+        let summary = AuditSummary::from_results([&image], 1, 1);
+        let jacket = &summary.per_class["jacket"];
+        assert_eq!(
+            (jacket.issues, jacket.high_issues, jacket.medium_issues),
+            (2, 1, 0)
+        );
+        let coat = &summary.per_class["coat"];
+        assert_eq!(
+            (coat.issues, coat.high_issues, coat.medium_issues),
+            (1, 0, 1)
+        );
+
+        let mut result = AuditResult::new("d".into(), "yolo".into(), 0.25, 0.5, 1, 1);
+        result.add_image_result(image);
+        result.finalize();
+        assert_eq!(
+            result.per_class["coat"],
+            PerClassStats {
+                class_name: "coat".to_string(),
+                annotation_count: 0,
+                issue_count: 1,
+                high_count: 0,
+                medium_count: 1,
+                class_mismatch_count: 0,
+                missing_label_count: 0,
+            }
+        );
+        assert_eq!(result.per_class["jacket"].class_mismatch_count, 1);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["per_class"]["jacket"]["issue_count"], 2);
+    }
+
+    #[test]
+    fn systematic_mismatches_are_reported_as_label_swaps() {
+        let image = |gt: &str, det: Option<&str>| {
+            let mut image = ImageResult::new("a.jpg".to_string(), &[], 1);
+            image.gt_classes = vec![gt.to_string()];
+            if let Some(det) = det {
+                image.add_issue(
+                    Issue::builder("a.jpg", IssueSeverity::High, IssueType::ClassMismatch)
+                        .gt_class(gt)
+                        .detected_class(det)
+                        .build(),
+                );
+            }
+            image
+        };
+        let mut images = Vec::new();
+        // Cats and dogs swapped; a few jackets seen as coats
+        images.extend((0..8).map(|_| image("cat", Some("dog"))));
+        images.extend((0..6).map(|_| image("dog", Some("cat"))));
+        images.extend((0..2).map(|_| image("dog", None)));
+        images.extend((0..5).map(|_| image("jacket", Some("coat"))));
+        images.extend((0..20).map(|_| image("jacket", None)));
+
+        let summary = AuditSummary::from_results(&images, images.len(), images.len());
+        let swaps = LabelSwap::find(&summary, DEFAULT_LABEL_SWAP_MIN_SHARE);
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(
+            swaps[0].to_string(),
+            "possible systematic label swap: 'cat' ↔ 'dog' (14 instances)"
+        );
+        assert_eq!(swaps[0].share, 1.0);
+
+        // Jackets seen as coats are a fifth of the jackets
+        let swaps = LabelSwap::find(&summary, 0.15);
+        assert_eq!(swaps.len(), 2);
+        assert_eq!(swaps[1].gt_class, "jacket");
+    }
+
+    #[test]
+    fn aggregation_of_many_results_is_incremental() {
+        let severities = [
+            IssueSeverity::High,
+            IssueSeverity::Medium,
+            IssueSeverity::Low,
+        ];
+        let n = 1_000;
+        let mut result = AuditResult::new(String::new(), String::new(), 0.25, 0.5, n, n);
+        result.add_split("train".to_string(), n / 2, n / 2);
+        result.add_split("val".to_string(), n / 2, n / 2);
+
+        let (mut issues, mut flagged) = (0, 0);
+        for i in 0..n {
+            let mut image = ImageResult::new(format!("{}.jpg", i), &[], 0);
+            image.split = Some(if i % 2 == 0 { "train" } else { "val" }.to_string());
+            // Image i has i % 4 issues, cycling through the severities
+            for j in 0..i % 4 {
+                let severity = severities[(i + j) % 3];
+                image.add_issue(
+                    Issue::builder(image.filename.clone(), severity, IssueType::ClassMismatch)
+                        .gt_class("jacket")
+                        .detected_class(format!("class{}", j))
+                        .build(),
+                );
+            }
+            issues += i % 4;
+            flagged += usize::from(i % 4 > 0);
+            result.add_image_result(image);
+
+            assert_eq!(result.total_issues(), issues);
+            assert_eq!(result.images_with_issues(), flagged);
+            assert_eq!(
+                result.by_split["train"].total_issues + result.by_split["val"].total_issues,
+                issues
+            );
+        }
+        // Derived data waits for `finalize`
+        assert!(result.per_class.is_empty());
+        assert!(result.class_confusion.is_empty());
+        result.finalize();
+
+        let expected = AuditSummary::from_results(&result.image_results, n, n);
+        assert_eq!(result.total_issues(), expected.total_issues);
+        assert_eq!(result.summary.by_severity, expected.by_severity);
+        assert_eq!(result.summary.by_type, expected.by_type);
+        assert_eq!(result.images_with_issues(), n / 4 * 3);
+        assert_eq!(result.images_clean(), n / 4);
+        assert_eq!(result.clean_rate(), 0.25);
+        assert_eq!(result.summary.confusion_matrix.len(), 3);
+        assert_eq!(result.summary.confusion_matrix[0].detected_class, "class0");
+
+        // Each result is stored once; flagged images are a view of them
+        assert_eq!(result.image_results.len(), n);
+        let flagged_images = result.flagged_images();
+        assert_eq!(flagged_images.len(), flagged);
+        assert_eq!(flagged_images[0].issues.len(), 3);
+        assert!(flagged_images
+            .windows(2)
+            .all(|w| w[0].issues.len() >= w[1].issues.len()));
+
+        // Adding a result counts that result alone, without recounting the others
+        result.image_results[0].issues.clear();
+        result.add_image_result(ImageResult::new("extra.jpg".to_string(), &[], 0));
+        assert_eq!(result.total_issues(), issues);
+    }
 }
@@ -0,0 +1,380 @@
+//! Issue severities and CI failure policy for audit results
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::models::{AuditResult, IssueSeverity, IssueType};
+
+/// Issue-count thresholds above which an audit fails
+#[derive(Debug, Clone, Default)]
+pub struct FailurePolicy {
+    /// Maximum allowed high severity issues
+    pub max_high: Option<usize>,
+    /// Maximum allowed medium-or-higher severity issues
+    pub max_medium: Option<usize>,
+    /// Maximum allowed low severity issues
+    pub max_low: Option<usize>,
+    /// Maximum allowed issues of any severity
+    pub max_total: Option<usize>,
+    /// Maximum allowed issues per issue type, keyed by type name (e.g. `missing_label`)
+    pub max_by_type: BTreeMap<String, usize>,
+    /// Fail if any image has no label file
+    pub missing_label_files: bool,
+}
+
+impl FailurePolicy {
+    /// Whether any threshold is configured
+    pub fn is_active(&self) -> bool {
+        self.max_high.is_some()
+            || self.max_medium.is_some()
+            || self.max_low.is_some()
+            || self.max_total.is_some()
+            || !self.max_by_type.is_empty()
+            || self.missing_label_files
+    }
+}
+
+/// Severity of one issue type: fixed, or chosen by model confidence
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SeverityRule {
+    Fixed(IssueSeverity),
+    /// Checked in order; the first band the issue's confidence reaches applies
+    Bands(Vec<ConfidenceBand>),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfidenceBand {
+    /// Lowest confidence the band applies to; a band without one matches any issue
+    #[serde(default)]
+    pub min_confidence: Option<f32>,
+    pub severity: IssueSeverity,
+}
+
+/// Severity to give each issue type, consulted by the detectors
+///
+/// Types without a rule keep their built-in severity
+/// (`IssueType::default_severity`).
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct SeverityPolicy {
+    /// Rules keyed by issue type name (e.g. `missing_label`)
+    pub rules: BTreeMap<String, SeverityRule>,
+}
+
+impl SeverityPolicy {
+    pub fn severity(&self, issue_type: &IssueType, confidence: Option<f32>) -> IssueSeverity {
+        let band_matches = |band: &&ConfidenceBand| match (band.min_confidence, confidence) {
+            (None, _) => true,
+            (Some(min), Some(confidence)) => confidence >= min,
+            (Some(_), None) => false,
+        };
+        match self.rules.get(&issue_type.to_string()) {
+            Some(SeverityRule::Fixed(severity)) => *severity,
+            Some(SeverityRule::Bands(bands)) => bands
+                .iter()
+                .find(band_matches)
+                .map(|band| band.severity)
+                .unwrap_or_else(|| issue_type.default_severity()),
+            None => issue_type.default_severity(),
+        }
+    }
+}
+
+/// CI thresholds of a policy file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FailOn {
+    pub high: Option<usize>,
+    pub medium: Option<usize>,
+    pub low: Option<usize>,
+    pub total: Option<usize>,
+    #[serde(default)]
+    pub types: BTreeMap<String, usize>,
+    #[serde(default)]
+    pub missing_labels: bool,
+}
+
+/// Contents of a `--policy` YAML file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyFile {
+    #[serde(default)]
+    pub severity: SeverityPolicy,
+    #[serde(default)]
+    pub fail_on: FailOn,
+}
+
+impl PolicyFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read policy: {}", path.display()))?;
+        let policy: Self = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse policy: {}", path.display()))?;
+
+        // Catch typos in issue type names, which would otherwise be ignored
+        for name in policy
+            .severity
+            .rules
+            .keys()
+            .chain(policy.fail_on.types.keys())
+        {
+            name.parse::<IssueType>()
+                .with_context(|| format!("Invalid policy: {}", path.display()))?;
+        }
+        Ok(policy)
+    }
+
+    /// Thresholds of the file, with `--fail-on-*` values taking precedence
+    pub fn failure_policy(
+        &self,
+        fail_on_high: Option<usize>,
+        fail_on_medium: Option<usize>,
+        fail_on_low: Option<usize>,
+        fail_on_total: Option<usize>,
+        fail_on_missing_labels: bool,
+    ) -> FailurePolicy {
+        FailurePolicy {
+            max_high: fail_on_high.or(self.fail_on.high),
+            max_medium: fail_on_medium.or(self.fail_on.medium),
+            max_low: fail_on_low.or(self.fail_on.low),
+            max_total: fail_on_total.or(self.fail_on.total),
+            max_by_type: self.fail_on.types.clone(),
+            missing_label_files: fail_on_missing_labels || self.fail_on.missing_labels,
+        }
+    }
+}
+
+/// Describe every threshold the result exceeds
+///
+/// Baselined and suppressed issues never count toward a threshold.
+pub fn threshold_violations(result: &AuditResult, policy: &FailurePolicy) -> Vec<String> {
+    let mut violations = Vec::new();
+    let baselined = result.summary.baselined_issues + result.summary.suppressed_issues > 0;
+
+    if let Some(threshold) = policy.max_high {
+        if result.new_high_count() > threshold {
+            violations.push(format!(
+                "{} severity issues ({}) exceed threshold ({})",
+                if baselined { "New high" } else { "High" },
+                result.new_high_count(),
+                threshold
+            ));
+        }
+    }
+
+    if let Some(threshold) = policy.max_medium {
+        let medium_plus = result.new_high_count() + result.new_medium_count();
+        if medium_plus > threshold {
+            violations.push(format!(
+                "{} severity issues ({}) exceed threshold ({})",
+                if baselined { "New medium+" } else { "Medium+" },
+                medium_plus,
+                threshold
+            ));
+        }
+    }
+
+    if let Some(threshold) = policy.max_low {
+        if result.new_low_count() > threshold {
+            violations.push(format!(
+                "{} severity issues ({}) exceed threshold ({})",
+                if baselined { "New low" } else { "Low" },
+                result.new_low_count(),
+                threshold
+            ));
+        }
+    }
+
+    if let Some(threshold) = policy.max_total {
+        if result.new_issue_count() > threshold {
+            violations.push(format!(
+                "{} issues ({}) exceed threshold ({})",
+                if baselined { "New" } else { "Total" },
+                result.new_issue_count(),
+                threshold
+            ));
+        }
+    }
+
+    for (issue_type, threshold) in &policy.max_by_type {
+        let count = result
+            .all_issues()
+            .filter(|i| i.is_new() && i.issue_type.to_string() == *issue_type)
+            .count();
+        if count > *threshold {
+            violations.push(format!(
+                "{}{} issues ({}) exceed threshold ({})",
+                if baselined { "New " } else { "" },
+                issue_type,
+                count,
+                threshold
+            ));
+        }
+    }
+
+    if policy.missing_label_files && result.missing_label_files > 0 {
+        violations.push(format!(
+            "{} images have no label file",
+            result.missing_label_files
+        ));
+    }
+
+    violations
+}
+
+/// Returns true if the result passes all thresholds
+pub fn check_thresholds(result: &AuditResult, policy: &FailurePolicy) -> bool {
+    threshold_violations(result, policy).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ImageResult, Issue, IssueSeverity, IssueType};
+
+    fn result_with(severities: &[IssueSeverity]) -> AuditResult {
+        let mut result = AuditResult::new(
+            "dataset".to_string(),
+            "zero-shot".to_string(),
+            0.25,
+            0.5,
+            1,
+            1,
+        );
+        let mut image = ImageResult::new("a.jpg".to_string(), &[], 0);
+        for &severity in severities {
+            image.add_issue(Issue::builder("a.jpg", severity, IssueType::Localization).build());
+        }
+        result.add_image_result(image);
+        result
+    }
+
+    #[test]
+    fn default_policy_file_matches_built_in_severities() {
+        let file: PolicyFile =
+            serde_yaml::from_str(include_str!("../policies/default.yaml")).unwrap();
+        for issue_type in [
+            IssueType::ClassMismatch,
+            IssueType::MissingLabel,
+            IssueType::SpuriousLabel,
+            IssueType::Localization,
+            IssueType::OutOfBounds,
+            IssueType::ImageQuality,
+            IssueType::ParseError,
+            IssueType::InvalidClassId,
+            IssueType::DuplicateAnnotation,
+            IssueType::AnnotationOverlap,
+            IssueType::DuplicateImage,
+            IssueType::ImageError,
+            IssueType::DatasetConsistency,
+            IssueType::SuspectPreAnnotation,
+            IssueType::CoordinateFormat,
+            IssueType::BoxSize,
+            IssueType::ExifOrientation,
+        ] {
+            assert_eq!(
+                file.severity.severity(&issue_type, Some(0.9)),
+                issue_type.default_severity()
+            );
+        }
+        assert!(!file
+            .failure_policy(None, None, None, None, false)
+            .is_active());
+    }
+
+    #[test]
+    fn confidence_bands_pick_first_match() {
+        let file: PolicyFile = serde_yaml::from_str(
+            r#"
+severity:
+  missing_label:
+    - min_confidence: 0.8
+      severity: high
+    - severity: medium
+fail_on:
+  types:
+    missing_label: 0
+"#,
+        )
+        .unwrap();
+        let severity = |c| file.severity.severity(&IssueType::MissingLabel, c);
+        assert_eq!(severity(Some(0.85)), IssueSeverity::High);
+        assert_eq!(severity(Some(0.5)), IssueSeverity::Medium);
+        assert_eq!(severity(None), IssueSeverity::Medium);
+
+        let policy = file.failure_policy(None, None, None, None, false);
+        let mut result = result_with(&[IssueSeverity::Low]);
+        assert!(check_thresholds(&result, &policy));
+        result.image_results[0].issues[0].issue_type = IssueType::MissingLabel;
+        assert!(!check_thresholds(&result, &policy));
+    }
+
+    #[test]
+    fn test_check_thresholds() {
+        let result = result_with(&[IssueSeverity::High, IssueSeverity::Medium]);
+
+        assert!(check_thresholds(&result, &FailurePolicy::default()));
+        assert!(check_thresholds(
+            &result,
+            &FailurePolicy {
+                max_high: Some(1),
+                max_medium: Some(2),
+                ..Default::default()
+            }
+        ));
+        assert!(!check_thresholds(
+            &result,
+            &FailurePolicy {
+                max_high: Some(0),
+                max_medium: None,
+                ..Default::default()
+            }
+        ));
+        assert!(!check_thresholds(
+            &result,
+            &FailurePolicy {
+                max_high: None,
+                max_medium: Some(1),
+                ..Default::default()
+            }
+        ));
+
+        let result = result_with(&[IssueSeverity::Medium, IssueSeverity::Low]);
+        let total = |max_total| FailurePolicy {
+            max_total: Some(max_total),
+            ..Default::default()
+        };
+        assert!(check_thresholds(&result, &total(2)));
+        assert!(!check_thresholds(&result, &total(1)));
+        let low = |max_low| FailurePolicy {
+            max_low: Some(max_low),
+            ..Default::default()
+        };
+        assert!(check_thresholds(&result, &low(1)));
+        assert!(!check_thresholds(&result, &low(0)));
+    }
+
+    #[test]
+    fn missing_label_files_fail_only_when_asked() {
+        let file: PolicyFile = serde_yaml::from_str("fail_on:\n  missing_labels: true\n").unwrap();
+        let mut result = result_with(&[]);
+        result.missing_label_files = 2;
+        assert!(check_thresholds(
+            &result,
+            &PolicyFile::default().failure_policy(None, None, None, None, false)
+        ));
+        for policy in [
+            file.failure_policy(None, None, None, None, false),
+            PolicyFile::default().failure_policy(None, None, None, None, true),
+        ] {
+            assert_eq!(
+                threshold_violations(&result, &policy),
+                vec!["2 images have no label file".to_string()]
+            );
+        }
+    }
+}
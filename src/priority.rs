@@ -0,0 +1,195 @@
+//! Relabeling priority: which flagged images to send to annotators first
+//!
+//! Every image gets a score from its issues, so a confident class mismatch
+//! on a busy image outranks a handful of low severity notes. Suppressed issues
+//! don't count.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::models::{ImageResult, IssueSeverity};
+
+/// How the score is computed, as written to reports
+pub const FORMULA: &str = "sum over issues of (severity weight + confidence weight * model confidence) + classes weight * distinct classes involved";
+
+/// Weights of the relabel-priority score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriorityWeights {
+    pub high: f32,
+    pub medium: f32,
+    pub low: f32,
+    /// Per unit of model confidence, for issues where the model disagrees
+    pub confidence: f32,
+    /// Per distinct GT or detected class named by the image's issues
+    pub classes: f32,
+}
+
+impl Default for PriorityWeights {
+    fn default() -> Self {
+        Self {
+            high: 3.0,
+            medium: 1.5,
+            low: 0.5,
+            confidence: 2.0,
+            classes: 0.5,
+        }
+    }
+}
+
+impl PriorityWeights {
+    /// Relabel priority of `image`; 0 without unsuppressed issues
+    pub fn score(&self, image: &ImageResult) -> f32 {
+        let mut score = 0.0;
+        let mut classes = BTreeSet::new();
+        for issue in image.issues.iter().filter(|i| !i.suppressed) {
+            score += match issue.severity {
+                IssueSeverity::High => self.high,
+                IssueSeverity::Medium => self.medium,
+                IssueSeverity::Low => self.low,
+            };
+            score += self.confidence * issue.confidence.unwrap_or(0.0);
+            classes.extend(issue.gt_class.as_deref());
+            classes.extend(issue.detected_class.as_deref());
+        }
+        score + self.classes * classes.len() as f32
+    }
+}
+
+/// Parses `high=3,medium=1.5,low=0.5,confidence=2,classes=0.5`; weights left
+/// out keep their defaults
+impl std::str::FromStr for PriorityWeights {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut weights = Self::default();
+        for pair in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (name, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Expected NAME=WEIGHT, got '{}'", pair))?;
+            let value: f32 = value
+                .trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid weight for '{}': '{}'", name, value))?;
+            let slot = match name.trim() {
+                "high" => &mut weights.high,
+                "medium" => &mut weights.medium,
+                "low" => &mut weights.low,
+                "confidence" => &mut weights.confidence,
+                "classes" => &mut weights.classes,
+                other => anyhow::bail!(
+                    "Unknown priority weight '{}' (expected high, medium, low, confidence or classes)",
+                    other
+                ),
+            };
+            *slot = value;
+        }
+        Ok(weights)
+    }
+}
+
+/// Scoring metadata stored with a report, so a worklist's order can be explained
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriorityScoring {
+    pub formula: String,
+    pub weights: PriorityWeights,
+}
+
+impl Default for PriorityScoring {
+    fn default() -> Self {
+        Self::new(PriorityWeights::default())
+    }
+}
+
+impl PriorityScoring {
+    pub fn new(weights: PriorityWeights) -> Self {
+        Self {
+            formula: FORMULA.to_string(),
+            weights,
+        }
+    }
+}
+
+/// Images with a positive priority, highest first, cut to `top` if given
+pub fn worklist(images: &[ImageResult], top: Option<usize>) -> Vec<&ImageResult> {
+    let mut list: Vec<&ImageResult> = images.iter().filter(|i| i.relabel_priority > 0.0).collect();
+    list.sort_by(|a, b| b.relabel_priority.total_cmp(&a.relabel_priority));
+    list.truncate(top.unwrap_or(usize::MAX));
+    list
+}
+
+/// One worklist per class named by an unsuppressed issue, like `worklist`
+pub fn worklists_by_class(
+    images: &[ImageResult],
+    top: Option<usize>,
+) -> BTreeMap<String, Vec<&ImageResult>> {
+    let mut by_class: BTreeMap<String, Vec<&ImageResult>> = BTreeMap::new();
+    for image in worklist(images, None) {
+        let classes: BTreeSet<&str> = image
+            .issues
+            .iter()
+            .filter(|i| !i.suppressed)
+            .flat_map(|i| [i.gt_class.as_deref(), i.detected_class.as_deref()])
+            .flatten()
+            .collect();
+        for class in classes {
+            by_class.entry(class.to_string()).or_default().push(image);
+        }
+    }
+    for list in by_class.values_mut() {
+        list.truncate(top.unwrap_or(usize::MAX));
+    }
+    by_class
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Issue, IssueType};
+
+    fn image(name: &str, issues: &[(IssueSeverity, Option<f32>, &str)]) -> ImageResult {
+        let mut image = ImageResult::new(name.to_string(), &[], 0);
+        for &(severity, confidence, detected) in issues {
+            let mut issue = Issue::builder(name, severity, IssueType::ClassMismatch)
+                .gt_class("car")
+                .detected_class(detected)
+                .build();
+            issue.confidence = confidence;
+            image.add_issue(issue);
+        }
+        let weights = PriorityWeights::default();
+        image.relabel_priority = weights.score(&image);
+        image
+    }
+
+    #[test]
+    fn confident_disagreements_rank_first() {
+        use IssueSeverity::{High, Low};
+        let images = [
+            image("notes.jpg", &[(Low, None, "car"), (Low, None, "car")]),
+            image("truck.jpg", &[(High, Some(0.9), "truck")]),
+            image("bus.jpg", &[(High, Some(0.3), "bus")]),
+        ];
+        // 3 + 2 * 0.9 + 0.5 * 2 classes
+        assert!((images[1].relabel_priority - 5.8).abs() < 1e-5);
+        assert!((images[0].relabel_priority - 1.5).abs() < 1e-5);
+
+        let names = |list: Vec<&ImageResult>| -> Vec<String> {
+            list.iter().map(|i| i.filename.clone()).collect()
+        };
+        assert_eq!(names(worklist(&images, Some(2))), ["truck.jpg", "bus.jpg"]);
+        let by_class = worklists_by_class(&images, None);
+        assert_eq!(names(by_class["car"].clone()).len(), 3);
+        assert_eq!(names(by_class["truck"].clone()), ["truck.jpg"]);
+    }
+
+    #[test]
+    fn weights_parse_by_name() {
+        let weights: PriorityWeights = "high=10, classes=0".parse().unwrap();
+        assert_eq!(weights.high, 10.0);
+        assert_eq!(weights.classes, 0.0);
+        assert_eq!(weights.medium, PriorityWeights::default().medium);
+        assert!("severe=1".parse::<PriorityWeights>().is_err());
+        assert!("high".parse::<PriorityWeights>().is_err());
+    }
+}
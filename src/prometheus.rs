@@ -0,0 +1,137 @@
+//! Audit results as Prometheus metrics, served over HTTP for scraping
+
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use crate::models::{AuditResult, IssueSeverity};
+
+/// Render `result` in the Prometheus text exposition format
+pub fn render(result: &AuditResult) -> String {
+    let mut issues: BTreeMap<(&str, String), usize> = BTreeMap::new();
+    for issue in result.all_issues() {
+        let severity = match issue.severity {
+            IssueSeverity::High => "high",
+            IssueSeverity::Medium => "medium",
+            IssueSeverity::Low => "low",
+        };
+        *issues
+            .entry((severity, issue.issue_type.to_string()))
+            .or_insert(0) += 1;
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# HELP gtaudit_issues_total Issues found, by severity and type"
+    );
+    let _ = writeln!(out, "# TYPE gtaudit_issues_total gauge");
+    for ((severity, issue_type), count) in &issues {
+        let _ = writeln!(
+            out,
+            "gtaudit_issues_total{{severity=\"{}\",type=\"{}\"}} {}",
+            severity, issue_type, count
+        );
+    }
+
+    let gauges = [
+        (
+            "gtaudit_images_total",
+            "Images in the dataset",
+            result.total_images,
+        ),
+        (
+            "gtaudit_images_audited",
+            "Images audited",
+            result.images_audited,
+        ),
+        (
+            "gtaudit_images_with_issues",
+            "Audited images with at least one issue",
+            result.images_with_issues(),
+        ),
+    ];
+    for (name, help, value) in gauges {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} gauge", name);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+    out
+}
+
+/// HTTP server answering `GET /metrics` with the latest rendered result
+pub struct MetricsServer {
+    metrics: Arc<Mutex<String>>,
+    handle: JoinHandle<()>,
+}
+
+impl MetricsServer {
+    /// Start serving on `addr` in a background thread
+    ///
+    /// A bare port such as `:9091` listens on all interfaces.
+    pub fn start(addr: &str, result: &AuditResult) -> Result<Self> {
+        let addr = match addr.strip_prefix(':') {
+            Some(port) => format!("0.0.0.0:{}", port),
+            None => addr.to_string(),
+        };
+        let server = tiny_http::Server::http(&addr)
+            .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", addr, e))?;
+
+        let metrics = Arc::new(Mutex::new(render(result)));
+        let shared = Arc::clone(&metrics);
+        let handle = std::thread::spawn(move || {
+            let content_type: tiny_http::Header = "Content-Type: text/plain; version=0.0.4"
+                .parse()
+                .expect("valid header");
+            for request in server.incoming_requests() {
+                let response = if request.url() == "/metrics" {
+                    let body = shared.lock().map(|m| m.clone()).unwrap_or_default();
+                    tiny_http::Response::from_string(body).with_header(content_type.clone())
+                } else {
+                    tiny_http::Response::from_string("Not found\n").with_status_code(404)
+                };
+                let _ = request.respond(response);
+            }
+        });
+
+        Ok(Self { metrics, handle })
+    }
+
+    /// Serve metrics for `result` from now on
+    pub fn update(&self, result: &AuditResult) {
+        let rendered = render(result);
+        if let Ok(mut metrics) = self.metrics.lock() {
+            *metrics = rendered;
+        }
+    }
+
+    /// Block until the server stops, which it only does when the process exits
+    pub fn join(self) {
+        let _ = self.handle.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ImageResult, Issue, IssueType};
+
+    #[test]
+    fn render_counts_issues_by_severity_and_type() {
+        let mut image = ImageResult::new("a.jpg".to_string(), &[], 0);
+        for severity in [IssueSeverity::High, IssueSeverity::High, IssueSeverity::Low] {
+            image.add_issue(Issue::builder("a.jpg", severity, IssueType::OutOfBounds).build());
+        }
+        let mut result = AuditResult::new("d".into(), "yolo".into(), 0.25, 0.5, 4, 2);
+        result.add_image_result(image);
+
+        let text = render(&result);
+        assert!(text.contains("gtaudit_issues_total{severity=\"high\",type=\"out_of_bounds\"} 2\n"));
+        assert!(text.contains("gtaudit_issues_total{severity=\"low\",type=\"out_of_bounds\"} 1\n"));
+        assert!(text.contains("gtaudit_images_total 4\n"));
+        assert!(text.contains("gtaudit_images_audited 2\n"));
+        assert!(text.contains("gtaudit_images_with_issues 1\n"));
+    }
+}
@@ -1,23 +1,76 @@
 //! Report generation for audit results
 
 use anyhow::Result;
+use base64::Engine;
+use image::{ImageFormat, Rgb, RgbImage};
 use minijinja::{context, Environment};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
+use std::io::Cursor;
 use std::path::Path;
 
-use crate::models::AuditResult;
+use crate::baseline::fingerprint;
+use crate::dataset::YoloDataset;
+use crate::heatmap::SpatialHeatmap;
+use crate::metrics::IouHistogram;
+use crate::models::{Annotation, AuditResult, ImageResult, Issue, IssueSeverity, IssueType};
+use crate::policy::{self, FailurePolicy};
 
 /// Trait for report generators
 pub trait Reporter {
-    fn generate(&self, result: &AuditResult, output_path: &Path) -> Result<()>;
+    /// The report as text
+    fn render(&self, result: &AuditResult) -> Result<String>;
+
+    /// Write the report to `output_path`
+    fn generate(&self, result: &AuditResult, output_path: &Path) -> Result<()> {
+        fs::write(output_path, self.render(result)?)?;
+        Ok(())
+    }
+}
+
+/// How much per-image data a JSON report carries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportDetail {
+    /// Summary counts only
+    Summary,
+    /// Summary plus the images with issues
+    #[default]
+    Flagged,
+    /// Every audited image, so `AuditResult::from_json` can recompute the summaries
+    Full,
+}
+
+impl std::str::FromStr for ReportDetail {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "summary" => Ok(Self::Summary),
+            "flagged" => Ok(Self::Flagged),
+            "full" => Ok(Self::Full),
+            other => anyhow::bail!(
+                "Unknown report detail '{}' (expected summary, flagged or full)",
+                other
+            ),
+        }
+    }
 }
 
 /// JSON report generator
-pub struct JsonReporter;
+pub struct JsonReporter {
+    pub detail: ReportDetail,
+    /// Flagged images included in the report, the worst first; the summary
+    /// still covers every image, and the report records that it was cut
+    pub max_images: Option<usize>,
+}
 
 impl JsonReporter {
     pub fn new() -> Self {
-        Self
+        Self {
+            detail: ReportDetail::default(),
+            max_images: None,
+        }
     }
 }
 
@@ -28,19 +81,157 @@ impl Default for JsonReporter {
 }
 
 impl Reporter for JsonReporter {
-    fn generate(&self, result: &AuditResult, output_path: &Path) -> Result<()> {
-        let json = serde_json::to_string_pretty(result)?;
-        fs::write(output_path, json)?;
-        Ok(())
+    fn render(&self, result: &AuditResult) -> Result<String> {
+        let mut value = serde_json::to_value(result)?;
+        if let Some(summary) = value["summary"].as_object_mut() {
+            summary.insert("images_clean".to_string(), result.images_clean().into());
+            summary.insert("clean_rate".to_string(), result.clean_rate().into());
+        }
+        if let Some(report) = value.as_object_mut() {
+            if let Some(max_images) = self.max_images {
+                report.insert(
+                    "flagged_images".to_string(),
+                    serde_json::to_value(result.worst_images(max_images))?,
+                );
+                let total = result.flagged_images().len();
+                report.insert("flagged_images_total".to_string(), total.into());
+                report.insert(
+                    "flagged_images_truncated".to_string(),
+                    (total > max_images).into(),
+                );
+            }
+            match self.detail {
+                ReportDetail::Summary => {
+                    report.remove("flagged_images");
+                }
+                ReportDetail::Flagged => {}
+                ReportDetail::Full => {
+                    report.insert("image_results".to_string(), result.full_image_results()?);
+                }
+            }
+        }
+        Ok(serde_json::to_string_pretty(&value)?)
     }
 }
 
+/// Flagged images shown per page of the HTML report
+pub const HTML_PAGE_SIZE: usize = 100;
+
+/// Flagged images an HTML report details by default; every card and
+/// thumbnail is in the file, so many more make it slow to open
+pub const HTML_MAX_IMAGES: usize = 1000;
+
 /// HTML report generator
-pub struct HtmlReporter;
+pub struct HtmlReporter {
+    /// Datasets flagged images are looked up in to draw thumbnails; none are
+    /// embedded when empty
+    thumbnail_sources: Vec<(Option<String>, YoloDataset)>,
+    /// Flagged images included in the report; the rest are only counted
+    max_images: usize,
+}
 
 impl HtmlReporter {
     pub fn new() -> Self {
-        Self
+        Self {
+            thumbnail_sources: Vec::new(),
+            max_images: HTML_MAX_IMAGES,
+        }
+    }
+
+    /// Include only the first `max_images` flagged images rather than
+    /// `HTML_MAX_IMAGES`, keeping large reports loadable
+    ///
+    /// The summary still covers every image.
+    pub fn with_max_images(mut self, max_images: usize) -> Self {
+        self.max_images = max_images;
+        self
+    }
+
+    /// Embed a thumbnail of each flagged image, found in `datasets` by split and key
+    pub fn with_thumbnails(mut self, datasets: Vec<(Option<String>, YoloDataset)>) -> Self {
+        self.thumbnail_sources = datasets;
+        self
+    }
+
+    /// PNG data URI of the image with its labels drawn, or `None` when it can't be loaded
+    fn thumbnail(&self, image: &ImageResult) -> Option<String> {
+        let dataset = self
+            .thumbnail_sources
+            .iter()
+            .find(|(split, _)| *split == image.split)
+            .map(|(_, dataset)| dataset)?;
+        let path = dataset.image_path(&image.filename)?;
+        let annotations = dataset.load_annotations(&path);
+        let (width, height) = THUMBNAIL_SIZE;
+        let mut thumb = image::open(&path).ok()?.thumbnail(width, height).to_rgb8();
+        draw_labels(&mut thumb, image, &annotations);
+
+        let mut png = Vec::new();
+        thumb
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .ok()?;
+        Some(format!(
+            "data:image/png;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(png)
+        ))
+    }
+}
+
+/// Largest (width, height) of thumbnails embedded in HTML reports
+pub const THUMBNAIL_SIZE: (u32, u32) = (320, 240);
+
+const CLEAN_COLOR: Rgb<u8> = Rgb([16, 185, 129]);
+const WARNING_COLOR: Rgb<u8> = Rgb([245, 158, 11]);
+const ERROR_COLOR: Rgb<u8> = Rgb([239, 68, 68]);
+
+/// Outline each label on a thumbnail, colored by the worst issue on its line
+///
+/// Labels without issues are green, those with a high-severity issue red and
+/// the rest amber.
+fn draw_labels(thumb: &mut RgbImage, image: &ImageResult, annotations: &[Annotation]) {
+    let (width, height) = thumb.dimensions();
+    for annotation in annotations {
+        let severities: Vec<IssueSeverity> = image
+            .issues
+            .iter()
+            .filter(|issue| {
+                issue.line_num == Some(annotation.line_num)
+                    || issue.other_line_num == Some(annotation.line_num)
+            })
+            .map(|issue| issue.severity)
+            .collect();
+        let color = if severities.contains(&IssueSeverity::High) {
+            ERROR_COLOR
+        } else if severities.is_empty() {
+            CLEAN_COLOR
+        } else {
+            WARNING_COLOR
+        };
+        let (x1, y1, x2, y2) = annotation.bbox.to_pixel_coords(width, height);
+        draw_rect(thumb, (x1, y1, x2, y2), color);
+    }
+}
+
+/// Draw a two pixel wide rectangle outline, clipped to the image
+fn draw_rect(img: &mut RgbImage, (x1, y1, x2, y2): (u32, u32, u32, u32), color: Rgb<u8>) {
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        return;
+    }
+    let (x2, y2) = (x2.min(width - 1), y2.min(height - 1));
+    for x in x1..=x2 {
+        for y in [y1, y1 + 1, y2.saturating_sub(1), y2] {
+            if y < height {
+                img.put_pixel(x, y, color);
+            }
+        }
+    }
+    for y in y1..=y2 {
+        for x in [x1, x1 + 1, x2.saturating_sub(1), x2] {
+            if x < width {
+                img.put_pixel(x, y, color);
+            }
+        }
     }
 }
 
@@ -51,32 +242,979 @@ impl Default for HtmlReporter {
 }
 
 impl Reporter for HtmlReporter {
-    fn generate(&self, result: &AuditResult, output_path: &Path) -> Result<()> {
+    fn render(&self, result: &AuditResult) -> Result<String> {
         let mut env = Environment::new();
         env.add_template("report", HTML_TEMPLATE)?;
 
+        let flagged_images = result.worst_images(self.max_images);
+        let thumbnails: Vec<Option<String>> = if self.thumbnail_sources.is_empty() {
+            Vec::new()
+        } else {
+            flagged_images
+                .iter()
+                .map(|image| self.thumbnail(image))
+                .collect()
+        };
+
         let template = env.get_template("report")?;
-        let html = template.render(context! {
+        Ok(template.render(context! {
             result => result,
             generated_at => &result.generated_at,
             dataset_path => &result.dataset_path,
             method => &result.method,
             confidence_threshold => result.confidence_threshold,
             iou_threshold => result.iou_threshold,
+            excluded_classes => &result.excluded_classes,
             total_images => result.total_images,
             images_audited => result.images_audited,
             images_with_issues => result.images_with_issues(),
+            images_clean => result.images_clean(),
+            clean_percent => format!("{:.1}", result.clean_rate() * 100.0),
             total_issues => result.total_issues(),
+            baselined_issues => result.summary.baselined_issues,
+            new_issues => result.new_issue_count(),
+            suppressed_issues => result.all_issues().filter(|i| i.suppressed).collect::<Vec<_>>(),
             high_count => result.high_count(),
             medium_count => result.medium_count(),
             low_count => result.low_count(),
-            issues_by_type => result.issues_by_type(),
-            flagged_images => &result.flagged_images,
-        })?;
+            issue_legend => IssueLegendEntry::from_result(result),
+            flagged_images => flagged_images,
+            flagged_total => result.flagged_images().len(),
+            page_size => HTML_PAGE_SIZE,
+            dataset_issues => &result.dataset_issues,
+            label_swaps => result
+                .label_swaps
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            per_class => &result.summary.per_class,
+            box_stats => &result.box_stats,
+            metrics => &result.metrics,
+            by_split => &result.by_split,
+            class_confusion => ConfusionTable::from_result(result),
+            spatial_heatmap => result.spatial_heatmap.as_ref().map(HeatmapView::new),
+            iou_histogram => result.iou_histogram.as_ref().map(IouHistogramView::new),
+            embed_thumbnails => !self.thumbnail_sources.is_empty(),
+            thumbnails => thumbnails,
+        })?)
+    }
+}
 
-        fs::write(output_path, html)?;
-        Ok(())
+/// Issue type found in a result, with the names and help shown in the HTML legend
+#[derive(Serialize)]
+struct IssueLegendEntry {
+    id: String,
+    code: &'static str,
+    name: &'static str,
+    help: &'static str,
+    count: usize,
+}
+
+impl IssueLegendEntry {
+    /// Entries for the types in `result`, most frequent first
+    fn from_result(result: &AuditResult) -> Vec<Self> {
+        result
+            .issues_by_type()
+            .into_iter()
+            .map(|(id, count)| {
+                let issue_type = id.parse::<IssueType>().ok();
+                Self {
+                    code: issue_type.as_ref().map_or("", |t| t.short_code()),
+                    name: issue_type.as_ref().map_or("", |t| t.display_name()),
+                    help: issue_type.as_ref().map_or("", |t| t.help_text()),
+                    id,
+                    count,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Class confusion pairs laid out as a GT × detected grid for the HTML report
+#[derive(Serialize)]
+struct ConfusionTable {
+    columns: Vec<String>,
+    rows: Vec<ConfusionRow>,
+}
+
+#[derive(Serialize)]
+struct ConfusionRow {
+    gt_class: String,
+    cells: Vec<ConfusionCell>,
+}
+
+#[derive(Serialize)]
+struct ConfusionCell {
+    count: usize,
+    /// Count relative to the largest cell, used for the background color
+    intensity: f32,
+}
+
+impl ConfusionTable {
+    fn from_result(result: &AuditResult) -> Option<Self> {
+        if result.class_confusion.is_empty() {
+            return None;
+        }
+
+        let gt_classes: BTreeSet<&String> =
+            result.class_confusion.keys().map(|(gt, _)| gt).collect();
+        let det_classes: BTreeSet<&String> =
+            result.class_confusion.keys().map(|(_, det)| det).collect();
+        let max = result.class_confusion.values().copied().max().unwrap_or(1) as f32;
+
+        let rows = gt_classes
+            .iter()
+            .map(|gt| ConfusionRow {
+                gt_class: gt.to_string(),
+                cells: det_classes
+                    .iter()
+                    .map(|det| {
+                        let count = result
+                            .class_confusion
+                            .get(&(gt.to_string(), det.to_string()))
+                            .copied()
+                            .unwrap_or(0);
+                        ConfusionCell {
+                            count,
+                            intensity: count as f32 / max,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Some(Self {
+            columns: det_classes.into_iter().cloned().collect(),
+            rows,
+        })
+    }
+}
+
+/// Spatial heatmap laid out as SVG cells for the HTML report
+#[derive(Serialize)]
+struct HeatmapView {
+    grid: usize,
+    total: usize,
+    cells: Vec<HeatmapCell>,
+}
+
+#[derive(Serialize)]
+struct HeatmapCell {
+    row: usize,
+    col: usize,
+    count: usize,
+    /// HSL lightness in percent; fuller cells are darker
+    lightness: u32,
+}
+
+impl HeatmapView {
+    fn new(heatmap: &SpatialHeatmap) -> Self {
+        let max = heatmap.max().max(1) as f32;
+        let cells = heatmap
+            .counts()
+            .iter()
+            .enumerate()
+            .flat_map(|(row, counts)| {
+                counts
+                    .iter()
+                    .enumerate()
+                    .map(move |(col, &count)| HeatmapCell {
+                        row,
+                        col,
+                        count,
+                        lightness: (95.0 - 60.0 * count as f32 / max).round() as u32,
+                    })
+            })
+            .collect();
+        Self {
+            grid: heatmap.grid(),
+            total: heatmap.total(),
+            cells,
+        }
+    }
+}
+
+/// IoU histogram laid out as SVG bars for the HTML report, in a view box 10
+/// units wide per bucket and 100 high
+#[derive(Serialize)]
+struct IouHistogramView {
+    width: usize,
+    total: usize,
+    bars: Vec<IouBar>,
+}
+
+#[derive(Serialize)]
+struct IouBar {
+    x: usize,
+    y: u32,
+    height: u32,
+    /// IoU range, e.g. `0.30-0.40`
+    range: String,
+    count: usize,
+}
+
+impl IouHistogramView {
+    fn new(histogram: &IouHistogram) -> Self {
+        let max = histogram.max().max(1) as f32;
+        let bars = histogram
+            .buckets()
+            .iter()
+            .enumerate()
+            .map(|(i, &(low, high, count))| {
+                let height = (100.0 * count as f32 / max).round() as u32;
+                IouBar {
+                    x: 10 * i + 1,
+                    y: 100 - height,
+                    height,
+                    range: format!("{:.2}-{:.2}", low, high),
+                    count,
+                }
+            })
+            .collect();
+        Self {
+            width: 10 * histogram.buckets().len(),
+            total: histogram.total(),
+            bars,
+        }
+    }
+}
+
+/// JUnit XML report generator for CI test dashboards
+///
+/// Each audited image becomes a `<testcase>`. High severity issues are
+/// `<failure>`s and medium ones `<error>`s, so either fails the test; low
+/// severity issues are only noted in `<system-out>`. Baselined and suppressed
+/// issues are left out, so accepting an issue makes its test case pass.
+pub struct JunitReporter {
+    /// Report medium severity issues as `<failure>` rather than `<error>`
+    pub medium_as_failure: bool,
+    /// Leave out the passing test cases of images without issues
+    pub flagged_only: bool,
+    /// Failure thresholds, recorded as suite properties
+    pub policy: FailurePolicy,
+}
+
+/// Outcome of one JUnit test case
+#[derive(PartialEq, Eq)]
+enum JunitOutcome {
+    Passed,
+    Failed,
+    Errored,
+}
+
+impl JunitReporter {
+    pub fn new() -> Self {
+        Self {
+            medium_as_failure: false,
+            flagged_only: false,
+            policy: FailurePolicy::default(),
+        }
+    }
+
+    /// Append a test case for the new issues among `issues` and return its outcome
+    fn push_testcase(
+        &self,
+        xml: &mut String,
+        name: &str,
+        classname: &str,
+        issues: &[Issue],
+    ) -> JunitOutcome {
+        let issues: Vec<&Issue> = issues.iter().filter(|i| i.is_new()).collect();
+        if issues.is_empty() {
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\"/>\n",
+                xml_escape(name),
+                classname
+            ));
+            return JunitOutcome::Passed;
+        }
+
+        xml.push_str(&format!(
+            "    <testcase name=\"{}\" classname=\"{}\">\n",
+            xml_escape(name),
+            classname
+        ));
+        let mut outcome = JunitOutcome::Passed;
+        let mut notes = Vec::new();
+        for issue in issues {
+            let element = match issue.severity {
+                IssueSeverity::High => "failure",
+                IssueSeverity::Medium if self.medium_as_failure => "failure",
+                IssueSeverity::Medium => "error",
+                IssueSeverity::Low => {
+                    notes.push(format!("[{}] {}", issue.issue_type, issue.description));
+                    continue;
+                }
+            };
+            if element == "failure" {
+                outcome = JunitOutcome::Failed;
+            } else if outcome == JunitOutcome::Passed {
+                outcome = JunitOutcome::Errored;
+            }
+            push_junit_issue(xml, element, issue);
+        }
+        if !notes.is_empty() {
+            xml.push_str(&format!(
+                "      <system-out>{}</system-out>\n",
+                xml_escape(&notes.join("\n"))
+            ));
+        }
+        xml.push_str("    </testcase>\n");
+        outcome
+    }
+
+    /// Suite `<properties>`: how the audit was run and what it must stay under
+    fn properties(&self, result: &AuditResult) -> Vec<(&'static str, String)> {
+        let mut properties = vec![
+            ("generator", result.generator.clone()),
+            ("generator_version", result.generator_version.clone()),
+            ("method", result.method.clone()),
+            (
+                "confidence_threshold",
+                result.confidence_threshold.to_string(),
+            ),
+            ("iou_threshold", result.iou_threshold.to_string()),
+        ];
+        let thresholds = [
+            ("fail_on_high", self.policy.max_high),
+            ("fail_on_medium", self.policy.max_medium),
+            ("fail_on_low", self.policy.max_low),
+            ("fail_on_total", self.policy.max_total),
+        ];
+        for (name, max) in thresholds {
+            if let Some(max) = max {
+                properties.push((name, max.to_string()));
+            }
+        }
+        properties
+    }
+}
+
+impl Default for JunitReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reporter for JunitReporter {
+    fn render(&self, result: &AuditResult) -> Result<String> {
+        let classname = xml_escape(&result.dataset_path);
+
+        let mut cases = String::new();
+        let mut outcomes = Vec::new();
+        for image in &result.image_results {
+            if self.flagged_only && !image.issues.iter().any(Issue::is_new) {
+                continue;
+            }
+            outcomes.push(self.push_testcase(
+                &mut cases,
+                &image.filename,
+                &classname,
+                &image.issues,
+            ));
+        }
+        // Dataset-level issues get a test case of their own
+        if result.dataset_issues.iter().any(Issue::is_new) {
+            outcomes.push(self.push_testcase(
+                &mut cases,
+                "dataset",
+                &classname,
+                &result.dataset_issues,
+            ));
+        }
+
+        let tests = outcomes.len();
+        let failures = outcomes
+            .iter()
+            .filter(|o| **o == JunitOutcome::Failed)
+            .count();
+        let errors = outcomes
+            .iter()
+            .filter(|o| **o == JunitOutcome::Errored)
+            .count();
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuites name=\"gt-audit\" tests=\"{}\" failures=\"{}\" errors=\"{}\" time=\"{:.3}\">\n",
+            tests, failures, errors, result.elapsed_secs
+        ));
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"0\" time=\"{:.3}\" timestamp=\"{}\">\n",
+            classname,
+            tests,
+            failures,
+            errors,
+            result.elapsed_secs,
+            xml_escape(&result.generated_at)
+        ));
+        xml.push_str("    <properties>\n");
+        for (name, value) in self.properties(result) {
+            xml.push_str(&format!(
+                "      <property name=\"{}\" value=\"{}\"/>\n",
+                name,
+                xml_escape(&value)
+            ));
+        }
+        xml.push_str("    </properties>\n");
+        xml.push_str(&cases);
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        Ok(xml)
+    }
+}
+
+/// Append a JUnit `<failure>` or `<error>` element describing `issue`
+fn push_junit_issue(xml: &mut String, element: &str, issue: &Issue) {
+    let mut body = format!("severity: {:?}", issue.severity).to_lowercase();
+    if let Some(line_num) = issue.line_num {
+        body.push_str(&format!("\nline: {}", line_num));
+    }
+    if let Some(explanation) = &issue.explanation {
+        body.push_str(&format!("\n{}", explanation));
+    }
+    xml.push_str(&format!(
+        "      <{} message=\"{}\" type=\"{}\">{}</{}>\n",
+        element,
+        xml_escape(&issue.description),
+        issue.issue_type,
+        xml_escape(&body),
+        element
+    ));
+}
+
+/// Markdown report generator, compact enough for a pull-request comment
+pub struct MarkdownReporter {
+    /// Flagged images listed in detail; the rest are only counted
+    pub max_images: usize,
+    /// Use emoji severity markers matching the terminal output
+    pub github: bool,
+    /// Failure thresholds, echoed in the footer
+    pub policy: FailurePolicy,
+}
+
+/// Longest issue description shown before truncating
+const MD_MAX_DESCRIPTION: usize = 120;
+
+impl MarkdownReporter {
+    pub fn new() -> Self {
+        Self {
+            max_images: 20,
+            github: false,
+            policy: FailurePolicy::default(),
+        }
+    }
+
+    fn severity_label(&self, severity: IssueSeverity) -> &'static str {
+        match (severity, self.github) {
+            (IssueSeverity::High, true) => "🔴 High",
+            (IssueSeverity::Medium, true) => "🟡 Medium",
+            (IssueSeverity::Low, true) => "⚪ Low",
+            (IssueSeverity::High, false) => "High",
+            (IssueSeverity::Medium, false) => "Medium",
+            (IssueSeverity::Low, false) => "Low",
+        }
+    }
+
+    /// Shields.io badge with the audit outcome, as a Markdown image
+    ///
+    /// Shows pass/fail when failure thresholds are set, otherwise the count
+    /// of the most severe issues.
+    pub fn badge(&self, result: &AuditResult) -> String {
+        let (message, color) = if self.policy.is_active() {
+            if policy::check_thresholds(result, &self.policy) {
+                ("passing".to_string(), "brightgreen")
+            } else {
+                ("failing".to_string(), "red")
+            }
+        } else if result.high_count() > 0 {
+            (format!("{} high", result.high_count()), "red")
+        } else if result.medium_count() > 0 {
+            (format!("{} medium", result.medium_count()), "yellow")
+        } else if result.low_count() > 0 {
+            (format!("{} low", result.low_count()), "lightgrey")
+        } else {
+            ("clean".to_string(), "brightgreen")
+        };
+        format!(
+            "![gt-audit](https://img.shields.io/badge/{}-{}-{})",
+            shields_escape("gt-audit"),
+            shields_escape(&message),
+            color
+        )
+    }
+}
+
+impl Default for MarkdownReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reporter for MarkdownReporter {
+    fn render(&self, result: &AuditResult) -> Result<String> {
+        let mut md = String::from("## gt-audit report\n\n");
+        md.push_str(&format!(
+            "**Dataset:** `{}` · **Method:** {} · **Audited:** {}/{} images · **Flagged:** {} images, {} issues\n\n",
+            result.dataset_path,
+            result.method,
+            result.images_audited,
+            result.total_images,
+            result.images_with_issues(),
+            result.total_issues()
+        ));
+        if result.partial {
+            md.push_str(&format!(
+                "> **Interrupted:** {} images were not audited; results are partial\n\n",
+                result.images_skipped
+            ));
+        }
+        for swap in &result.label_swaps {
+            md.push_str(&format!("> **Warning:** {}\n\n", swap));
+        }
+        let summary = &result.summary;
+        if summary.baselined_issues + summary.suppressed_issues > 0 {
+            md.push_str(&format!("**New:** {} issues", result.new_issue_count()));
+            if summary.baselined_issues > 0 {
+                md.push_str(&format!(
+                    " · **Baselined:** {} issues",
+                    summary.baselined_issues
+                ));
+            }
+            if summary.suppressed_issues > 0 {
+                md.push_str(&format!(
+                    " · **Suppressed:** {} issues",
+                    summary.suppressed_issues
+                ));
+            }
+            md.push_str("\n\n");
+        }
+
+        md.push_str("| Severity | Issues |\n|---|---:|\n");
+        for (severity, count) in [
+            (IssueSeverity::High, result.high_count()),
+            (IssueSeverity::Medium, result.medium_count()),
+            (IssueSeverity::Low, result.low_count()),
+        ] {
+            md.push_str(&format!(
+                "| {} | {} |\n",
+                self.severity_label(severity),
+                count
+            ));
+        }
+        md.push('\n');
+
+        if !result.summary.by_type.is_empty() {
+            let mut types: Vec<(&String, &usize)> = result.summary.by_type.iter().collect();
+            types.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            md.push_str("**Top issue types**\n\n| Type | Issues |\n|---|---:|\n");
+            for (issue_type, count) in types.into_iter().take(5) {
+                md.push_str(&format!("| {} | {} |\n", issue_type, count));
+            }
+            md.push('\n');
+        }
+
+        if !result.summary.per_class.is_empty() {
+            md.push_str("**Per class**\n\n");
+            md.push_str(
+                "| Class | Images | Annotations | Issues | Class mismatch | Missing | Spurious | Median area | Median aspect |\n",
+            );
+            md.push_str("|---|---:|---:|---:|---:|---:|---:|---:|---:|\n");
+            for (class, stats) in &result.summary.per_class {
+                let (area, aspect) = match result.box_stats.classes.get(class) {
+                    Some(boxes) => (
+                        format!("{:.4}", boxes.area.median),
+                        format!("{:.2}", boxes.aspect_ratio.median),
+                    ),
+                    None => ("-".to_string(), "-".to_string()),
+                };
+                md.push_str(&format!(
+                    "| {} | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+                    md_cell(class, MD_MAX_DESCRIPTION),
+                    stats.images,
+                    stats.annotations,
+                    stats.issues,
+                    stats.class_mismatches,
+                    stats.missing_labels,
+                    stats.spurious_labels,
+                    area,
+                    aspect
+                ));
+            }
+            md.push('\n');
+        }
+
+        // Suppressed issues are listed on their own at the end
+        let flagged: Vec<&ImageResult> = result
+            .flagged_images()
+            .into_iter()
+            .filter(|image| image.issues.iter().any(|i| !i.suppressed))
+            .collect();
+        if !flagged.is_empty() {
+            let shown = flagged.len().min(self.max_images);
+            md.push_str(&format!(
+                "**Flagged images** (showing {} of {})\n\n",
+                shown,
+                flagged.len()
+            ));
+            for image in flagged.into_iter().take(shown) {
+                let issues: Vec<&Issue> = image.issues.iter().filter(|i| !i.suppressed).collect();
+                let name = match &image.split {
+                    Some(split) => format!("[{}] {}", split, image.filename),
+                    None => image.filename.clone(),
+                };
+                md.push_str(&format!(
+                    "<details>\n<summary><code>{}</code> · {} issues</summary>\n\n",
+                    html_escape(&name),
+                    issues.len()
+                ));
+                md.push_str("| Severity | Type | Line | Description |\n|---|---|---:|---|\n");
+                for issue in issues {
+                    md.push_str(&format!(
+                        "| {} | {} | {} | {} |\n",
+                        self.severity_label(issue.severity),
+                        issue.issue_type,
+                        issue.line_num.map(|l| l.to_string()).unwrap_or_default(),
+                        md_cell(&issue.description, MD_MAX_DESCRIPTION)
+                    ));
+                }
+                md.push_str("\n</details>\n\n");
+            }
+        }
+
+        let dataset_issues: Vec<&Issue> = result
+            .dataset_issues
+            .iter()
+            .filter(|i| !i.suppressed)
+            .collect();
+        if !dataset_issues.is_empty() {
+            let shown = dataset_issues.len().min(self.max_images);
+            md.push_str(&format!(
+                "**Dataset issues** (showing {} of {})\n\n",
+                shown,
+                dataset_issues.len()
+            ));
+            md.push_str("| Severity | Type | File | Description |\n|---|---|---|---|\n");
+            for issue in dataset_issues.into_iter().take(shown) {
+                md.push_str(&format!(
+                    "| {} | {} | `{}` | {} |\n",
+                    self.severity_label(issue.severity),
+                    issue.issue_type,
+                    md_cell(&issue.image, MD_MAX_DESCRIPTION),
+                    md_cell(&issue.description, MD_MAX_DESCRIPTION)
+                ));
+            }
+            md.push('\n');
+        }
+
+        let suppressed: Vec<&Issue> = result.all_issues().filter(|i| i.suppressed).collect();
+        if !suppressed.is_empty() {
+            let shown = suppressed.len().min(self.max_images);
+            md.push_str(&format!(
+                "<details>\n<summary>Suppressed issues (showing {} of {})</summary>\n\n",
+                shown,
+                suppressed.len()
+            ));
+            md.push_str("| Severity | Type | File | Description |\n|---|---|---|---|\n");
+            for issue in suppressed.into_iter().take(shown) {
+                md.push_str(&format!(
+                    "| {} | {} | `{}` | {} |\n",
+                    self.severity_label(issue.severity),
+                    issue.issue_type,
+                    md_cell(&issue.image, MD_MAX_DESCRIPTION),
+                    md_cell(&issue.description, MD_MAX_DESCRIPTION)
+                ));
+            }
+            md.push_str("\n</details>\n\n");
+        }
+
+        md.push_str(&format!(
+            "<details>\n<summary>README badge</summary>\n\n```markdown\n{}\n```\n\n</details>\n\n",
+            self.badge(result)
+        ));
+
+        let mut thresholds = vec![
+            format!("confidence {:.2}", result.confidence_threshold),
+            format!("IoU {:.2}", result.iou_threshold),
+        ];
+        if let Some(max) = self.policy.max_high {
+            thresholds.push(format!("fail-on-high {}", max));
+        }
+        if let Some(max) = self.policy.max_medium {
+            thresholds.push(format!("fail-on-medium {}", max));
+        }
+        if let Some(max) = self.policy.max_low {
+            thresholds.push(format!("fail-on-low {}", max));
+        }
+        if let Some(max) = self.policy.max_total {
+            thresholds.push(format!("fail-on-total {}", max));
+        }
+        for (issue_type, max) in &self.policy.max_by_type {
+            thresholds.push(format!("fail-on {} {}", issue_type, max));
+        }
+        md.push_str(&format!(
+            "---\n<sub>Generated by {} v{} · {}</sub>\n",
+            result.generator,
+            result.generator_version,
+            thresholds.join(" · ")
+        ));
+
+        Ok(md)
+    }
+}
+
+/// Make text safe for a single Markdown table cell, truncating long values
+fn md_cell(s: &str, max_chars: usize) -> String {
+    let mut text: String = s.chars().take(max_chars).collect();
+    if s.chars().count() > max_chars {
+        text.push('…');
+    }
+    html_escape(&text).replace('|', "\\|").replace('\n', " ")
+}
+
+/// Escape text for a shields.io static badge path segment
+fn shields_escape(s: &str) -> String {
+    s.replace('-', "--").replace('_', "__").replace(' ', "%20")
+}
+
+/// Escape characters GitHub would otherwise treat as inline HTML
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// SARIF 2.1.0 report generator for GitHub code scanning
+///
+/// Each issue becomes a result located in its label file, with the GT line
+/// as the region when known.
+pub struct SarifReporter;
+
+impl SarifReporter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Build the SARIF log for `result`
+    pub fn to_value(&self, result: &AuditResult) -> serde_json::Value {
+        let with_baseline = result.summary.baselined_issues > 0;
+
+        let rule_ids: BTreeSet<String> = result
+            .flagged_images()
+            .into_iter()
+            .flat_map(|img| &img.issues)
+            .chain(&result.dataset_issues)
+            .map(|i| i.issue_type.to_string())
+            .collect();
+        let rules: Vec<SarifRule> = rule_ids
+            .into_iter()
+            .map(|id| {
+                let issue_type = id.parse::<IssueType>().ok();
+                SarifRule {
+                    name: issue_type.as_ref().map(|t| t.display_name()),
+                    short_description: SarifMessage {
+                        text: rule_description(&id).to_string(),
+                    },
+                    help: issue_type.as_ref().map(|t| SarifMessage {
+                        text: t.help_text().to_string(),
+                    }),
+                    id,
+                }
+            })
+            .collect();
+
+        // Dataset-level issues are located at the file they name
+        let located = result
+            .flagged_images()
+            .into_iter()
+            .flat_map(|image| {
+                let uri = artifact_uri(image.label_file.as_deref().unwrap_or(&image.filename));
+                image.issues.iter().map(move |issue| (uri.clone(), issue))
+            })
+            .chain(
+                result
+                    .dataset_issues
+                    .iter()
+                    .map(|issue| (artifact_uri(&issue.image), issue)),
+            );
+
+        let mut results = Vec::new();
+        for (uri, issue) in located {
+            results.push(SarifResult {
+                rule_id: issue.issue_type.to_string(),
+                level: match issue.severity {
+                    IssueSeverity::High => "error",
+                    IssueSeverity::Medium => "warning",
+                    IssueSeverity::Low => "note",
+                },
+                message: SarifMessage {
+                    text: match &issue.explanation {
+                        Some(explanation) => {
+                            format!("{} ({})", issue.description, explanation)
+                        }
+                        None => issue.description.clone(),
+                    },
+                },
+                locations: vec![SarifLocation {
+                    physical_location: SarifPhysicalLocation {
+                        artifact_location: SarifArtifactLocation { uri },
+                        region: issue
+                            .line_num
+                            .filter(|&l| l > 0)
+                            .map(|start_line| SarifRegion { start_line }),
+                    },
+                }],
+                partial_fingerprints: [("gtAudit/v1".to_string(), fingerprint(issue))]
+                    .into_iter()
+                    .collect(),
+                baseline_state: with_baseline.then_some(if issue.baselined {
+                    "unchanged"
+                } else {
+                    "new"
+                }),
+                suppressions: if issue.suppressed {
+                    vec![serde_json::json!({ "kind": "external" })]
+                } else {
+                    Vec::new()
+                },
+            });
+        }
+
+        serde_json::json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": result.generator,
+                        "version": result.generator_version,
+                        "informationUri": "https://github.com/ARTIFACTIQ/gt-audit",
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }],
+        })
+    }
+}
+
+impl Default for SarifReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reporter for SarifReporter {
+    fn render(&self, result: &AuditResult) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.to_value(result))?)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRule {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'static str>,
+    short_description: SarifMessage,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<SarifMessage>,
+}
+
+#[derive(Serialize)]
+struct SarifMessage {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    level: &'static str,
+    message: SarifMessage,
+    locations: Vec<SarifLocation>,
+    partial_fingerprints: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline_state: Option<&'static str>,
+    /// `[{"kind": "external"}]` for issues accepted in the suppression file
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    suppressions: Vec<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    physical_location: SarifPhysicalLocation,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<SarifRegion>,
+}
+
+#[derive(Serialize)]
+struct SarifArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRegion {
+    start_line: usize,
+}
+
+/// One-line description of each issue type, used as the SARIF rule text
+fn rule_description(rule_id: &str) -> &'static str {
+    match rule_id {
+        "class_mismatch" => "Model detects a different class than the GT label",
+        "missing_label" => "Model detects an object with no GT label nearby",
+        "spurious_label" => "GT label where the model detects nothing",
+        "localization" => "GT box disagrees with the detected box of the same class, or with its class's usual size and shape",
+        "out_of_bounds" => "GT box extends outside the image or has non-positive size",
+        "image_quality" => {
+            "Image is too blurry, dark or overexposed for its labels to be learnable, or the model's scores on it are degenerate"
+        }
+        "parse_error" => "Label row is neither a valid box nor a valid polygon",
+        "invalid_class_id" => "Label class id is not declared by the dataset",
+        "duplicate_annotation" => "Two GT boxes of the same class are near-identical",
+        "annotation_overlap" => "Two GT boxes of the same class overlap heavily",
+        "duplicate_image" => "Image is a near-duplicate of another image in the dataset",
+        "image_error" => "Image could not be decoded or audited",
+        "dataset_consistency" => "Label file without an image, or image without labels",
+        "suspect_pre_annotation" => "GT box stored with a low pre-annotation confidence",
+        "coordinate_format" => "Label file uses pixel or corner coordinates instead of normalized boxes",
+        "box_size" => "GT box is only a few pixels in size or covers nearly the whole image",
+        "exif_orientation" => "Image is stored rotated with an EXIF orientation tag",
+        _ => "Ground truth label issue",
+    }
+}
+
+/// Relative, forward-slash URI for a label file, as code scanning expects
+fn artifact_uri(path: &str) -> String {
+    let path = Path::new(path);
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path);
+    let uri = relative.to_string_lossy().replace('\\', "/");
+    uri.strip_prefix("./").unwrap_or(&uri).to_string()
+}
+
+/// Escape the five XML special characters
+pub(crate) fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
     }
+    out
 }
 
 const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
@@ -174,6 +1312,23 @@ const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
             display: none;
         }
         .issue-details.expanded { display: block; }
+        .thumbnail {
+            display: block;
+            max-width: 320px;
+            max-height: 240px;
+            margin-bottom: 0.75rem;
+            border-radius: 4px;
+        }
+        .thumbnail.placeholder {
+            width: 320px;
+            height: 120px;
+            display: flex;
+            align-items: center;
+            justify-content: center;
+            background: rgba(0,0,0,0.2);
+            color: var(--text-muted);
+            font-size: 0.8rem;
+        }
         .issue-item {
             padding: 0.5rem;
             margin-bottom: 0.5rem;
@@ -182,6 +1337,90 @@ const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
             font-size: 0.85rem;
         }
         .issue-type { color: var(--primary); font-weight: 600; }
+        .controls {
+            display: flex;
+            flex-wrap: wrap;
+            gap: 0.75rem 1.5rem;
+            align-items: center;
+            margin-bottom: 1rem;
+            font-size: 0.85rem;
+        }
+        .controls input[type="search"], .controls select, .controls button {
+            background: var(--bg-card);
+            border: 1px solid var(--border);
+            border-radius: 4px;
+            color: var(--text);
+            padding: 0.35rem 0.6rem;
+            font: inherit;
+        }
+        .controls input[type="search"] { min-width: 260px; }
+        .controls button { cursor: pointer; }
+        .controls button:hover { border-color: var(--primary); }
+        .filter-group label { margin-right: 0.75rem; white-space: nowrap; }
+        .filter-title { color: var(--text-muted); margin-right: 0.5rem; }
+        #showing, #page-info { color: var(--text-muted); }
+        .pager { display: flex; gap: 0.75rem; align-items: center; margin: 1rem 0; font-size: 0.85rem; }
+        .pager button {
+            background: var(--bg-card);
+            border: 1px solid var(--border);
+            border-radius: 4px;
+            color: var(--text);
+            padding: 0.35rem 0.6rem;
+            cursor: pointer;
+        }
+        .pager button:disabled { opacity: 0.4; cursor: default; }
+        .truncated {
+            padding: 0.75rem 1rem;
+            margin-bottom: 1rem;
+            border: 1px solid var(--warning);
+            border-radius: 8px;
+            color: var(--warning);
+            font-size: 0.85rem;
+        }
+        .hidden { display: none !important; }
+        .issue-item.baselined { opacity: 0.55; }
+        .badge-baselined { background: var(--border); color: var(--text-muted); margin: 0 0.5rem 0 0; }
+        h2.inline { display: inline; }
+        .data-table {
+            width: 100%;
+            border-collapse: collapse;
+            font-size: 0.85rem;
+            margin-bottom: 1rem;
+        }
+        .data-table th, .data-table td {
+            padding: 0.5rem 0.75rem;
+            border-bottom: 1px solid var(--border);
+            text-align: right;
+        }
+        .data-table th:first-child, .data-table td:first-child { text-align: left; }
+        .data-table th {
+            color: var(--text-muted);
+            font-weight: 600;
+            cursor: pointer;
+            user-select: none;
+        }
+        .data-table th:hover { color: var(--primary); }
+        .confusion-table th { cursor: default; }
+        .confusion-table td { text-align: center; }
+        .heatmap {
+            width: 100%;
+            max-width: 400px;
+            border: 1px solid var(--border);
+        }
+        .iou-histogram {
+            width: 100%;
+            max-width: 600px;
+            height: 160px;
+            border-bottom: 1px solid var(--border);
+        }
+        .iou-axis {
+            display: flex;
+            justify-content: space-between;
+            max-width: 600px;
+        }
+        .legend-table th { cursor: default; }
+        .legend-table td:nth-child(2), .legend-table th:nth-child(2),
+        .legend-table td:last-child, .legend-table th:last-child { text-align: left; }
         footer {
             margin-top: 3rem;
             padding-top: 2rem;
@@ -198,8 +1437,22 @@ const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
         <h1>Ground Truth Audit Report</h1>
         <p class="meta">
             Generated: {{ generated_at }} | Method: {{ method }} |
-            Confidence: {{ confidence_threshold }} | IoU: {{ iou_threshold }}
+            Confidence: {{ confidence_threshold }} | IoU: {{ iou_threshold }}{% if excluded_classes %} |
+            Excluded classes: {{ excluded_classes|join(", ") }}{% endif %}
         </p>
+        {% if result.partial %}
+        <div class="truncated">
+            The audit was interrupted: {{ result.images_skipped }} images were not audited, so
+            these results are partial.
+        </div>
+        {% endif %}
+        {% if label_swaps %}
+        <div class="truncated">
+            {% for swap in label_swaps %}
+            <div>⚠️ {{ swap }}</div>
+            {% endfor %}
+        </div>
+        {% endif %}
 
         <div class="summary-grid">
             <div class="summary-card">
@@ -214,6 +1467,10 @@ const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
                 <div class="value">{{ images_with_issues }}</div>
                 <div class="label">With Issues</div>
             </div>
+            <div class="summary-card">
+                <div class="value">{{ images_clean }}</div>
+                <div class="label">Clean ({{ clean_percent }}%)</div>
+            </div>
             <div class="summary-card">
                 <div class="value high">{{ high_count }}</div>
                 <div class="label">High Severity</div>
@@ -226,14 +1483,263 @@ const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
                 <div class="value low">{{ low_count }}</div>
                 <div class="label">Low Severity</div>
             </div>
+            {% if baselined_issues or suppressed_issues %}
+            <div class="summary-card">
+                <div class="value">{{ new_issues }}</div>
+                <div class="label">New Issues</div>
+            </div>
+            {% endif %}
+            {% if baselined_issues %}
+            <div class="summary-card">
+                <div class="value low">{{ baselined_issues }}</div>
+                <div class="label">Baselined</div>
+            </div>
+            {% endif %}
+            {% if suppressed_issues %}
+            <div class="summary-card">
+                <div class="value low">{{ suppressed_issues|length }}</div>
+                <div class="label">Suppressed</div>
+            </div>
+            {% endif %}
+        </div>
+
+        {% if issue_legend %}
+        <div class="issues-section">
+            <h2>Issue Types</h2>
+            <table class="data-table legend-table">
+                <thead>
+                    <tr><th>Code</th><th>Type</th><th>Issues</th><th>What it means</th></tr>
+                </thead>
+                <tbody>
+                    {% for entry in issue_legend %}
+                    <tr>
+                        <td>{{ entry.code }}</td>
+                        <td>{{ entry.name or entry.id }}</td>
+                        <td>{{ entry.count }}</td>
+                        <td>{{ entry.help }}</td>
+                    </tr>
+                    {% endfor %}
+                </tbody>
+            </table>
+        </div>
+        {% endif %}
+
+        {% if by_split %}
+        <div class="issues-section">
+            <h2>Per-Split Breakdown</h2>
+            <table class="data-table sortable">
+                <thead>
+                    <tr>
+                        <th>Split</th>
+                        <th>Images</th>
+                        <th>Audited</th>
+                        <th>With Issues</th>
+                        <th>High</th>
+                        <th>Medium</th>
+                        <th>Low</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {% for name, split in by_split|dictsort %}
+                    <tr>
+                        <td>{{ name }}</td>
+                        <td>{{ split.total_images }}</td>
+                        <td>{{ split.images_audited }}</td>
+                        <td>{{ split.images_with_issues }}</td>
+                        <td class="high">{{ split.by_severity.high or 0 }}</td>
+                        <td class="medium">{{ split.by_severity.medium or 0 }}</td>
+                        <td class="low">{{ split.by_severity.low or 0 }}</td>
+                    </tr>
+                    {% endfor %}
+                </tbody>
+            </table>
+        </div>
+        {% endif %}
+
+        {% if per_class %}
+        <div class="issues-section">
+            <h2>Per-Class Breakdown</h2>
+            <table class="data-table sortable">
+                <thead>
+                    <tr>
+                        <th>Class</th>
+                        <th>Images</th>
+                        <th>Annotations</th>
+                        <th>Issues</th>
+                        <th>High</th>
+                        <th>Medium</th>
+                        <th>Class Mismatches</th>
+                        <th>Missing Labels</th>
+                        <th>Spurious Labels</th>
+                        {% if box_stats %}
+                        <th>Median Area</th>
+                        <th>Median Aspect</th>
+                        {% endif %}
+                    </tr>
+                </thead>
+                <tbody>
+                    {% for name, c in per_class|items %}
+                    <tr>
+                        <td>{{ name }}</td>
+                        <td>{{ c.images }}</td>
+                        <td>{{ c.annotations }}</td>
+                        <td>{{ c.issues }}</td>
+                        <td class="high">{{ c.high_issues }}</td>
+                        <td class="medium">{{ c.medium_issues }}</td>
+                        <td>{{ c.class_mismatches }}</td>
+                        <td>{{ c.missing_labels }}</td>
+                        <td>{{ c.spurious_labels }}</td>
+                        {% if box_stats %}
+                        {% if box_stats[name] %}
+                        <td title="5th-95th percentile: {{ box_stats[name].area.p5|round(4) }}-{{ box_stats[name].area.p95|round(4) }}">{{ box_stats[name].area.median|round(4) }}</td>
+                        <td title="5th-95th percentile: {{ box_stats[name].aspect_ratio.p5|round(2) }}-{{ box_stats[name].aspect_ratio.p95|round(2) }}">{{ box_stats[name].aspect_ratio.median|round(2) }}</td>
+                        {% else %}
+                        <td>-</td>
+                        <td>-</td>
+                        {% endif %}
+                        {% endif %}
+                    </tr>
+                    {% endfor %}
+                </tbody>
+            </table>
+        </div>
+        {% endif %}
+
+        {% if metrics %}
+        <div class="issues-section">
+            <h2>Detection Metrics</h2>
+            <p class="meta">
+                Precision: {{ metrics.precision|round(3) }} |
+                Recall: {{ metrics.recall|round(3) }} |
+                F1: {{ metrics.f1|round(3) }}{% if metrics.map50 is not none %} |
+                mAP@0.5: {{ metrics.map50|round(3) }}{% endif %}
+            </p>
+            <table class="data-table sortable">
+                <thead>
+                    <tr>
+                        <th>Class</th>
+                        <th>GT</th>
+                        <th>TP</th>
+                        <th>FP</th>
+                        <th>FN</th>
+                        <th>Precision</th>
+                        <th>Recall</th>
+                        <th>F1</th>
+                        <th>AP@0.5</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    {% for name, m in metrics.per_class|items %}
+                    <tr>
+                        <td>{{ name }}</td>
+                        <td>{{ m.gt_count }}</td>
+                        <td>{{ m.true_positives }}</td>
+                        <td>{{ m.false_positives }}</td>
+                        <td>{{ m.false_negatives }}</td>
+                        <td>{{ m.precision|round(3) }}</td>
+                        <td>{{ m.recall|round(3) }}</td>
+                        <td>{{ m.f1|round(3) }}</td>
+                        <td>{% if m.ap50 is not none %}{{ m.ap50|round(3) }}{% else %}-{% endif %}</td>
+                    </tr>
+                    {% endfor %}
+                </tbody>
+            </table>
+        </div>
+        {% endif %}
+
+        {% if spatial_heatmap %}
+        <div class="issues-section">
+            <h2>Spatial Distribution</h2>
+            <p class="meta">Where the centers of {{ spatial_heatmap.total }} GT boxes fall in the image; darker cells hold more.</p>
+            <svg class="heatmap" viewBox="0 0 {{ spatial_heatmap.grid }} {{ spatial_heatmap.grid }}" shape-rendering="crispEdges">
+                {% for cell in spatial_heatmap.cells %}
+                <rect x="{{ cell.col }}" y="{{ cell.row }}" width="1" height="1" style="fill: hsl(120, 80%, {{ cell.lightness }}%);"><title>{{ cell.count }}</title></rect>
+                {% endfor %}
+            </svg>
+        </div>
+        {% endif %}
+
+        {% if iou_histogram %}
+        <div class="issues-section">
+            <h2>Match Quality</h2>
+            <p class="meta">IoU of {{ iou_histogram.total }} detections matched to GT boxes; bars on the left are loose matches.</p>
+            <svg class="iou-histogram" viewBox="0 0 {{ iou_histogram.width }} 100" preserveAspectRatio="none">
+                {% for bar in iou_histogram.bars %}
+                <rect x="{{ bar.x }}" y="{{ bar.y }}" width="8" height="{{ bar.height }}" style="fill: var(--primary);"><title>IoU {{ bar.range }}: {{ bar.count }}</title></rect>
+                {% endfor %}
+            </svg>
+            <div class="iou-axis meta"><span>0</span><span>IoU</span><span>1</span></div>
+        </div>
+        {% endif %}
+
+        {% if class_confusion %}
+        <div class="issues-section">
+            <h2>Class Confusion</h2>
+            <p class="meta">Rows are GT classes, columns are what the model detected instead.</p>
+            <table class="data-table confusion-table">
+                <thead>
+                    <tr>
+                        <th>GT \ Detected</th>
+                        {% for col in class_confusion.columns %}<th>{{ col }}</th>{% endfor %}
+                    </tr>
+                </thead>
+                <tbody>
+                    {% for row in class_confusion.rows %}
+                    <tr>
+                        <td>{{ row.gt_class }}</td>
+                        {% for cell in row.cells %}
+                        <td style="background: rgba(239, 68, 68, {{ cell.intensity * 0.8 }});">{% if cell.count > 0 %}{{ cell.count }}{% endif %}</td>
+                        {% endfor %}
+                    </tr>
+                    {% endfor %}
+                </tbody>
+            </table>
         </div>
+        {% endif %}
 
         <div class="issues-section">
-            <h2>Flagged Images ({{ flagged_images|length }})</h2>
+            <h2>Flagged Images ({{ flagged_total }})</h2>
+            {% if flagged_images|length < flagged_total %}
+            <div class="truncated">
+                Only the first {{ flagged_images|length }} of {{ flagged_total }} flagged images are
+                included in this report (--max-report-images). The summary covers all of them.
+            </div>
+            {% endif %}
+            {% if flagged_images %}
+            <div class="controls">
+                <input type="search" id="search" placeholder="Search filenames and classes">
+                <div class="filter-group">
+                    <span class="filter-title">Severity</span>
+                    {% for sev in ["high", "medium", "low"] %}
+                    <label><input type="checkbox" class="severity-filter" value="{{ sev }}" checked> {{ sev }}</label>
+                    {% endfor %}
+                </div>
+                <div class="filter-group">
+                    <span class="filter-title">Type</span>
+                    {% for entry in issue_legend %}
+                    <label title="{{ entry.help }}"><input type="checkbox" class="type-filter" value="{{ entry.id }}" checked> {{ entry.name or entry.id }} ({{ entry.count }})</label>
+                    {% endfor %}
+                </div>
+                <label>Sort
+                    <select id="sort">
+                        <option value="total">Total issues</option>
+                        <option value="high">High severity issues</option>
+                        <option value="filename">Filename</option>
+                    </select>
+                </label>
+                <button type="button" id="toggle-all">Expand all</button>
+                <span id="showing"></span>
+            </div>
+            {% endif %}
+            <div id="flagged-images">
             {% for img in flagged_images %}
-            <div class="issue-card {% if img.high_count() > 0 %}high{% elif img.medium_count() > 0 %}medium{% else %}low{% endif %}">
+            <div class="issue-card {% if img.high_count() > 0 %}high{% elif img.medium_count() > 0 %}medium{% else %}low{% endif %}"
+                 data-filename="{% if img.split %}{{ img.split }}/{% endif %}{{ img.filename }}"
+                 data-classes="{% for issue in img.issues %}{{ issue.gt_class or "" }} {{ issue.detected_class or "" }} {% endfor %}"
+                 data-high="{{ img.high_count() }}"
+                 data-total="{{ img.issues|length }}">
                 <div class="issue-header" onclick="toggleDetails(this)">
-                    <span class="issue-filename">{{ img.filename }}</span>
+                    <span class="issue-filename">{% if img.split %}[{{ img.split }}] {% endif %}{{ img.filename }}</span>
                     <span>
                         {% if img.high_count() > 0 %}<span class="badge badge-high">{{ img.high_count() }} HIGH</span>{% endif %}
                         {% if img.medium_count() > 0 %}<span class="badge badge-medium">{{ img.medium_count() }} MED</span>{% endif %}
@@ -241,19 +1747,59 @@ const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
                     </span>
                 </div>
                 <div class="issue-details">
+                    {% if embed_thumbnails %}
+                    {% if thumbnails[loop.index0] %}
+                    <img class="thumbnail" src="{{ thumbnails[loop.index0] }}" alt="{{ img.filename }}">
+                    {% else %}
+                    <div class="thumbnail placeholder">Image unavailable</div>
+                    {% endif %}
+                    {% endif %}
                     <p style="color: var(--text-muted); margin-bottom: 0.5rem; font-size: 0.8rem;">
                         GT: {{ img.gt_count }} objects | Detected: {{ img.detection_count }}
                     </p>
-                    {% for issue in img.issues %}
-                    <div class="issue-item">
-                        <span class="issue-type">{{ issue.issue_type }}</span>: {{ issue.description }}
+                    {% for issue in img.issues if not issue.suppressed %}
+                    <div class="issue-item{% if issue.baselined %} baselined{% endif %}" data-severity="{{ issue.severity }}" data-type="{{ issue.issue_type }}">
+                        {% if issue.baselined %}<span class="badge badge-baselined">BASELINED</span> {% endif %}<span class="issue-type">{{ issue.issue_type }}</span>: {{ issue.description }}
                         {% if issue.explanation %}<br><small style="color: var(--text-muted);">{{ issue.explanation }}</small>{% endif %}
                     </div>
                     {% endfor %}
                 </div>
             </div>
             {% endfor %}
+            </div>
+            {% if flagged_images|length > page_size %}
+            <div class="pager">
+                <button type="button" id="prev-page">&lsaquo; Prev</button>
+                <span id="page-info"></span>
+                <button type="button" id="next-page">Next &rsaquo;</button>
+            </div>
+            {% endif %}
+        </div>
+
+        {% if dataset_issues %}
+        <div class="issues-section">
+            <h2>Dataset Issues ({{ dataset_issues|length }})</h2>
+            {% for issue in dataset_issues if not issue.suppressed %}
+            <div class="issue-item{% if issue.baselined %} baselined{% endif %}">
+                {% if issue.baselined %}<span class="badge badge-baselined">BASELINED</span> {% endif %}<span class="issue-type">{{ issue.issue_type }}</span>: {{ issue.description }}
+                {% if issue.explanation %}<br><small style="color: var(--text-muted);">{{ issue.explanation }}</small>{% endif %}
+            </div>
+            {% endfor %}
+        </div>
+        {% endif %}
+
+        {% if suppressed_issues %}
+        <div class="issues-section">
+            <details>
+                <summary><h2 class="inline">Suppressed Issues ({{ suppressed_issues|length }})</h2></summary>
+                {% for issue in suppressed_issues %}
+                <div class="issue-item baselined">
+                    <span class="badge badge-baselined">SUPPRESSED</span> <code>{{ issue.image }}</code> <span class="issue-type">{{ issue.issue_type }}</span>: {{ issue.description }}
+                </div>
+                {% endfor %}
+            </details>
         </div>
+        {% endif %}
 
         <footer>
             <p>Generated by <a href="https://github.com/ARTIFACTIQ/gt-audit">gt-audit</a> |
@@ -266,6 +1812,119 @@ const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
             const details = header.nextElementSibling;
             details.classList.toggle('expanded');
         }
+
+        // Flagged image filters: an image matches while one of its issues
+        // passes the severity and type filters and the search matches its
+        // filename or classes. Matches are shown a page at a time.
+        const flagged = document.getElementById('flagged-images');
+        const cards = Array.from(flagged.querySelectorAll('.issue-card'));
+        const pageSize = {{ page_size }};
+        let matching = [];
+        let page = 0;
+
+        function checked(selector) {
+            return new Set(Array.from(document.querySelectorAll(selector))
+                .filter(function (box) { return box.checked; })
+                .map(function (box) { return box.value; }));
+        }
+
+        function applyFilters() {
+            const severities = checked('.severity-filter');
+            const types = checked('.type-filter');
+            const query = document.getElementById('search').value.trim().toLowerCase();
+            matching = [];
+            cards.forEach(function (card) {
+                let visibleIssues = 0;
+                card.querySelectorAll('.issue-item').forEach(function (item) {
+                    const pass = severities.has(item.dataset.severity) && types.has(item.dataset.type);
+                    item.classList.toggle('hidden', !pass);
+                    if (pass) { visibleIssues++; }
+                });
+                const text = (card.dataset.filename + ' ' + card.dataset.classes).toLowerCase();
+                if (visibleIssues > 0 && (!query || text.includes(query))) {
+                    matching.push(card);
+                }
+            });
+            document.getElementById('showing').textContent =
+                'Showing ' + matching.length.toLocaleString() + ' of ' + cards.length.toLocaleString() + ' images';
+            page = 0;
+            showPage();
+        }
+
+        function showPage() {
+            const pages = Math.max(1, Math.ceil(matching.length / pageSize));
+            page = Math.min(Math.max(page, 0), pages - 1);
+            const onPage = new Set(matching.slice(page * pageSize, (page + 1) * pageSize));
+            cards.forEach(function (card) { card.classList.toggle('hidden', !onPage.has(card)); });
+            const pageInfo = document.getElementById('page-info');
+            if (pageInfo) {
+                pageInfo.textContent = 'Page ' + (page + 1) + ' of ' + pages;
+                document.getElementById('prev-page').disabled = page === 0;
+                document.getElementById('next-page').disabled = page === pages - 1;
+            }
+        }
+
+        function applySort() {
+            const key = document.getElementById('sort').value;
+            cards.sort(function (a, b) {
+                if (key !== 'filename') {
+                    const diff = Number(b.dataset[key]) - Number(a.dataset[key]);
+                    if (diff !== 0) { return diff; }
+                }
+                return a.dataset.filename.localeCompare(b.dataset.filename);
+            });
+            cards.forEach(function (card) { flagged.appendChild(card); });
+            applyFilters();
+        }
+
+        if (cards.length > 0) {
+            document.querySelectorAll('.severity-filter, .type-filter').forEach(function (box) {
+                box.addEventListener('change', applyFilters);
+            });
+            document.getElementById('search').addEventListener('input', applyFilters);
+            document.getElementById('sort').addEventListener('change', applySort);
+            if (document.getElementById('page-info')) {
+                document.getElementById('prev-page').addEventListener('click', function () {
+                    page--;
+                    showPage();
+                    flagged.scrollIntoView();
+                });
+                document.getElementById('next-page').addEventListener('click', function () {
+                    page++;
+                    showPage();
+                    flagged.scrollIntoView();
+                });
+            }
+            document.getElementById('toggle-all').addEventListener('click', function () {
+                const expand = this.textContent === 'Expand all';
+                cards.forEach(function (card) {
+                    if (!card.classList.contains('hidden')) {
+                        card.querySelector('.issue-details').classList.toggle('expanded', expand);
+                    }
+                });
+                this.textContent = expand ? 'Collapse all' : 'Expand all';
+            });
+            applyFilters();
+        }
+
+        document.querySelectorAll('table.sortable th').forEach(function (th) {
+            th.addEventListener('click', function () {
+                const table = th.closest('table');
+                const tbody = table.querySelector('tbody');
+                const asc = th.dataset.order !== 'asc';
+                table.querySelectorAll('th').forEach(function (h) { delete h.dataset.order; });
+                th.dataset.order = asc ? 'asc' : 'desc';
+                const rows = Array.from(tbody.querySelectorAll('tr'));
+                rows.sort(function (a, b) {
+                    const x = a.children[th.cellIndex].textContent.trim();
+                    const y = b.children[th.cellIndex].textContent.trim();
+                    const nx = parseFloat(x), ny = parseFloat(y);
+                    const cmp = (!isNaN(nx) && !isNaN(ny)) ? nx - ny : x.localeCompare(y);
+                    return asc ? cmp : -cmp;
+                });
+                rows.forEach(function (row) { tbody.appendChild(row); });
+            });
+        });
     </script>
 </body>
 </html>
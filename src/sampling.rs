@@ -0,0 +1,214 @@
+//! Choosing which images to audit: glob filters and `--sample`
+
+use anyhow::{Context, Result};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How a sample of images is drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SampleStrategy {
+    /// Every image equally likely
+    #[default]
+    Uniform,
+    /// Every class represented by a minimum number of images where possible
+    Stratified,
+}
+
+impl std::str::FromStr for SampleStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "uniform" => Ok(Self::Uniform),
+            "stratified" => Ok(Self::Stratified),
+            other => anyhow::bail!(
+                "Unknown sample strategy '{}' (expected uniform or stratified)",
+                other
+            ),
+        }
+    }
+}
+
+/// Glob patterns restricting which images are audited
+///
+/// Patterns containing `/` are matched against the image path relative to
+/// the images directory; others only against the file name.
+#[derive(Debug, Clone, Default)]
+pub struct ImageFilter {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl ImageFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let compile = |patterns: &[String]| -> Result<Vec<glob::Pattern>> {
+            patterns
+                .iter()
+                .map(|p| {
+                    glob::Pattern::new(p).with_context(|| format!("Invalid glob pattern '{}'", p))
+                })
+                .collect()
+        };
+        Ok(Self {
+            include: compile(include)?,
+            exclude: compile(exclude)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether an image, given by its dataset-relative key, passes the filter
+    pub fn matches(&self, key: &str) -> bool {
+        let file_name = key.rsplit('/').next().unwrap_or(key);
+        let hit = |pattern: &glob::Pattern| {
+            if pattern.as_str().contains('/') {
+                pattern.matches(key)
+            } else {
+                pattern.matches(file_name)
+            }
+        };
+        (self.include.is_empty() || self.include.iter().any(hit)) && !self.exclude.iter().any(hit)
+    }
+}
+
+/// A class with fewer images in the dataset than the stratified minimum
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassShortfall {
+    pub class_id: i32,
+    /// Images containing the class, all of which were sampled
+    pub available: usize,
+}
+
+/// Indices of `n` items drawn uniformly at random
+pub fn uniform(len: usize, n: usize, seed: u64) -> Vec<usize> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut indices: Vec<usize> = (0..len).collect();
+    indices.shuffle(&mut rng);
+    indices.truncate(n);
+    indices
+}
+
+/// Per-class minimum that splits a sample of `n` evenly across the classes present
+pub fn even_share(classes: &[BTreeSet<i32>], n: usize) -> usize {
+    let present: BTreeSet<i32> = classes.iter().flatten().copied().collect();
+    n / present.len().max(1)
+}
+
+/// Indices of `n` items so that every class appears in at least `min_per_class` of them
+///
+/// `classes[i]` holds the class ids of item `i`. Classes are served rarest
+/// first, each taking items containing it (in seeded random order) until it
+/// reaches the minimum; the rest of the budget is filled uniformly, so common
+/// classes stay roughly proportional. Returned indices are in ascending order.
+pub fn stratified(
+    classes: &[BTreeSet<i32>],
+    n: usize,
+    min_per_class: usize,
+    seed: u64,
+) -> (Vec<usize>, Vec<ClassShortfall>) {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut order: Vec<usize> = (0..classes.len()).collect();
+    order.shuffle(&mut rng);
+
+    let mut available: BTreeMap<i32, usize> = BTreeMap::new();
+    for ids in classes {
+        for id in ids {
+            *available.entry(*id).or_insert(0) += 1;
+        }
+    }
+    let mut rarest: Vec<(i32, usize)> = available.iter().map(|(id, n)| (*id, *n)).collect();
+    rarest.sort_by_key(|(id, n)| (*n, *id));
+
+    let mut selected = vec![false; classes.len()];
+    let mut taken = 0;
+    let mut covered: BTreeMap<i32, usize> = BTreeMap::new();
+
+    for (class_id, _) in &rarest {
+        for &i in &order {
+            if taken == n || covered.get(class_id).copied().unwrap_or(0) >= min_per_class {
+                break;
+            }
+            if selected[i] || !classes[i].contains(class_id) {
+                continue;
+            }
+            selected[i] = true;
+            taken += 1;
+            for id in &classes[i] {
+                *covered.entry(*id).or_insert(0) += 1;
+            }
+        }
+    }
+
+    for &i in &order {
+        if taken == n {
+            break;
+        }
+        if !selected[i] {
+            selected[i] = true;
+            taken += 1;
+        }
+    }
+
+    let shortfalls = rarest
+        .into_iter()
+        .filter(|(_, available)| *available < min_per_class)
+        .map(|(class_id, available)| ClassShortfall {
+            class_id,
+            available,
+        })
+        .collect();
+
+    let indices = (0..classes.len()).filter(|&i| selected[i]).collect();
+    (indices, shortfalls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_filter_matches_file_names_and_paths() {
+        let filter = ImageFilter::new(
+            &["frame_*.jpg".to_string(), "night/*".to_string()],
+            &["*_blurred.jpg".to_string()],
+        )
+        .unwrap();
+        assert!(filter.matches("day/frame_001.jpg"));
+        assert!(filter.matches("night/img.png"));
+        assert!(!filter.matches("day/img.png"));
+        assert!(!filter.matches("frame_002_blurred.jpg"));
+
+        assert!(ImageFilter::default().matches("anything.jpg"));
+        assert!(ImageFilter::new(&["[".to_string()], &[]).is_err());
+    }
+
+    #[test]
+    fn stratified_covers_rare_classes_deterministically() {
+        // 100 images of class 0, 3 of class 1, 30 of class 2
+        let mut classes: Vec<BTreeSet<i32>> = vec![BTreeSet::from([0]); 100];
+        classes.extend(vec![BTreeSet::from([1]); 3]);
+        classes.extend(vec![BTreeSet::from([0, 2]); 30]);
+
+        let (picked, shortfalls) = stratified(&classes, 40, 10, 42);
+        assert_eq!(picked.len(), 40);
+        let count = |id: i32| picked.iter().filter(|&&i| classes[i].contains(&id)).count();
+        assert_eq!(count(1), 3);
+        assert!(count(2) >= 10);
+        assert_eq!(
+            shortfalls,
+            vec![ClassShortfall {
+                class_id: 1,
+                available: 3
+            }]
+        );
+
+        assert_eq!(stratified(&classes, 40, 10, 42).0, picked);
+        assert_eq!(even_share(&classes, 40), 13);
+        assert_eq!(even_share(&[], 40), 40);
+    }
+}
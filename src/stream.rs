@@ -0,0 +1,74 @@
+//! Streaming output: image results written as JSON Lines while the audit runs
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use crate::models::{AuditSummary, ImageResult, Issue};
+
+/// Last line of a stream, once every image is written
+#[derive(Serialize)]
+struct StreamSummary<'a> {
+    summary: &'a AuditSummary,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    dataset_issues: &'a [Issue],
+}
+
+/// Writer thread appending one line per image result to a file
+///
+/// Results are serialized on the calling thread and handed to the writer over
+/// a channel, so workers never wait on each other for the file.
+pub struct ResultStream {
+    sender: mpsc::Sender<Result<String>>,
+    writer: JoinHandle<Result<()>>,
+}
+
+impl ResultStream {
+    /// Create (or truncate) `path` and start the writer thread
+    pub fn create(path: &Path) -> Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let (sender, receiver) = mpsc::channel::<Result<String>>();
+        let writer = std::thread::spawn(move || -> Result<()> {
+            let mut out = BufWriter::new(file);
+            while let Ok(line) = receiver.recv() {
+                out.write_all(line?.as_bytes())?;
+                // Flush once the channel is drained, so readers see whole lines promptly
+                while let Ok(line) = receiver.try_recv() {
+                    out.write_all(line?.as_bytes())?;
+                }
+                out.flush()?;
+            }
+            Ok(())
+        });
+        Ok(Self { sender, writer })
+    }
+
+    /// Queue a finished image; safe to call from worker threads
+    ///
+    /// Serialization and write errors stop the stream and surface from `finish`.
+    pub fn send(&self, result: &ImageResult) {
+        let line = serde_json::to_string(result)
+            .map(|json| format!("{}\n", json))
+            .with_context(|| format!("Failed to serialize the result of {}", result.filename));
+        let _ = self.sender.send(line);
+    }
+
+    /// Write the summary line and wait for everything to reach the file
+    pub fn finish(self, summary: &AuditSummary, dataset_issues: &[Issue]) -> Result<()> {
+        let json = serde_json::to_string(&StreamSummary {
+            summary,
+            dataset_issues,
+        })?;
+        let _ = self.sender.send(Ok(format!("{}\n", json)));
+        drop(self.sender);
+        self.writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("Streaming output writer panicked"))?
+            .context("Failed to write streaming output")
+    }
+}
@@ -0,0 +1,226 @@
+//! Suppression rules for issues the team has decided to accept
+//!
+//! Rules live in `.gtauditignore` in the dataset root, a YAML list such as:
+//!
+//! ```yaml
+//! - image_glob: "night/*.jpg"
+//!   issue_type: class_mismatch
+//!   gt_class: bag
+//!   detected_class: backpack
+//!   reason: bag and backpack are ambiguous on small crops
+//! - fingerprint: 0f3c9e1d2b7a4c5e8f9a0b1c2d3e4f5a
+//! ```
+//!
+//! An issue is suppressed when every field a rule sets matches it. Unlike a
+//! baseline, which records every issue of one run, rules are written and
+//! pruned by hand and may cover issues that have not occurred yet.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::baseline::fingerprint;
+use crate::models::{AuditResult, Issue, IssueType};
+use crate::sampling::ImageFilter;
+
+/// Suppression file read from the dataset root
+pub const SUPPRESSIONS_FILE: &str = ".gtauditignore";
+
+/// One suppression rule; fields left out match any issue
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuppressionRule {
+    /// Issue fingerprint, as in baselines and SARIF reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// Glob over the image path; without a `/`, over the file name only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_glob: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue_type: Option<IssueType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gt_class: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_class: Option<String>,
+    /// Why the issue is accepted; not matched against anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl SuppressionRule {
+    /// Rule matching exactly the kind of issue `issue` is, in the same image
+    pub fn for_issue(issue: &Issue) -> Self {
+        Self {
+            fingerprint: None,
+            image_glob: Some(glob::Pattern::escape(&issue.image)),
+            issue_type: Some(issue.issue_type.clone()),
+            gt_class: issue.gt_class.clone(),
+            detected_class: issue.detected_class.clone(),
+            reason: None,
+        }
+    }
+}
+
+/// Compiled suppression rules
+#[derive(Debug, Clone, Default)]
+pub struct Suppressions {
+    rules: Vec<(SuppressionRule, ImageFilter)>,
+}
+
+impl Suppressions {
+    pub fn new(rules: Vec<SuppressionRule>) -> Result<Self> {
+        let rules = rules
+            .into_iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                if rule.fingerprint.is_none()
+                    && rule.image_glob.is_none()
+                    && rule.issue_type.is_none()
+                    && rule.gt_class.is_none()
+                    && rule.detected_class.is_none()
+                {
+                    anyhow::bail!("Suppression rule {} matches every issue", i + 1);
+                }
+                let filter = ImageFilter::new(rule.image_glob.as_slice(), &[])?;
+                Ok((rule, filter))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read suppressions: {}", path.display()))?;
+        let rules: Option<Vec<SuppressionRule>> = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse suppressions: {}", path.display()))?;
+        Self::new(rules.unwrap_or_default())
+            .with_context(|| format!("Invalid suppressions: {}", path.display()))
+    }
+
+    /// Rules from `.gtauditignore` in `dataset`, if there is one
+    pub fn discover(dataset: &Path) -> Result<Option<Self>> {
+        let path = dataset.join(SUPPRESSIONS_FILE);
+        if path.is_file() {
+            Self::load(&path).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether any rule matches `issue`
+    pub fn matches(&self, issue: &Issue) -> bool {
+        self.rules.iter().any(|(rule, filter)| {
+            rule.fingerprint
+                .as_ref()
+                .is_none_or(|fp| *fp == fingerprint(issue))
+                && filter.matches(&issue.image)
+                && rule
+                    .issue_type
+                    .as_ref()
+                    .is_none_or(|t| *t == issue.issue_type)
+                && (rule.gt_class.is_none() || rule.gt_class == issue.gt_class)
+                && (rule.detected_class.is_none() || rule.detected_class == issue.detected_class)
+        })
+    }
+
+    /// Mark the issues of `result` that a rule matches
+    pub fn apply(&self, result: &mut AuditResult) {
+        result.apply_suppressions(|issue| self.matches(issue));
+    }
+}
+
+/// Suppression file with a rule for every distinct issue in `result` not
+/// already suppressed, for pruning by hand
+///
+/// Each rule is preceded by a comment with the issue's description.
+pub fn template(result: &AuditResult) -> Result<String> {
+    let mut out = format!(
+        "# gt-audit suppressions: delete the rules for issues that should still be reported,\n\
+         # and add a `reason` to the ones you keep. Save as {} in the dataset root.\n",
+        SUPPRESSIONS_FILE
+    );
+    let mut seen = std::collections::HashSet::new();
+    for issue in result.all_issues().filter(|i| !i.suppressed) {
+        let rule = SuppressionRule::for_issue(issue);
+        let yaml = serde_yaml::to_string(&[&rule])?;
+        if seen.insert(yaml.clone()) {
+            out.push_str(&format!("\n# {}\n{}", issue.description, yaml));
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ImageResult, IssueSeverity};
+
+    fn issue(image: &str, gt: &str, detected: &str) -> Issue {
+        Issue::builder(image, IssueSeverity::High, IssueType::ClassMismatch)
+            .description(format!("GT '{}' detected as '{}'", gt, detected))
+            .gt_class(gt)
+            .detected_class(detected)
+            .confidence(0.9)
+            .iou(0.8)
+            .line_num(1)
+            .build()
+    }
+
+    #[test]
+    fn rules_match_on_every_field_they_set() {
+        let rules: Vec<SuppressionRule> = serde_yaml::from_str(
+            r#"
+- image_glob: "night/*.jpg"
+  issue_type: class_mismatch
+  gt_class: bag
+  detected_class: backpack
+  reason: ambiguous on small crops
+"#,
+        )
+        .unwrap();
+        let suppressions = Suppressions::new(rules).unwrap();
+
+        assert!(suppressions.matches(&issue("night/a.jpg", "bag", "backpack")));
+        assert!(!suppressions.matches(&issue("day/a.jpg", "bag", "backpack")));
+        assert!(!suppressions.matches(&issue("night/a.jpg", "bag", "suitcase")));
+
+        let by_fingerprint = Suppressions::new(vec![SuppressionRule {
+            fingerprint: Some(fingerprint(&issue("day/a.jpg", "car", "truck"))),
+            ..Default::default()
+        }])
+        .unwrap();
+        assert!(by_fingerprint.matches(&issue("day/a.jpg", "car", "truck")));
+        assert!(!by_fingerprint.matches(&issue("day/b.jpg", "car", "truck")));
+
+        assert!(Suppressions::new(vec![SuppressionRule::default()]).is_err());
+    }
+
+    #[test]
+    fn suppressed_issues_leave_the_new_counts() {
+        let mut result = AuditResult::new("d".into(), "yolo".into(), 0.25, 0.5, 1, 1);
+        let mut image = ImageResult::new("night/a.jpg".to_string(), &[], 0);
+        image.add_issue(issue("night/a.jpg", "bag", "backpack"));
+        image.add_issue(issue("night/a.jpg", "car", "truck"));
+        result.add_image_result(image);
+
+        let template = template(&result).unwrap();
+        let rules: Vec<SuppressionRule> = serde_yaml::from_str(&template).unwrap();
+        assert_eq!(rules.len(), 2);
+        assert!(template.contains("# GT 'bag' detected as 'backpack'"));
+
+        Suppressions::new(rules[..1].to_vec())
+            .unwrap()
+            .apply(&mut result);
+        assert_eq!(result.high_count(), 2);
+        assert_eq!(result.new_high_count(), 1);
+        assert_eq!(result.summary.suppressed_issues, 1);
+    }
+}
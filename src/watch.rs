@@ -0,0 +1,189 @@
+//! Re-audit images as their label files change
+
+use anyhow::{Context, Result};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::audit::{self, AuditObserver, AuditOptions};
+use crate::baseline::fingerprint;
+use crate::models::{AuditResult, Issue};
+use crate::sampling::ImageFilter;
+
+/// Label changes arriving less than this apart are handled together
+pub const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Issues gained and lost by one re-audited image
+#[derive(Debug, Clone)]
+pub struct ImageChange {
+    pub image: String,
+    pub added: Vec<Issue>,
+    pub removed: Vec<Issue>,
+}
+
+impl ImageChange {
+    /// Compare the issues of an image before and after, matching them by fingerprint
+    pub fn between(image: String, before: &[Issue], after: &[Issue]) -> Self {
+        let mut previous: HashMap<String, Vec<&Issue>> = HashMap::new();
+        for issue in before {
+            previous.entry(fingerprint(issue)).or_default().push(issue);
+        }
+
+        let mut added = Vec::new();
+        for issue in after {
+            match previous.get_mut(&fingerprint(issue)).and_then(|m| m.pop()) {
+                Some(_) => {}
+                None => added.push(issue.clone()),
+            }
+        }
+        let removed = previous.into_values().flatten().cloned().collect();
+
+        Self {
+            image,
+            added,
+            removed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Watch the label directories of an audited dataset until the process exits
+///
+/// `result` must come from an audit run with the same `options`. After each
+/// burst of created or modified label files, the matching images are
+/// re-audited, merged into `result`, and `on_change` is called with the
+/// updated result and the issues each image gained or lost.
+pub fn watch(
+    options: &AuditOptions,
+    result: &mut AuditResult,
+    observer: &dyn AuditObserver,
+    mut on_change: impl FnMut(&mut AuditResult, &[ImageChange]) -> Result<()>,
+) -> Result<()> {
+    let datasets = audit::load_datasets(options)?;
+    let auditor = audit::create_auditor(options, &datasets[0].1, observer)?;
+    let filter = ImageFilter::new(&options.include_images, &options.exclude_images)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+
+    // Events carry canonical paths; map them back onto the dataset's own paths
+    let mut roots: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for (_, dataset) in &datasets {
+        for source in &dataset.sources {
+            let canonical = source.labels_dir.canonicalize().with_context(|| {
+                format!("Label directory not found: {}", source.labels_dir.display())
+            })?;
+            watcher
+                .watch(&canonical, RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {}", canonical.display()))?;
+            roots.push((canonical, source.labels_dir.clone()));
+        }
+    }
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        collect_label_paths(event, &roots, &mut changed);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect_label_paths(event, &roots, &mut changed);
+        }
+
+        let mut changes = Vec::new();
+        for label_path in changed {
+            let Some((split, dataset, image_path)) = datasets.iter().find_map(|(split, ds)| {
+                ds.image_for_label(&label_path)
+                    .map(|image| (split, ds, image))
+            }) else {
+                continue;
+            };
+            if !filter.matches(&dataset.image_key(&image_path)) {
+                continue;
+            }
+
+            match audit::audit_image(&auditor, dataset, split.as_deref(), &image_path, options) {
+                Ok(image_result) => {
+                    let image = image_result.filename.clone();
+                    let after = image_result.issues.clone();
+                    let before = result
+                        .replace_image_result(image_result)
+                        .map(|r| r.issues)
+                        .unwrap_or_default();
+                    changes.push(ImageChange::between(image, &before, &after));
+                }
+                Err(e) => observer.on_warning(&format!(
+                    "Failed to audit {}: {:#}",
+                    image_path.display(),
+                    e
+                )),
+            }
+        }
+
+        if !changes.is_empty() {
+            on_change(result, &changes)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Add the label files touched by a create/modify event to `changed`
+fn collect_label_paths(
+    event: notify::Result<Event>,
+    roots: &[(PathBuf, PathBuf)],
+    changed: &mut BTreeSet<PathBuf>,
+) {
+    let Ok(event) = event else {
+        return;
+    };
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        return;
+    }
+
+    for path in event.paths {
+        if path.extension().and_then(|e| e.to_str()) != Some("txt") {
+            continue;
+        }
+        if let Some(label_path) = roots.iter().find_map(|(canonical, labels_dir)| {
+            path.strip_prefix(canonical)
+                .ok()
+                .map(|relative| labels_dir.join(relative))
+        }) {
+            changed.insert(label_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{IssueSeverity, IssueType};
+
+    fn issue(issue_type: IssueType, line_num: usize) -> Issue {
+        Issue::builder("a.jpg", IssueSeverity::High, issue_type)
+            .line_num(line_num)
+            .build()
+    }
+
+    #[test]
+    fn change_lists_only_differing_issues() {
+        let before = vec![
+            issue(IssueType::OutOfBounds, 1),
+            issue(IssueType::ParseError, 2),
+        ];
+        let after = vec![
+            issue(IssueType::OutOfBounds, 1),
+            issue(IssueType::SpuriousLabel, 3),
+        ];
+
+        let change = ImageChange::between("a.jpg".to_string(), &before, &after);
+        assert_eq!(change.added.len(), 1);
+        assert_eq!(change.added[0].issue_type, IssueType::SpuriousLabel);
+        assert_eq!(change.removed.len(), 1);
+        assert_eq!(change.removed[0].issue_type, IssueType::ParseError);
+        assert!(ImageChange::between("a.jpg".to_string(), &after, &after).is_empty());
+    }
+}
@@ -0,0 +1,634 @@
+use gt_audit::audit::load_datasets;
+use gt_audit::report::{
+    HtmlReporter, JsonReporter, JunitReporter, MarkdownReporter, ReportDetail, Reporter,
+    SarifReporter, HTML_MAX_IMAGES,
+};
+use gt_audit::{
+    run_audit, run_audit_with, AuditObserver, AuditOptions, AuditResult, ImageResult, Issue,
+    IssueSeverity, IssueType,
+};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn run_audit_clean_dataset_has_no_issues() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("clean").into(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    assert_eq!(result.images_audited, 1);
+    assert_eq!(result.total_issues(), 0);
+}
+
+#[test]
+fn run_audit_flags_invalid_bbox() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("dirty").into(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    assert_eq!(result.high_count(), 1);
+    assert_eq!(
+        result.flagged_images()[0].issues[0].issue_type,
+        IssueType::OutOfBounds
+    );
+}
+
+#[test]
+fn run_audit_reports_undecodable_images() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("corrupt").into(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    assert_eq!(result.images_audited, 2);
+    assert_eq!(result.summary.failed_images, 1);
+    assert_eq!(result.flagged_images().len(), 1);
+    let image = &result.flagged_images()[0];
+    assert_eq!(image.filename, "truncated.png");
+    assert_eq!(image.issues[0].issue_type, IssueType::ImageError);
+    assert!(image.issues[0]
+        .description
+        .starts_with("Failed to load image"));
+}
+
+#[test]
+fn run_audit_streams_results_then_summary() {
+    let path = std::env::temp_dir().join(format!("gt-audit-stream-{}.jsonl", std::process::id()));
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("corrupt").into(),
+        stream_output: Some(path.clone()),
+        ..Default::default()
+    })
+    .unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let lines: Vec<serde_json::Value> = content
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    let streamed: Vec<&str> = lines[..2]
+        .iter()
+        .map(|line| line["filename"].as_str().unwrap())
+        .collect();
+    assert!(streamed.contains(&"truncated.png"));
+    assert_eq!(
+        lines[2]["summary"]["total_issues"],
+        result.total_issues() as u64
+    );
+}
+
+#[test]
+fn streamed_results_are_filtered_and_marked_like_the_report() {
+    use gt_audit::suppress::{SuppressionRule, Suppressions};
+
+    let options = || AuditOptions {
+        dataset_path: fixture("dirty").into(),
+        ..Default::default()
+    };
+    let plain = run_audit(options()).unwrap();
+    assert!(plain.all_issues().count() > 0);
+    let rules = plain.all_issues().map(SuppressionRule::for_issue).collect();
+
+    let path = std::env::temp_dir().join(format!(
+        "gt-audit-stream-suppressed-{}.jsonl",
+        std::process::id()
+    ));
+    let result = run_audit(AuditOptions {
+        stream_output: Some(path.clone()),
+        suppressions: Some(Suppressions::new(rules).unwrap()),
+        ..options()
+    })
+    .unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(result.all_issues().all(|issue| issue.suppressed));
+    let image: serde_json::Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+    let issues = image["issues"].as_array().unwrap();
+    assert!(!issues.is_empty());
+    assert!(issues.iter().all(|issue| issue["suppressed"] == true));
+}
+
+#[test]
+fn cancelled_audit_is_marked_partial() {
+    struct Cancelled;
+    impl AuditObserver for Cancelled {
+        fn is_cancelled(&self) -> bool {
+            true
+        }
+    }
+
+    let result = run_audit_with(
+        AuditOptions {
+            dataset_path: fixture("corrupt").into(),
+            ..Default::default()
+        },
+        &Cancelled,
+    )
+    .unwrap();
+    assert!(result.partial);
+    assert_eq!(result.images_skipped, 2);
+    assert_eq!(result.images_audited, 0);
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert_eq!(json["partial"], true);
+    assert_eq!(json["images_skipped"], 2);
+}
+
+#[test]
+fn run_audit_reports_dataset_consistency() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("inconsistent").into(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let issues = &result.dataset_issues;
+    assert_eq!(issues.len(), 3);
+    assert!(issues
+        .iter()
+        .all(|i| i.issue_type == IssueType::DatasetConsistency));
+    let images: Vec<&str> = issues.iter().map(|i| i.image.as_str()).collect();
+    assert_eq!(
+        images,
+        ["labels/val/stale.txt", "unlabeled.png", "background.png"]
+    );
+    assert_eq!(result.summary.by_type.get("dataset_consistency"), Some(&3));
+}
+
+#[test]
+fn pixel_coordinate_labels_are_reported_once_or_rescaled() {
+    let options = || AuditOptions {
+        dataset_path: fixture("pixel_coords").into(),
+        ..Default::default()
+    };
+    let result = run_audit(options()).unwrap();
+    let issues = &result.flagged_images()[0].issues;
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].issue_type, IssueType::CoordinateFormat);
+    assert_eq!(issues[0].line_num, None);
+
+    let result = run_audit(AuditOptions {
+        assume_pixel_coords: true,
+        ..options()
+    })
+    .unwrap();
+    assert_eq!(result.total_issues(), 0);
+}
+
+#[test]
+fn io_workers_decode_images_ahead_with_the_same_results() {
+    let options = |io_workers| AuditOptions {
+        dataset_path: fixture("corrupt").into(),
+        io_workers,
+        ..Default::default()
+    };
+    let issues = |result: AuditResult| -> Vec<(String, IssueType)> {
+        result
+            .image_results
+            .iter()
+            .flat_map(|r| &r.issues)
+            .map(|i| (i.image.clone(), i.issue_type.clone()))
+            .collect()
+    };
+    let serial = run_audit(options(None)).unwrap();
+    let read_ahead = run_audit(options(Some(2))).unwrap();
+    assert_eq!(read_ahead.images_audited, serial.images_audited);
+    assert!(serial.total_issues() > 0);
+    assert_eq!(issues(read_ahead), issues(serial));
+}
+
+#[test]
+fn run_audit_flags_low_confidence_pre_annotations() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("preannotated").into(),
+        min_gt_confidence: Some(0.5),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let suspect: Vec<_> = result
+        .all_issues()
+        .filter(|i| i.issue_type == IssueType::SuspectPreAnnotation)
+        .collect();
+    assert_eq!(suspect.len(), 1);
+    assert_eq!(suspect[0].line_num, Some(2));
+    assert_eq!(suspect[0].confidence, Some(0.2));
+}
+
+#[test]
+fn markdown_report_lists_flagged_images() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("dirty").into(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let md = MarkdownReporter {
+        github: true,
+        ..MarkdownReporter::new()
+    }
+    .render(&result)
+    .unwrap();
+
+    assert!(md.contains("| 🔴 High | 1 |"));
+    assert!(md.contains("<summary><code>sample.png</code> · 1 issues</summary>"));
+    assert!(md.contains("out_of_bounds"));
+    assert!(md.contains("![gt-audit](https://img.shields.io/badge/gt--audit-1%20high-red)"));
+}
+
+#[test]
+fn html_report_embeds_thumbnails_or_placeholders() {
+    let render = |name: &str| {
+        let options = AuditOptions {
+            dataset_path: fixture(name).into(),
+            ..Default::default()
+        };
+        let result = run_audit(options.clone()).unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "gt-audit-report-{}-{}.html",
+            name,
+            std::process::id()
+        ));
+        HtmlReporter::new()
+            .with_thumbnails(load_datasets(&options).unwrap())
+            .generate(&result, &path)
+            .unwrap();
+        let html = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        html
+    };
+
+    let html = render("dirty");
+    assert!(html.contains("src=\"data:image/png;base64,"));
+    assert!(html.contains("class=\"type-filter\" value=\"out_of_bounds\" checked> Out of Bounds"));
+    assert!(html.contains("<h2>Issue Types</h2>"));
+    assert!(html.contains("data-severity=\"high\" data-type=\"out_of_bounds\""));
+    assert!(!html.contains("Image unavailable"));
+
+    // The undecodable image gets a placeholder instead
+    let html = render("corrupt");
+    assert!(!html.contains("data:image/png;base64,"));
+    assert!(html.contains("Image unavailable"));
+}
+
+#[test]
+fn box_stats_are_gathered_per_class_before_auditing() {
+    let options = || AuditOptions {
+        dataset_path: fixture("nested").into(),
+        include_images: vec!["scene01/*".to_string()],
+        ..Default::default()
+    };
+    // The outlier check, and the pass over every label file, are opt-in
+    assert!(run_audit(options()).unwrap().box_stats.is_empty());
+
+    let result = run_audit(AuditOptions {
+        box_outlier_mads: 6.0,
+        ..options()
+    })
+    .unwrap();
+
+    // Statistics cover every label file, not only the audited images
+    assert_eq!(result.images_audited, 1);
+    let cars = &result.box_stats.classes["car"];
+    assert_eq!(cars.count, 2);
+    assert!((cars.area.median - 0.01).abs() < 1e-6);
+    assert!((cars.aspect_ratio.median - 1.0).abs() < 1e-6);
+    assert_eq!(result.box_stats.classes["person"].count, 1);
+
+    let json = serde_json::to_value(&result).unwrap();
+    assert!(json["box_stats"]["car"]["area"]["median"].is_number());
+}
+
+#[test]
+fn spatial_heatmap_bins_box_centers_in_reports() {
+    let mut result = run_audit(AuditOptions {
+        dataset_path: fixture("nested").into(),
+        ..Default::default()
+    })
+    .unwrap();
+    let path = std::env::temp_dir().join(format!("gt-audit-heatmap-{}", std::process::id()));
+
+    JsonReporter::new()
+        .generate(&result, &path.with_extension("json"))
+        .unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(path.with_extension("json")).unwrap())
+            .unwrap();
+    std::fs::remove_file(path.with_extension("json")).unwrap();
+    let rows = json["spatial_heatmap"].as_array().unwrap();
+    assert_eq!(rows.len(), 20);
+    for (row, col) in [(5, 5), (10, 10), (15, 15)] {
+        assert_eq!(rows[row][col], 1);
+    }
+
+    HtmlReporter::new()
+        .generate(&result, &path.with_extension("html"))
+        .unwrap();
+    let html = std::fs::read_to_string(path.with_extension("html")).unwrap();
+    std::fs::remove_file(path.with_extension("html")).unwrap();
+    assert!(html.contains("<h2>Spatial Distribution</h2>"));
+    assert!(html.contains("style=\"fill: hsl(120, 80%, 35%);\"><title>1</title>"));
+
+    // The grid size survives recomputing the summaries
+    result.set_heatmap_grid(4);
+    result.finalize();
+    let heatmap = result.spatial_heatmap.as_ref().unwrap();
+    assert_eq!(heatmap.grid(), 4);
+    assert_eq!(heatmap.total(), 3);
+}
+
+#[test]
+fn iou_histogram_is_drawn_in_html_reports() {
+    let mut result = AuditResult::new("d".into(), "yolo".into(), 0.25, 0.5, 1, 1);
+    let mut image = ImageResult::new("a.jpg".to_string(), &[], 2);
+    image.match_ious = vec![0.45, 0.95];
+    result.add_image_result(image);
+    result.set_iou_buckets(5);
+    result.finalize();
+
+    let json = serde_json::to_string(&result).unwrap();
+    assert!(json.contains("\"iou_histogram\":[[0.0,0.2,0],[0.2,0.4,0],[0.4,0.6,1],"));
+    let html = HtmlReporter::new().render(&result).unwrap();
+    assert!(html.contains("<h2>Match Quality</h2>"));
+    assert!(html.contains("<title>IoU 0.80-1.00: 1</title>"));
+}
+
+#[test]
+fn html_report_details_a_limited_number_of_images_by_default() {
+    let mut result = AuditResult::new("d".into(), "yolo".into(), 0.25, 0.5, 0, 0);
+    for i in 0..=HTML_MAX_IMAGES {
+        let name = format!("{}.jpg", i);
+        let mut image = ImageResult::new(name.clone(), &[], 0);
+        image.add_issue(Issue::builder(name, IssueSeverity::Low, IssueType::BoxSize).build());
+        result.add_image_result(image);
+    }
+    result.finalize();
+
+    let html = HtmlReporter::new().render(&result).unwrap();
+    assert!(html.contains(&format!(
+        "Only the first {} of {} flagged images",
+        HTML_MAX_IMAGES,
+        HTML_MAX_IMAGES + 1
+    )));
+    assert_eq!(html.matches("data-filename=").count(), HTML_MAX_IMAGES);
+}
+
+#[test]
+fn html_report_truncates_flagged_images_with_notice() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("dirty").into(),
+        ..Default::default()
+    })
+    .unwrap();
+    let path = std::env::temp_dir().join(format!("gt-audit-truncated-{}.html", std::process::id()));
+    HtmlReporter::new()
+        .with_max_images(0)
+        .generate(&result, &path)
+        .unwrap();
+    let html = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(html.contains("Flagged Images (1)"));
+    assert!(html.contains("Only the first 0 of 1 flagged images"));
+    assert!(!html.contains("data-filename="));
+    // Summary tiles still count the truncated image
+    assert!(html.contains("<div class=\"value high\">1</div>"));
+}
+
+#[test]
+fn junit_report_maps_severities_and_records_properties() {
+    let junit = |name: &str, reporter: JunitReporter| {
+        let result = run_audit(AuditOptions {
+            dataset_path: fixture(name).into(),
+            ..Default::default()
+        })
+        .unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "gt-audit-junit-{}-{}.xml",
+            name,
+            std::process::id()
+        ));
+        reporter.generate(&result, &path).unwrap();
+        let xml = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        xml
+    };
+
+    let xml = junit("dirty", JunitReporter::new());
+    assert!(xml.contains("tests=\"1\" failures=\"1\" errors=\"0\""));
+    assert!(xml.contains("<testcase name=\"sample.png\""));
+    assert!(xml.contains("<failure message=\""));
+    assert!(xml.contains(&format!(
+        "<property name=\"generator_version\" value=\"{}\"/>",
+        env!("CARGO_PKG_VERSION")
+    )));
+    assert!(xml.contains("<property name=\"iou_threshold\" value=\"0.5\"/>"));
+
+    // Clean images pass, or are left out entirely
+    let xml = junit("clean", JunitReporter::new());
+    assert!(xml.contains("<testcase name=\"sample.png\" classname="));
+    assert!(xml.contains("failures=\"0\" errors=\"0\""));
+    let reporter = JunitReporter {
+        flagged_only: true,
+        ..JunitReporter::new()
+    };
+    assert!(!junit("clean", reporter).contains("<testcase"));
+}
+
+#[test]
+fn junit_report_medium_issues_are_errors_unless_medium_as_failure() {
+    let mut image = ImageResult::new("a.jpg".to_string(), &[], 1);
+    image.add_issue(
+        Issue::builder("a.jpg", IssueSeverity::Medium, IssueType::MissingLabel)
+            .description("unlabeled car")
+            .build(),
+    );
+    let mut result = AuditResult::new("d".into(), "yolo".into(), 0.25, 0.5, 1, 1);
+    result.add_image_result(image);
+    result.finalize();
+
+    let xml = JunitReporter::new().render(&result).unwrap();
+    assert!(xml.contains("tests=\"1\" failures=\"0\" errors=\"1\""));
+    assert!(xml.contains("<error message=\"unlabeled car\" type=\"missing_label\">"));
+    assert!(!xml.contains("<failure"));
+
+    let reporter = JunitReporter {
+        medium_as_failure: true,
+        ..JunitReporter::new()
+    };
+    let xml = reporter.render(&result).unwrap();
+    assert!(xml.contains("tests=\"1\" failures=\"1\" errors=\"0\""));
+    assert!(xml.contains("<failure message=\"unlabeled car\" type=\"missing_label\">"));
+    assert!(!xml.contains("<error"));
+}
+
+#[test]
+fn junit_report_leaves_out_suppressed_and_baselined_issues() {
+    let audit = || {
+        run_audit(AuditOptions {
+            dataset_path: fixture("dirty").into(),
+            ..Default::default()
+        })
+        .unwrap()
+    };
+    let flagged_only = JunitReporter {
+        flagged_only: true,
+        ..JunitReporter::new()
+    };
+
+    let mut suppressed = audit();
+    assert!(suppressed.total_issues() > 0);
+    suppressed.apply_suppressions(|_| true);
+    let xml = JunitReporter::new().render(&suppressed).unwrap();
+    assert!(xml.contains("tests=\"1\" failures=\"0\" errors=\"0\""));
+    assert!(!xml.contains("<failure"));
+    let xml = flagged_only.render(&suppressed).unwrap();
+    assert!(!xml.contains("<testcase"));
+
+    let mut baselined = audit();
+    baselined.apply_baseline(|_| true);
+    let xml = JunitReporter::new().render(&baselined).unwrap();
+    assert!(xml.contains("failures=\"0\" errors=\"0\""));
+    assert!(!xml.contains("<failure"));
+}
+
+#[test]
+fn sarif_report_has_required_2_1_0_structure() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("dirty").into(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let sarif = SarifReporter::new().to_value(&result);
+
+    // Properties the SARIF 2.1.0 schema marks as required, plus the
+    // constraints GitHub code scanning enforces on upload
+    assert_eq!(
+        sarif["$schema"],
+        "https://json.schemastore.org/sarif-2.1.0.json"
+    );
+    assert_eq!(sarif["version"], "2.1.0");
+    let runs = sarif["runs"].as_array().unwrap();
+    assert_eq!(runs.len(), 1);
+    let run = &runs[0];
+    assert_eq!(run["tool"]["driver"]["name"], "gt-audit");
+
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    let rule_ids: Vec<&str> = rules.iter().map(|r| r["id"].as_str().unwrap()).collect();
+    assert!(rule_ids.iter().all(|id| !id.is_empty()));
+    let unique: std::collections::HashSet<_> = rule_ids.iter().collect();
+    assert_eq!(unique.len(), rule_ids.len());
+
+    let results = run["results"].as_array().unwrap();
+    assert!(!results.is_empty());
+    for res in results {
+        let rule_id = res["ruleId"].as_str().unwrap();
+        assert!(
+            rule_ids.contains(&rule_id),
+            "ruleId '{}' has no rule",
+            rule_id
+        );
+        let level = res["level"].as_str().unwrap();
+        assert!(["none", "note", "warning", "error"].contains(&level));
+        assert!(!res["message"]["text"].as_str().unwrap().is_empty());
+        let locations = res["locations"].as_array().unwrap();
+        assert!(!locations.is_empty());
+        for location in locations {
+            let uri = &location["physicalLocation"]["artifactLocation"]["uri"];
+            assert!(!uri.as_str().unwrap().is_empty());
+        }
+    }
+    assert_eq!(results.len(), 1);
+
+    let res = &results[0];
+    assert_eq!(res["ruleId"], "out_of_bounds");
+    let rule = rules.iter().find(|r| r["id"] == res["ruleId"]).unwrap();
+    assert_eq!(rule["name"], "Out of Bounds");
+    assert!(rule["help"]["text"].as_str().is_some());
+    assert_eq!(res["level"], "error");
+    assert!(res["message"]["text"].as_str().is_some());
+
+    let location = &res["locations"][0]["physicalLocation"];
+    let uri = location["artifactLocation"]["uri"].as_str().unwrap();
+    assert!(uri.ends_with("labels/val/sample.txt"));
+    assert!(!uri.contains('\\'));
+    assert_eq!(location["region"]["startLine"], 1);
+}
+
+#[test]
+fn full_json_report_round_trips() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("malformed").into(),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let path = std::env::temp_dir().join(format!("gt-audit-report-{}.json", std::process::id()));
+    JsonReporter {
+        detail: ReportDetail::Full,
+        max_images: None,
+    }
+    .generate(&result, &path)
+    .unwrap();
+    let loaded = AuditResult::from_json(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(result.total_issues() > 0);
+    assert_eq!(loaded.total_issues(), result.total_issues());
+    assert_eq!(loaded.high_count(), result.high_count());
+    assert_eq!(loaded.image_results.len(), result.image_results.len());
+    assert_eq!(loaded.images_clean(), result.images_clean());
+    let names = |r: &AuditResult| -> Vec<String> {
+        r.flagged_images()
+            .iter()
+            .map(|i| i.filename.clone())
+            .collect()
+    };
+    assert_eq!(names(&loaded), names(&result));
+    assert_eq!(loaded.spatial_heatmap, result.spatial_heatmap);
+    assert_eq!(
+        loaded.summary.per_class.keys().collect::<Vec<_>>(),
+        result.summary.per_class.keys().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn truncated_json_reports_are_marked_and_refused() {
+    let result = run_audit(AuditOptions {
+        dataset_path: fixture("malformed").into(),
+        ..Default::default()
+    })
+    .unwrap();
+    assert!(!result.flagged_images().is_empty());
+
+    let path = std::env::temp_dir().join(format!("gt-audit-top-{}.json", std::process::id()));
+    JsonReporter {
+        detail: ReportDetail::Flagged,
+        max_images: Some(0),
+    }
+    .generate(&result, &path)
+    .unwrap();
+    let report: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    let loaded = AuditResult::from_json(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        report["flagged_images_total"],
+        result.flagged_images().len()
+    );
+    assert_eq!(report["flagged_images_truncated"], true);
+    let error = loaded.unwrap_err().to_string();
+    assert!(error.contains("--report-top"), "{}", error);
+}
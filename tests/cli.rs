@@ -0,0 +1,359 @@
+use std::process::Command;
+
+fn gt_audit() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_gt-audit"))
+}
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn validate_passes_clean_dataset() {
+    let status = gt_audit()
+        .args(["validate", &fixture("clean"), "--fail-on-high", "0"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn validate_fails_when_high_threshold_exceeded() {
+    let status = gt_audit()
+        .args(["validate", &fixture("dirty"), "--fail-on-high", "0"])
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(1));
+}
+
+#[test]
+fn validate_checks_only_the_listed_images() {
+    let validate = |dataset: &str| {
+        gt_audit()
+            .args(["validate", "--fail-on-high", "0", "--images"])
+            .arg(format!("{}/images/val/sample.png", fixture(dataset)))
+            .status()
+            .unwrap()
+    };
+    assert!(validate("clean").success());
+    assert_eq!(validate("dirty").code(), Some(1));
+}
+
+#[test]
+fn validate_rejects_non_positive_soft_nms_sigma() {
+    let output = gt_audit()
+        .args([
+            "validate",
+            &fixture("clean"),
+            "--soft-nms",
+            "--soft-nms-sigma",
+            "0",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--soft-nms-sigma must be positive"));
+}
+
+#[test]
+fn validate_ignores_soft_nms_sigma_without_soft_nms() {
+    let status = gt_audit()
+        .args(["validate", &fixture("clean"), "--soft-nms-sigma", "0"])
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn validate_rejects_unknown_report_extensions() {
+    let output = gt_audit()
+        .args(["validate", &fixture("clean"), "-o"])
+        .arg(std::env::temp_dir().join("gt-audit-issues.csv"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unsupported report extension '.csv'"));
+}
+
+#[test]
+fn validate_passes_without_thresholds() {
+    let status = gt_audit()
+        .args(["validate", &fixture("dirty")])
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn validate_logs_json_lines_to_stderr() {
+    let output = gt_audit()
+        .args(["validate", &fixture("dirty"), "-v", "--log-format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let events: Vec<serde_json::Value> = stderr
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert!(events
+        .iter()
+        .any(|e| e["level"] == "DEBUG" && e["fields"]["message"] == "Audited image"));
+}
+
+#[test]
+fn command_line_switches_turn_off_config_settings() {
+    let dir = std::env::temp_dir().join(format!("gt-audit-switch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("gt-audit.toml");
+    std::fs::write(&config, "junit_flagged_only = true\n").unwrap();
+    let report = dir.join("report.xml");
+    let junit = |switch: Option<&str>| {
+        let status = gt_audit()
+            .args(["validate", &fixture("clean"), "-q", "--config"])
+            .arg(&config)
+            .args(switch)
+            .arg("-o")
+            .arg(&report)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        std::fs::read_to_string(&report).unwrap()
+    };
+
+    assert!(!junit(None).contains("<testcase"));
+    assert!(junit(Some("--junit-flagged-only=false")).contains("<testcase"));
+    assert!(!junit(Some("--junit-flagged-only")).contains("<testcase"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn validate_prints_human_output_on_one_stream() {
+    let report = std::env::temp_dir().join(format!("gt-audit-human-{}.json", std::process::id()));
+    let output = gt_audit()
+        .args(["validate", &fixture("dirty"), "--fail-on-high", "0", "-o"])
+        .arg(&report)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&report).unwrap();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    for line in [
+        "Loading dataset",
+        "AUDIT SUMMARY",
+        "JSON report saved",
+        "FAIL",
+    ] {
+        assert!(stdout.contains(line), "{} missing from stdout", line);
+        assert!(!stderr.contains(line), "{} on stderr", line);
+    }
+}
+
+#[test]
+fn config_init_writes_template_and_reports_record_options() {
+    let dir = std::env::temp_dir().join(format!("gt-audit-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("gt-audit.toml");
+    let init = |force: bool| {
+        let mut cmd = gt_audit();
+        cmd.args(["config", "init", config.to_str().unwrap()]);
+        if force {
+            cmd.arg("--force");
+        }
+        cmd.status().unwrap()
+    };
+    assert!(init(false).success());
+    assert!(!init(false).success());
+    assert!(init(true).success());
+
+    std::fs::write(&config, "fail_on_high = 5\nconfidence = 0.3\n").unwrap();
+    let report = dir.join("report.json");
+    let status = gt_audit()
+        .args(["validate", &fixture("dirty"), "--config"])
+        .arg(&config)
+        .args(["--confidence", "0.4", "-o"])
+        .arg(&report)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(json["config"]["fail_on_high"], 5);
+    assert_eq!(json["config"]["confidence"], 0.4);
+    assert!(json["config"]["output"].is_string());
+}
+
+#[test]
+fn validate_writes_every_output() {
+    let dir = std::env::temp_dir().join(format!("gt-audit-outputs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let output = gt_audit()
+        .args(["validate", &fixture("dirty"), "-o"])
+        .arg(dir.join("report.json"))
+        .arg("-o")
+        .arg(dir.join("report.html"))
+        .args([
+            "-o", "-", "--format", "json", "--format", "html", "--format", "markdown",
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+    let html = std::fs::read_to_string(dir.join("report.html")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(json["summary"]["images_with_issues"], 1);
+    assert!(html.contains("<html"));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("## gt-audit report"));
+
+    // A missing directory fails before the audit runs
+    let output = gt_audit()
+        .args([
+            "validate",
+            &fixture("dirty"),
+            "-o",
+            "/nonexistent/gt-audit/report.json",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Output directory does not exist"));
+}
+
+#[test]
+fn validate_exports_a_worklist() {
+    let dir = std::env::temp_dir().join(format!("gt-audit-worklist-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let status = gt_audit()
+        .args(["validate", &fixture("dirty"), "--top", "10", "-o"])
+        .arg(dir.join("report.json"))
+        .arg("--export-worklist")
+        .arg(dir.join("worklist.txt"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let worklist = std::fs::read_to_string(dir.join("worklist.txt")).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("report.json")).unwrap()).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let lines: Vec<&str> = worklist.lines().collect();
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].ends_with("sample.png"));
+    assert!(std::path::Path::new(lines[0]).is_file());
+    assert!(
+        json["flagged_images"][0]["relabel_priority"]
+            .as_f64()
+            .unwrap()
+            > 0.0
+    );
+    assert!(json["priority_scoring"]["formula"].is_string());
+}
+
+#[test]
+fn export_writes_label_studio_tasks() {
+    let dir = std::env::temp_dir().join(format!("gt-audit-tasks-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let report = dir.join("report.json");
+    let status = gt_audit()
+        .args(["validate", &fixture("dirty"), "-o"])
+        .arg(&report)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    std::fs::write(
+        dir.join("config.xml"),
+        r#"<View>
+  <Image name="img" value="$photo"/>
+  <RectangleLabels name="box" toName="img">
+    <Label value="Human"/>
+  </RectangleLabels>
+</View>"#,
+    )
+    .unwrap();
+
+    let status = gt_audit()
+        .args(["export", &fixture("dirty"), "--report"])
+        .arg(&report)
+        .args([
+            "--to",
+            "label-studio",
+            "--images-prefix",
+            "https://data.example.com/",
+        ])
+        .arg("--label-config")
+        .arg(dir.join("config.xml"))
+        .arg("-o")
+        .arg(dir.join("tasks.json"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let status = gt_audit()
+        .args(["export", &fixture("dirty"), "--report"])
+        .arg(&report)
+        .args(["--to", "cvat", "-o"])
+        .arg(dir.join("annotations.xml"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let tasks: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("tasks.json")).unwrap()).unwrap();
+    let cvat = std::fs::read_to_string(dir.join("annotations.xml")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    let task = &tasks[0];
+    assert_eq!(
+        task["data"]["photo"],
+        "https://data.example.com/images/val/sample.png"
+    );
+    let region = &task["predictions"][0]["result"][0];
+    assert_eq!(region["from_name"], "box");
+    assert_eq!(region["to_name"], "img");
+    assert_eq!(region["value"]["rectanglelabels"][0], "Human");
+    assert!(!task["meta"]["issues"].as_array().unwrap().is_empty());
+    assert!(cvat.contains("<image id=\"0\" name=\"images/val/sample.png\""));
+    assert!(cvat.contains("<box label=\"person\""));
+}
+
+#[test]
+fn per_class_reports_of_clashing_class_names_are_kept_apart() {
+    let dir = std::env::temp_dir().join(format!("gt-audit-per-class-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("images/val")).unwrap();
+    std::fs::create_dir_all(dir.join("labels/val")).unwrap();
+    std::fs::write(dir.join("classes.txt"), "car door\ncar_door\n").unwrap();
+    std::fs::copy(
+        format!("{}/images/val/sample.png", fixture("clean")),
+        dir.join("images/val/a.png"),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("labels/val/a.txt"),
+        "0 0.25 0.25 0.1 0.1\n1 0.75 0.75 0.1 0.1\n",
+    )
+    .unwrap();
+
+    let reports = dir.join("by-class");
+    let status = gt_audit()
+        .arg("stats")
+        .arg(&dir)
+        .arg("--per-class-report")
+        .arg(&reports)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let class_name = |file: &str| {
+        let report: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(reports.join(file)).unwrap()).unwrap();
+        report["class_name"].as_str().unwrap().to_string()
+    };
+    assert_eq!(class_name("car_door.json"), "car door");
+    assert_eq!(class_name("car_door_2.json"), "car_door");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
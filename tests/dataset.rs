@@ -0,0 +1,465 @@
+use std::path::Path;
+
+use gt_audit::dataset::{writer_for, CoordinateMistake, DatasetFormat, LabeledDataset};
+use gt_audit::leakage::{find_duplicates, HashMode};
+use gt_audit::models::Annotation;
+use gt_audit::{IssueSeverity, MultiSplitDataset, YoloDataset};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn flat_layout_maps_labels_by_stem() {
+    let dataset = YoloDataset::load(Path::new(&fixture("clean"))).unwrap();
+    let images = dataset.get_images();
+
+    assert_eq!(images.len(), 1);
+    assert_eq!(
+        dataset.get_label_path(&images[0]),
+        dataset.labels_dir.join("sample.txt")
+    );
+    assert_eq!(dataset.image_key(&images[0]), "sample.png");
+    assert_eq!(dataset.load_annotations(&images[0]).len(), 1);
+}
+
+#[test]
+fn nested_layout_mirrors_label_subpaths() {
+    let dataset = YoloDataset::load(Path::new(&fixture("nested"))).unwrap();
+    let images = dataset.get_images();
+
+    assert_eq!(images.len(), 2);
+    assert_eq!(dataset.image_key(&images[0]), "scene01/frame0001.png");
+    assert_eq!(dataset.image_key(&images[1]), "scene02/frame0001.png");
+    assert_eq!(
+        dataset.get_label_path(&images[1]),
+        dataset.labels_dir.join("scene02").join("frame0001.txt")
+    );
+
+    // Same stem in different folders must not share labels
+    let scene01 = dataset.load_annotations(&images[0]);
+    let scene02 = dataset.load_annotations(&images[1]);
+    assert_eq!(scene01.len(), 1);
+    assert_eq!(scene01[0].class_name, "person");
+    assert_eq!(scene02.len(), 2);
+    assert_eq!(scene02[0].class_name, "car");
+}
+
+#[test]
+fn consistency_check_separates_missing_and_empty_labels() {
+    let dataset = YoloDataset::load(Path::new(&fixture("inconsistent"))).unwrap();
+    let report = dataset.consistency_check();
+
+    let names = |paths: &[std::path::PathBuf]| -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    };
+    assert_eq!(names(&report.orphaned_labels), ["stale.txt"]);
+    assert_eq!(names(&report.missing_labels), ["unlabeled.png"]);
+    assert_eq!(names(&report.empty_labels), ["background.png"]);
+
+    let clean = YoloDataset::load(Path::new(&fixture("clean"))).unwrap();
+    assert!(clean.consistency_check().is_empty());
+}
+
+#[test]
+fn image_list_checks_only_the_given_images() {
+    let root = Path::new(&fixture("inconsistent")).to_path_buf();
+    let images = [
+        root.join("images/val/unlabeled.png"),
+        root.join("images/val/labeled.png"),
+    ];
+    assert_eq!(gt_audit::dataset::image_files_root(&images), root);
+
+    let dataset = YoloDataset::from_image_files(&images, None).unwrap();
+    assert_eq!(dataset.get_images(), [images[1].clone(), images[0].clone()]);
+    assert_eq!(
+        dataset.get_label_path(&images[1]),
+        root.join("labels/val/labeled.txt")
+    );
+    assert_eq!(dataset.load_annotations(&images[1]).len(), 1);
+
+    // stale.txt belongs to no given image but is not reported either
+    let report = dataset.consistency_check();
+    assert!(report.orphaned_labels.is_empty());
+    assert_eq!(report.missing_labels, [images[0].clone()]);
+
+    let classes = Path::new(&fixture("nested")).join("classes.txt");
+    let dataset = YoloDataset::from_image_files(&images, Some(&classes)).unwrap();
+    assert_eq!(dataset.get_class_name(1), "car");
+    assert!(YoloDataset::from_image_files(&[root.join("missing.png")], None).is_err());
+}
+
+#[test]
+fn class_name_check_finds_duplicates_and_gaps() {
+    let mut dataset = YoloDataset::load(Path::new(&fixture("clean"))).unwrap();
+    assert!(dataset.class_name_check().is_empty());
+
+    dataset.class_names = [(0, "person"), (1, "car"), (3, "bus"), (7, "Person")]
+        .into_iter()
+        .map(|(id, name)| (id, name.to_string()))
+        .collect();
+    let report = dataset.class_name_check();
+    assert_eq!(report.duplicates, vec![("person".to_string(), vec![0, 7])]);
+    assert_eq!(report.missing_ids, vec![2, 4, 5, 6]);
+    assert_eq!(report.warnings().len(), 2);
+}
+
+#[test]
+fn yaml_split_keys_select_image_dirs() {
+    let path = fixture("yaml_paths");
+    let nested = Path::new(&path).join("../nested");
+
+    // Defaults to the val split
+    let dataset = YoloDataset::load(Path::new(&path)).unwrap();
+    assert_eq!(dataset.images_dir, nested.join("images/val/scene02"));
+    assert_eq!(dataset.labels_dir, nested.join("labels/val/scene02"));
+    assert_eq!(dataset.get_images().len(), 1);
+    assert_eq!(dataset.class_names.len(), 2);
+
+    let train = YoloDataset::load_split(Path::new(&path), "train").unwrap();
+    assert_eq!(train.images_dir, nested.join("images/val/scene01"));
+
+    // A list of directories merges all of them
+    let test = YoloDataset::load_split(Path::new(&path), "test").unwrap();
+    let images = test.get_images();
+    assert_eq!(images.len(), 2);
+    assert_eq!(test.load_annotations(&images[1]).len(), 2);
+
+    assert!(YoloDataset::load_split(Path::new(&path), "holdout").is_err());
+}
+
+#[test]
+fn roboflow_exports_load_with_every_split() {
+    let path = Path::new(&fixture("roboflow")).to_path_buf();
+
+    // `../valid/images` in data.yaml resolves inside the dataset
+    let dataset = YoloDataset::load(&path).unwrap();
+    assert_eq!(dataset.images_dir, path.join("valid/images"));
+    assert_eq!(dataset.labels_dir, path.join("valid/labels"));
+    assert_eq!(dataset.class_names.len(), 2);
+
+    let all = MultiSplitDataset::load(&path, "all").unwrap();
+    assert_eq!(all.split_names(), ["train", "val", "test"]);
+    for spec in [",", " ", ""] {
+        let err = MultiSplitDataset::load(&path, spec).err().unwrap();
+        assert_eq!(err.to_string(), "No splits given in --splits");
+    }
+    let test = &all.splits["test"];
+    assert_eq!(test.load_annotations(&test.get_images()[0]).len(), 2);
+
+    // Without a top-level YAML, the layout and the per-split data.yaml are enough
+    let root = std::env::temp_dir().join(format!("gt-audit-roboflow-{}", std::process::id()));
+    for split in ["train", "valid"] {
+        for dir in ["images", "labels"] {
+            std::fs::create_dir_all(root.join(split).join(dir)).unwrap();
+        }
+    }
+    std::fs::write(root.join("valid/data.yaml"), "names: ['person', 'car']\n").unwrap();
+    let dataset = YoloDataset::load(&root).unwrap();
+    assert_eq!(dataset.images_dir, root.join("valid/images"));
+    assert_eq!(dataset.labels_dir, root.join("valid/labels"));
+    assert_eq!(dataset.class_names[&1], "car");
+    let train = YoloDataset::load_split(&root, "train").unwrap();
+    assert_eq!(train.labels_dir, root.join("train/labels"));
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn leakage_groups_identical_images_across_splits() {
+    let dataset = MultiSplitDataset::load(Path::new(&fixture("yaml_paths")), "all").unwrap();
+    let report = find_duplicates(&dataset, HashMode::Bytes).unwrap();
+
+    // Every fixture frame is the same PNG, and test lists both scenes again
+    assert_eq!(report.images_hashed, 4);
+    assert_eq!(report.groups.len(), 1);
+    assert!(report.groups[0].cross_split);
+    assert_eq!(report.groups[0].severity, IssueSeverity::High);
+    assert_eq!(report.groups[0].files.len(), 4);
+}
+
+#[test]
+fn polygon_rows_become_enclosing_boxes() {
+    let dataset = YoloDataset::load(Path::new(&fixture("segmentation"))).unwrap();
+    let images = dataset.get_images();
+    let (annotations, errors) = dataset.load_labels(&images[0]);
+
+    assert_eq!(annotations.len(), 1);
+    let bbox = &annotations[0].bbox;
+    assert!((bbox.x - 0.4).abs() < 1e-6 && (bbox.y - 0.55).abs() < 1e-6);
+    assert!((bbox.w - 0.4).abs() < 1e-6 && (bbox.h - 0.5).abs() < 1e-6);
+    assert_eq!(annotations[0].polygon.as_ref().unwrap().len(), 3);
+
+    // A 3-value row is neither a box nor a polygon
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line_num, 2);
+}
+
+#[test]
+fn box_rows_with_trailing_polygons_keep_both() {
+    let dataset = YoloDataset::load(Path::new(&fixture("box_polygons"))).unwrap();
+    let images = dataset.get_images();
+    let (annotations, errors) = dataset.load_labels(&images[0]);
+    assert!(errors.is_empty());
+    assert_eq!(annotations.len(), 2);
+
+    // Box columns, then three polygon points inside it
+    let bbox = &annotations[0].bbox;
+    assert!((bbox.x - 0.4).abs() < 1e-6 && (bbox.w - 0.4).abs() < 1e-6);
+    assert_eq!(annotations[0].polygon.as_ref().unwrap().len(), 3);
+
+    // Ten values that don't start with a matching box are a five-point polygon
+    let bbox = &annotations[1].bbox;
+    assert!((bbox.x - 0.2).abs() < 1e-6 && (bbox.y - 0.25).abs() < 1e-6);
+    assert_eq!(annotations[1].polygon.as_ref().unwrap().len(), 5);
+}
+
+#[test]
+fn pixel_and_corner_label_files_are_recognized() {
+    let boxes = |rows: &[[f32; 4]]| -> Vec<Annotation> {
+        rows.iter()
+            .map(|[x, y, w, h]| Annotation::builder().bbox(*x, *y, *w, *h).build())
+            .collect()
+    };
+
+    let pixels = boxes(&[[345.0, 220.0, 80.0, 120.0], [40.0, 0.5, 12.0, 30.0]]);
+    assert_eq!(
+        CoordinateMistake::detect(&pixels),
+        Some(CoordinateMistake::Pixels)
+    );
+
+    // x1 y1 x2 y2 read as a center and size reaches past the right and bottom edges
+    let corners = boxes(&[[0.6, 0.5, 0.9, 0.8], [0.55, 0.1, 0.95, 0.5]]);
+    assert_eq!(
+        CoordinateMistake::detect(&corners),
+        Some(CoordinateMistake::Corners)
+    );
+
+    let normalized = boxes(&[[0.5, 0.5, 0.2, 0.3], [0.95, 0.5, 0.2, 0.2]]);
+    assert_eq!(CoordinateMistake::detect(&normalized), None);
+    assert_eq!(CoordinateMistake::detect(&[]), None);
+}
+
+#[test]
+fn malformed_rows_are_reported_and_crlf_is_accepted() {
+    let dataset = YoloDataset::load(Path::new(&fixture("malformed"))).unwrap();
+    let images = dataset.get_images();
+    let (annotations, errors) = dataset.load_labels(&images[0]);
+
+    // CRLF endings and trailing whitespace do not break valid rows
+    assert_eq!(annotations.len(), 2);
+
+    let lines: Vec<usize> = errors.iter().map(|e| e.line_num).collect();
+    assert_eq!(lines, vec![4, 5, 6]);
+    assert_eq!(errors[0].content, "foo 0.5 0.5 0.2 0.2");
+    assert!(errors[1].reason.contains("NaN"));
+    assert_eq!(dataset.count_label_errors(), 3);
+}
+
+#[test]
+fn class_ids_are_checked_against_declared_names() {
+    let dataset = YoloDataset::load(Path::new(&fixture("clean"))).unwrap();
+
+    assert_eq!(dataset.label_class_ids().get(&0), Some(&1));
+    assert_eq!(dataset.class_id_range(), Some((0, 0)));
+    assert!(dataset.is_valid_class_id(0));
+    assert!(!dataset.is_valid_class_id(1));
+    assert!(!dataset.is_valid_class_id(-1));
+}
+
+#[test]
+fn conversion_round_trips_through_coco_and_voc() {
+    let original = LabeledDataset::load(Path::new(&fixture("clean")), DatasetFormat::Yolo).unwrap();
+    let out = std::env::temp_dir().join(format!("gt-audit-convert-{}", std::process::id()));
+
+    let mut dataset = original.clone();
+    for (format, dir) in [
+        (DatasetFormat::Coco, "coco"),
+        (DatasetFormat::Voc, "voc"),
+        (DatasetFormat::Yolo, "yolo"),
+    ] {
+        writer_for(format).write(&dataset, &out.join(dir)).unwrap();
+        dataset = LabeledDataset::load(&out.join(dir), format).unwrap();
+    }
+    std::fs::remove_dir_all(&out).unwrap();
+
+    assert_eq!(dataset.class_names, original.class_names);
+    assert_eq!(dataset.images.len(), 1);
+    let (before, after) = (&original.images[0], &dataset.images[0]);
+    assert_eq!(after.key, before.key);
+    assert_eq!(after.annotations.len(), before.annotations.len());
+    let (a, b) = (&before.annotations[0], &after.annotations[0]);
+    assert_eq!(a.class_name, b.class_name);
+    assert!((a.bbox.iou(&b.bbox) - 1.0).abs() < 1e-3);
+}
+
+#[test]
+fn malformed_coco_polygons_keep_the_box() {
+    let dir = std::env::temp_dir().join(format!("gt-audit-coco-odd-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("instances.json"),
+        r#"{
+  "images": [{"id": 1, "file_name": "a.jpg", "width": 100, "height": 100}],
+  "categories": [{"id": 1, "name": "car"}],
+  "annotations": [{"id": 1, "image_id": 1, "category_id": 1, "bbox": [10, 10, 20, 20],
+                   "segmentation": [[10, 10, 30, 10, 30, 30, 10]]}]
+}"#,
+    )
+    .unwrap();
+    let dataset = LabeledDataset::load(&dir, DatasetFormat::Coco).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let ann = &dataset.images[0].annotations[0];
+    assert!(ann.polygon.is_none());
+    assert!((ann.bbox.x - 0.2).abs() < 1e-6);
+}
+
+#[test]
+fn written_yolo_labels_load_back_unchanged() {
+    use gt_audit::dataset::write_yolo_labels;
+
+    let root = std::env::temp_dir().join(format!("gt-audit-yolo-labels-{}", std::process::id()));
+    std::fs::create_dir_all(root.join("images/val")).unwrap();
+    std::fs::write(root.join("classes.txt"), "person\ncar\n").unwrap();
+    std::fs::copy(
+        Path::new(&fixture("clean")).join("images/val/sample.png"),
+        root.join("images/val/sample.png"),
+    )
+    .unwrap();
+    let dataset = YoloDataset::load(&root).unwrap();
+    let image = &dataset.get_images()[0];
+
+    let annotations = [
+        Annotation::builder()
+            .class(0, "person")
+            .bbox(0.5, 0.25, 0.3, 0.125)
+            .build(),
+        Annotation::builder()
+            .class(1, "car")
+            .bbox(0.123457, 0.9, 0.05, 0.2)
+            .gt_confidence(0.35)
+            .line_num(2)
+            .build(),
+        Annotation::builder()
+            .class(1, "car")
+            .bbox(0.4, 0.55, 0.4, 0.5)
+            .polygon(vec![(0.2, 0.3), (0.6, 0.3), (0.4, 0.8)])
+            .line_num(3)
+            .build(),
+    ];
+    write_yolo_labels(&annotations, &dataset.get_label_path(image)).unwrap();
+    let loaded = dataset.load_annotations(image);
+    std::fs::remove_dir_all(&root).unwrap();
+
+    assert_eq!(loaded.len(), annotations.len());
+    for (a, b) in annotations.iter().zip(&loaded) {
+        assert_eq!(b.class_id, a.class_id);
+        assert_eq!(b.class_name, a.class_name);
+        assert_eq!(b.line_num, a.line_num);
+        assert_eq!(b.gt_confidence, a.gt_confidence);
+        assert_eq!(b.polygon, a.polygon);
+        for (x, y) in [
+            (a.bbox.x, b.bbox.x),
+            (a.bbox.y, b.bbox.y),
+            (a.bbox.w, b.bbox.w),
+            (a.bbox.h, b.bbox.h),
+        ] {
+            assert!((x - y).abs() < 1e-6, "{} != {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn export_strips_or_skips_flagged_annotations() {
+    use gt_audit::dataset::DatasetExporter;
+    use gt_audit::{AuditResult, ImageResult, Issue, IssueType};
+
+    let dataset = YoloDataset::load(Path::new(&fixture("clean"))).unwrap();
+    let issue = |severity: IssueSeverity, line_num: usize| {
+        Issue::builder("sample.png", severity, IssueType::SpuriousLabel)
+            .line_num(line_num)
+            .build()
+    };
+    let audited = |severity: IssueSeverity| {
+        let mut image = ImageResult::new("sample.png".to_string(), &[], 0);
+        image.add_issue(issue(severity, 1));
+        let mut result = AuditResult::new(fixture("clean"), "yolo".into(), 0.25, 0.5, 1, 1);
+        result.add_image_result(image);
+        result.finalize();
+        result
+    };
+
+    let low = audited(IssueSeverity::Low);
+    let (kept, summary) = DatasetExporter::new(&dataset, &low, false)
+        .clean_dataset()
+        .unwrap();
+    assert_eq!(kept.annotation_count(), 1);
+    assert_eq!(summary.annotations_stripped, 0);
+
+    let high = audited(IssueSeverity::High);
+    let (stripped, summary) = DatasetExporter::new(&dataset, &high, false)
+        .clean_dataset()
+        .unwrap();
+    assert_eq!(stripped.images.len(), 1);
+    assert_eq!(stripped.annotation_count(), 0);
+    assert_eq!(summary.annotations_stripped, 1);
+
+    let (skipped, summary) = DatasetExporter::new(&dataset, &low, true)
+        .clean_dataset()
+        .unwrap();
+    assert!(skipped.images.is_empty());
+    assert_eq!(summary.images_skipped, 1);
+
+    let path = std::env::temp_dir().join(format!("gt-audit-export-{}.json", std::process::id()));
+    DatasetExporter::new(&dataset, &high, false)
+        .write_coco(&path)
+        .unwrap();
+    let coco = LabeledDataset::load(&path, DatasetFormat::Coco).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(coco.images.len(), 1);
+    assert_eq!(coco.annotation_count(), 0);
+}
+
+#[test]
+fn stats_count_boxes_per_image_and_by_area() {
+    let dataset = YoloDataset::load(Path::new(&fixture("inconsistent"))).unwrap();
+    let stats = dataset.compute_stats();
+
+    assert_eq!(stats.total_images, 3);
+    assert_eq!(stats.total_annotations, 1);
+    assert_eq!(stats.min_annotations_per_image, 0);
+    assert_eq!(stats.max_annotations_per_image, 1);
+    assert_eq!(stats.images_without_annotations, 2);
+    assert_eq!(stats.class_imbalance_ratio, 1.0);
+    let buckets: Vec<(&str, usize)> = stats
+        .area_buckets
+        .iter()
+        .map(|b| (b.range.as_str(), b.count))
+        .collect();
+    assert_eq!(
+        buckets,
+        [
+            ("<0.1%", 0),
+            ("0.1-1%", 0),
+            ("1-10%", 1),
+            ("10-50%", 0),
+            (">50%", 0)
+        ]
+    );
+}
+
+#[test]
+fn split_by_class_lists_images_containing_each_class() {
+    let dataset = YoloDataset::load(Path::new(&fixture("inconsistent"))).unwrap();
+    let by_class = dataset.split_by_class();
+
+    assert_eq!(by_class.len(), 1);
+    let images: Vec<_> = by_class["person"]
+        .iter()
+        .map(|p| p.file_name().unwrap().to_str().unwrap())
+        .collect();
+    assert_eq!(images, ["labeled.png"]);
+}